@@ -70,7 +70,9 @@ pub use self::{
     systems::{
         AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem, SamplerProcessor,
     },
+    tint::TintChannel,
     transform::TransformChannel,
+    ui::{UiImageChannel, UiOpacityChannel, UiTextChannel},
     ui_transform::UiTransformChannel,
     util::{get_animation_set, SamplerPrimitive},
 };
@@ -82,6 +84,8 @@ mod resources;
 mod skinning;
 mod sprite;
 mod systems;
+mod tint;
 mod transform;
+mod ui;
 mod ui_transform;
 mod util;
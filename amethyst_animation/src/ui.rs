@@ -0,0 +1,166 @@
+use std::sync::Once;
+
+use amethyst_core::math::zero;
+use amethyst_ui::{UiImage, UiOpacity, UiText};
+use log::warn;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    resources::{AnimationSampling, ApplyData, BlendMethod},
+    util::SamplerPrimitive,
+};
+
+/// Channels that can be animated on `UiImage`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UiImageChannel {
+    /// The linear RGBA colour of a `UiImage::SolidColor`.
+    /// Samples applied to other kinds of images are ignored with a warning; animate the
+    /// `Tint` of their entity instead.
+    Color,
+}
+
+impl<'a> ApplyData<'a> for UiImage {
+    type ApplyData = ();
+}
+
+impl AnimationSampling for UiImage {
+    type Primitive = SamplerPrimitive<f32>;
+    type Channel = UiImageChannel;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>, _: &()) {
+        use crate::util::SamplerPrimitive::*;
+
+        use self::UiImageChannel::*;
+
+        match (channel, *data) {
+            (&Color, Vec4(ref d)) => {
+                if let UiImage::SolidColor(ref mut color) = self {
+                    *color = *d;
+                } else {
+                    static WARNING: Once = Once::new();
+                    WARNING.call_once(|| {
+                        warn!(
+                            "The `Color` channel of `UiImage` only applies to \
+                             `UiImage::SolidColor`, use the `Tint` channel to colour other images"
+                        )
+                    });
+                }
+            }
+            _ => panic!("Attempt to apply invalid sample to UiImage"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel, _: &()) -> SamplerPrimitive<f32> {
+        use self::UiImageChannel::*;
+        match (channel, self) {
+            (Color, UiImage::SolidColor(color)) => SamplerPrimitive::Vec4(*color),
+            (Color, _) => SamplerPrimitive::Vec4([1.0; 4]),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::UiImageChannel::*;
+        match channel {
+            Color => SamplerPrimitive::Vec4([zero(); 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
+
+/// Channels that can be animated on `UiText`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UiTextChannel {
+    /// The RGBA colour of the text.
+    Color,
+}
+
+impl<'a> ApplyData<'a> for UiText {
+    type ApplyData = ();
+}
+
+impl AnimationSampling for UiText {
+    type Primitive = SamplerPrimitive<f32>;
+    type Channel = UiTextChannel;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>, _: &()) {
+        use crate::util::SamplerPrimitive::*;
+
+        use self::UiTextChannel::*;
+
+        match (channel, *data) {
+            (&Color, Vec4(ref d)) => {
+                self.color = *d;
+            }
+            _ => panic!("Attempt to apply invalid sample to UiText"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel, _: &()) -> SamplerPrimitive<f32> {
+        use self::UiTextChannel::*;
+        match channel {
+            Color => SamplerPrimitive::Vec4(self.color),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::UiTextChannel::*;
+        match channel {
+            Color => SamplerPrimitive::Vec4([zero(); 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
+
+/// Channels that can be animated on `UiOpacity`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UiOpacityChannel {
+    /// The opacity, between 0 and 1.
+    Opacity,
+}
+
+impl<'a> ApplyData<'a> for UiOpacity {
+    type ApplyData = ();
+}
+
+impl AnimationSampling for UiOpacity {
+    type Primitive = SamplerPrimitive<f32>;
+    type Channel = UiOpacityChannel;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>, _: &()) {
+        use crate::util::SamplerPrimitive::*;
+
+        use self::UiOpacityChannel::*;
+
+        match (channel, *data) {
+            (&Opacity, Scalar(d)) => {
+                self.0 = d;
+            }
+            _ => panic!("Attempt to apply invalid sample to UiOpacity"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel, _: &()) -> SamplerPrimitive<f32> {
+        use self::UiOpacityChannel::*;
+        match channel {
+            Opacity => SamplerPrimitive::Scalar(self.0),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::UiOpacityChannel::*;
+        match channel {
+            Opacity => SamplerPrimitive::Scalar(zero()),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
@@ -13,6 +13,14 @@ use crate::{
 pub enum UiTransformChannel {
     /// The 2 dimensional position for an UI entity
     Translation,
+    /// The width and height of an UI entity
+    Size,
+    /// The local z order of an UI entity
+    Z,
+    /// The horizontal and vertical scale factors of an UI entity
+    Scale,
+    /// The rotation in radians of an UI entity
+    Rotation,
 }
 
 impl<'a> ApplyData<'a> for UiTransform {
@@ -33,6 +41,19 @@ impl AnimationSampling for UiTransform {
                 self.local_x = d[0];
                 self.local_y = d[1];
             }
+            (&Size, Vec2(ref d)) => {
+                self.width = d[0];
+                self.height = d[1];
+            }
+            (&Z, Scalar(d)) => {
+                self.local_z = d;
+            }
+            (&Scale, Vec2(ref d)) => {
+                self.scale = *d;
+            }
+            (&Rotation, Scalar(d)) => {
+                self.rotation = d;
+            }
             _ => panic!("Attempt to apply invalid sample to UiTransform"),
         }
     }
@@ -41,12 +62,17 @@ impl AnimationSampling for UiTransform {
         use self::UiTransformChannel::*;
         match channel {
             Translation => SamplerPrimitive::Vec2([self.local_x, self.local_y]),
+            Size => SamplerPrimitive::Vec2([self.width, self.height]),
+            Z => SamplerPrimitive::Scalar(self.local_z),
+            Scale => SamplerPrimitive::Vec2(self.scale),
+            Rotation => SamplerPrimitive::Scalar(self.rotation),
         }
     }
     fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
         use self::UiTransformChannel::*;
         match channel {
-            Translation => SamplerPrimitive::Vec2([zero(); 2]),
+            Translation | Size | Scale => SamplerPrimitive::Vec2([zero(); 2]),
+            Z | Rotation => SamplerPrimitive::Scalar(zero()),
         }
     }
 
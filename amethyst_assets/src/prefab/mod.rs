@@ -137,6 +137,11 @@ impl<T> PrefabEntity<T> {
         PrefabEntity { parent, data }
     }
 
+    /// Get the index of the parent
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
     /// Set parent index
     pub fn set_parent(&mut self, parent: usize) {
         self.parent = Some(parent);
@@ -222,6 +227,11 @@ impl<T> Prefab<T> {
         index
     }
 
+    /// Tag given to the entities created from the prefab, set when the prefab is loaded
+    pub fn tag(&self) -> Option<u64> {
+        self.tag
+    }
+
     /// Number of entities in the prefab, including the main entity
     pub fn len(&self) -> usize {
         self.entities.len()
@@ -35,6 +35,7 @@ pub struct InputHandler {
     mouse_position: Option<(f32, f32)>,
     mouse_wheel_vertical: f32,
     mouse_wheel_horizontal: f32,
+    mouse_wheel_delta: (f32, f32),
 }
 
 impl InputHandler {
@@ -248,6 +249,8 @@ impl InputHandler {
                     if delta_y != 0.0 {
                         self.mouse_wheel_vertical = delta_y.signum();
                     }
+                    self.mouse_wheel_delta.0 += delta_x;
+                    self.mouse_wheel_delta.1 += delta_y;
                     self.invoke_wheel_moved(delta_x, delta_y, event_handler);
                 }
                 DeviceEvent::MouseWheel {
@@ -259,6 +262,8 @@ impl InputHandler {
                     if y != 0.0 {
                         self.mouse_wheel_vertical = y.signum() as f32;
                     }
+                    self.mouse_wheel_delta.0 += x as f32;
+                    self.mouse_wheel_delta.1 += y as f32;
                     self.invoke_wheel_moved(x as f32, y as f32, event_handler);
                 }
                 _ => {}
@@ -396,6 +401,7 @@ impl InputHandler {
     pub fn send_frame_begin(&mut self) {
         self.mouse_wheel_vertical = 0.0;
         self.mouse_wheel_horizontal = 0.0;
+        self.mouse_wheel_delta = (0.0, 0.0);
         self.mouse_last_position = self.mouse_position;
     }
 
@@ -433,6 +439,18 @@ impl InputHandler {
         }
     }
 
+    /// Returns how much the mouse wheel was scrolled this frame, adding up all its events.
+    /// The amount is in lines for most mouse wheels, and in pixels for touchpads.
+    ///
+    /// If "horizontal" is true this will return the horizontal amount.
+    pub fn mouse_wheel_delta(&self, horizontal: bool) -> f32 {
+        if horizontal {
+            self.mouse_wheel_delta.0
+        } else {
+            self.mouse_wheel_delta.1
+        }
+    }
+
     /// Returns an iterator over all pressed scan codes
     pub fn scan_codes_that_are_down(&self) -> impl Iterator<Item = u32> + '_ {
         self.pressed_keys.iter().map(|k| k.1)
@@ -1206,6 +1224,21 @@ mod tests {
         assert!(!handler.button_is_down(Button::Mouse(MouseButton::Left)));
     }
 
+    #[test]
+    fn mouse_wheel_delta_adds_up_the_events_of_a_frame() {
+        use approx::assert_ulps_eq;
+        let mut handler = InputHandler::new();
+        let mut events = EventChannel::<InputEvent>::new();
+        handler.send_event(&mouse_wheel(0.0, 3.0), &mut events, HIDPI);
+        handler.send_event(&mouse_wheel(-1.0, 2.0), &mut events, HIDPI);
+        assert_ulps_eq!(handler.mouse_wheel_delta(false), 5.0);
+        assert_ulps_eq!(handler.mouse_wheel_delta(true), -1.0);
+        assert_ulps_eq!(handler.mouse_wheel_value(false), 1.0);
+        handler.send_frame_begin();
+        assert_ulps_eq!(handler.mouse_wheel_delta(false), 0.0);
+        assert_ulps_eq!(handler.mouse_wheel_delta(true), 0.0);
+    }
+
     #[test]
     fn basic_mouse_wheel_check() {
         use approx::assert_ulps_eq;
@@ -1,4 +1,3 @@
-
 #version 450
 
 layout(set = 1, binding = 0) uniform sampler2D tex;
@@ -6,14 +5,81 @@ layout(set = 1, binding = 0) uniform sampler2D tex;
 layout(location = 0) in vec2 in_tex_coords;
 layout(location = 1) in vec4 in_color;
 layout(location = 2) in vec4 in_color_bias;
+layout(location = 3) in vec2 in_local;
+layout(location = 4) flat in vec2 in_dimensions;
+layout(location = 5) flat in vec4 in_shape;
+layout(location = 6) flat in vec4 in_radii;
+layout(location = 7) flat in vec4 in_gradient;
+// The sRGB colors packed by `pack_color`, see `UiArgs::shape_colors`.
+layout(location = 8) flat in uvec4 in_shape_colors;
 
 layout(location = 0) out vec4 out_color;
 
+// Signed distance from p to a box of half_size centered on the origin, with the radii of the
+// top left, top right, bottom right and bottom left corners. p is y up.
+float rounded_box(vec2 p, vec2 half_size, vec4 radii) {
+    float r = p.x > 0.0 ? (p.y > 0.0 ? radii.y : radii.z) : (p.y > 0.0 ? radii.x : radii.w);
+    r = min(r, min(half_size.x, half_size.y));
+    vec2 q = abs(p) - half_size + vec2(r);
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - r;
+}
+
+vec3 srgb_to_linear(vec3 c) {
+    return mix(c / 12.92, pow((c + vec3(0.055)) / 1.055, vec3(2.4)), step(vec3(0.04045), c));
+}
+
+vec4 linear_color(uint packed) {
+    vec4 color = unpackUnorm4x8(packed);
+    return vec4(srgb_to_linear(color.rgb), color.a);
+}
+
+vec4 shape_color() {
+    // Shadows are drawn in a quad grown by the blur on every side.
+    float blur = in_shape.z;
+    float dist = rounded_box(in_local, in_dimensions * 0.5 - vec2(blur), in_radii);
+    float edge = max(blur * 2.0, 1.0);
+    float coverage = 1.0 - smoothstep(-edge * 0.5, edge * 0.5, dist);
+
+    // From (0, 0) at the top left corner to (1, 1) at the bottom right one.
+    vec2 uv = vec2(in_local.x, -in_local.y) / in_dimensions + vec2(0.5);
+    float t = 0.0;
+    if (in_shape.w == 1.0) {
+        vec2 dir = in_gradient.zw - in_gradient.xy;
+        t = dot(uv - in_gradient.xy, dir) / max(dot(dir, dir), 0.0001);
+    } else if (in_shape.w == 2.0) {
+        t = length(uv - in_gradient.xy) / max(in_gradient.z, 0.0001);
+    }
+    vec4 color = mix(in_color, linear_color(in_shape_colors.x), clamp(t, 0.0, 1.0));
+
+    if (in_shape.y > 0.0) {
+        float border = smoothstep(-0.5, 0.5, dist + in_shape.y);
+        color = mix(color, linear_color(in_shape_colors.y), border);
+    }
+    return vec4(color.rgb, color.a * coverage);
+}
+
+// Bilinear gradient between the sRGB colors of the top left, top right, bottom left and bottom
+// right corners, interpolated in sRGB and converted back to linear.
+vec4 corner_gradient() {
+    vec2 uv = in_tex_coords;
+    vec4 top = mix(unpackUnorm4x8(in_shape_colors.x), unpackUnorm4x8(in_shape_colors.y), uv.x);
+    vec4 bottom = mix(unpackUnorm4x8(in_shape_colors.z), unpackUnorm4x8(in_shape_colors.w), uv.x);
+    vec4 color = mix(top, bottom, uv.y);
+    return vec4(srgb_to_linear(color.rgb), color.a);
+}
+
 void main() {
-    vec4 color = (texture(tex, in_tex_coords) + in_color_bias) * in_color;
+    vec4 color;
+    if (in_shape.x == 0.0) {
+        color = (texture(tex, in_tex_coords) + in_color_bias) * in_color;
+    } else if (in_shape.x == 2.0) {
+        color = corner_gradient();
+    } else {
+        color = shape_color();
+    }
     if (color.a == 0.0) {
         discard;
     }
 
     out_color = color;
-} 
\ No newline at end of file
+}
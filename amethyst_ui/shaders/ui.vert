@@ -9,18 +9,35 @@ layout(location = 1) in vec2 dimensions;
 layout(location = 2) in vec4 tex_coord_bounds;
 layout(location = 3) in vec4 color;
 layout(location = 4) in vec4 color_bias;
+layout(location = 5) in vec4 transform;
+// Packed as half floats, see `UiArgs`.
+layout(location = 6) in uvec2 shape;
+layout(location = 7) in uvec2 radii;
+layout(location = 8) in uvec2 gradient;
+layout(location = 9) in uvec4 shape_colors;
 
 layout(location = 0) out vec2 out_tex_coords;
 layout(location = 1) out vec4 out_color;
 layout(location = 2) out vec4 out_color_bias;
+layout(location = 3) out vec2 out_local;
+layout(location = 4) flat out vec2 out_dimensions;
+layout(location = 5) flat out vec4 out_shape;
+layout(location = 6) flat out vec4 out_radii;
+layout(location = 7) flat out vec4 out_gradient;
+layout(location = 8) flat out uvec4 out_shape_colors;
 
+// With y down, like the texture coordinates.
 const vec2 positions[4] = vec2[](
-    vec2(0.5, -0.5), // Right bottom
-    vec2(-0.5, -0.5), // Left bottom
-    vec2(0.5, 0.5), // Right top
-    vec2(-0.5, 0.5) // Left top
+    vec2(0.5, -0.5), // Right top
+    vec2(-0.5, -0.5), // Left top
+    vec2(0.5, 0.5), // Right bottom
+    vec2(-0.5, 0.5) // Left bottom
 );
 
+vec4 unpack_halves(uvec2 packed) {
+    return vec4(unpackHalf2x16(packed.x), unpackHalf2x16(packed.y));
+}
+
 void main() {
     vec2 pos = positions[gl_VertexIndex];
 
@@ -28,10 +45,20 @@ void main() {
     out_tex_coords = mix(tex_coord_bounds.xy, tex_coord_bounds.zw, coords_base);
     out_color = color;
     out_color_bias = color_bias;
+    // Shapes are computed in the unrotated quad, from its center with y up.
+    out_local = dimensions * vec2(pos.x, -pos.y);
+    out_dimensions = dimensions;
+    out_shape = unpack_halves(shape);
+    out_radii = unpack_halves(radii);
+    out_gradient = unpack_halves(gradient);
+    out_shape_colors = shape_colors;
 
     vec2 center = coords * inverse_window_size;
     center.y = 1.0 - center.y; 
-    vec2 final_pos = (center + dimensions * inverse_window_size * pos) * 2.0 - vec2(1.0);
+    // Rotate and scale the corner in ui space (y up), then flip it like the center.
+    vec2 offset = mat2(transform.xy, transform.zw) * (dimensions * vec2(pos.x, -pos.y));
+    offset.y = -offset.y;
+    vec2 final_pos = (center + offset * inverse_window_size) * 2.0 - vec2(1.0);
 
     gl_Position = vec4(final_pos, 0.0, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,613 @@
+use std::{collections::HashMap, fmt};
+
+use log::warn;
+
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entity, Join, ReaderId, Resource, RunNow, World, WorldExt,
+    },
+    shrev::EventChannel,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{Selected, UiEvent, UiEventType, UiSpinBox, UiText};
+
+type Getter = Box<dyn Fn(&World, Option<Entity>) -> Option<UiBindingValue> + Send + Sync>;
+type Setter = Box<dyn Fn(&World, Option<Entity>, UiBindingValue) + Send + Sync>;
+type BindingFormatter = Box<dyn Fn(&UiBindingValue) -> String + Send + Sync>;
+type WidgetGetter = Box<dyn Fn(&World, Entity) -> Option<UiBindingValue> + Send + Sync>;
+type WidgetSetter = Box<dyn Fn(&World, Entity, &UiBindingValue) -> bool + Send + Sync>;
+
+/// A value read from, or written to, a binding source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiBindingValue {
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    Text(String),
+}
+
+impl UiBindingValue {
+    /// Parses `text` into a value of the same kind as `self`.
+    pub fn parse_like(&self, text: &str) -> Option<UiBindingValue> {
+        let text = text.trim();
+        match self {
+            UiBindingValue::Bool(_) => text.parse().ok().map(UiBindingValue::Bool),
+            UiBindingValue::Int(_) => text.parse().ok().map(UiBindingValue::Int),
+            UiBindingValue::Float(_) => text.parse().ok().map(UiBindingValue::Float),
+            UiBindingValue::Text(_) => Some(UiBindingValue::Text(text.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for UiBindingValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UiBindingValue::Bool(value) => value.fmt(f),
+            UiBindingValue::Int(value) => value.fmt(f),
+            UiBindingValue::Float(value) => value.fmt(f),
+            UiBindingValue::Text(value) => value.fmt(f),
+        }
+    }
+}
+
+/// Resource holding the named sources `UiBinding`s can refer to, and the widgets other than
+/// `UiText`s they can be shown in.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// world.fetch_mut::<UiBindingRegistry>()
+///     .register_resource("gold", |inventory: &Inventory| UiBindingValue::Int(inventory.gold))
+///     .register_component("health", |health: &Health| UiBindingValue::Float(health.0 as f64));
+/// ```
+pub struct UiBindingRegistry {
+    sources: HashMap<String, (Getter, Option<Setter>)>,
+    widgets: Vec<(WidgetGetter, WidgetSetter)>,
+}
+
+impl Default for UiBindingRegistry {
+    fn default() -> Self {
+        let mut registry = UiBindingRegistry {
+            sources: HashMap::new(),
+            widgets: Vec::new(),
+        };
+        registry.register_widget(
+            |spin_box: &UiSpinBox| Some(UiBindingValue::Float(spin_box.value())),
+            |spin_box: &mut UiSpinBox, value| match *value {
+                UiBindingValue::Int(value) => spin_box.set_value(value as f64),
+                UiBindingValue::Float(value) => spin_box.set_value(value),
+                _ => {}
+            },
+        );
+        registry
+    }
+}
+
+impl fmt::Debug for UiBindingRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UiBindingRegistry")
+            .field("sources", &self.sources.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl UiBindingRegistry {
+    /// Registers a source reading a field of the resource `R`.
+    pub fn register_resource<R, G>(&mut self, key: &str, get: G) -> &mut Self
+    where
+        R: Resource,
+        G: Fn(&R) -> UiBindingValue + Send + Sync + 'static,
+    {
+        self.sources
+            .insert(key.to_string(), (resource_getter(get), None));
+        self
+    }
+
+    /// Registers a source reading and writing a field of the resource `R`.
+    pub fn register_resource_two_way<R, G, S>(&mut self, key: &str, get: G, set: S) -> &mut Self
+    where
+        R: Resource,
+        G: Fn(&R) -> UiBindingValue + Send + Sync + 'static,
+        S: Fn(&mut R, UiBindingValue) + Send + Sync + 'static,
+    {
+        let setter: Setter = Box::new(move |world, _, value| {
+            if let Some(mut resource) = world.try_fetch_mut::<R>() {
+                set(&mut resource, value);
+            }
+        });
+        self.sources
+            .insert(key.to_string(), (resource_getter(get), Some(setter)));
+        self
+    }
+
+    /// Registers a source reading a field of the component `C` on the entity given by the binding.
+    pub fn register_component<C, G>(&mut self, key: &str, get: G) -> &mut Self
+    where
+        C: Component,
+        G: Fn(&C) -> UiBindingValue + Send + Sync + 'static,
+    {
+        self.sources
+            .insert(key.to_string(), (component_getter(get), None));
+        self
+    }
+
+    /// Registers a source reading and writing a field of the component `C` on the entity given by
+    /// the binding.
+    pub fn register_component_two_way<C, G, S>(&mut self, key: &str, get: G, set: S) -> &mut Self
+    where
+        C: Component,
+        G: Fn(&C) -> UiBindingValue + Send + Sync + 'static,
+        S: Fn(&mut C, UiBindingValue) + Send + Sync + 'static,
+    {
+        let setter: Setter = Box::new(move |world, entity, value| {
+            if let Some(entity) = entity {
+                if let Some(component) = world.write_storage::<C>().get_mut(entity) {
+                    set(component, value);
+                }
+            }
+        });
+        self.sources
+            .insert(key.to_string(), (component_getter(get), Some(setter)));
+        self
+    }
+
+    /// Registers a widget component, like a slider or a toggle, that bindings show their value in
+    /// instead of a `UiText`. `set` shows a value of the source in the widget, and `get` reads the
+    /// value of the widget, which two-way bindings write back to their source when the widget
+    /// sends a `ValueCommit` or a `NumberChanged` event.
+    ///
+    /// `UiSpinBox`es are registered by default. The component has to be registered in the `World`.
+    pub fn register_widget<C, G, S>(&mut self, get: G, set: S) -> &mut Self
+    where
+        C: Component,
+        G: Fn(&C) -> Option<UiBindingValue> + Send + Sync + 'static,
+        S: Fn(&mut C, &UiBindingValue) + Send + Sync + 'static,
+    {
+        let getter: WidgetGetter = Box::new(move |world, entity| {
+            world.read_storage::<C>().get(entity).and_then(|c| get(c))
+        });
+        let setter: WidgetSetter = Box::new(move |world, entity, value| {
+            match world.write_storage::<C>().get_mut(entity) {
+                Some(component) => {
+                    set(component, value);
+                    true
+                }
+                None => false,
+            }
+        });
+        self.widgets.push((getter, setter));
+        self
+    }
+
+    fn get(&self, world: &World, key: &str, entity: Option<Entity>) -> Option<UiBindingValue> {
+        self.sources
+            .get(key)
+            .and_then(|(get, _)| get(world, entity))
+    }
+
+    fn set(&self, world: &World, key: &str, entity: Option<Entity>, value: UiBindingValue) {
+        match self.sources.get(key) {
+            Some((_, Some(set))) => set(world, entity, value),
+            Some((_, None)) => warn!("Ui binding source '{}' is read only", key),
+            None => warn!("Unknown ui binding source '{}'", key),
+        }
+    }
+
+    /// The value of the registered widget of `entity`, if it has one.
+    fn widget_value(&self, world: &World, entity: Entity) -> Option<UiBindingValue> {
+        self.widgets.iter().find_map(|(get, _)| get(world, entity))
+    }
+
+    /// Shows `value` in the registered widget of `entity`, returning false if it has none.
+    fn show_in_widget(&self, world: &World, entity: Entity, value: &UiBindingValue) -> bool {
+        self.widgets
+            .iter()
+            .any(|(_, set)| set(world, entity, value))
+    }
+}
+
+fn resource_getter<R, G>(get: G) -> Getter
+where
+    R: Resource,
+    G: Fn(&R) -> UiBindingValue + Send + Sync + 'static,
+{
+    Box::new(move |world, _| world.try_fetch::<R>().map(|resource| get(&resource)))
+}
+
+fn component_getter<C, G>(get: G) -> Getter
+where
+    C: Component,
+    G: Fn(&C) -> UiBindingValue + Send + Sync + 'static,
+{
+    Box::new(move |world, entity| {
+        entity.and_then(|entity| world.read_storage::<C>().get(entity).map(|c| get(c)))
+    })
+}
+
+/// Binds the `UiText`, or the widget registered in the `UiBindingRegistry`, of this entity to a
+/// source registered in the `UiBindingRegistry`.
+///
+/// The text is only rewritten when the source value changes.
+/// Two-way bindings also write the text back to the source on `ValueCommit`, for example
+/// when the user presses enter in an editable text field. While the text is edited, it shows the
+/// value without the formatter, and it is restored if the committed text can't be parsed.
+pub struct UiBinding {
+    /// The key of the source in the `UiBindingRegistry`.
+    pub key: String,
+    /// The entity holding the component, for component sources.
+    pub entity: Option<Entity>,
+    /// Converts the value to the displayed text. Uses `Display` if `None`.
+    pub formatter: Option<BindingFormatter>,
+    /// Writes the committed text back to the source.
+    pub two_way: bool,
+    last_value: Option<UiBindingValue>,
+    /// Whether the text was `Selected` at the end of the last update.
+    editing: bool,
+    /// Whether the text has to be written again.
+    dirty: bool,
+}
+
+impl fmt::Debug for UiBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UiBinding")
+            .field("key", &self.key)
+            .field("entity", &self.entity)
+            .field("two_way", &self.two_way)
+            .field("last_value", &self.last_value)
+            .field("editing", &self.editing)
+            .finish()
+    }
+}
+
+impl UiBinding {
+    /// Binds to a resource source.
+    pub fn resource<S: ToString>(key: S) -> Self {
+        UiBinding {
+            key: key.to_string(),
+            entity: None,
+            formatter: None,
+            two_way: false,
+            last_value: None,
+            editing: false,
+            dirty: false,
+        }
+    }
+
+    /// Binds to a component source on `entity`.
+    pub fn component<S: ToString>(key: S, entity: Entity) -> Self {
+        UiBinding {
+            entity: Some(entity),
+            ..UiBinding::resource(key)
+        }
+    }
+
+    /// Set the formatter.
+    pub fn with_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(&UiBindingValue) -> String + Send + Sync + 'static,
+    {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Write the committed text back to the source.
+    pub fn two_way(mut self) -> Self {
+        self.two_way = true;
+        self
+    }
+
+    /// The text showing `value`, without the formatter while it is edited.
+    fn display(&self, value: &UiBindingValue) -> String {
+        match self.formatter {
+            Some(ref formatter) if !self.editing => formatter(value),
+            _ => value.to_string(),
+        }
+    }
+}
+
+impl Component for UiBinding {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Thread local system updating the bound `UiText`s and writing back two-way bindings.
+///
+/// It needs access to the whole `World` to read the registered sources, so it is added with
+/// `add_thread_local`.
+#[derive(Debug, Default)]
+pub struct UiBindingSystem {
+    ui_reader_id: Option<ReaderId<UiEvent>>,
+}
+
+impl UiBindingSystem {
+    /// Creates a new `UiBindingSystem`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a> RunNow<'a> for UiBindingSystem {
+    fn run_now(&mut self, world: &'a World) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_binding_system");
+
+        let registry = world.fetch::<UiBindingRegistry>();
+
+        // Write back the committed values first, so that they are displayed formatted.
+        let committed = {
+            let reader_id = self
+                .ui_reader_id
+                .as_mut()
+                .expect("`UiBindingSystem::setup` was not called before being run.");
+            let bindings = world.read_storage::<UiBinding>();
+            let texts = world.read_storage::<UiText>();
+            world
+                .fetch::<EventChannel<UiEvent>>()
+                .read(reader_id)
+                .filter(|event| match event.event_type {
+                    UiEventType::ValueCommit | UiEventType::NumberChanged { .. } => true,
+                    _ => false,
+                })
+                .filter_map(|event| {
+                    let binding = bindings.get(event.target).filter(|b| b.two_way)?;
+                    let value = registry.widget_value(world, event.target).or_else(|| {
+                        texts
+                            .get(event.target)
+                            .map(|text| UiBindingValue::Text(text.text.clone()))
+                    })?;
+                    let value = match binding.last_value {
+                        Some(ref last) => last.parse_like(&value.to_string()),
+                        None => Some(value),
+                    };
+                    Some((event.target, binding.key.clone(), binding.entity, value))
+                })
+                .collect::<Vec<_>>()
+        };
+        for (target, key, entity, value) in committed {
+            match value {
+                Some(value) => registry.set(world, &key, entity, value),
+                None => warn!("Invalid value committed to the ui binding source '{}'", key),
+            }
+            // The text is restored if it can't be parsed, or shows how the value was parsed.
+            if let Some(binding) = world.write_storage::<UiBinding>().get_mut(target) {
+                binding.dirty = true;
+            }
+        }
+
+        let changed = {
+            let mut bindings = world.write_storage::<UiBinding>();
+            let selecteds = world.read_storage::<Selected>();
+            let entities = world.entities();
+            (&*entities, &mut bindings)
+                .join()
+                .filter_map(|(entity, binding)| {
+                    let value = registry.get(world, &binding.key, binding.entity)?;
+                    let editing = binding.two_way && selecteds.contains(entity);
+                    let started_or_stopped = editing != binding.editing;
+                    binding.editing = editing;
+                    // Don't overwrite what the user is typing.
+                    if editing && !started_or_stopped && !binding.dirty {
+                        return None;
+                    }
+                    if !started_or_stopped
+                        && !binding.dirty
+                        && binding.last_value.as_ref() == Some(&value)
+                    {
+                        return None;
+                    }
+                    binding.dirty = false;
+                    let text = binding.display(&value);
+                    binding.last_value = Some(value.clone());
+                    Some((entity, text, value))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (entity, text, value) in changed {
+            if !registry.show_in_widget(world, entity, &value) {
+                if let Some(ui_text) = world.write_storage::<UiText>().get_mut(entity) {
+                    ui_text.text = text;
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        world.register::<UiBinding>();
+        world.register::<UiText>();
+        world.register::<UiSpinBox>();
+        world.register::<Selected>();
+        world
+            .entry::<UiBindingRegistry>()
+            .or_insert_with(Default::default);
+        self.ui_reader_id = Some(
+            world
+                .entry::<EventChannel<UiEvent>>()
+                .or_insert_with(Default::default)
+                .register_reader(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{font::default::test_font, Anchor, LineMode};
+
+    #[derive(Default)]
+    struct Volume(i64);
+
+    struct Slider(i64);
+
+    impl Component for Slider {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    fn setup() -> (World, UiBindingSystem, Entity) {
+        let mut world = World::new();
+        let mut system = UiBindingSystem::new();
+        system.setup(&mut world);
+        world.insert(Volume(50));
+        world
+            .fetch_mut::<UiBindingRegistry>()
+            .register_resource_two_way(
+                "volume",
+                |volume: &Volume| UiBindingValue::Int(volume.0),
+                |volume: &mut Volume, value| {
+                    if let UiBindingValue::Int(value) = value {
+                        volume.0 = value;
+                    }
+                },
+            );
+        let text = UiText::new(
+            test_font(),
+            String::new(),
+            [1.; 4],
+            10.,
+            LineMode::Single,
+            Anchor::Middle,
+        );
+        let entity = world
+            .create_entity()
+            .with(text)
+            .with(
+                UiBinding::resource("volume")
+                    .with_formatter(|value| format!("{} %", value))
+                    .two_way(),
+            )
+            .build();
+        system.run_now(&world);
+        (world, system, entity)
+    }
+
+    fn text(world: &World, entity: Entity) -> String {
+        world
+            .read_storage::<UiText>()
+            .get(entity)
+            .unwrap()
+            .text
+            .clone()
+    }
+
+    fn commit(world: &mut World, system: &mut UiBindingSystem, entity: Entity, typed: &str) {
+        world
+            .write_storage::<UiText>()
+            .get_mut(entity)
+            .unwrap()
+            .text = typed.to_string();
+        world
+            .write_resource::<EventChannel<UiEvent>>()
+            .single_write(UiEvent::new(UiEventType::ValueCommit, entity));
+        system.run_now(world);
+    }
+
+    #[test]
+    fn formats_the_value_unless_edited() {
+        let (mut world, mut system, entity) = setup();
+        assert_eq!(text(&world, entity), "50 %");
+
+        world
+            .write_storage::<Selected>()
+            .insert(entity, Selected)
+            .unwrap();
+        system.run_now(&world);
+        assert_eq!(text(&world, entity), "50");
+
+        world.write_storage::<Selected>().remove(entity);
+        system.run_now(&world);
+        assert_eq!(text(&world, entity), "50 %");
+    }
+
+    #[test]
+    fn commits_the_edited_value() {
+        let (mut world, mut system, entity) = setup();
+        world
+            .write_storage::<Selected>()
+            .insert(entity, Selected)
+            .unwrap();
+        system.run_now(&world);
+
+        commit(&mut world, &mut system, entity, "75");
+        assert_eq!(world.read_resource::<Volume>().0, 75);
+        assert_eq!(text(&world, entity), "75");
+
+        world.write_storage::<Selected>().remove(entity);
+        system.run_now(&world);
+        assert_eq!(text(&world, entity), "75 %");
+    }
+
+    #[test]
+    fn restores_the_text_of_invalid_values() {
+        let (mut world, mut system, entity) = setup();
+        commit(&mut world, &mut system, entity, "loud");
+        assert_eq!(world.read_resource::<Volume>().0, 50);
+        assert_eq!(text(&world, entity), "50 %");
+    }
+
+    #[test]
+    fn shows_and_commits_registered_widget_values() {
+        let (mut world, mut system, _) = setup();
+        world.register::<Slider>();
+        world.fetch_mut::<UiBindingRegistry>().register_widget(
+            |slider: &Slider| Some(UiBindingValue::Int(slider.0)),
+            |slider: &mut Slider, value| {
+                if let UiBindingValue::Int(value) = *value {
+                    slider.0 = value;
+                }
+            },
+        );
+        let slider = world
+            .create_entity()
+            .with(Slider(0))
+            .with(UiBinding::resource("volume").two_way())
+            .build();
+        system.run_now(&world);
+        assert_eq!(world.read_storage::<Slider>().get(slider).unwrap().0, 50);
+
+        world.write_storage::<Slider>().get_mut(slider).unwrap().0 = 20;
+        world
+            .write_resource::<EventChannel<UiEvent>>()
+            .single_write(UiEvent::new(UiEventType::ValueCommit, slider));
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<Volume>().0, 20);
+    }
+
+    #[test]
+    fn shows_and_commits_spin_box_values() {
+        let (mut world, mut system, _) = setup();
+        let spin_box = world
+            .create_entity()
+            .with(UiSpinBox::new(0., 0., 100., 1.))
+            .with(UiBinding::resource("volume").two_way())
+            .build();
+        system.run_now(&world);
+        assert_eq!(
+            world
+                .read_storage::<UiSpinBox>()
+                .get(spin_box)
+                .unwrap()
+                .value(),
+            50.
+        );
+
+        world
+            .write_storage::<UiSpinBox>()
+            .get_mut(spin_box)
+            .unwrap()
+            .set_value(20.);
+        world
+            .write_resource::<EventChannel<UiEvent>>()
+            .single_write(UiEvent::new(
+                UiEventType::NumberChanged { value: 20. },
+                spin_box,
+            ));
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<Volume>().0, 20);
+    }
+}
@@ -2,10 +2,14 @@
 
 use crate::{
     BlinkSystem, CacheSelectionOrderSystem, DragWidgetSystemDesc, FontAsset, NoCustomUi,
-    ResizeSystemDesc, SelectionKeyboardSystemDesc, SelectionMouseSystemDesc,
-    TextEditingInputSystemDesc, TextEditingMouseSystemDesc, ToNativeWidget,
-    UiButtonActionRetriggerSystemDesc, UiButtonSystemDesc, UiLoaderSystemDesc, UiMouseSystem,
-    UiSoundRetriggerSystemDesc, UiSoundSystemDesc, UiTransformSystemDesc, WidgetId,
+    ResizeSystemDesc, SelectionKeyboardSystemDesc, SelectionMouseSystemDesc, SvgAsset,
+    TextEditingInputSystemDesc, TextEditingMouseSystemDesc, ToNativeWidget, UiBindingSystem,
+    UiButtonActionRetriggerSystemDesc, UiButtonSystemDesc, UiColorPickerSystemDesc,
+    UiEventHandlerSystem, UiListViewSystemDesc, UiLoaderSystemDesc, UiMenuSystemDesc,
+    UiModalSystem, UiMouseSystem, UiPrefabReloadSystem, UiSoundRetriggerSystemDesc,
+    UiSoundSystemDesc, UiSpinBoxSystemDesc, UiTabsSystemDesc, UiTheme, UiThemeSystemDesc,
+    UiTooltipSystemDesc, UiTransformSystemDesc, UiTreeViewSystemDesc, UiWindowSystemDesc,
+    UiWorldSystem, WidgetId,
 };
 use amethyst_assets::Processor;
 use amethyst_core::{
@@ -47,21 +51,47 @@ where
             "ui_loader",
             &[],
         );
+        builder.add(
+            UiPrefabReloadSystem::<<C as ToNativeWidget>::PrefabData, W>::default(),
+            "ui_prefab_reload",
+            &["ui_loader"],
+        );
+        builder.add(
+            UiWorldSystem::new(),
+            "ui_world_system",
+            &["transform_system"],
+        );
         builder.add(
             UiTransformSystemDesc::default().build(world),
             "ui_transform",
-            &["transform_system"],
+            &["transform_system", "ui_world_system"],
         );
+        builder.add(UiModalSystem::new(), "ui_modal_system", &["ui_transform"]);
         builder.add(
             UiMouseSystem::<T>::new(),
             "ui_mouse_system",
-            &["input_system", "ui_transform"],
+            &["input_system", "ui_transform", "ui_modal_system"],
         );
         builder.add(
             Processor::<FontAsset>::new(),
             "font_processor",
             &["ui_loader"],
         );
+        builder.add(
+            Processor::<SvgAsset>::new(),
+            "svg_processor",
+            &["ui_loader"],
+        );
+        builder.add(
+            Processor::<UiTheme>::new(),
+            "ui_theme_processor",
+            &["ui_loader"],
+        );
+        builder.add(
+            UiThemeSystemDesc::default().build(world),
+            "ui_theme_system",
+            &["ui_loader", "ui_theme_processor"],
+        );
         builder.add(
             CacheSelectionOrderSystem::<G>::new(),
             "selection_order_cache",
@@ -89,6 +119,34 @@ where
             // Hard requirement. The system assumes the text to edit is selected.
             &["ui_mouse_selection", "ui_keyboard_selection"],
         );
+        builder.add(
+            UiListViewSystemDesc::<G>::default().build(world),
+            "ui_list_view_system",
+            &["ui_mouse_selection", "ui_keyboard_selection"],
+        );
+        builder.add(
+            UiTreeViewSystemDesc::<G>::default().build(world),
+            "ui_tree_view_system",
+            &["ui_mouse_selection", "ui_keyboard_selection"],
+        );
+        builder.add(
+            UiSpinBoxSystemDesc::<G>::default().build(world),
+            "ui_spin_box_system",
+            &[
+                "ui_mouse_selection",
+                "ui_keyboard_selection",
+                "ui_text_editing_input_system",
+            ],
+        );
+        builder.add(
+            UiColorPickerSystemDesc::<T, G>::default().build(world),
+            "ui_color_picker_system",
+            &[
+                "ui_mouse_selection",
+                "ui_keyboard_selection",
+                "ui_text_editing_input_system",
+            ],
+        );
         builder.add(
             ResizeSystemDesc::default().build(world),
             "ui_resize_system",
@@ -104,6 +162,11 @@ where
             "ui_drag_system",
             &["ui_mouse_system"],
         );
+        builder.add(
+            UiWindowSystemDesc::<T>::default().build(world),
+            "ui_window_system",
+            &["ui_mouse_system", "ui_drag_system"],
+        );
 
         builder.add(
             UiButtonActionRetriggerSystemDesc::default().build(world),
@@ -121,9 +184,30 @@ where
             &["ui_sound_system"],
         );
 
+        builder.add(
+            UiTooltipSystemDesc::<T>::default().build(world),
+            "ui_tooltip_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            UiMenuSystemDesc::<T, W>::default().build(world),
+            "ui_menu_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            UiTabsSystemDesc::<T>::default().build(world),
+            "ui_tabs_system",
+            &["ui_mouse_system"],
+        );
+
         // Required for text editing. You want the cursor image to blink.
         builder.add(BlinkSystem, "blink_system", &[]);
 
+        // Needs the whole `World` to read the binding sources.
+        builder.add_thread_local(UiBindingSystem::new());
+        // Needs the whole `World` to call the event handlers.
+        builder.add_thread_local(UiEventHandlerSystem::new());
+
         Ok(())
     }
 }
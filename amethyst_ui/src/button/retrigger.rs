@@ -1,4 +1,5 @@
 use amethyst_core::ecs::prelude::{Component, DenseVecStorage};
+use winit::MouseButton;
 
 use crate::{
     event::{UiEvent, UiEventType},
@@ -45,8 +46,8 @@ impl EventRetrigger for UiButtonActionRetrigger {
         R: EventReceiver<Self::Out>,
     {
         match event.event_type {
-            UiEventType::ClickStart => out.receive(&self.on_click_start),
-            UiEventType::ClickStop => out.receive(&self.on_click_stop),
+            UiEventType::ClickStart(MouseButton::Left) => out.receive(&self.on_click_start),
+            UiEventType::ClickStop(MouseButton::Left) => out.receive(&self.on_click_stop),
             UiEventType::HoverStart => out.receive(&self.on_hover_start),
             UiEventType::HoverStop => out.receive(&self.on_hover_stop),
             _ => {}
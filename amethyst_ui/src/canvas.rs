@@ -0,0 +1,71 @@
+//! Module for the UiCanvas component.
+
+use amethyst_core::{
+    ecs::prelude::{Component, DenseVecStorage, Entity, ReadStorage},
+    ParentHierarchy,
+};
+use amethyst_rendy::bundle::Target;
+
+/// Sends a ui element and all its descendants to another render target than the main one.
+///
+/// The element is laid out as if it were the root of a screen of `width` by `height` pixels,
+/// which should match the size of the target. It is only drawn by the `RenderUi` plugin created
+/// with the same target, for example:
+///
+/// ```rust,ignore
+/// RenderingBundle::<DefaultBackend>::new()
+///     .with_plugin(RenderUi::default().with_target(Target::Custom("minimap")))
+///     .with_plugin(RenderUi::default().with_image(TargetImage::Color(Target::Custom("minimap"), 0)))
+/// ```
+///
+/// The second plugin can then show the canvas in the main ui with a `UiImage::RenderTarget`.
+/// Mouse events are forwarded to the canvas through the image, so the canvas stays interactive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiCanvas {
+    /// The render target this canvas is drawn into.
+    pub target: Target,
+    /// The width of the canvas in pixels.
+    pub width: f32,
+    /// The height of the canvas in pixels.
+    pub height: f32,
+}
+
+impl UiCanvas {
+    /// Creates a new `UiCanvas` drawn into `target`.
+    pub fn new(target: Target, width: f32, height: f32) -> Self {
+        UiCanvas {
+            target,
+            width,
+            height,
+        }
+    }
+}
+
+impl Component for UiCanvas {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns the closest canvas containing `entity`, which can be the entity itself.
+pub(crate) fn canvas_of<'a>(
+    entity: Entity,
+    hierarchy: &ParentHierarchy,
+    canvases: &'a ReadStorage<'_, UiCanvas>,
+) -> Option<&'a UiCanvas> {
+    let mut current = Some(entity);
+    while let Some(e) = current {
+        if let Some(canvas) = canvases.get(e) {
+            return Some(canvas);
+        }
+        current = hierarchy.parent(e);
+    }
+    None
+}
+
+/// Returns the render target `entity` is drawn into.
+pub(crate) fn target_of(
+    entity: Entity,
+    hierarchy: &ParentHierarchy,
+    canvases: &ReadStorage<'_, UiCanvas>,
+) -> Target {
+    canvas_of(entity, hierarchy, canvases).map_or(Target::Main, |canvas| canvas.target)
+}
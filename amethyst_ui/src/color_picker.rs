@@ -0,0 +1,786 @@
+//! Color pickers.
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use winit::MouseButton;
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        ReaderId, System, SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_window::ScreenDimensions;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    font::default::get_default_font,
+    image::{linear_to_srgba, srgba_to_linear},
+    list::{set_layout, transform_id},
+    rows::view_of,
+    Anchor, FontAsset, FontHandle, Interactable, LineMode, Selectable, Selected, TextEditing,
+    UiEvent, UiEventType, UiImage, UiText, UiTransform,
+};
+
+const DEFAULT_FONT_SIZE: f32 = 16.0;
+const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const DEFAULT_BAR_SIZE: f32 = 16.0;
+const DEFAULT_ROW_HEIGHT: f32 = 24.0;
+const DEFAULT_SPACING: f32 = 4.0;
+const DEFAULT_SWATCHES: [[f32; 4]; 8] = [
+    [0.0, 0.0, 0.0, 1.0],
+    [1.0, 1.0, 1.0, 1.0],
+    [1.0, 0.0, 0.0, 1.0],
+    [1.0, 1.0, 0.0, 1.0],
+    [0.0, 1.0, 0.0, 1.0],
+    [0.0, 1.0, 1.0, 1.0],
+    [0.0, 0.0, 1.0, 1.0],
+    [1.0, 0.0, 1.0, 1.0],
+];
+const MARKER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const MARKER_SIZE: f32 = 6.0;
+/// The longest text which can be typed in the hex field, `#RRGGBBAA`.
+const MAX_LENGTH: usize = 9;
+
+/// The areas of a color picker which can be dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PickerArea {
+    Square,
+    Hue,
+    Alpha,
+}
+
+/// The entities making up a color picker.
+#[derive(Debug, Clone)]
+struct ColorPickerParts {
+    square: Entity,
+    square_marker: Entity,
+    hue: Entity,
+    hue_segments: Vec<Entity>,
+    hue_marker: Entity,
+    alpha: Entity,
+    alpha_marker: Entity,
+    field: Entity,
+    preview: Entity,
+    swatches: Vec<Entity>,
+}
+
+impl ColorPickerParts {
+    fn area(&self, entity: Entity) -> Option<PickerArea> {
+        if entity == self.square {
+            Some(PickerArea::Square)
+        } else if entity == self.hue {
+            Some(PickerArea::Hue)
+        } else if entity == self.alpha {
+            Some(PickerArea::Alpha)
+        } else {
+            None
+        }
+    }
+}
+
+/// Component turning its entity into a color picker.
+///
+/// The picker fills the `UiTransform` of its entity with a saturation/value square next to a
+/// vertical hue strip, an alpha slider below them, then a hex text field next to a preview of
+/// the color, and a row of swatches. The square, the strip and the slider can be clicked and
+/// dragged, the field accepts `#RGB`, `#RRGGBB` and `#RRGGBBAA` when the edit is committed or the
+/// field stops being `Selected`, and clicking a swatch picks its color. Every change made by the
+/// user sends a `ColorChanged` event to the picker.
+///
+/// Colors are given and returned in linear RGBA, like `UiImage::SolidColor` and `UiText::color`.
+/// The hex text is in sRGB, like in most image editors.
+#[derive(Debug, Clone)]
+pub struct UiColorPicker {
+    /// The hue in degrees, kept when the color becomes grey.
+    hue: f32,
+    saturation: f32,
+    value: f32,
+    alpha: f32,
+    /// The linear RGBA colors of the swatches.
+    pub swatches: Vec<[f32; 4]>,
+    /// The font of the hex field. Defaults to the default font.
+    pub font: Option<FontHandle>,
+    /// The font size of the hex field.
+    pub font_size: f32,
+    /// The color of the text of the hex field.
+    pub text_color: [f32; 4],
+    /// The width of the hue strip and the height of the alpha slider.
+    pub bar_size: f32,
+    /// The height of the hex field and the size of the swatches.
+    pub row_height: f32,
+    /// The space between the parts of the picker.
+    pub spacing: f32,
+    /// The selection order of the hex field, see `Selectable`.
+    pub selection_order: u32,
+    parts: Option<ColorPickerParts>,
+    /// Whether the images and the text have to be updated.
+    dirty: bool,
+    /// Whether the hex field was `Selected` at the end of the last update.
+    editing: bool,
+}
+
+impl UiColorPicker {
+    /// Creates a color picker showing the linear RGBA `color`.
+    pub fn new(color: [f32; 4]) -> Self {
+        let mut picker = UiColorPicker {
+            hue: 0.0,
+            saturation: 0.0,
+            value: 0.0,
+            alpha: 1.0,
+            swatches: DEFAULT_SWATCHES.to_vec(),
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            text_color: DEFAULT_TXT_COLOR,
+            bar_size: DEFAULT_BAR_SIZE,
+            row_height: DEFAULT_ROW_HEIGHT,
+            spacing: DEFAULT_SPACING,
+            selection_order: 0,
+            parts: None,
+            dirty: true,
+            editing: false,
+        };
+        picker.set_color(color);
+        picker
+    }
+
+    /// Sets the linear RGBA colors of the swatches.
+    pub fn with_swatches(mut self, swatches: Vec<[f32; 4]>) -> Self {
+        self.swatches = swatches;
+        self
+    }
+
+    /// Sets the font of the hex field.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of the hex field.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Sets the color of the text of the hex field.
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    /// Sets the selection order of the hex field.
+    pub fn with_selection_order(mut self, selection_order: u32) -> Self {
+        self.selection_order = selection_order;
+        self
+    }
+
+    /// Returns the color, in linear RGBA.
+    pub fn color(&self) -> [f32; 4] {
+        srgba_to_linear(self.srgba())
+    }
+
+    /// Sets the color, in linear RGBA. No `ColorChanged` event is sent.
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.set_srgba(linear_to_srgba(color));
+    }
+
+    /// Returns the hue in degrees, the saturation and the value of the color, between 0 and 1.
+    pub fn hsv(&self) -> (f32, f32, f32) {
+        (self.hue, self.saturation, self.value)
+    }
+
+    /// Returns the color as `#RRGGBBAA`, in sRGB.
+    pub fn hex(&self) -> String {
+        let [r, g, b, a] = self.srgba();
+        let byte = |channel: f32| (channel.max(0.0).min(1.0) * 255.0).round() as u8;
+        format!(
+            "#{:02X}{:02X}{:02X}{:02X}",
+            byte(r),
+            byte(g),
+            byte(b),
+            byte(a)
+        )
+    }
+
+    /// Returns the color in sRGB.
+    fn srgba(&self) -> [f32; 4] {
+        let [r, g, b] = hsv_to_rgb(self.hue, self.saturation, self.value);
+        [r, g, b, self.alpha]
+    }
+
+    /// Sets the color in sRGB, keeping the hue and the saturation which the color does not define.
+    fn set_srgba(&mut self, [r, g, b, a]: [f32; 4]) {
+        let clamp = |channel: f32| channel.max(0.0).min(1.0);
+        let (hue, saturation, value) = rgb_to_hsv(clamp(r), clamp(g), clamp(b));
+        if value > 0.0 {
+            if saturation > 0.0 {
+                self.hue = hue;
+            }
+            self.saturation = saturation;
+        }
+        self.value = value;
+        self.alpha = clamp(a);
+        self.dirty = true;
+    }
+
+    /// Picks the color at `(u, v)` in `area`, measured from its top left corner.
+    fn pick(&mut self, area: PickerArea, (u, v): (f32, f32)) {
+        match area {
+            PickerArea::Square => {
+                self.saturation = u;
+                self.value = 1.0 - v;
+            }
+            PickerArea::Hue => self.hue = v * 360.0,
+            PickerArea::Alpha => self.alpha = u,
+        }
+        self.dirty = true;
+    }
+}
+
+impl Component for UiColorPicker {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Converts a hue in degrees, a saturation and a value to sRGB.
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> [f32; 3] {
+    let sector = (hue / 60.0).rem_euclid(6.0);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    [r + m, g + m, b + m]
+}
+
+/// Converts sRGB to a hue in degrees, a saturation and a value.
+fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let hue = if chroma <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+    (hue, saturation, max)
+}
+
+/// Parses `#RGB`, `#RRGGBB` or `#RRGGBBAA` to sRGB. The `#` is optional.
+fn parse_hex(text: &str) -> Option<[f32; 4]> {
+    let text = text.trim();
+    let digits = if text.starts_with('#') {
+        &text[1..]
+    } else {
+        text
+    };
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |range: std::ops::Range<usize>, repeat: bool| {
+        let value = u8::from_str_radix(&digits[range], 16).ok()?;
+        let value = if repeat { value * 17 } else { value };
+        Some(f32::from(value) / 255.0)
+    };
+    match digits.len() {
+        3 => Some([
+            channel(0..1, true)?,
+            channel(1..2, true)?,
+            channel(2..3, true)?,
+            1.0,
+        ]),
+        6 | 8 => Some([
+            channel(0..2, false)?,
+            channel(2..4, false)?,
+            channel(4..6, false)?,
+            if digits.len() == 8 {
+                channel(6..8, false)?
+            } else {
+                1.0
+            },
+        ]),
+        _ => None,
+    }
+}
+
+/// The linear RGBA color of the pure `hue`, in degrees.
+fn hue_color(hue: f32) -> [f32; 4] {
+    let [r, g, b] = hsv_to_rgb(hue, 1.0, 1.0);
+    srgba_to_linear([r, g, b, 1.0])
+}
+
+/// System creating the parts of the `UiColorPicker`s and handling their input.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiColorPickerSystemDesc))]
+pub struct UiColorPickerSystem<T, G>
+where
+    T: BindingTypes,
+    G: Send + Sync + 'static + PartialEq,
+{
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+    /// The picker and the area being dragged.
+    #[system_desc(skip)]
+    dragging: Option<(Entity, PickerArea)>,
+    phantom: PhantomData<(T, G)>,
+}
+
+impl<T, G> UiColorPickerSystem<T, G>
+where
+    T: BindingTypes,
+    G: Send + Sync + 'static + PartialEq,
+{
+    /// Creates a new `UiColorPickerSystem`.
+    pub fn new(ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            ui_reader_id,
+            dragging: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, G> System<'a> for UiColorPickerSystem<T, G>
+where
+    T: BindingTypes,
+    G: Send + Sync + 'static + PartialEq,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<FontAsset>>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, InputHandler<T>>,
+        Write<'a, EventChannel<UiEvent>>,
+        WriteStorage<'a, UiColorPicker>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, TextEditing>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, Interactable>,
+        WriteStorage<'a, Selectable<G>>,
+        ReadStorage<'a, Selected>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            font_assets,
+            hierarchy,
+            screen_dimensions,
+            input,
+            mut ui_events,
+            mut pickers,
+            mut ui_transforms,
+            mut texts,
+            mut editables,
+            mut images,
+            mut parents,
+            mut interactables,
+            mut selectables,
+            selecteds,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_color_picker_system");
+
+        // The input on the pickers, as swatches picked and hex commits.
+        let mut picked = HashMap::new();
+        let mut commits = Vec::new();
+        let events = ui_events
+            .read(&mut self.ui_reader_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        for event in events {
+            let picker_entity = match view_of(event.target, &hierarchy, |e| pickers.contains(e)) {
+                Some(picker_entity) => picker_entity,
+                None => continue,
+            };
+            let picker = pickers
+                .get(picker_entity)
+                .expect("unreachable: We just found this picker");
+            let parts = match picker.parts {
+                Some(ref parts) => parts,
+                None => continue,
+            };
+            match event.event_type {
+                UiEventType::ClickStart(MouseButton::Left) => {
+                    if let Some(area) = parts.area(event.target) {
+                        self.dragging = Some((picker_entity, area));
+                    }
+                }
+                UiEventType::Click(MouseButton::Left) => {
+                    if let Some(index) = parts.swatches.iter().position(|s| *s == event.target) {
+                        if let Some(swatch) = picker.swatches.get(index) {
+                            picked.insert(picker_entity, *swatch);
+                        }
+                    }
+                }
+                UiEventType::ValueCommit if event.target == parts.field => {
+                    commits.push(picker_entity);
+                }
+                _ => {}
+            }
+        }
+
+        // Where the dragged area is under the mouse, from its top left corner.
+        let dragged = self.dragging.and_then(|(picker_entity, area)| {
+            let parts = pickers.get(picker_entity)?.parts.as_ref()?;
+            let part = match area {
+                PickerArea::Square => parts.square,
+                PickerArea::Hue => parts.hue,
+                PickerArea::Alpha => parts.alpha,
+            };
+            let transform = ui_transforms.get(part)?;
+            let (x, y) = input.mouse_position()?;
+            let (x, y) = transform.to_layout_space(x, screen_dimensions.height() - y)?;
+            let left = transform.pixel_x() - transform.pixel_width() / 2.0;
+            let top = transform.pixel_y() + transform.pixel_height() / 2.0;
+            let unit = |offset: f32, size: f32| (offset / size.max(1.0)).max(0.0).min(1.0);
+            Some((
+                picker_entity,
+                area,
+                (
+                    unit(x - left, transform.pixel_width()),
+                    unit(top - y, transform.pixel_height()),
+                ),
+            ))
+        });
+        if !input.mouse_button_is_down(MouseButton::Left) {
+            self.dragging = None;
+        }
+
+        for (picker_entity, picker) in (&*entities, &mut pickers).join() {
+            let font = picker
+                .font
+                .get_or_insert_with(|| get_default_font(&loader, &font_assets))
+                .clone();
+
+            // Create the parts.
+            if picker.parts.is_none() {
+                let id = transform_id(picker_entity, &ui_transforms);
+                let mut create_part = |name: String, parent: Entity| {
+                    let part = entities.create();
+                    let _ = ui_transforms.insert(
+                        part,
+                        UiTransform::new(
+                            format!("{}_{}", id, name),
+                            Anchor::TopLeft,
+                            Anchor::TopLeft,
+                            0.,
+                            0.,
+                            1.,
+                            0.,
+                            0.,
+                        ),
+                    );
+                    let _ = parents.insert(part, Parent { entity: parent });
+                    part
+                };
+                let square = create_part("square".to_string(), picker_entity);
+                let hue = create_part("hue".to_string(), picker_entity);
+                let alpha = create_part("alpha".to_string(), picker_entity);
+                let parts = ColorPickerParts {
+                    square,
+                    square_marker: create_part("square_marker".to_string(), square),
+                    hue,
+                    hue_segments: (0..6)
+                        .map(|i| create_part(format!("hue_{}", i), hue))
+                        .collect(),
+                    hue_marker: create_part("hue_marker".to_string(), hue),
+                    alpha,
+                    alpha_marker: create_part("alpha_marker".to_string(), alpha),
+                    field: create_part("field".to_string(), picker_entity),
+                    preview: create_part("preview".to_string(), picker_entity),
+                    swatches: (0..picker.swatches.len())
+                        .map(|i| create_part(format!("swatch_{}", i), picker_entity))
+                        .collect(),
+                };
+
+                for part in [parts.square, parts.hue, parts.alpha, parts.field].iter() {
+                    let _ = interactables.insert(*part, Interactable);
+                }
+                for marker in [parts.square_marker, parts.hue_marker, parts.alpha_marker].iter() {
+                    let _ = images.insert(*marker, UiImage::SolidColor(MARKER_COLOR));
+                }
+                for (i, segment) in parts.hue_segments.iter().enumerate() {
+                    let top = hue_color(i as f32 * 60.0);
+                    let bottom = hue_color((i + 1) as f32 * 60.0);
+                    let _ = images.insert(
+                        *segment,
+                        UiImage::Gradient {
+                            top_left: top,
+                            top_right: top,
+                            bottom_left: bottom,
+                            bottom_right: bottom,
+                        },
+                    );
+                }
+                for (swatch, color) in parts.swatches.iter().zip(picker.swatches.iter()) {
+                    let _ = images.insert(*swatch, UiImage::SolidColor(*color));
+                    let _ = interactables.insert(*swatch, Interactable);
+                }
+                let _ = texts.insert(
+                    parts.field,
+                    UiText::new(
+                        font,
+                        String::new(),
+                        picker.text_color,
+                        picker.font_size,
+                        LineMode::Single,
+                        Anchor::MiddleLeft,
+                    ),
+                );
+                let _ = editables.insert(
+                    parts.field,
+                    TextEditing::new(MAX_LENGTH, [1., 1., 1., 1.], [0., 0., 0., 1.], false),
+                );
+                let _ = selectables.insert(parts.field, Selectable::new(picker.selection_order));
+                picker.parts = Some(parts);
+                picker.dirty = true;
+            }
+            let parts = picker
+                .parts
+                .clone()
+                .expect("unreachable: The parts were just created");
+
+            // Apply the input.
+            let previous = picker.color();
+            if let Some((dragged_entity, area, position)) = dragged {
+                if dragged_entity == picker_entity {
+                    picker.pick(area, position);
+                }
+            }
+            if let Some(swatch) = picked.get(&picker_entity) {
+                picker.set_color(*swatch);
+            }
+            let editing = selecteds.contains(parts.field);
+            if commits.contains(&picker_entity) || (picker.editing && !editing) {
+                // Invalid text shows the current color again.
+                match texts
+                    .get(parts.field)
+                    .and_then(|text| parse_hex(&text.text))
+                {
+                    Some(srgba) => picker.set_srgba(srgba),
+                    None => picker.dirty = true,
+                }
+            }
+            let color = picker.color();
+            if color != previous {
+                ui_events.single_write(UiEvent::new(
+                    UiEventType::ColorChanged { color },
+                    picker_entity,
+                ));
+            }
+
+            // The square and the hue strip fill the top of the picker, above the alpha slider,
+            // the hex field and the swatches.
+            if let Some((width, height)) = ui_transforms
+                .get(picker_entity)
+                .map(|transform| (transform.pixel_width(), transform.pixel_height()))
+            {
+                let (bar, row, spacing) = (picker.bar_size, picker.row_height, picker.spacing);
+                let square_width = (width - bar - spacing).max(0.0);
+                let square_height = (height - bar - row * 2.0 - spacing * 3.0).max(0.0);
+                let alpha_y = square_height + spacing;
+                let field_y = alpha_y + bar + spacing;
+                let swatches_y = field_y + row + spacing;
+                set_layout(
+                    parts.square,
+                    (0.0, 0.0, square_width, square_height),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    parts.hue,
+                    (square_width + spacing, 0.0, bar, square_height),
+                    &mut ui_transforms,
+                );
+                for (i, segment) in parts.hue_segments.iter().enumerate() {
+                    let segment_height = square_height / 6.0;
+                    set_layout(
+                        *segment,
+                        (0.0, i as f32 * segment_height, bar, segment_height),
+                        &mut ui_transforms,
+                    );
+                }
+                set_layout(parts.alpha, (0.0, alpha_y, width, bar), &mut ui_transforms);
+                set_layout(
+                    parts.field,
+                    (0.0, field_y, (width - row - spacing).max(0.0), row),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    parts.preview,
+                    (width - row, field_y, row, row),
+                    &mut ui_transforms,
+                );
+                for (i, swatch) in parts.swatches.iter().enumerate() {
+                    set_layout(
+                        *swatch,
+                        (i as f32 * (row + spacing), swatches_y, row, row),
+                        &mut ui_transforms,
+                    );
+                }
+
+                // The markers show the picked saturation and value, hue and alpha.
+                set_layout(
+                    parts.square_marker,
+                    (
+                        picker.saturation * square_width - MARKER_SIZE / 2.0,
+                        (1.0 - picker.value) * square_height - MARKER_SIZE / 2.0,
+                        MARKER_SIZE,
+                        MARKER_SIZE,
+                    ),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    parts.hue_marker,
+                    (0.0, picker.hue / 360.0 * square_height - 1.0, bar, 2.0),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    parts.alpha_marker,
+                    (picker.alpha * width - 1.0, 0.0, 2.0, bar),
+                    &mut ui_transforms,
+                );
+            }
+
+            // The hex text is not replaced while it is edited.
+            if picker.dirty || picker.editing != editing {
+                let [r, g, b, _] = color;
+                let _ = images.insert(
+                    parts.square,
+                    UiImage::Gradient {
+                        top_left: [1.0, 1.0, 1.0, 1.0],
+                        top_right: hue_color(picker.hue),
+                        bottom_left: [0.0, 0.0, 0.0, 1.0],
+                        bottom_right: [0.0, 0.0, 0.0, 1.0],
+                    },
+                );
+                let _ = images.insert(
+                    parts.alpha,
+                    UiImage::Gradient {
+                        top_left: [r, g, b, 0.0],
+                        top_right: [r, g, b, 1.0],
+                        bottom_left: [r, g, b, 0.0],
+                        bottom_right: [r, g, b, 1.0],
+                    },
+                );
+                let _ = images.insert(parts.preview, UiImage::SolidColor(color));
+                if !editing {
+                    let hex = picker.hex();
+                    if let Some(ui_text) = texts.get_mut(parts.field) {
+                        if ui_text.text != hex {
+                            if let Some(edit) = editables.get_mut(parts.field) {
+                                edit.cursor_position = hex.chars().count() as isize;
+                                edit.highlight_vector = 0;
+                            }
+                            ui_text.text = hex;
+                        }
+                    }
+                }
+                picker.dirty = false;
+            }
+            picker.editing = editing;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &[f32], b: &[f32]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5)
+    }
+
+    #[test]
+    fn hsv_to_rgb_covers_every_sector() {
+        assert!(close(&hsv_to_rgb(0.0, 1.0, 1.0), &[1.0, 0.0, 0.0]));
+        assert!(close(&hsv_to_rgb(30.0, 0.5, 1.0), &[1.0, 0.75, 0.5]));
+        assert!(close(&hsv_to_rgb(120.0, 1.0, 1.0), &[0.0, 1.0, 0.0]));
+        assert!(close(&hsv_to_rgb(180.0, 1.0, 1.0), &[0.0, 1.0, 1.0]));
+        assert!(close(&hsv_to_rgb(240.0, 1.0, 0.5), &[0.0, 0.0, 0.5]));
+        assert!(close(&hsv_to_rgb(300.0, 1.0, 1.0), &[1.0, 0.0, 1.0]));
+        assert!(close(&hsv_to_rgb(90.0, 0.0, 0.25), &[0.25, 0.25, 0.25]));
+    }
+
+    #[test]
+    fn hsv_to_rgb_wraps_the_hue() {
+        assert!(close(
+            &hsv_to_rgb(360.0, 1.0, 1.0),
+            &hsv_to_rgb(0.0, 1.0, 1.0)
+        ));
+        assert!(close(
+            &hsv_to_rgb(-60.0, 1.0, 1.0),
+            &hsv_to_rgb(300.0, 1.0, 1.0)
+        ));
+    }
+
+    #[test]
+    fn rgb_to_hsv_inverts_hsv_to_rgb() {
+        for &(hue, saturation, value) in &[
+            (0.0, 1.0, 1.0),
+            (45.0, 0.5, 0.75),
+            (120.0, 1.0, 0.5),
+            (200.0, 0.25, 1.0),
+            (330.0, 0.8, 0.6),
+        ] {
+            let [r, g, b] = hsv_to_rgb(hue, saturation, value);
+            let (h, s, v) = rgb_to_hsv(r, g, b);
+            assert!(close(&[h, s, v], &[hue, saturation, value]), "{}", hue);
+        }
+    }
+
+    #[test]
+    fn rgb_to_hsv_of_greys_has_no_hue_or_saturation() {
+        assert_eq!(rgb_to_hsv(0.5, 0.5, 0.5), (0.0, 0.0, 0.5));
+        assert_eq!(rgb_to_hsv(0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn parse_hex_reads_short_and_long_forms() {
+        assert_eq!(parse_hex("#F00"), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse_hex("0f8"), Some([0.0, 1.0, 136.0 / 255.0, 1.0]));
+        assert_eq!(parse_hex(" #336699 "), Some([0.2, 0.4, 0.6, 1.0]));
+        assert_eq!(parse_hex("#33669980"), Some([0.2, 0.4, 0.6, 128.0 / 255.0]));
+        assert_eq!(parse_hex("ffffff00"), Some([1.0, 1.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn parse_hex_rejects_invalid_text() {
+        for text in &[
+            "", "#", "#12", "#1234", "#12345", "#1234567", "#GGG", "##123", "#+1+2+3", "#1é2",
+        ] {
+            assert_eq!(parse_hex(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn hex_round_trips_through_the_picker() {
+        let mut picker = UiColorPicker::new([0.0, 0.0, 0.0, 1.0]);
+        picker.set_srgba(parse_hex("#3366CC80").unwrap());
+        assert_eq!(picker.hex(), "#3366CC80");
+    }
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::{
+    any::Any,
+    cmp::Ordering,
     collections::{HashMap, HashSet},
+    fmt,
     marker::PhantomData,
+    sync::Arc,
 };
 
 use amethyst_core::{
@@ -11,39 +15,300 @@ use amethyst_core::{
     },
     math::Vector2,
     shrev::EventChannel,
-    Hidden, HiddenPropagate, ParentHierarchy,
+    Hidden, HiddenPropagate, Parent, ParentHierarchy,
 };
 use amethyst_derive::SystemDesc;
 use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_rendy::resources::Tint;
 use amethyst_window::ScreenDimensions;
+use winit::MouseButton;
 
 use crate::{
-    get_parent_pixel_size, targeted_below, Interactable, ScaleMode, UiEvent, UiEventType,
-    UiTransform,
+    get_parent_pixel_size, targeted, targeted_below, Interactable, ScaleMode, UiEvent, UiEventType,
+    UiImage, UiModalStack, UiOpacity, UiText, UiTransform,
 };
 
+/// How much a ghost is drawn above the element it copies.
+const GHOST_Z: f32 = 100.0;
+/// The opacity of ghosts.
+const GHOST_OPACITY: f32 = 0.6;
+
 /// Component that denotes whether a given ui widget is draggable.
 /// Requires UiTransform to work, and its expected way of usage is
 /// through UiTransformData prefab.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Draggable;
+///
+/// The constraints are expressed in the units of `local_x` and `local_y`, which are pixels or
+/// fractions of the parent size depending on the `ScaleMode` of the `UiTransform`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Draggable {
+    /// Only lets the element move along one axis.
+    pub axis: Option<DragAxis>,
+    /// Keeps the element inside a rectangle.
+    pub bounds: Option<DragBounds>,
+    /// Snaps the element to a grid or to points.
+    pub snap: Option<DragSnap>,
+    /// How many pixels the mouse has to move before the element starts being dragged.
+    pub threshold: f32,
+}
+
+impl Draggable {
+    /// Creates a new `Draggable` without constraints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lets the element move along `axis`.
+    pub fn with_axis(mut self, axis: DragAxis) -> Self {
+        self.axis = Some(axis);
+        self
+    }
+
+    /// Keeps the element inside `bounds`.
+    pub fn with_bounds(mut self, bounds: DragBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Snaps the element to a grid or to points.
+    pub fn with_snap(mut self, snap: DragSnap) -> Self {
+        self.snap = Some(snap);
+        self
+    }
+
+    /// Sets how many pixels the mouse has to move before the element starts being dragged.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Applies the constraints to the local position `position` the mouse would move the element
+    /// to. `bounds` is the `[min_x, max_x, min_y, max_y]` range of local positions allowed.
+    fn constrain(
+        &self,
+        position: (f32, f32),
+        origin: (f32, f32),
+        bounds: Option<[f32; 4]>,
+    ) -> (f32, f32) {
+        let (mut x, mut y) = match self.axis {
+            Some(DragAxis::X) => (position.0, origin.1),
+            Some(DragAxis::Y) => (origin.0, position.1),
+            None => position,
+        };
+        let clamp = |x: f32, y: f32| match bounds {
+            Some([min_x, max_x, min_y, max_y]) => {
+                (x.max(min_x).min(max_x), y.max(min_y).min(max_y))
+            }
+            None => (x, y),
+        };
+        let clamped = clamp(x, y);
+        x = clamped.0;
+        y = clamped.1;
+        match self.snap {
+            Some(DragSnap::Grid {
+                x: step_x,
+                y: step_y,
+            }) => {
+                if step_x > 0.0 && self.axis != Some(DragAxis::Y) {
+                    x = (x / step_x).round() * step_x;
+                }
+                if step_y > 0.0 && self.axis != Some(DragAxis::X) {
+                    y = (y / step_y).round() * step_y;
+                }
+            }
+            Some(DragSnap::Points(ref points)) => {
+                // Points that aren't finite can't be snapped to.
+                let nearest = points
+                    .iter()
+                    .filter(|point| point[0].is_finite() && point[1].is_finite())
+                    .min_by(|a, b| {
+                        let da = (a[0] - x).powi(2) + (a[1] - y).powi(2);
+                        let db = (b[0] - x).powi(2) + (b[1] - y).powi(2);
+                        da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+                    });
+                if let Some(point) = nearest {
+                    x = point[0];
+                    y = point[1];
+                }
+            }
+            None => {}
+        }
+        // Snapping can't move the element out of its bounds.
+        clamp(x, y)
+    }
+}
 
 impl Component for Draggable {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// An axis a `Draggable` can be locked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DragAxis {
+    /// The element only moves horizontally.
+    X,
+    /// The element only moves vertically.
+    Y,
+}
+
+/// The rectangle a `Draggable` is kept in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DragBounds {
+    /// The element stays fully inside its parent, or the screen for root elements.
+    Parent,
+    /// The local position of the element stays inside this range.
+    Rect {
+        /// The smallest `local_x`.
+        min_x: f32,
+        /// The largest `local_x`.
+        max_x: f32,
+        /// The smallest `local_y`.
+        min_y: f32,
+        /// The largest `local_y`.
+        max_y: f32,
+    },
+}
+
+/// Where a `Draggable` snaps to while being dragged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DragSnap {
+    /// The local position snaps to multiples of these steps. A step of zero doesn't snap.
+    Grid {
+        /// The horizontal step.
+        x: f32,
+        /// The vertical step.
+        y: f32,
+    },
+    /// The local position snaps to the nearest of these points.
+    Points(Vec<[f32; 2]>),
+}
+
+/// Data carried by a `Draggable` element, which `DropTarget`s can accept or refuse.
+///
+/// Without a payload, a `Draggable` can be dropped anywhere and is left where it was dropped.
+pub struct DragPayload {
+    value: Box<dyn Any + Send + Sync>,
+    /// Moves the element back to where it was picked up when it is not dropped on a
+    /// `DropTarget` accepting the payload. Defaults to true.
+    pub return_to_origin: bool,
+    /// Drags a translucent copy of the element instead of the element itself, which stays in
+    /// place. The copy has the `UiTransform`, `UiImage`, `UiText` and `Tint` of the element, but
+    /// not its children. Defaults to false.
+    pub ghost: bool,
+}
+
+impl fmt::Debug for DragPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DragPayload")
+            .field("return_to_origin", &self.return_to_origin)
+            .field("ghost", &self.ghost)
+            .finish()
+    }
+}
+
+impl DragPayload {
+    /// Creates a new payload holding `value`.
+    pub fn new<V: Any + Send + Sync>(value: V) -> Self {
+        DragPayload {
+            value: Box::new(value),
+            return_to_origin: true,
+            ghost: false,
+        }
+    }
+
+    /// Returns the value of the payload if it is a `V`.
+    pub fn get<V: Any>(&self) -> Option<&V> {
+        self.value.downcast_ref()
+    }
+
+    /// Checks if the value of the payload is a `V`.
+    pub fn is<V: Any>(&self) -> bool {
+        self.value.is::<V>()
+    }
+
+    /// Sets whether the element goes back to where it was picked up on rejected drops.
+    pub fn with_return_to_origin(mut self, return_to_origin: bool) -> Self {
+        self.return_to_origin = return_to_origin;
+        self
+    }
+
+    /// Drags a translucent copy of the element instead of the element itself.
+    pub fn with_ghost(mut self) -> Self {
+        self.ghost = true;
+        self
+    }
+}
+
+impl Component for DragPayload {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Component marking an element on which `Draggable` elements carrying a `DragPayload` can be
+/// dropped.
+///
+/// The target receives `DragEnter` and `DragLeave` events while a payload is dragged over it or
+/// one of its children, and a `Drop` event when an accepted payload is dropped on it.
+#[derive(Clone)]
+pub struct DropTarget {
+    accept: Arc<dyn Fn(&DragPayload) -> bool + Send + Sync>,
+}
+
+impl fmt::Debug for DropTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropTarget").finish()
+    }
+}
+
+impl DropTarget {
+    /// Creates a drop target accepting the payloads for which `accept` returns true.
+    pub fn new<F>(accept: F) -> Self
+    where
+        F: Fn(&DragPayload) -> bool + Send + Sync + 'static,
+    {
+        DropTarget {
+            accept: Arc::new(accept),
+        }
+    }
+
+    /// Creates a drop target accepting the payloads holding a `V`.
+    pub fn accepting<V: Any>() -> Self {
+        DropTarget::new(DragPayload::is::<V>)
+    }
+
+    /// Checks if `payload` can be dropped on this target.
+    pub fn accepts(&self, payload: &DragPayload) -> bool {
+        (self.accept)(payload)
+    }
+}
+
+impl Component for DropTarget {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// An element being dragged.
+#[derive(Debug)]
+struct Drag {
+    /// The mouse position when the drag started.
+    first: Vector2<f32>,
+    /// The local position of the element when the drag started.
+    origin: (f32, f32),
+    /// Whether the mouse moved past the threshold of the `Draggable`.
+    started: bool,
+    /// The copy of the element being moved instead of it, if any.
+    ghost: Option<Entity>,
+    /// The drop target under the mouse.
+    hovered: Option<Entity>,
+}
+
 #[derive(Debug, SystemDesc)]
 #[system_desc(name(DragWidgetSystemDesc))]
 pub struct DragWidgetSystem<T: BindingTypes> {
     #[system_desc(event_channel_reader)]
     ui_reader_id: ReaderId<UiEvent>,
 
-    /// hashmap whose keys are every entities being dragged,
-    /// and whose element is a tuple whose first element is
-    /// the original mouse position when drag first started,
-    /// and second element the mouse position one frame ago
+    /// Every entity being dragged.
     #[system_desc(skip)]
-    record: HashMap<Entity, (Vector2<f32>, Vector2<f32>)>,
+    record: HashMap<Entity, Drag>,
 
     phantom: PhantomData<T>,
 }
@@ -74,8 +339,16 @@ where
         ReadStorage<'s, HiddenPropagate>,
         ReadStorage<'s, Draggable>,
         ReadStorage<'s, Interactable>,
+        ReadStorage<'s, DragPayload>,
+        ReadStorage<'s, DropTarget>,
+        Read<'s, UiModalStack>,
         Write<'s, EventChannel<UiEvent>>,
         WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, Parent>,
+        WriteStorage<'s, UiImage>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, Tint>,
+        WriteStorage<'s, UiOpacity>,
     );
 
     fn run(
@@ -89,23 +362,47 @@ where
             hidden_props,
             draggables,
             interactables,
+            payloads,
+            drop_targets,
+            modal_stack,
             mut ui_events,
             mut ui_transforms,
+            mut parents,
+            mut images,
+            mut texts,
+            mut tints,
+            mut opacities,
         ): Self::SystemData,
     ) {
         let mouse_pos = input_handler.mouse_position().unwrap_or((0., 0.));
         let mouse_pos = Vector2::new(mouse_pos.0, screen_dimensions.height() - mouse_pos.1);
 
+        forget_deleted(&mut self.record, &entities, &mut ui_events);
+
         let mut click_stopped: HashSet<Entity> = HashSet::new();
 
         for event in ui_events.read(&mut self.ui_reader_id) {
             match event.event_type {
-                UiEventType::ClickStart => {
-                    if draggables.get(event.target).is_some() {
-                        self.record.insert(event.target, (mouse_pos, mouse_pos));
+                UiEventType::ClickStart(MouseButton::Left) => {
+                    if draggables.get(event.target).is_none() {
+                        continue;
                     }
+                    let transform = match ui_transforms.get(event.target) {
+                        Some(transform) => transform,
+                        None => continue,
+                    };
+                    self.record.insert(
+                        event.target,
+                        Drag {
+                            first: mouse_pos,
+                            origin: (transform.local_x, transform.local_y),
+                            started: false,
+                            ghost: None,
+                            hovered: None,
+                        },
+                    );
                 }
-                UiEventType::ClickStop => {
+                UiEventType::ClickStop(MouseButton::Left) => {
                     if self.record.contains_key(&event.target) {
                         click_stopped.insert(event.target);
                     }
@@ -120,38 +417,128 @@ where
             }
         }
 
-        for (entity, (first, prev)) in self.record.iter_mut() {
+        for (entity, drag) in self.record.iter_mut() {
+            let draggable = match draggables.get(*entity) {
+                Some(draggable) => draggable,
+                None => continue,
+            };
+            let offset_from_mouse = mouse_pos - drag.first;
+            if !drag.started {
+                if offset_from_mouse.norm() < draggable.threshold {
+                    continue;
+                }
+                drag.started = true;
+                if payloads.get(*entity).map_or(false, |payload| payload.ghost) {
+                    drag.ghost = create_ghost(
+                        *entity,
+                        &entities,
+                        &mut ui_transforms,
+                        &mut parents,
+                        &mut images,
+                        &mut texts,
+                        &mut tints,
+                        &mut opacities,
+                    );
+                }
+            }
+
             ui_events.single_write(UiEvent::new(
                 UiEventType::Dragging {
-                    offset_from_mouse: mouse_pos - *first,
+                    offset_from_mouse,
                     new_position: mouse_pos,
                 },
                 *entity,
             ));
 
-            let change = mouse_pos - *prev;
-
-            let (parent_width, parent_height) =
-                get_parent_pixel_size(*entity, &hierarchy, &ui_transforms, &screen_dimensions);
-
-            let ui_transform = ui_transforms.get_mut(*entity).unwrap();
-            let (scale_x, scale_y) = match ui_transform.scale_mode {
-                ScaleMode::Pixel => (1.0, 1.0),
-                ScaleMode::Percent => (parent_width, parent_height),
-            };
-
-            ui_transform.local_x += change[0] / scale_x;
-            ui_transform.local_y += change[1] / scale_y;
+            let moved = drag.ghost.unwrap_or(*entity);
+            // The ghost has the same parent, which isn't in the hierarchy until the next frame.
+            let parent = parent_rect(*entity, &hierarchy, &ui_transforms, &screen_dimensions);
+            if let Some(ui_transform) = ui_transforms.get(moved) {
+                let unit = match ui_transform.scale_mode {
+                    ScaleMode::Pixel => (1.0, 1.0),
+                    ScaleMode::Percent => (parent.2, parent.3),
+                };
+                // The mouse moves in screen pixels, the element in ui pixels.
+                let scale_factor = ui_transform.scale_factor;
+                let free = (
+                    drag.origin.0 + offset_from_mouse[0] / scale_factor / unit.0,
+                    drag.origin.1 + offset_from_mouse[1] / scale_factor / unit.1,
+                );
+                let bounds = match draggable.bounds {
+                    Some(DragBounds::Parent) => Some(inside_parent(ui_transform, parent, unit)),
+                    Some(DragBounds::Rect {
+                        min_x,
+                        max_x,
+                        min_y,
+                        max_y,
+                    }) => Some([min_x, max_x, min_y, max_y]),
+                    None => None,
+                };
+                let position = draggable.constrain(free, drag.origin, bounds);
+                if position != (ui_transform.local_x, ui_transform.local_y) {
+                    let ui_transform = ui_transforms
+                        .get_mut(moved)
+                        .expect("unreachable: We just got this transform");
+                    ui_transform.local_x = position.0;
+                    ui_transform.local_y = position.1;
+                }
+            }
 
-            *prev = mouse_pos;
+            // Only elements carrying a payload interact with drop targets.
+            if payloads.contains(*entity) {
+                let hovered = drop_target_at(
+                    (mouse_pos[0], mouse_pos[1]),
+                    (*entity, drag.ghost),
+                    &entities,
+                    &ui_transforms,
+                    &interactables,
+                    &hiddens,
+                    &hidden_props,
+                    &drop_targets,
+                    &modal_stack,
+                    &hierarchy,
+                );
+                if hovered != drag.hovered {
+                    if let Some(left) = drag.hovered {
+                        ui_events.single_write(UiEvent::new(
+                            UiEventType::DragLeave { dragged: *entity },
+                            left,
+                        ));
+                    }
+                    if let Some(entered) = hovered {
+                        let accepted = match (drop_targets.get(entered), payloads.get(*entity)) {
+                            (Some(target), Some(payload)) => target.accepts(payload),
+                            _ => false,
+                        };
+                        ui_events.single_write(UiEvent::new(
+                            UiEventType::DragEnter {
+                                dragged: *entity,
+                                accepted,
+                            },
+                            entered,
+                        ));
+                    }
+                    drag.hovered = hovered;
+                }
+            }
         }
 
         for entity in click_stopped.iter() {
+            let drag = match self.record.remove(entity) {
+                Some(drag) if drag.started => drag,
+                // The mouse didn't move past the threshold, this was a click.
+                _ => continue,
+            };
+            let moved = drag.ghost.unwrap_or(*entity);
+            let height = ui_transforms
+                .get(moved)
+                .map_or(std::f32::MAX, |t| t.global_z);
+
             ui_events.single_write(UiEvent::new(
                 UiEventType::Dropped {
                     dropped_on: targeted_below(
                         (mouse_pos[0], mouse_pos[1]),
-                        ui_transforms.get(*entity).unwrap().global_z,
+                        height,
                         (
                             &*entities,
                             &ui_transforms,
@@ -159,13 +546,323 @@ where
                             !&hiddens,
                             !&hidden_props,
                         )
-                            .join(),
+                            .join()
+                            .filter(|(e, _, _, _, _)| *e != *entity && Some(*e) != drag.ghost),
                     ),
                 },
                 *entity,
             ));
 
-            self.record.remove(entity);
+            if let Some(payload) = payloads.get(*entity) {
+                let accepted_by = drag.hovered.filter(|target| {
+                    drop_targets
+                        .get(*target)
+                        .map_or(false, |drop_target| drop_target.accepts(payload))
+                });
+                if let Some(hovered) = drag.hovered {
+                    ui_events.single_write(UiEvent::new(
+                        UiEventType::DragLeave { dragged: *entity },
+                        hovered,
+                    ));
+                }
+                match accepted_by {
+                    Some(target) => {
+                        ui_events.single_write(UiEvent::new(
+                            UiEventType::Drop { dragged: *entity },
+                            target,
+                        ));
+                    }
+                    None if payload.return_to_origin && drag.ghost.is_none() => {
+                        if let Some(ui_transform) = ui_transforms.get_mut(*entity) {
+                            ui_transform.local_x = drag.origin.0;
+                            ui_transform.local_y = drag.origin.1;
+                        }
+                    }
+                    None => {}
+                }
+            }
+
+            if let Some(ghost) = drag.ghost {
+                let _ = entities.delete(ghost);
+            }
+        }
+    }
+}
+
+/// Stops the drags of the deleted entities, deleting their ghost and leaving the drop target
+/// they were over.
+fn forget_deleted(
+    record: &mut HashMap<Entity, Drag>,
+    entities: &Entities<'_>,
+    ui_events: &mut EventChannel<UiEvent>,
+) {
+    record.retain(|entity, drag| {
+        if entities.is_alive(*entity) {
+            return true;
+        }
+        if let Some(ghost) = drag.ghost {
+            let _ = entities.delete(ghost);
+        }
+        if let Some(hovered) = drag.hovered {
+            ui_events.single_write(UiEvent::new(
+                UiEventType::DragLeave { dragged: *entity },
+                hovered,
+            ));
+        }
+        false
+    });
+}
+
+/// Finds the `DropTarget` under the position `pos`, which is either one of the elements the mouse
+/// targets there, with the same opaque and transparent rules as the `UiMouseSystem`, or one of
+/// their ancestors. The dragged element, its children and its ghost are ignored, and so are the
+/// elements outside of the top-most modal.
+#[allow(clippy::too_many_arguments)]
+fn drop_target_at(
+    pos: (f32, f32),
+    (dragged, ghost): (Entity, Option<Entity>),
+    entities: &Entities<'_>,
+    ui_transforms: &WriteStorage<'_, UiTransform>,
+    interactables: &ReadStorage<'_, Interactable>,
+    hiddens: &ReadStorage<'_, Hidden>,
+    hidden_props: &ReadStorage<'_, HiddenPropagate>,
+    drop_targets: &ReadStorage<'_, DropTarget>,
+    modal_stack: &UiModalStack,
+    hierarchy: &ParentHierarchy,
+) -> Option<Entity> {
+    let dragged_part = |mut entity: Entity| loop {
+        if entity == dragged || Some(entity) == ghost {
+            return true;
+        }
+        match hierarchy.parent(entity) {
+            Some(parent) => entity = parent,
+            None => return false,
+        }
+    };
+    let candidates = (
+        &**entities,
+        ui_transforms,
+        interactables.maybe(),
+        !hiddens,
+        !hidden_props,
+    )
+        .join()
+        .filter(|(e, _, _, _, _)| !dragged_part(*e));
+    let mut targets = targeted(pos, candidates)
+        .into_iter()
+        .filter(|target| modal_stack.allows(*target, hierarchy))
+        .collect::<Vec<_>>();
+    // The top-most target inside a drop target wins.
+    let z = |entity: &Entity| ui_transforms.get(*entity).map_or(0.0, |t| t.global_z);
+    targets.sort_by(|a, b| z(b).partial_cmp(&z(a)).expect("Unexpected NaN"));
+
+    targets.into_iter().find_map(|target| {
+        let mut current = Some(target);
+        while let Some(entity) = current {
+            if drop_targets.contains(entity) {
+                return Some(entity);
+            }
+            current = hierarchy.parent(entity);
+        }
+        None
+    })
+}
+
+/// Copies the `UiTransform`, `UiImage`, `UiText` and `Tint` of `entity` to a translucent ghost
+/// drawn on top of it.
+#[allow(clippy::too_many_arguments)]
+fn create_ghost(
+    entity: Entity,
+    entities: &Entities<'_>,
+    ui_transforms: &mut WriteStorage<'_, UiTransform>,
+    parents: &mut WriteStorage<'_, Parent>,
+    images: &mut WriteStorage<'_, UiImage>,
+    texts: &mut WriteStorage<'_, UiText>,
+    tints: &mut WriteStorage<'_, Tint>,
+    opacities: &mut WriteStorage<'_, UiOpacity>,
+) -> Option<Entity> {
+    let mut transform = ui_transforms.get(entity)?.clone();
+    transform.id = format!("{}_ghost", transform.id);
+    transform.local_z += GHOST_Z;
+    transform.opaque = false;
+    transform.transparent_target = false;
+
+    let ghost = entities.create();
+    ui_transforms
+        .insert(ghost, transform)
+        .expect("unreachable: We just created the entity");
+    if let Some(parent) = parents.get(entity).cloned() {
+        let _ = parents.insert(ghost, parent);
+    }
+    if let Some(image) = images.get(entity).cloned() {
+        let _ = images.insert(ghost, image);
+    }
+    if let Some(text) = texts.get(entity).cloned() {
+        let _ = texts.insert(ghost, text);
+    }
+    if let Some(tint) = tints.get(entity).cloned() {
+        let _ = tints.insert(ghost, tint);
+    }
+    let _ = opacities.insert(ghost, UiOpacity(GHOST_OPACITY));
+    Some(ghost)
+}
+
+/// Returns the center and size of the parent of `entity` in pixels, which is the screen in ui
+/// pixels for root elements.
+fn parent_rect(
+    entity: Entity,
+    hierarchy: &ParentHierarchy,
+    ui_transforms: &WriteStorage<'_, UiTransform>,
+    screen_dimensions: &ScreenDimensions,
+) -> (f32, f32, f32, f32) {
+    match hierarchy.parent(entity).and_then(|p| ui_transforms.get(p)) {
+        Some(parent) => (
+            parent.pixel_x,
+            parent.pixel_y,
+            parent.pixel_width,
+            parent.pixel_height,
+        ),
+        None => {
+            let (width, height) =
+                get_parent_pixel_size(entity, hierarchy, ui_transforms, screen_dimensions);
+            (width / 2.0, height / 2.0, width, height)
+        }
+    }
+}
+
+/// Computes the range of local positions keeping `transform` inside the `parent` rect, as
+/// `[min_x, max_x, min_y, max_y]`. `unit` is the size of a unit of the local position in pixels.
+fn inside_parent(
+    transform: &UiTransform,
+    parent: (f32, f32, f32, f32),
+    unit: (f32, f32),
+) -> [f32; 4] {
+    // Where the center of the element would be with a local position of zero.
+    let base_x = transform.pixel_x - transform.local_x * unit.0;
+    let base_y = transform.pixel_y - transform.local_y * unit.1;
+    let range = |base: f32, center: f32, parent_size: f32, size: f32, unit: f32| {
+        let min = (center - (parent_size - size) / 2.0 - base) / unit;
+        let max = (center + (parent_size - size) / 2.0 - base) / unit;
+        // Elements larger than their parent stay centered.
+        if min > max {
+            let mid = (center - base) / unit;
+            (mid, mid)
+        } else {
+            (min, max)
         }
+    };
+    let (min_x, max_x) = range(base_x, parent.0, parent.2, transform.pixel_width, unit.0);
+    let (min_y, max_y) = range(base_y, parent.1, parent.3, transform.pixel_height, unit.1);
+    [min_x, max_x, min_y, max_y]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, World, WorldExt};
+
+    #[test]
+    fn drags_of_deleted_entities_are_forgotten() {
+        let mut world = World::new();
+        let mut channel = EventChannel::<UiEvent>::new();
+        let mut reader = channel.register_reader();
+        let mut record = HashMap::new();
+        let (dragged, kept, ghost, target) = (
+            world.create_entity().build(),
+            world.create_entity().build(),
+            world.create_entity().build(),
+            world.create_entity().build(),
+        );
+        let drag = |ghost, hovered| Drag {
+            first: Vector2::zeros(),
+            origin: (0.0, 0.0),
+            started: true,
+            ghost,
+            hovered,
+        };
+        record.insert(dragged, drag(Some(ghost), Some(target)));
+        record.insert(kept, drag(None, None));
+        world.delete_entity(dragged).unwrap();
+
+        forget_deleted(&mut record, &world.entities(), &mut channel);
+        world.maintain();
+
+        assert_eq!(record.keys().collect::<Vec<_>>(), vec![&kept]);
+        assert!(!world.is_alive(ghost));
+        let events = channel.read(&mut reader).collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].target, target);
+        assert_eq!(events[0].event_type, UiEventType::DragLeave { dragged });
+    }
+
+    #[test]
+    fn constrain_locks_the_axis() {
+        let draggable = Draggable::new().with_axis(DragAxis::X);
+        assert_eq!(
+            draggable.constrain((5.0, 7.0), (1.0, 2.0), None),
+            (5.0, 2.0)
+        );
+        let draggable = Draggable::new().with_axis(DragAxis::Y);
+        assert_eq!(
+            draggable.constrain((5.0, 7.0), (1.0, 2.0), None),
+            (1.0, 7.0)
+        );
+    }
+
+    #[test]
+    fn constrain_clamps_to_the_bounds() {
+        let draggable = Draggable::new();
+        let bounds = Some([0.0, 10.0, -5.0, 5.0]);
+        assert_eq!(
+            draggable.constrain((15.0, -7.0), (0.0, 0.0), bounds),
+            (10.0, -5.0)
+        );
+        assert_eq!(
+            draggable.constrain((3.0, 4.0), (0.0, 0.0), bounds),
+            (3.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn constrain_snaps_inside_the_bounds() {
+        let grid = Draggable::new().with_snap(DragSnap::Grid { x: 4.0, y: 0.0 });
+        assert_eq!(grid.constrain((5.0, 3.3), (0.0, 0.0), None), (4.0, 3.3));
+        // The nearest grid line is out of bounds.
+        let bounds = Some([0.0, 10.5, 0.0, 9.0]);
+        assert_eq!(grid.constrain((11.0, 3.0), (0.0, 0.0), bounds), (10.5, 3.0));
+
+        let points = Draggable::new().with_snap(DragSnap::Points(vec![
+            [std::f32::NAN, 0.0],
+            [0.0, 0.0],
+            [10.0, 10.0],
+        ]));
+        assert_eq!(points.constrain((7.0, 6.0), (0.0, 0.0), None), (10.0, 10.0));
+        assert_eq!(points.constrain((1.0, 2.0), (0.0, 0.0), None), (0.0, 0.0));
+    }
+
+    #[test]
+    fn inside_parent_keeps_the_element_in_its_parent() {
+        let mut transform = UiTransform::new(
+            String::default(),
+            crate::Anchor::Middle,
+            crate::Anchor::Middle,
+            10.0,
+            0.0,
+            0.0,
+            20.0,
+            10.0,
+        );
+        transform.pixel_x = 60.0;
+        transform.pixel_y = 50.0;
+        transform.pixel_width = 20.0;
+        transform.pixel_height = 10.0;
+
+        // A 100x100 parent centered on (50, 50), in pixels.
+        let bounds = inside_parent(&transform, (50.0, 50.0, 100.0, 100.0), (1.0, 1.0));
+        assert_eq!(bounds, [-40.0, 40.0, -45.0, 45.0]);
+        // The same in fractions of the parent size.
+        transform.local_x = 0.1;
+        let bounds = inside_parent(&transform, (50.0, 50.0, 100.0, 100.0), (100.0, 100.0));
+        assert_eq!(bounds, [-0.4, 0.4, -0.45, 0.45]);
     }
 }
@@ -1,19 +1,28 @@
-use crate::transform::UiTransform;
+use crate::{
+    canvas::{target_of, UiCanvas},
+    opacity::{UiGlobalOpacities, UiOpacity, UiOpacityConfig},
+    transform::UiTransform,
+    UiImage, UiModalStack,
+};
 use amethyst_core::{
     ecs::{
         prelude::{
             Component, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, Write,
         },
-        storage::NullStorage,
+        storage::{GenericReadStorage, NullStorage},
     },
     math::Vector2,
     shrev::EventChannel,
-    Hidden, HiddenPropagate,
+    Hidden, HiddenPropagate, ParentHierarchy, Time,
 };
 use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_rendy::bundle::{Target, TargetImage};
 use amethyst_window::ScreenDimensions;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, marker::PhantomData};
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+};
 use winit::MouseButton;
 
 /// An event that pertains to a specific `Entity`, for example a `UiEvent` for clicking on a widget
@@ -27,15 +36,28 @@ pub trait TargetedEvent {
 /// Click happens if you start and stop clicking on the same ui element.
 #[derive(Debug, Clone, PartialEq)]
 pub enum UiEventType {
-    /// When an element is clicked normally.
+    /// When an element is clicked normally with a mouse button.
+    /// Includes touch events, which use the left button.
+    Click(MouseButton),
+    /// When the element starts being clicked (On mouse down).
     /// Includes touch events.
-    Click,
-    /// When the element starts being clicked (On left mouse down).
+    ClickStart(MouseButton),
+    /// When the element stops being clicked (On mouse up).
     /// Includes touch events.
-    ClickStart,
-    /// When the element stops being clicked (On left mouse up).
-    /// Includes touch events.
-    ClickStop,
+    ClickStop(MouseButton),
+    /// When an element is clicked twice in a row with the same button, within the time and distance
+    /// set in the `UiMouseConfig`. Sent after the second `Click`.
+    DoubleClick(MouseButton),
+    /// When a button is held down on an element for the time set in the `UiMouseConfig`, without
+    /// the cursor moving away. No `Click` is sent when the button is released afterwards.
+    LongPress(MouseButton),
+    /// When the mouse wheel is used over an element.
+    Scroll {
+        /// The horizontal and vertical amounts scrolled, as returned by
+        /// `InputHandler::mouse_wheel_delta`: in lines for most mouse wheels, and in pixels for
+        /// touchpads.
+        delta: Vector2<f32>,
+    },
     /// When the cursor gets over an element.
     HoverStart,
     /// When the cursor stops being over an element.
@@ -52,6 +74,62 @@ pub enum UiEventType {
         /// The entity on which the dragged object was dropped.
         dropped_on: Option<Entity>,
     },
+    /// When an element carrying a `DragPayload` is dragged over a `DropTarget` or one of its
+    /// children. Sent to the drop target.
+    DragEnter {
+        /// The dragged entity.
+        dragged: Entity,
+        /// Whether the drop target accepts the payload of the dragged entity.
+        accepted: bool,
+    },
+    /// When an element carrying a `DragPayload` is dragged away from a `DropTarget`, or dropped on
+    /// it. Sent to the drop target.
+    DragLeave {
+        /// The dragged entity.
+        dragged: Entity,
+    },
+    /// When an element is dropped on a `DropTarget` accepting its `DragPayload`. Sent to the drop
+    /// target.
+    Drop {
+        /// The dropped entity.
+        dragged: Entity,
+    },
+    /// When a node of a `UiTreeView` is selected. Sent to the tree.
+    NodeSelected {
+        /// The id of the node.
+        node: u64,
+    },
+    /// When a node of a `UiTreeView` is double clicked, or enter is pressed while it is selected.
+    /// Sent to the tree.
+    NodeActivated {
+        /// The id of the node.
+        node: u64,
+    },
+    /// When a node of a `UiTreeView` is expanded. Sent to the tree.
+    NodeExpanded {
+        /// The id of the node.
+        node: u64,
+    },
+    /// When a node of a `UiTreeView` is collapsed. Sent to the tree.
+    NodeCollapsed {
+        /// The id of the node.
+        node: u64,
+    },
+    /// When the active tab of a `UiTabs` changes. Sent to the tab container.
+    ActiveTabChanged {
+        /// The index of the new active tab.
+        index: usize,
+    },
+    /// When the value of a `UiSpinBox` is changed by user input. Sent to the spin box.
+    NumberChanged {
+        /// The new value.
+        value: f64,
+    },
+    /// When the color of a `UiColorPicker` is changed by user input. Sent to the color picker.
+    ColorChanged {
+        /// The new color, in linear RGBA.
+        color: [f32; 4],
+    },
     /// When the value of a UiText element has been changed by user input.
     ValueChange,
     /// When the value of a UiText element has been committed by user action.
@@ -62,6 +140,19 @@ pub enum UiEventType {
     Blur,
 }
 
+/// The phase of the propagation of a `UiEvent` through the ui hierarchy.
+///
+/// Events are first captured from the root down to the target, then bubble back up to the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiEventPhase {
+    /// The event goes down from the root to the parent of its target.
+    Capture,
+    /// The event is at its target.
+    Target,
+    /// The event goes up from the parent of its target to the root.
+    Bubble,
+}
+
 /// A ui event instance.
 #[derive(Debug, Clone)]
 pub struct UiEvent {
@@ -69,12 +160,40 @@ pub struct UiEvent {
     pub event_type: UiEventType,
     /// The entity on which the event happened.
     pub target: Entity,
+    current_target: Entity,
+    phase: UiEventPhase,
 }
 
 impl UiEvent {
     /// Creates a new UiEvent.
     pub fn new(event_type: UiEventType, target: Entity) -> Self {
-        UiEvent { event_type, target }
+        UiEvent {
+            event_type,
+            target,
+            current_target: target,
+            phase: UiEventPhase::Target,
+        }
+    }
+
+    /// The entity currently handling the event, which is `target` or one of its ancestors.
+    /// Events read from the `EventChannel<UiEvent>` are not propagated, so this is always `target`
+    /// there. See `UiEventHandler`.
+    pub fn current_target(&self) -> Entity {
+        self.current_target
+    }
+
+    /// Where the event is in its propagation.
+    pub fn phase(&self) -> UiEventPhase {
+        self.phase
+    }
+
+    /// Returns this event as seen by `current_target` during `phase`.
+    pub(crate) fn propagated(&self, current_target: Entity, phase: UiEventPhase) -> Self {
+        UiEvent {
+            current_target,
+            phase,
+            ..self.clone()
+        }
     }
 }
 
@@ -94,12 +213,156 @@ impl Component for Interactable {
     type Storage = NullStorage<Interactable>;
 }
 
+/// Resource configuring the mouse events sent by the `UiMouseSystem`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiMouseConfig {
+    /// Maximum time in seconds between two clicks for them to make a `DoubleClick`.
+    pub double_click_time: f32,
+    /// Time in seconds a button has to be held down on an element for a `LongPress`.
+    pub long_press_time: f32,
+    /// Maximum distance in pixels the cursor can move between the two clicks of a `DoubleClick`,
+    /// or while a button is held down for a `LongPress`.
+    pub click_distance: f32,
+}
+
+impl Default for UiMouseConfig {
+    fn default() -> Self {
+        UiMouseConfig {
+            double_click_time: 0.5,
+            long_press_time: 0.6,
+            click_distance: 5.0,
+        }
+    }
+}
+
+/// A mouse button being held down.
+#[derive(Debug)]
+struct Press {
+    started_on: HashSet<Entity>,
+    time: f64,
+    position: (f32, f32),
+    long_pressed: bool,
+}
+
+/// The last click of a mouse button, waiting for a second one.
+#[derive(Debug)]
+struct LastClick {
+    on: HashSet<Entity>,
+    time: f64,
+    position: (f32, f32),
+}
+
+/// The presses and clicks of the mouse buttons, turned into click events.
+#[derive(Default, Debug)]
+struct Clicks {
+    presses: HashMap<MouseButton, Press>,
+    last_clicks: HashMap<MouseButton, LastClick>,
+}
+
+impl Clicks {
+    /// Sends the click events of the buttons `started` and `stopped` at `now`, and the long
+    /// presses of the buttons still `down`, with the cursor at `position` over `targets`.
+    #[allow(clippy::too_many_arguments)]
+    fn update(
+        &mut self,
+        started: &[MouseButton],
+        down: &HashSet<MouseButton>,
+        stopped: &[MouseButton],
+        targets: &HashSet<Entity>,
+        position: (f32, f32),
+        now: f64,
+        config: &UiMouseConfig,
+        events: &mut EventChannel<UiEvent>,
+    ) {
+        for button in started.iter().cloned() {
+            for target in targets.iter() {
+                events.single_write(UiEvent::new(UiEventType::ClickStart(button), *target));
+            }
+            self.presses.insert(
+                button,
+                Press {
+                    started_on: targets.clone(),
+                    time: now,
+                    position,
+                    long_pressed: false,
+                },
+            );
+        }
+
+        for (button, press) in self.presses.iter_mut() {
+            if press.long_pressed
+                || !down.contains(button)
+                || now - press.time < f64::from(config.long_press_time)
+                || !within(press.position, position, config.click_distance)
+            {
+                continue;
+            }
+            press.long_pressed = true;
+            for target in press.started_on.intersection(targets) {
+                events.single_write(UiEvent::new(UiEventType::LongPress(*button), *target));
+            }
+        }
+
+        for button in stopped.iter().cloned() {
+            let clicked = match self.presses.get(&button) {
+                Some(press) if !press.long_pressed => press
+                    .started_on
+                    .intersection(targets)
+                    .cloned()
+                    .collect::<HashSet<_>>(),
+                _ => continue,
+            };
+            for target in clicked.iter() {
+                events.single_write(UiEvent::new(UiEventType::Click(button), *target));
+            }
+
+            let double_clicked = self
+                .last_clicks
+                .remove(&button)
+                .filter(|last| {
+                    now - last.time <= f64::from(config.double_click_time)
+                        && within(last.position, position, config.click_distance)
+                })
+                .map(|last| last.on.intersection(&clicked).cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            for target in double_clicked.iter() {
+                events.single_write(UiEvent::new(UiEventType::DoubleClick(button), *target));
+            }
+            // A third click starts a new double click instead of making another one.
+            if double_clicked.is_empty() && !clicked.is_empty() {
+                self.last_clicks.insert(
+                    button,
+                    LastClick {
+                        on: clicked,
+                        time: now,
+                        position,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Ends the presses of the buttons `stopped`, sending `ClickStop` to where they started.
+    fn release(&mut self, stopped: &[MouseButton], events: &mut EventChannel<UiEvent>) {
+        for button in stopped.iter().cloned() {
+            if let Some(press) = self.presses.remove(&button) {
+                for click_start_target in press.started_on {
+                    events.single_write(UiEvent::new(
+                        UiEventType::ClickStop(button),
+                        click_start_target,
+                    ));
+                }
+            }
+        }
+    }
+}
+
 /// The system that generates events for `Interactable` enabled entities.
 /// The generic types A and B represent the A and B generic parameter of the InputHandler<A,B>.
 #[derive(Default, Debug)]
 pub struct UiMouseSystem<T: BindingTypes> {
-    was_down: bool,
-    click_started_on: HashSet<Entity>,
+    was_down: HashSet<MouseButton>,
+    clicks: Clicks,
     last_targets: HashSet<Entity>,
     _marker: PhantomData<T>,
 }
@@ -108,8 +371,8 @@ impl<T: BindingTypes> UiMouseSystem<T> {
     /// Creates a new UiMouseSystem.
     pub fn new() -> Self {
         UiMouseSystem {
-            was_down: false,
-            click_started_on: HashSet::new(),
+            was_down: HashSet::new(),
+            clicks: Clicks::default(),
             last_targets: HashSet::new(),
             _marker: PhantomData,
         }
@@ -125,25 +388,58 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
         ReadStorage<'a, Interactable>,
         Read<'a, InputHandler<T>>,
         ReadExpect<'a, ScreenDimensions>,
+        Read<'a, UiModalStack>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadStorage<'a, UiOpacity>,
+        Read<'a, UiOpacityConfig>,
+        ReadStorage<'a, UiCanvas>,
+        ReadStorage<'a, UiImage>,
+        Read<'a, UiMouseConfig>,
+        Read<'a, Time>,
         Write<'a, EventChannel<UiEvent>>,
     );
 
     fn run(
         &mut self,
-        (entities, hiddens, hidden_props, transform, react, input, screen_dimensions, mut events): Self::SystemData,
+        (
+            entities,
+            hiddens,
+            hidden_props,
+            transform,
+            react,
+            input,
+            screen_dimensions,
+            modal_stack,
+            hierarchy,
+            opacities,
+            opacity_config,
+            canvases,
+            images,
+            config,
+            time,
+            mut events,
+        ): Self::SystemData,
     ) {
-        let down = input.mouse_button_is_down(MouseButton::Left);
+        let down = input
+            .mouse_buttons_that_are_down()
+            .cloned()
+            .collect::<HashSet<_>>();
 
         // TODO: To replace on InputHandler generate OnMouseDown and OnMouseUp events
-        let click_started = down && !self.was_down;
-        let click_stopped = !down && self.was_down;
+        let clicks_started = down.difference(&self.was_down).cloned().collect::<Vec<_>>();
+        let clicks_stopped = self.was_down.difference(&down).cloned().collect::<Vec<_>>();
+        // Real time, so that the ui keeps working while the game is paused.
+        let now = time.absolute_real_time_seconds();
 
         if let Some((pos_x, pos_y)) = input.mouse_position() {
             let x = pos_x as f32;
             let y = screen_dimensions.height() - pos_y as f32;
 
-            let targets = targeted(
-                (x, y),
+            let global_opacities = UiGlobalOpacities::compute(&entities, &opacities, &hierarchy);
+            let (hierarchy, global_opacities, canvases) =
+                (&*hierarchy, &global_opacities, &canvases);
+            let ignore_transparent = opacity_config.ignore_transparent_input;
+            let candidates = |render_target: Target| {
                 (
                     &*entities,
                     &transform,
@@ -151,8 +447,29 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
                     !&hiddens,
                     !&hidden_props,
                 )
-                    .join(),
-            );
+                    .join()
+                    .filter(move |(entity, _, _, _, _)| {
+                        target_of(*entity, hierarchy, canvases) == render_target
+                            && (!ignore_transparent || global_opacities.get(*entity) > 0.0)
+                    })
+            };
+
+            let mut targets = targeted((x, y), candidates(Target::Main));
+
+            // Forward the mouse to the canvases shown in the targeted images.
+            let forwarded = targets
+                .iter()
+                .filter_map(|target| {
+                    canvas_position((x, y), *target, &transform, &images, canvases)
+                })
+                .collect::<Vec<_>>();
+            for (render_target, position) in forwarded {
+                targets.extend(targeted(position, candidates(render_target)));
+            }
+
+            // Entities outside of the top-most modal don't receive mouse events.
+            targets.retain(|target| modal_stack.allows(*target, hierarchy));
+
             for target in targets.difference(&self.last_targets) {
                 events.single_write(UiEvent::new(UiEventType::HoverStart, *target));
             }
@@ -160,14 +477,24 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
                 events.single_write(UiEvent::new(UiEventType::HoverStop, *last_target));
             }
 
-            if click_started {
-                self.click_started_on = targets.clone();
+            self.clicks.update(
+                &clicks_started,
+                &down,
+                &clicks_stopped,
+                &targets,
+                (x, y),
+                now,
+                &config,
+                &mut events,
+            );
+
+            let delta = Vector2::new(
+                input.mouse_wheel_delta(true),
+                input.mouse_wheel_delta(false),
+            );
+            if delta != Vector2::zeros() {
                 for target in targets.iter() {
-                    events.single_write(UiEvent::new(UiEventType::ClickStart, *target));
-                }
-            } else if click_stopped {
-                for click_start_target in self.click_started_on.intersection(&targets) {
-                    events.single_write(UiEvent::new(UiEventType::Click, *click_start_target));
+                    events.single_write(UiEvent::new(UiEventType::Scroll { delta }, *target));
                 }
             }
 
@@ -175,16 +502,45 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
         }
 
         // Could be used for drag and drop
-        if click_stopped {
-            for click_start_target in self.click_started_on.drain() {
-                events.single_write(UiEvent::new(UiEventType::ClickStop, click_start_target));
-            }
-        }
+        self.clicks.release(&clicks_stopped, &mut events);
 
         self.was_down = down;
     }
 }
 
+/// Checks if the positions `a` and `b` are at most `distance` apart.
+fn within(a: (f32, f32), b: (f32, f32), distance: f32) -> bool {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    dx * dx + dy * dy <= distance * distance
+}
+
+/// Translates the screen position `pos` to the position inside the `UiCanvas` shown by the
+/// `UiImage::RenderTarget` of `entity`, if any.
+pub(crate) fn canvas_position<T, I>(
+    pos: (f32, f32),
+    entity: Entity,
+    transforms: &T,
+    images: &I,
+    canvases: &ReadStorage<'_, UiCanvas>,
+) -> Option<(Target, (f32, f32))>
+where
+    T: GenericReadStorage<Component = UiTransform>,
+    I: GenericReadStorage<Component = UiImage>,
+{
+    let render_target = match images.get(entity) {
+        Some(UiImage::RenderTarget(TargetImage::Color(render_target, _))) => *render_target,
+        _ => return None,
+    };
+    let canvas = canvases
+        .join()
+        .find(|canvas| canvas.target == render_target)?;
+    let transform = transforms.get(entity)?;
+    let (x, y) = transform.to_layout_space(pos.0, pos.1)?;
+    let u = (x - transform.pixel_x) / transform.pixel_width + 0.5;
+    let v = (y - transform.pixel_y) / transform.pixel_height + 0.5;
+    Some((render_target, (u * canvas.width, v * canvas.height)))
+}
+
 /// Finds all interactable entities at the position `pos` which don't have any opaque entities on
 /// top blocking them.
 pub fn targeted<'a, I>(pos: (f32, f32), transforms: I) -> HashSet<Entity>
@@ -228,3 +584,152 @@ where
         })
         .and_then(|(e, _, m, _, _)| m.map(|_m| e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, World, WorldExt};
+
+    const LEFT: MouseButton = MouseButton::Left;
+
+    /// Feeds the mouse buttons down at each of the given times to `Clicks`, with the cursor at
+    /// the position of each time over `target`, and returns the click events sent.
+    fn clicks(target: Entity, frames: &[(f64, bool, (f32, f32))]) -> Vec<UiEventType> {
+        let mut events = EventChannel::new();
+        let mut reader = events.register_reader();
+        let mut clicks = Clicks::default();
+        let targets = std::iter::once(target).collect::<HashSet<_>>();
+        let mut was_down = false;
+        for &(now, is_down, position) in frames {
+            let down = if is_down {
+                std::iter::once(LEFT).collect()
+            } else {
+                HashSet::new()
+            };
+            let started = if is_down && !was_down {
+                vec![LEFT]
+            } else {
+                vec![]
+            };
+            let stopped = if !is_down && was_down {
+                vec![LEFT]
+            } else {
+                vec![]
+            };
+            clicks.update(
+                &started,
+                &down,
+                &stopped,
+                &targets,
+                position,
+                now,
+                &UiMouseConfig::default(),
+                &mut events,
+            );
+            clicks.release(&stopped, &mut events);
+            was_down = is_down;
+        }
+        events
+            .read(&mut reader)
+            .map(|event| event.event_type.clone())
+            .filter(|event_type| match event_type {
+                UiEventType::Click(_) | UiEventType::DoubleClick(_) | UiEventType::LongPress(_) => {
+                    true
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    fn target() -> Entity {
+        World::new().create_entity().build()
+    }
+
+    #[test]
+    fn two_quick_clicks_make_a_double_click() {
+        let frames = [
+            (0.0, true, (0., 0.)),
+            (0.1, false, (0., 0.)),
+            (0.2, true, (0., 0.)),
+            (0.3, false, (2., 2.)),
+        ];
+        assert_eq!(
+            clicks(target(), &frames),
+            vec![
+                UiEventType::Click(LEFT),
+                UiEventType::Click(LEFT),
+                UiEventType::DoubleClick(LEFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn slow_or_distant_clicks_are_not_double_clicks() {
+        let slow = [
+            (0.0, true, (0., 0.)),
+            (0.1, false, (0., 0.)),
+            (0.5, true, (0., 0.)),
+            (0.7, false, (0., 0.)),
+        ];
+        assert_eq!(
+            clicks(target(), &slow),
+            vec![UiEventType::Click(LEFT), UiEventType::Click(LEFT)]
+        );
+        let distant = [
+            (0.0, true, (0., 0.)),
+            (0.1, false, (0., 0.)),
+            (0.2, true, (10., 0.)),
+            (0.3, false, (10., 0.)),
+        ];
+        assert_eq!(
+            clicks(target(), &distant),
+            vec![UiEventType::Click(LEFT), UiEventType::Click(LEFT)]
+        );
+    }
+
+    #[test]
+    fn a_third_click_starts_a_new_double_click() {
+        let frames = [
+            (0.0, true, (0., 0.)),
+            (0.1, false, (0., 0.)),
+            (0.2, true, (0., 0.)),
+            (0.3, false, (0., 0.)),
+            (0.4, true, (0., 0.)),
+            (0.5, false, (0., 0.)),
+        ];
+        assert_eq!(
+            clicks(target(), &frames),
+            vec![
+                UiEventType::Click(LEFT),
+                UiEventType::Click(LEFT),
+                UiEventType::DoubleClick(LEFT),
+                UiEventType::Click(LEFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn holding_a_button_makes_a_long_press_instead_of_a_click() {
+        let frames = [
+            (0.0, true, (0., 0.)),
+            (0.5, true, (0., 0.)),
+            (0.7, true, (1., 1.)),
+            (0.8, true, (1., 1.)),
+            (0.9, false, (1., 1.)),
+        ];
+        assert_eq!(
+            clicks(target(), &frames),
+            vec![UiEventType::LongPress(LEFT)]
+        );
+    }
+
+    #[test]
+    fn moving_away_while_held_prevents_the_long_press() {
+        let frames = [
+            (0.0, true, (0., 0.)),
+            (0.7, true, (10., 0.)),
+            (0.8, false, (10., 0.)),
+        ];
+        assert_eq!(clicks(target(), &frames), vec![UiEventType::Click(LEFT)]);
+    }
+}
@@ -67,3 +67,17 @@ pub fn get_default_font(loader: &Loader, storage: &AssetStorage<FontAsset>) -> F
         storage,
     )
 }
+
+/// A handle to the built-in font, for the tests creating `UiText`s without a `Loader`.
+#[cfg(test)]
+pub(crate) fn test_font() -> FontHandle {
+    use amethyst_assets::{ProcessableAsset, ProcessingState};
+
+    let data = TtfFormat
+        .import_simple(include_bytes!("./square.ttf").to_vec())
+        .expect("Unable to import fallback font './square.ttf'");
+    match FontAsset::process(data) {
+        Ok(ProcessingState::Loaded(font)) => AssetStorage::new().insert(font),
+        _ => unreachable!("Fonts are loaded in one step"),
+    }
+}
@@ -5,6 +5,8 @@ use amethyst_assets::{Asset, Format, Handle, ProcessableAsset, ProcessingState};
 use amethyst_core::ecs::prelude::VecStorage;
 use amethyst_error::{format_err, Error, ResultExt};
 
+use crate::svg::SvgImage;
+
 /// A loaded set of fonts from a file.
 #[derive(Clone)]
 #[allow(missing_debug_implementations)]
@@ -51,3 +53,48 @@ impl Format<FontData> for TtfFormat {
             .with_context(|_| format_err!("Font parsing error"))
     }
 }
+
+/// A loaded SVG image, drawn by `UiImage::Vector`.
+#[derive(Debug, Clone)]
+pub struct SvgAsset(pub SvgImage);
+
+/// A handle to an SVG image stored with `amethyst_assets`.
+pub type SvgHandle = Handle<SvgAsset>;
+
+#[derive(Debug, Clone)]
+pub struct SvgData(SvgImage);
+
+amethyst_assets::register_format_type!(SvgData);
+
+impl Asset for SvgAsset {
+    const NAME: &'static str = "ui::Svg";
+    type Data = SvgData;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl ProcessableAsset for SvgAsset {
+    fn process(data: SvgData) -> Result<ProcessingState<SvgAsset>, Error> {
+        Ok(ProcessingState::Loaded(SvgAsset(data.0)))
+    }
+}
+
+/// Loads SVG files, supports paths, basic shapes, groups with transforms, and solid or gradient
+/// fills and strokes.
+///
+/// Text, clipping, masks, filters, patterns, `<use>` elements and style sheets are ignored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SvgFormat;
+
+amethyst_assets::register_format!("SVG", SvgFormat as SvgData);
+impl Format<SvgData> for SvgFormat {
+    fn name(&self) -> &'static str {
+        "SVG"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<SvgData, Error> {
+        let text = String::from_utf8(bytes).map_err(|_| format_err!("SVG is not UTF-8"))?;
+        SvgImage::parse(&text)
+            .map(SvgData)
+            .with_context(|_| format_err!("SVG parsing error"))
+    }
+}
@@ -1,8 +1,9 @@
 //! Module containing the system managing glyphbrush state for visible UI Text components.
 
 use crate::{
-    pass::UiArgs, text::CachedGlyph, FontAsset, LineMode, Selected, TextEditing, UiText,
-    UiTransform,
+    pass::{UiArgs, IDENTITY_TRANSFORM, NO_SHAPE},
+    text::CachedGlyph,
+    FontAsset, LineMode, Selected, TextEditing, UiText, UiTransform,
 };
 use amethyst_assets::{AssetStorage, Handle};
 use amethyst_core::{
@@ -201,7 +202,10 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                 });
                 let base_color = mul_blend(&ui_text.color, &tint_color);
 
-                let scale = Scale::uniform(ui_text.font_size);
+                // Text is rasterised at the size it is drawn at on the screen, and its glyphs are
+                // scaled back to the layout space of the transform.
+                let scale_factor = transform.scale_factor;
+                let scale = Scale::uniform(ui_text.font_size * scale_factor);
 
                 let text = match (ui_text.password, editing) {
                     (false, None) => vec![SectionText {
@@ -297,12 +301,17 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                     // Needs a recenter because we are using [-0.5,0.5] for the mesh
                     // instead of the expected [0,1]
                     screen_position: (
-                        transform.pixel_x + transform.pixel_width * ui_text.align.norm_offset().0,
+                        (transform.pixel_x + transform.pixel_width * ui_text.align.norm_offset().0)
+                            * scale_factor,
                         // invert y because layout calculates it in reverse
                         -(transform.pixel_y
-                            + transform.pixel_height * ui_text.align.norm_offset().1),
+                            + transform.pixel_height * ui_text.align.norm_offset().1)
+                            * scale_factor,
+                    ),
+                    bounds: (
+                        transform.pixel_width * scale_factor,
+                        transform.pixel_height * scale_factor,
                     ),
-                    bounds: (transform.pixel_width, transform.pixel_height),
                     // There is no other way to inject some glyph metadata than using Z.
                     // Fortunately depth is not required, so this slot is instead used to
                     // distinguish computed glyphs indented to be used for various entities.
@@ -326,9 +335,9 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                         let pos = g.position();
                         let advance_width = g.unpositioned().h_metrics().advance_width;
                         CachedGlyph {
-                            x: pos.x,
-                            y: -pos.y,
-                            advance_width,
+                            x: pos.x / scale_factor,
+                            y: -pos.y / scale_factor,
+                            advance_width: advance_width / scale_factor,
                         }
                     });
 
@@ -344,7 +353,8 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                         };
 
                         let advance_width =
-                            font_asset.glyph(c).scaled(scale).h_metrics().advance_width;
+                            font_asset.glyph(c).scaled(scale).h_metrics().advance_width
+                                / scale_factor;
 
                         let cached_glyph = CachedGlyph {
                             x,
@@ -460,6 +470,11 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                             tex_coord_bounds: tex_coord_bounds.into(),
                             color: glyph.color.into(),
                             color_bias: [1., 1., 1., 0.].into(),
+                            transform: IDENTITY_TRANSFORM.into(),
+                            shape: NO_SHAPE.into(),
+                            radii: [0, 0].into(),
+                            gradient: [0, 0].into(),
+                            shape_colors: [0, 0, 0, 0].into(),
                         },
                     )
                 },
@@ -493,7 +508,10 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                             .iter()
                             .take_while(|(id, _)| *id == e_id)
                             .count();
-                        let entity_verts = vertices[glyph_ctr..glyph_ctr + len].iter().map(|v| v.1);
+                        let scale_factor = transform.scale_factor;
+                        let entity_verts = vertices[glyph_ctr..glyph_ctr + len]
+                            .iter()
+                            .map(|v| unscaled(v.1, scale_factor));
                         glyph_ctr += len;
 
                         if let Some(glyph_data) = glyphs.get_mut(entity) {
@@ -545,6 +563,11 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                                 tex_coord_bounds: [0., 0., 1., 1.].into(),
                                 color: bg_color.into(),
                                 color_bias: [1., 1., 1., 0.].into(),
+                                transform: IDENTITY_TRANSFORM.into(),
+                                shape: NO_SHAPE.into(),
+                                radii: [0, 0].into(),
+                                gradient: [0, 0].into(),
+                                shape_colors: [0, 0, 0, 0].into(),
                             });
                             let mut glyph_data = glyphs.get_mut(entity).unwrap();
                             glyph_data.sel_vertices.extend(iter);
@@ -676,6 +699,17 @@ fn selection_span(editing: &TextEditing, string: &str) -> Option<(usize, usize)>
     }
 }
 
+/// Moves a glyph rasterised at `scale_factor` back to the layout space of its transform.
+fn unscaled(mut args: UiArgs, scale_factor: f32) -> UiArgs {
+    if scale_factor != 1.0 {
+        let coords: [f32; 2] = args.coords.into();
+        let dimensions: [f32; 2] = args.dimensions.into();
+        args.coords = [coords[0] / scale_factor, coords[1] / scale_factor].into();
+        args.dimensions = [dimensions[0] / scale_factor, dimensions[1] / scale_factor].into();
+    }
+    args
+}
+
 fn mul_blend(a: &[f32; 4], b: &[f32; 4]) -> [f32; 4] {
     [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
 }
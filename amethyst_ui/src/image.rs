@@ -1,6 +1,12 @@
 use amethyst_assets::Handle;
 use amethyst_core::ecs::{Component, DenseVecStorage};
-use amethyst_rendy::{SpriteRender, Texture};
+use amethyst_rendy::{
+    bundle::TargetImage,
+    palette::{LinSrgba, Srgba},
+    SpriteRender, Texture,
+};
+
+use crate::{SvgAsset, UiShape};
 
 /// Image used UI widgets, often as background.
 #[derive(Debug, Clone, PartialEq)]
@@ -64,8 +70,107 @@ pub enum UiImage {
     /// UiImage::SolidColor([r, g, b, a]);
     /// ```
     SolidColor([f32; 4]),
+    /// An image filled with a gradient between the linear RGBA colors of its corners.
+    ///
+    /// The colors are interpolated in sRGB space, so that a saturation/value square only needs
+    /// white, a pure hue and black corners. The gradient is interpolated per pixel by the ui
+    /// fragment shader.
+    Gradient {
+        /// Color of the top left corner
+        top_left: [f32; 4],
+        /// Color of the top right corner
+        top_right: [f32; 4],
+        /// Color of the bottom left corner
+        bottom_left: [f32; 4],
+        /// Color of the bottom right corner
+        bottom_right: [f32; 4],
+    },
+    /// A rectangle with rounded corners, a border, a gradient and a shadow, drawn without texture.
+    Shape(UiShape),
+    /// A vector image loaded with `SvgFormat`. It is rasterised at the size in pixels of its
+    /// `UiTransform`, keeping its aspect ratio, and again whenever that size changes.
+    Vector(Handle<SvgAsset>),
+    /// An image showing what was rendered into another render target, like a minimap or a
+    /// `UiCanvas`. The image must be made available with `RenderUi::with_image`.
+    RenderTarget(TargetImage),
 }
 
 impl Component for UiImage {
     type Storage = DenseVecStorage<Self>;
 }
+
+/// How a `UiImage` fills the `UiTransform` of its entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiImageFit {
+    /// The image is stretched to fill the transform.
+    Stretch,
+    /// The image keeps its aspect ratio and is as large as possible while staying inside the
+    /// transform, leaving empty bands on two sides.
+    Contain,
+    /// The image keeps its aspect ratio and is cropped to cover the whole transform.
+    Cover,
+    /// The image is repeated at its native size from the top left corner of the transform, and
+    /// cropped on the right and bottom edges. For `UiImage::NineSlice`, the edges and the center
+    /// are repeated instead of being stretched.
+    Tile,
+    /// The image is drawn at its size in pixels, centered on the transform.
+    NativeSize,
+}
+
+impl Default for UiImageFit {
+    fn default() -> Self {
+        UiImageFit::Stretch
+    }
+}
+
+/// Component changing how the `UiImage` of its entity is drawn. Images without one are stretched
+/// over their `UiTransform`.
+///
+/// The fit applies to `Texture`, `PartialTexture`, `Sprite`, `NineSlice` and `RenderTarget`
+/// images, while the flips apply to every image backed by a texture, including vector images.
+/// A `RenderTarget` image is fitted with the size of the target in pixels. A `NineSlice` image is
+/// fitted as a whole, then its slices are laid out over the fitted rect, so that its corners keep
+/// their size in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UiImageLayout {
+    /// How the image fills the transform.
+    pub fit: UiImageFit,
+    /// Whether the image is mirrored horizontally.
+    pub flip_x: bool,
+    /// Whether the image is mirrored vertically.
+    pub flip_y: bool,
+}
+
+impl UiImageLayout {
+    /// Creates a layout fitting the image with `fit`, without flipping it.
+    pub fn new(fit: UiImageFit) -> Self {
+        UiImageLayout {
+            fit,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// Sets whether the image is mirrored horizontally and vertically.
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+}
+
+impl Component for UiImageLayout {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Converts a linear RGBA color, as used by `UiImage::SolidColor`, to sRGB.
+pub(crate) fn linear_to_srgba([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    let (r, g, b, a) = Srgba::from_linear(LinSrgba::new(r, g, b, a)).into_components();
+    [r, g, b, a]
+}
+
+/// Converts an sRGB color to linear RGBA, as used by `UiImage::SolidColor`.
+pub(crate) fn srgba_to_linear([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    let (r, g, b, a) = Srgba::new(r, g, b, a).into_linear().into_components();
+    [r, g, b, a]
+}
@@ -6,14 +6,15 @@ use thread_profiler::profile_scope;
 
 use amethyst_core::{
     ecs::prelude::{
-        BitSet, ComponentEvent, Join, ReadExpect, ReadStorage, ReaderId, System, SystemData, World,
-        WriteStorage,
+        BitSet, ComponentEvent, Join, Read, ReadExpect, ReadStorage, ReaderId, System, SystemData,
+        World, WriteStorage,
     },
+    math::{Matrix3, Vector2},
     HierarchyEvent, Parent, ParentHierarchy, SystemDesc,
 };
 use amethyst_window::ScreenDimensions;
 
-use super::UiTransform;
+use super::{UiCanvas, UiScaler, UiTransform};
 
 /// Indicates if the position and margins should be calculated in pixel or
 /// relative to their parent size.
@@ -149,6 +150,7 @@ pub struct UiTransformSystem {
     transform_events_id: ReaderId<ComponentEvent>,
     parent_events_id: ReaderId<HierarchyEvent>,
     screen_size: (f32, f32),
+    scale_factor: f32,
 }
 
 impl UiTransformSystem {
@@ -162,6 +164,7 @@ impl UiTransformSystem {
             transform_events_id,
             parent_events_id,
             screen_size: (0.0, 0.0),
+            scale_factor: 1.0,
         }
     }
 }
@@ -170,14 +173,16 @@ impl<'a> System<'a> for UiTransformSystem {
     type SystemData = (
         WriteStorage<'a, UiTransform>,
         ReadStorage<'a, Parent>,
+        ReadStorage<'a, UiCanvas>,
         ReadExpect<'a, ScreenDimensions>,
         ReadExpect<'a, ParentHierarchy>,
+        Read<'a, UiScaler>,
     );
     fn run(&mut self, data: Self::SystemData) {
         #[cfg(feature = "profiler")]
         profile_scope!("ui_transform_system");
 
-        let (mut transforms, parents, screen_dim, hierarchy) = data;
+        let (mut transforms, parents, canvases, screen_dim, hierarchy, scaler) = data;
 
         self.transform_modified.clear();
 
@@ -204,19 +209,32 @@ impl<'a> System<'a> for UiTransformSystem {
         let current_screen_size = (screen_dim.width(), screen_dim.height());
         let screen_resized = current_screen_size != self.screen_size;
         self.screen_size = current_screen_size;
+        let scale_factor = scaler.scale_factor(&screen_dim);
+        let rescaled = scale_factor != self.scale_factor;
+        self.scale_factor = scale_factor;
+        let screen_resized = screen_resized || rescaled;
         if screen_resized {
             process_root_iter(
-                (&mut transforms, !&parents).join().map(|i| i.0),
+                (&mut transforms, canvases.maybe(), !&parents)
+                    .join()
+                    .map(|i| (i.0, i.1)),
                 &*screen_dim,
+                scale_factor,
             );
         } else {
             // Immutable borrow
             let self_transform_modified = &*self_transform_modified;
             process_root_iter(
-                (&mut transforms, !&parents, self_transform_modified)
+                (
+                    &mut transforms,
+                    canvases.maybe(),
+                    !&parents,
+                    self_transform_modified,
+                )
                     .join()
-                    .map(|i| i.0),
+                    .map(|i| (i.0, i.1)),
                 &*screen_dim,
+                scale_factor,
             );
         }
 
@@ -255,6 +273,7 @@ impl<'a> System<'a> for UiTransformSystem {
                     transform.pixel_y =
                         parent_transform_copy.pixel_y + parent_transform_copy.pixel_height * norm.1;
                     transform.global_z = parent_transform_copy.global_z + transform.local_z;
+                    transform.scale_factor = parent_transform_copy.scale_factor;
 
                     let new_size = match transform.stretch {
                         Stretch::NoStretch => (transform.width, transform.height),
@@ -312,6 +331,8 @@ impl<'a> System<'a> for UiTransformSystem {
                     let pivot_norm = transform.pivot.norm_offset();
                     transform.pixel_x += transform.pixel_width * -pivot_norm.0;
                     transform.pixel_y += transform.pixel_height * -pivot_norm.1;
+                    transform.global_matrix =
+                        parent_transform_copy.global_matrix * transform.local_matrix();
                 }
             }
             // Populate the modifications we just did.
@@ -338,27 +359,41 @@ impl<'a> System<'a> for UiTransformSystem {
     }
 }
 
-fn process_root_iter<'a, I>(iter: I, screen_dim: &ScreenDimensions)
+fn process_root_iter<'a, I>(iter: I, screen_dim: &ScreenDimensions, scale_factor: f32)
 where
-    I: Iterator<Item = &'a mut UiTransform>,
+    I: Iterator<Item = (&'a mut UiTransform, Option<&'a UiCanvas>)>,
 {
-    for transform in iter {
+    for (transform, canvas) in iter {
+        // Canvases are laid out in the size of their render target and are not scaled.
+        // Other roots are laid out in a virtual screen, which is then scaled to the real one.
+        let (screen_width, screen_height, scale_factor) = match canvas {
+            Some(canvas) => (canvas.width, canvas.height, 1.0),
+            None if transform.canvas_matrix.is_some() => {
+                (screen_dim.width(), screen_dim.height(), 1.0)
+            }
+            None => (
+                screen_dim.width() / scale_factor,
+                screen_dim.height() / scale_factor,
+                scale_factor,
+            ),
+        };
+        transform.scale_factor = scale_factor;
         let norm = transform.anchor.norm_offset();
-        transform.pixel_x = screen_dim.width() / 2.0 + screen_dim.width() * norm.0;
-        transform.pixel_y = screen_dim.height() / 2.0 + screen_dim.height() * norm.1;
+        transform.pixel_x = screen_width / 2.0 + screen_width * norm.0;
+        transform.pixel_y = screen_height / 2.0 + screen_height * norm.1;
         transform.global_z = transform.local_z;
 
         let new_size = match transform.stretch {
             Stretch::NoStretch => (transform.width, transform.height),
-            Stretch::X { x_margin } => (screen_dim.width() - x_margin * 2.0, transform.height),
-            Stretch::Y { y_margin } => (transform.width, screen_dim.height() - y_margin * 2.0),
+            Stretch::X { x_margin } => (screen_width - x_margin * 2.0, transform.height),
+            Stretch::Y { y_margin } => (transform.width, screen_height - y_margin * 2.0),
             Stretch::XY {
                 keep_aspect_ratio: false,
                 x_margin,
                 y_margin,
             } => (
-                screen_dim.width() - x_margin * 2.0,
-                screen_dim.height() - y_margin * 2.0,
+                screen_width - x_margin * 2.0,
+                screen_height - y_margin * 2.0,
             ),
             Stretch::XY {
                 keep_aspect_ratio: true,
@@ -366,8 +401,8 @@ where
                 y_margin,
             } => {
                 let scale = f32::min(
-                    (screen_dim.width() - x_margin * 2.0) / transform.width,
-                    (screen_dim.height() - y_margin * 2.0) / transform.height,
+                    (screen_width - x_margin * 2.0) / transform.width,
+                    (screen_height - y_margin * 2.0) / transform.height,
                 );
 
                 (transform.width * scale, transform.height * scale)
@@ -383,14 +418,77 @@ where
                 transform.pixel_height = transform.height;
             }
             ScaleMode::Percent => {
-                transform.pixel_x += transform.local_x * screen_dim.width();
-                transform.pixel_y += transform.local_y * screen_dim.height();
-                transform.pixel_width = transform.width * screen_dim.width();
-                transform.pixel_height = transform.height * screen_dim.height();
+                transform.pixel_x += transform.local_x * screen_width;
+                transform.pixel_y += transform.local_y * screen_height;
+                transform.pixel_width = transform.width * screen_width;
+                transform.pixel_height = transform.height * screen_height;
             }
         }
         let pivot_norm = transform.pivot.norm_offset();
         transform.pixel_x += transform.pixel_width * -pivot_norm.0;
         transform.pixel_y += transform.pixel_height * -pivot_norm.1;
+        transform.global_matrix = match transform.canvas_matrix {
+            Some(canvas_matrix) => {
+                canvas_matrix
+                    * Matrix3::new_translation(&-Vector2::new(transform.pixel_x, transform.pixel_y))
+                    * transform.local_matrix()
+            }
+            None => {
+                Matrix3::new_nonuniform_scaling(&Vector2::new(scale_factor, scale_factor))
+                    * transform.local_matrix()
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_rendy::bundle::Target;
+
+    fn top_right(id: &str) -> UiTransform {
+        UiTransform::new(
+            id.to_string(),
+            Anchor::TopRight,
+            Anchor::TopRight,
+            0.,
+            0.,
+            0.,
+            100.,
+            50.,
+        )
+    }
+
+    #[test]
+    fn canvas_roots_are_laid_out_in_their_target_without_scaling() {
+        let screen = ScreenDimensions::new(1600, 1200, 1.0);
+        let canvas = UiCanvas::new(Target::Custom("minimap"), 400., 300.);
+        let mut on_screen = top_right("screen");
+        let mut on_canvas = top_right("canvas");
+        let mut stretched = top_right("stretched").with_stretch(Stretch::XY {
+            x_margin: 10.,
+            y_margin: 10.,
+            keep_aspect_ratio: false,
+        });
+        process_root_iter(
+            vec![
+                (&mut on_screen, None),
+                (&mut on_canvas, Some(&canvas)),
+                (&mut stretched, Some(&canvas)),
+            ]
+            .into_iter(),
+            &screen,
+            2.0,
+        );
+
+        // The screen is 800 by 600 ui pixels, scaled twice.
+        assert_eq!((on_screen.pixel_x, on_screen.pixel_y), (750., 575.));
+        assert_eq!(on_screen.scale_factor, 2.0);
+        assert_eq!((on_canvas.pixel_x, on_canvas.pixel_y), (350., 275.));
+        assert_eq!(on_canvas.scale_factor, 1.0);
+        assert_eq!(
+            (stretched.pixel_width, stretched.pixel_height),
+            (380., 280.)
+        );
     }
 }
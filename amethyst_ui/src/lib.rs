@@ -10,6 +10,7 @@
 #![allow(clippy::new_without_default)]
 
 pub use self::{
+    binding::{UiBinding, UiBindingRegistry, UiBindingSystem, UiBindingValue},
     blink::BlinkSystem,
     bundle::UiBundle,
     button::{
@@ -17,9 +18,12 @@ pub use self::{
         UiButtonActionRetriggerSystemDesc, UiButtonActionType, UiButtonBuilder,
         UiButtonBuilderResources, UiButtonSystem, UiButtonSystemDesc,
     },
-    drag::{DragWidgetSystemDesc, Draggable},
+    canvas::UiCanvas,
+    color_picker::{UiColorPicker, UiColorPickerSystem, UiColorPickerSystemDesc},
+    drag::{DragPayload, DragWidgetSystemDesc, Draggable, DropTarget},
     event::{
-        targeted, targeted_below, Interactable, TargetedEvent, UiEvent, UiEventType, UiMouseSystem,
+        targeted, targeted_below, Interactable, TargetedEvent, UiEvent, UiEventPhase, UiEventType,
+        UiMouseConfig, UiMouseSystem,
     },
     event_retrigger::{
         EventReceiver, EventRetrigger, EventRetriggerSystem, EventRetriggerSystemDesc,
@@ -28,38 +32,71 @@ pub use self::{
         default::get_default_font,
         systemfont::{default_system_font, get_all_font_handles, list_system_font_families},
     },
-    format::{FontAsset, FontHandle, TtfFormat},
+    format::{FontAsset, FontHandle, SvgAsset, SvgFormat, SvgHandle, TtfFormat},
     glyphs::{UiGlyphsSystem, UiGlyphsSystemDesc},
-    image::UiImage,
+    image::{UiImage, UiImageFit, UiImageLayout},
     label::{UiLabel, UiLabelBuilder, UiLabelBuilderResources},
     layout::{Anchor, ScaleMode, Stretch, UiTransformSystem, UiTransformSystemDesc},
+    list::{UiListColumn, UiListSource, UiListView, UiListViewSystem, UiListViewSystemDesc},
+    menu::{
+        UiMenu, UiMenuEntry, UiMenuEvent, UiMenuItem, UiMenuKind, UiMenuStyle, UiMenuSystem,
+        UiMenuSystemDesc,
+    },
+    modal::{UiModal, UiModalStack, UiModalSystem},
+    opacity::{global_opacity, UiGlobalOpacities, UiOpacity, UiOpacityConfig},
     pass::{DrawUi, DrawUiDesc, RenderUi},
     prefab::{
         NoCustomUi, TextEditingPrefab, ToNativeWidget, UiButtonData, UiCreator, UiFormat,
         UiImageLoadPrefab, UiImagePrefab, UiLoader, UiLoaderSystem, UiLoaderSystemDesc, UiPrefab,
-        UiTextData, UiTransformData, UiWidget,
+        UiTabsData, UiTabsPrefab, UiTextData, UiTransformData, UiWidget, UiWindowData,
+        UiWindowPrefab,
     },
+    prefab_reload::UiPrefabReloadSystem,
+    propagation::{UiEventHandler, UiEventHandlerSystem, UiPropagation},
     resize::{ResizeSystem, ResizeSystemDesc, UiResize},
+    scaler::{UiScaleMode, UiScaler},
     selection::{
         Selectable, Selected, SelectionKeyboardSystem, SelectionKeyboardSystemDesc,
         SelectionMouseSystem, SelectionMouseSystemDesc,
     },
     selection_order_cache::{CacheSelectionOrderSystem, CachedSelectionOrder},
+    shape::{UiShadow, UiShape, UiShapeFill},
     sound::{
         UiPlaySoundAction, UiSoundRetrigger, UiSoundRetriggerSystem, UiSoundRetriggerSystemDesc,
         UiSoundSystem, UiSoundSystemDesc,
     },
+    spin_box::{UiSpinBox, UiSpinBoxSystem, UiSpinBoxSystemDesc},
+    svg::SvgImage,
+    tabs::{UiTabs, UiTabsConfig, UiTabsSystem, UiTabsSystemDesc},
     text::{LineMode, TextEditing, TextEditingMouseSystem, TextEditingMouseSystemDesc, UiText},
     text_editing::{TextEditingInputSystem, TextEditingInputSystemDesc},
+    theme::{
+        UiActiveTheme, UiClass, UiStyle, UiStyleOverrides, UiTheme, UiThemeSystem,
+        UiThemeSystemDesc,
+    },
+    tooltip::{
+        place_tooltip, UiTooltip, UiTooltipContent, UiTooltipData, UiTooltipPlacement,
+        UiTooltipStyle, UiTooltipSystem, UiTooltipSystemDesc,
+    },
     transform::{get_parent_pixel_size, UiFinder, UiTransform},
+    tree::{UiTreeNode, UiTreeView, UiTreeViewSystem, UiTreeViewSystemDesc},
+    vector::{UiVectorSystem, UiVectorSystemDesc},
     widgets::{Widget, WidgetId, Widgets},
+    window::{
+        UiWindow, UiWindowBuilder, UiWindowBuilderResources, UiWindowEdge, UiWindowFrame,
+        UiWindowLayout, UiWindowLayouts, UiWindowPart, UiWindowSystem, UiWindowSystemDesc,
+    },
+    world::{UiWorldAnchor, UiWorldCanvas, UiWorldSystem},
 };
 
 pub(crate) use amethyst_core::ecs::prelude::Entity;
 
+mod binding;
 mod blink;
 mod bundle;
 mod button;
+mod canvas;
+mod color_picker;
 mod drag;
 mod event;
 mod event_retrigger;
@@ -69,13 +106,31 @@ mod glyphs;
 mod image;
 mod label;
 mod layout;
+mod list;
+mod menu;
+mod modal;
+mod opacity;
 mod pass;
 mod prefab;
+mod prefab_reload;
+mod propagation;
 mod resize;
+mod rows;
+mod scaler;
 mod selection;
 mod selection_order_cache;
+mod shape;
 mod sound;
+mod spin_box;
+mod svg;
+mod tabs;
 mod text;
 mod text_editing;
+mod theme;
+mod tooltip;
 mod transform;
+mod tree;
+mod vector;
 mod widgets;
+mod window;
+mod world;
@@ -0,0 +1,826 @@
+//! Virtualised list and table views.
+
+use std::{cmp::Ordering, collections::HashMap, fmt, marker::PhantomData};
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReaderId, System,
+        SystemData, WriteStorage,
+    },
+    shrev::EventChannel,
+    HiddenPropagate, Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use winit::MouseButton;
+
+use crate::{
+    font::default::get_default_font,
+    rows::{create_row, hide_row, show_row, show_selection, sync_selection, view_of, Row},
+    Anchor, FontAsset, FontHandle, Interactable, LineMode, Selectable, Selected, Stretch, UiEvent,
+    UiEventType, UiImage, UiText, UiTransform,
+};
+
+const DEFAULT_ROW_HEIGHT: f32 = 24.0;
+const DEFAULT_FONT_SIZE: f32 = 18.0;
+const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+/// Space left between the border of a cell and its text.
+const CELL_PADDING: f32 = 4.0;
+
+/// Provides the rows shown by a `UiListView`.
+pub trait UiListSource: Send + Sync + 'static {
+    /// Returns the number of rows.
+    fn len(&self) -> usize;
+
+    /// Checks if there are no rows.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the text of the cell of `row` in `column`.
+    fn cell(&self, row: usize, column: usize) -> String;
+
+    /// Returns the height of `row`, or `None` to use the `row_height` of the view.
+    fn row_height(&self, _row: usize) -> Option<f32> {
+        None
+    }
+
+    /// Orders two rows when sorting by `column`. By default, cells which both parse as numbers
+    /// are compared as numbers, and other cells by their text.
+    fn compare(&self, a: usize, b: usize, column: usize) -> Ordering {
+        let (a, b) = (self.cell(a, column), self.cell(b, column));
+        match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+            (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or_else(|| a.cmp(&b)),
+            _ => a.cmp(&b),
+        }
+    }
+}
+
+impl UiListSource for Vec<String> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn cell(&self, row: usize, _column: usize) -> String {
+        self[row].clone()
+    }
+}
+
+impl UiListSource for Vec<Vec<String>> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> String {
+        self[row].get(column).cloned().unwrap_or_default()
+    }
+}
+
+/// A column of a `UiListView`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiListColumn {
+    /// The text of the column header.
+    pub title: String,
+    /// The width of the column. Columns with a width of zero share the width left by the others.
+    pub width: f32,
+    /// Whether clicking the header sorts the rows by this column.
+    pub sortable: bool,
+}
+
+impl UiListColumn {
+    /// Creates a new sortable column.
+    pub fn new<S: ToString>(title: S, width: f32) -> Self {
+        UiListColumn {
+            title: title.to_string(),
+            width,
+            sortable: true,
+        }
+    }
+
+    /// Sets whether clicking the header sorts the rows by this column.
+    pub fn with_sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+}
+
+/// A row showing a row of the source, with a cell per column.
+type ListRow = Row<usize, Vec<Entity>>;
+
+/// Component showing the rows of a `UiListSource` in the `UiTransform` of its entity.
+///
+/// Only the visible rows get entities, which are children of the list and are reused while
+/// scrolling with the mouse wheel. The list scrolls one row at a time, and rows which don't fit
+/// entirely are not shown, as ui elements aren't clipped by their parent.
+///
+/// With several columns, the list is a table: a header row shows the title of each column, and
+/// clicking a sortable header sorts the rows by that column, clicking it again reverses the order.
+/// Rows are `Selectable`, with the tab orders following `tab_order`, and the row selected with
+/// the mouse or the keyboard is kept while it is scrolled out of view.
+pub struct UiListView {
+    source: Box<dyn UiListSource>,
+    /// The columns of the list.
+    pub columns: Vec<UiListColumn>,
+    /// The height of the rows for which the source returns no height.
+    pub row_height: f32,
+    /// The height of the header row. There is no header when none of the columns have a title.
+    pub header_height: f32,
+    /// The font of the cells. Defaults to the default font.
+    pub font: Option<FontHandle>,
+    /// The font size of the cells.
+    pub font_size: f32,
+    /// The text color of the cells.
+    pub text_color: [f32; 4],
+    /// The image drawn behind the rows.
+    pub row_image: Option<UiImage>,
+    /// The image drawn behind the selected row.
+    pub selected_row_image: Option<UiImage>,
+    /// The image drawn behind the header.
+    pub header_image: Option<UiImage>,
+    /// The tab order of the first shown row, the next rows take the following orders.
+    pub tab_order: u32,
+    /// The source rows, in display order.
+    order: Vec<usize>,
+    /// The position in `order` of the first visible row.
+    first_row: usize,
+    sorting: Option<(usize, bool)>,
+    selected: Option<usize>,
+    /// Whether the focus is on the list, which keeps it while the selected row is out of view.
+    focused: bool,
+    dirty: bool,
+    rows: Vec<ListRow>,
+    headers: Vec<Entity>,
+}
+
+impl fmt::Debug for UiListView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UiListView")
+            .field("rows", &self.source.len())
+            .field("columns", &self.columns)
+            .field("first_row", &self.first_row)
+            .field("sorting", &self.sorting)
+            .field("selected", &self.selected)
+            .finish()
+    }
+}
+
+impl UiListView {
+    /// Creates a list with a single column showing the rows of `source`.
+    pub fn new<S: UiListSource>(source: S) -> Self {
+        UiListView {
+            source: Box::new(source),
+            columns: vec![UiListColumn::new("", 0.0)],
+            row_height: DEFAULT_ROW_HEIGHT,
+            header_height: DEFAULT_ROW_HEIGHT,
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            text_color: DEFAULT_TXT_COLOR,
+            row_image: None,
+            selected_row_image: None,
+            header_image: None,
+            tab_order: 0,
+            order: Vec::new(),
+            first_row: 0,
+            sorting: None,
+            selected: None,
+            focused: false,
+            dirty: true,
+            rows: Vec::new(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Sets the columns, making the list a table.
+    pub fn with_columns(mut self, columns: Vec<UiListColumn>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Sets the height of the rows for which the source returns no height.
+    pub fn with_row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    /// Sets the height of the header row.
+    pub fn with_header_height(mut self, header_height: f32) -> Self {
+        self.header_height = header_height;
+        self
+    }
+
+    /// Sets the font of the cells.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of the cells.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Sets the text color of the cells.
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    /// Sets the images drawn behind the rows and behind the selected row.
+    pub fn with_row_images(mut self, row: UiImage, selected: UiImage) -> Self {
+        self.row_image = Some(row);
+        self.selected_row_image = Some(selected);
+        self
+    }
+
+    /// Sets the image drawn behind the header.
+    pub fn with_header_image(mut self, image: UiImage) -> Self {
+        self.header_image = Some(image);
+        self
+    }
+
+    /// Sets the tab order of the first shown row.
+    pub fn with_tab_order(mut self, tab_order: u32) -> Self {
+        self.tab_order = tab_order;
+        self
+    }
+
+    /// Returns the source of the rows.
+    pub fn source(&self) -> &dyn UiListSource {
+        &*self.source
+    }
+
+    /// Replaces the source of the rows.
+    pub fn set_source<S: UiListSource>(&mut self, source: S) {
+        self.source = Box::new(source);
+        self.selected = None;
+        self.refresh();
+    }
+
+    /// Updates the shown rows after the source changed.
+    pub fn refresh(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns the selected row of the source, if any.
+    pub fn selected_row(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Selects a row of the source.
+    pub fn select_row(&mut self, row: Option<usize>) {
+        self.selected = row;
+    }
+
+    /// Returns the column the rows are sorted by and whether the order is ascending.
+    pub fn sorting(&self) -> Option<(usize, bool)> {
+        self.sorting
+    }
+
+    /// Sorts the rows by `column`, or shows them in the order of the source.
+    pub fn sort_by(&mut self, sorting: Option<(usize, bool)>) {
+        self.sorting = sorting;
+        self.refresh();
+    }
+
+    /// Returns the rows of the source, in the order they are shown.
+    pub fn display_order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Scrolls the list so that the row shown at `position` is the first visible one.
+    pub fn scroll_to(&mut self, position: usize) {
+        self.first_row = position;
+    }
+
+    fn has_header(&self) -> bool {
+        self.columns.iter().any(|column| !column.title.is_empty())
+    }
+
+    fn height_of(&self, row: usize) -> f32 {
+        self.source.row_height(row).unwrap_or(self.row_height)
+    }
+
+    /// Recomputes the display order of the rows.
+    fn update_order(&mut self) {
+        let source = &self.source;
+        self.order = (0..source.len()).collect();
+        if let Some((column, ascending)) = self.sorting {
+            self.order.sort_by(|a, b| {
+                let ordering = source.compare(*a, *b, column);
+                if ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            });
+        }
+        if self.selected.map_or(false, |row| row >= source.len()) {
+            self.selected = None;
+        }
+    }
+
+    /// Returns the largest first row for which the last rows fill `height`.
+    fn last_page_start(&self, height: f32) -> usize {
+        let mut used = 0.0;
+        for (position, row) in self.order.iter().enumerate().rev() {
+            used += self.height_of(*row);
+            if used > height {
+                return position + 1;
+            }
+        }
+        0
+    }
+
+    /// Returns the offsets and heights of the rows from `first_row` fitting in `height`.
+    fn visible_rows(&self, height: f32) -> Vec<(usize, f32, f32)> {
+        let mut visible = Vec::new();
+        let mut offset = 0.0;
+        for row in self.order.iter().skip(self.first_row) {
+            let row_height = self.height_of(*row);
+            if offset + row_height > height {
+                break;
+            }
+            visible.push((*row, offset, row_height));
+            offset += row_height;
+        }
+        visible
+    }
+}
+
+impl Component for UiListView {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns the left edge and the width of each column of a list `width` wide.
+fn column_layout(columns: &[UiListColumn], width: f32) -> Vec<(f32, f32)> {
+    let fixed: f32 = columns.iter().map(|column| column.width.max(0.0)).sum();
+    let flexible = columns.iter().filter(|column| column.width <= 0.0).count();
+    let flexible_width = if flexible > 0 {
+        ((width - fixed) / flexible as f32).max(0.0)
+    } else {
+        0.0
+    };
+    let mut x = 0.0;
+    columns
+        .iter()
+        .map(|column| {
+            let column_width = if column.width > 0.0 {
+                column.width
+            } else {
+                flexible_width
+            };
+            let layout = (x, column_width);
+            x += column_width;
+            layout
+        })
+        .collect()
+}
+
+/// System creating, recycling and updating the rows of the `UiListView`s.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiListViewSystemDesc))]
+pub struct UiListViewSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+    phantom: PhantomData<G>,
+}
+
+impl<G> UiListViewSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    /// Creates a new `UiListViewSystem`.
+    pub fn new(ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            ui_reader_id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, G> System<'a> for UiListViewSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<FontAsset>>,
+        ReadExpect<'a, ParentHierarchy>,
+        Read<'a, EventChannel<UiEvent>>,
+        WriteStorage<'a, UiListView>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, Interactable>,
+        WriteStorage<'a, Selectable<G>>,
+        WriteStorage<'a, Selected>,
+        WriteStorage<'a, HiddenPropagate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            font_assets,
+            hierarchy,
+            ui_events,
+            mut lists,
+            mut ui_transforms,
+            mut texts,
+            mut images,
+            mut parents,
+            mut interactables,
+            mut selectables,
+            mut selecteds,
+            mut hiddens,
+        ): Self::SystemData,
+    ) {
+        // A wheel step can reach a list through several of its elements, it scrolls it once.
+        let mut scrolls = HashMap::new();
+        for event in ui_events.read(&mut self.ui_reader_id) {
+            let list_entity = match view_of(event.target, &hierarchy, |e| lists.contains(e)) {
+                Some(list_entity) => list_entity,
+                None => continue,
+            };
+            match event.event_type {
+                UiEventType::Scroll { delta } => {
+                    scrolls.entry(list_entity).or_insert(delta[1]);
+                }
+                UiEventType::Click(MouseButton::Left) => {
+                    let list = lists
+                        .get_mut(list_entity)
+                        .expect("unreachable: We just found this list");
+                    let column = list.headers.iter().position(|e| *e == event.target);
+                    let sortable = |c: &usize| list.columns.get(*c).map_or(false, |c| c.sortable);
+                    if let Some(column) = column.filter(sortable) {
+                        let ascending = list.sorting != Some((column, true));
+                        list.sort_by(Some((column, ascending)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (list_entity, list) in (&*entities, &mut lists).join() {
+            let (width, height) = match ui_transforms.get(list_entity) {
+                Some(transform) => (transform.pixel_width(), transform.pixel_height()),
+                None => continue,
+            };
+            let font = list
+                .font
+                .get_or_insert_with(|| get_default_font(&loader, &font_assets))
+                .clone();
+            let new_text = |list: &UiListView, text: String| {
+                UiText::new(
+                    font.clone(),
+                    text,
+                    list.text_color,
+                    list.font_size,
+                    LineMode::Single,
+                    Anchor::MiddleLeft,
+                )
+            };
+            let columns = column_layout(&list.columns, width);
+            let header_height = if list.has_header() {
+                list.header_height
+            } else {
+                0.0
+            };
+
+            // A source changed without a refresh would leave rows out of its range in the order.
+            if list.dirty || list.order.len() != list.source.len() {
+                list.dirty = false;
+                list.update_order();
+                for row in list.rows.iter_mut() {
+                    row.bound = None;
+                }
+            }
+
+            // Rows created for other columns are replaced.
+            if list
+                .rows
+                .iter()
+                .any(|row| row.elements.len() != list.columns.len())
+            {
+                for row in list.rows.drain(..) {
+                    for cell in row.elements {
+                        let _ = entities.delete(cell);
+                    }
+                    let _ = entities.delete(row.entity);
+                }
+            }
+
+            // The header row.
+            if list.headers.len() != list.columns.len() {
+                for header in list.headers.drain(..) {
+                    let _ = entities.delete(header);
+                }
+                let id = transform_id(list_entity, &ui_transforms);
+                let titles = list
+                    .columns
+                    .iter()
+                    .map(|column| column.title.clone())
+                    .collect::<Vec<_>>();
+                for (index, title) in titles.into_iter().enumerate() {
+                    let header = entities.create();
+                    let transform = UiTransform::new(
+                        format!("{}_header_{}", id, index),
+                        Anchor::TopLeft,
+                        Anchor::TopLeft,
+                        0.,
+                        0.,
+                        1.,
+                        0.,
+                        0.,
+                    );
+                    let _ = ui_transforms.insert(header, transform);
+                    let _ = texts.insert(header, new_text(list, title));
+                    let _ = interactables.insert(header, Interactable);
+                    let _ = parents.insert(
+                        header,
+                        Parent {
+                            entity: list_entity,
+                        },
+                    );
+                    if let Some(image) = list.header_image.clone() {
+                        let _ = images.insert(header, image);
+                    }
+                    list.headers.push(header);
+                }
+            }
+            for (header, (x, column_width)) in list.headers.iter().zip(columns.iter()) {
+                set_layout(
+                    *header,
+                    (*x, 0.0, *column_width, header_height),
+                    &mut ui_transforms,
+                );
+                if header_height > 0.0 {
+                    hiddens.remove(*header);
+                } else if !hiddens.contains(*header) {
+                    let _ = hiddens.insert(*header, HiddenPropagate::new());
+                }
+            }
+
+            // Scrolling moves the first row, scrolling up shows the rows above.
+            let body_height = height - header_height;
+            let last_page_start = list.last_page_start(body_height);
+            if let Some(delta) = scrolls.get(&list_entity) {
+                let first_row = list.first_row as f32 - delta.round();
+                list.first_row = first_row.max(0.0) as usize;
+            }
+            list.first_row = list.first_row.min(last_page_start);
+            let visible = list.visible_rows(body_height);
+
+            // Selection changes made with the mouse or the keyboard on the shown rows.
+            sync_selection(
+                &list.rows,
+                &mut list.selected,
+                &mut list.focused,
+                &entities,
+                &selecteds,
+            );
+
+            // Create the missing rows.
+            while list.rows.len() < visible.len() {
+                let index = list.rows.len();
+                let id = transform_id(list_entity, &ui_transforms);
+                let entity = create_row(
+                    list_entity,
+                    &id,
+                    index,
+                    list.tab_order,
+                    &entities,
+                    &mut ui_transforms,
+                    &mut interactables,
+                    &mut selectables,
+                    &mut parents,
+                );
+                let cells = (0..list.columns.len())
+                    .map(|column| {
+                        let cell = entities.create();
+                        let _ = ui_transforms.insert(
+                            cell,
+                            UiTransform::new(
+                                format!("{}_row_{}_cell_{}", id, index, column),
+                                Anchor::MiddleLeft,
+                                Anchor::MiddleLeft,
+                                0.,
+                                0.,
+                                1.,
+                                0.,
+                                0.,
+                            )
+                            .with_stretch(Stretch::Y { y_margin: 0. })
+                            .into_transparent(),
+                        );
+                        let _ = texts.insert(cell, new_text(list, String::new()));
+                        let _ = parents.insert(cell, Parent { entity });
+                        cell
+                    })
+                    .collect();
+                list.rows.push(ListRow::new(entity, cells));
+            }
+
+            // Bind the shown rows, hide the others.
+            for (index, row) in list.rows.iter_mut().enumerate() {
+                let (source_row, offset, row_height) = match visible.get(index) {
+                    Some(visible) => *visible,
+                    None => {
+                        hide_row(row, &mut hiddens, &mut selecteds);
+                        continue;
+                    }
+                };
+                let rebound = show_row(
+                    row,
+                    (index, source_row),
+                    list.tab_order,
+                    &mut hiddens,
+                    &mut selectables,
+                );
+                set_layout(
+                    row.entity,
+                    (0.0, header_height + offset, width, row_height),
+                    &mut ui_transforms,
+                );
+                for (column, (cell, (x, column_width))) in
+                    row.elements.iter().zip(columns.iter()).enumerate()
+                {
+                    set_layout(
+                        *cell,
+                        (
+                            x + CELL_PADDING,
+                            0.0,
+                            column_width - CELL_PADDING * 2.0,
+                            0.0,
+                        ),
+                        &mut ui_transforms,
+                    );
+                    if rebound {
+                        if let Some(text) = texts.get_mut(*cell) {
+                            text.text = list.source.cell(source_row, column);
+                        }
+                    }
+                }
+            }
+
+            // Show the selection on the row bound to the selected source row.
+            show_selection(
+                &mut list.rows,
+                (list.selected, list.focused),
+                (list.row_image.as_ref(), list.selected_row_image.as_ref()),
+                &mut selecteds,
+                &mut images,
+            );
+        }
+    }
+}
+
+/// Returns the id of the `UiTransform` of `entity`, used to name the elements created for it.
+pub(crate) fn transform_id(
+    entity: Entity,
+    ui_transforms: &WriteStorage<'_, UiTransform>,
+) -> String {
+    ui_transforms
+        .get(entity)
+        .map_or_else(String::new, |transform| transform.id.clone())
+}
+
+/// Places `entity` at `(x, y)` from the top left corner of its parent, with the given size. A
+/// height of zero keeps the current one.
+pub(crate) fn set_layout(
+    entity: Entity,
+    (x, y, width, height): (f32, f32, f32, f32),
+    ui_transforms: &mut WriteStorage<'_, UiTransform>,
+) {
+    let current = match ui_transforms.get(entity) {
+        Some(transform) => (
+            transform.local_x,
+            transform.local_y,
+            transform.width,
+            transform.height,
+        ),
+        None => return,
+    };
+    let height = if height > 0.0 { height } else { current.3 };
+    if current != (x, -y, width, height) {
+        let transform = ui_transforms
+            .get_mut(entity)
+            .expect("unreachable: We just got this transform");
+        transform.local_x = x;
+        transform.local_y = -y;
+        transform.width = width;
+        transform.height = height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A source whose rows are as high as their index plus one.
+    struct Growing(usize);
+
+    impl UiListSource for Growing {
+        fn len(&self) -> usize {
+            self.0
+        }
+
+        fn cell(&self, row: usize, _column: usize) -> String {
+            row.to_string()
+        }
+
+        fn row_height(&self, row: usize) -> Option<f32> {
+            Some(row as f32 + 1.0)
+        }
+    }
+
+    fn list<S: UiListSource>(source: S) -> UiListView {
+        let mut list = UiListView::new(source).with_row_height(10.0);
+        list.update_order();
+        list
+    }
+
+    #[test]
+    fn column_layout_shares_the_width_left() {
+        let columns = vec![
+            UiListColumn::new("a", 30.0),
+            UiListColumn::new("b", 0.0),
+            UiListColumn::new("c", 10.0),
+            UiListColumn::new("d", 0.0),
+        ];
+        assert_eq!(
+            column_layout(&columns, 100.0),
+            vec![(0.0, 30.0), (30.0, 30.0), (60.0, 10.0), (70.0, 30.0)]
+        );
+        // The fixed columns keep their width when they don't fit.
+        assert_eq!(
+            column_layout(&columns, 20.0),
+            vec![(0.0, 30.0), (30.0, 0.0), (30.0, 10.0), (40.0, 0.0)]
+        );
+        assert!(column_layout(&[], 100.0).is_empty());
+    }
+
+    #[test]
+    fn numbers_are_compared_as_numbers() {
+        let source = vec![
+            "10".to_string(),
+            "9".to_string(),
+            " -2.5".to_string(),
+            "abc".to_string(),
+        ];
+        assert_eq!(source.compare(1, 0, 0), Ordering::Less);
+        assert_eq!(source.compare(2, 1, 0), Ordering::Less);
+        // Text is compared with the text of numbers.
+        assert_eq!(source.compare(3, 0, 0), Ordering::Greater);
+    }
+
+    #[test]
+    fn visible_rows_only_contains_whole_rows() {
+        let mut list = list(vec!["a".to_string(); 5]);
+        list.scroll_to(1);
+        assert_eq!(
+            list.visible_rows(25.0),
+            vec![(1, 0.0, 10.0), (2, 10.0, 10.0)]
+        );
+        assert!(list.visible_rows(5.0).is_empty());
+
+        let mut list = self::list(Growing(4));
+        list.sort_by(Some((0, false)));
+        list.update_order();
+        assert_eq!(list.display_order(), &[3, 2, 1, 0]);
+        assert_eq!(list.visible_rows(8.0), vec![(3, 0.0, 4.0), (2, 4.0, 3.0)]);
+    }
+
+    #[test]
+    fn last_page_start_fills_the_height() {
+        let list = list(vec!["a".to_string(); 5]);
+        assert_eq!(list.last_page_start(30.0), 2);
+        assert_eq!(list.last_page_start(35.0), 2);
+        assert_eq!(list.last_page_start(100.0), 0);
+
+        // The last rows are 4, 3 and 2 high.
+        let list = self::list(Growing(4));
+        assert_eq!(list.last_page_start(4.0), 3);
+        assert_eq!(list.last_page_start(7.0), 2);
+        assert_eq!(list.last_page_start(8.0), 2);
+        assert_eq!(list.last_page_start(10.0), 0);
+    }
+
+    #[test]
+    fn update_order_drops_a_selection_out_of_the_source() {
+        let mut list = list(vec!["a".to_string(); 3]);
+        list.select_row(Some(2));
+        list.set_source(vec!["b".to_string(); 2]);
+        list.select_row(Some(2));
+        list.update_order();
+        assert_eq!(list.display_order(), &[0, 1]);
+        assert_eq!(list.selected_row(), None);
+    }
+}
@@ -0,0 +1,1555 @@
+//! Menu bars and context menus.
+
+use std::marker::PhantomData;
+
+use winit::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        ReaderId, System, SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_window::ScreenDimensions;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    canvas::UiCanvas, event::canvas_position, font::default::get_default_font, list::transform_id,
+    Anchor, FontAsset, FontHandle, Interactable, LineMode, Stretch, TargetedEvent, UiEvent,
+    UiEventType, UiImage, UiText, UiTransform, WidgetId,
+};
+
+/// The z coordinate at which the popup menus are shown, above the regular ui elements but below
+/// the tooltips.
+const MENU_Z: f32 = 900.0;
+/// The text shown on the right of the items opening a submenu.
+const SUBMENU_ARROW: &str = ">";
+
+/// An entry of a `UiMenu`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiMenuEntry<W> {
+    /// An item, which is activated or opens a submenu.
+    Item(UiMenuItem<W>),
+    /// A line between two groups of items. Ignored in menu bars.
+    Separator,
+}
+
+impl<W> From<UiMenuItem<W>> for UiMenuEntry<W> {
+    fn from(item: UiMenuItem<W>) -> Self {
+        UiMenuEntry::Item(item)
+    }
+}
+
+/// An item of a `UiMenu`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiMenuItem<W> {
+    /// The id carried by the `UiMenuEvent` sent when the item is activated.
+    pub id: W,
+    /// The text of the item.
+    pub label: String,
+    /// The keyboard shortcut shown on the right of the item, like `"Ctrl+S"`. It is only
+    /// displayed, the shortcut itself is up to the input bindings of the game.
+    pub accelerator: Option<String>,
+    /// Whether the item can be activated, or open its submenu. Disabled items are greyed out.
+    pub enabled: bool,
+    /// Whether the item is checked, for check items. Activating a check item toggles it.
+    pub checked: Option<bool>,
+    /// The entries of the submenu opened by the item. Items with a submenu are not activated.
+    pub submenu: Vec<UiMenuEntry<W>>,
+}
+
+impl<W> UiMenuItem<W> {
+    /// Creates an enabled item, without accelerator nor submenu.
+    pub fn new<S: ToString>(id: W, label: S) -> Self {
+        UiMenuItem {
+            id,
+            label: label.to_string(),
+            accelerator: None,
+            enabled: true,
+            checked: None,
+            submenu: Vec::new(),
+        }
+    }
+
+    /// Sets the keyboard shortcut shown on the right of the item.
+    pub fn with_accelerator<S: ToString>(mut self, accelerator: S) -> Self {
+        self.accelerator = Some(accelerator.to_string());
+        self
+    }
+
+    /// Disables the item.
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Turns the item into a check item.
+    pub fn checkable(mut self, checked: bool) -> Self {
+        self.checked = Some(checked);
+        self
+    }
+
+    /// Sets the entries of the submenu opened by the item.
+    pub fn with_submenu(mut self, submenu: Vec<UiMenuEntry<W>>) -> Self {
+        self.submenu = submenu;
+        self
+    }
+}
+
+/// How a `UiMenu` is opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiMenuKind {
+    /// A horizontal bar showing the top level items in the `UiTransform` of the entity. Clicking
+    /// an item opens its submenu below it, and while a submenu is open, hovering another item
+    /// opens the submenu of that item instead.
+    Bar,
+    /// A popup menu opened at the cursor when the entity, or one of its children, is right
+    /// clicked.
+    Context,
+}
+
+/// Component turning its entity into a menu bar, or giving it a context menu.
+///
+/// The menus are shown as popups above the other elements, added to the root of the menu so that
+/// they are drawn in its canvas and scaled along with it, and their items with a submenu open it
+/// when hovered. Clicking an enabled item sends a `UiMenuEvent` with the id of the item and closes
+/// the menu. Pressing escape or clicking anywhere else also closes it. The look of the menus is
+/// set by the `UiMenuStyle` resource.
+#[derive(Debug)]
+pub struct UiMenu<W> {
+    kind: UiMenuKind,
+    entries: Vec<UiMenuEntry<W>>,
+    /// The entities of the bar items, with the index of their entry.
+    bar_items: Vec<(Entity, usize)>,
+    /// Whether the entries changed since the bar was created.
+    dirty: bool,
+}
+
+impl<W: WidgetId> UiMenu<W> {
+    /// Creates a menu bar.
+    pub fn bar(entries: Vec<UiMenuEntry<W>>) -> Self {
+        UiMenu {
+            kind: UiMenuKind::Bar,
+            entries,
+            bar_items: Vec::new(),
+            dirty: true,
+        }
+    }
+
+    /// Creates a context menu.
+    pub fn context(entries: Vec<UiMenuEntry<W>>) -> Self {
+        UiMenu {
+            kind: UiMenuKind::Context,
+            ..UiMenu::bar(entries)
+        }
+    }
+
+    /// Returns how the menu is opened.
+    pub fn kind(&self) -> UiMenuKind {
+        self.kind
+    }
+
+    /// Returns the entries of the menu.
+    pub fn entries(&self) -> &[UiMenuEntry<W>] {
+        &self.entries
+    }
+
+    /// Replaces the entries of the menu, closing it if it is open.
+    pub fn set_entries(&mut self, entries: Vec<UiMenuEntry<W>>) {
+        self.entries = entries;
+        self.dirty = true;
+    }
+
+    /// Returns the item with the id `id`, searching the submenus too.
+    pub fn item(&self, id: &W) -> Option<&UiMenuItem<W>> {
+        find_item(&self.entries, id)
+    }
+
+    /// Returns the item with the id `id`, searching the submenus too. The menu is closed if it is
+    /// open, so that the change shows the next time it opens.
+    pub fn item_mut(&mut self, id: &W) -> Option<&mut UiMenuItem<W>> {
+        self.dirty = true;
+        find_item_mut(&mut self.entries, id)
+    }
+}
+
+impl<W: WidgetId> Component for UiMenu<W> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn find_item<'a, W: WidgetId>(entries: &'a [UiMenuEntry<W>], id: &W) -> Option<&'a UiMenuItem<W>> {
+    entries.iter().find_map(|entry| match entry {
+        UiMenuEntry::Item(item) if item.id == *id => Some(item),
+        UiMenuEntry::Item(item) => find_item(&item.submenu, id),
+        UiMenuEntry::Separator => None,
+    })
+}
+
+fn find_item_mut<'a, W: WidgetId>(
+    entries: &'a mut [UiMenuEntry<W>],
+    id: &W,
+) -> Option<&'a mut UiMenuItem<W>> {
+    for entry in entries.iter_mut() {
+        if let UiMenuEntry::Item(item) = entry {
+            if item.id == *id {
+                return Some(item);
+            }
+            if let Some(found) = find_item_mut(&mut item.submenu, id) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the item at `path`, a list of indices into the entries of the menu, then of the
+/// submenus.
+fn item_at<'a, W>(entries: &'a [UiMenuEntry<W>], path: &[usize]) -> Option<&'a UiMenuItem<W>> {
+    let (first, rest) = path.split_first()?;
+    match entries.get(*first)? {
+        UiMenuEntry::Item(item) if rest.is_empty() => Some(item),
+        UiMenuEntry::Item(item) => item_at(&item.submenu, rest),
+        UiMenuEntry::Separator => None,
+    }
+}
+
+fn item_at_mut<'a, W>(
+    entries: &'a mut [UiMenuEntry<W>],
+    path: &[usize],
+) -> Option<&'a mut UiMenuItem<W>> {
+    let (first, rest) = path.split_first()?;
+    match entries.get_mut(*first)? {
+        UiMenuEntry::Item(item) if rest.is_empty() => Some(item),
+        UiMenuEntry::Item(item) => item_at_mut(&mut item.submenu, rest),
+        UiMenuEntry::Separator => None,
+    }
+}
+
+/// Resource describing how menus look.
+#[derive(Debug, Clone)]
+pub struct UiMenuStyle {
+    /// The font of the items. If `None`, the default font is used.
+    pub font: Option<FontHandle>,
+    /// The font size of the items.
+    pub font_size: f32,
+    /// The linear RGBA colour of the enabled items.
+    pub text_color: [f32; 4],
+    /// The linear RGBA colour of the disabled items.
+    pub disabled_text_color: [f32; 4],
+    /// The linear RGBA colour of the popups.
+    pub background: [f32; 4],
+    /// The linear RGBA colour behind the hovered item, and the items whose submenu is open.
+    pub highlight: [f32; 4],
+    /// The linear RGBA colour of the separators.
+    pub separator_color: [f32; 4],
+    /// The text shown on the left of the checked items.
+    pub check_mark: String,
+    /// The height of the items in the popups.
+    pub item_height: f32,
+    /// The height taken by a separator.
+    pub separator_height: f32,
+    /// The width of the popups.
+    pub popup_width: f32,
+    /// The width of the items of the menu bars.
+    pub bar_item_width: f32,
+}
+
+impl Default for UiMenuStyle {
+    fn default() -> Self {
+        UiMenuStyle {
+            font: None,
+            font_size: 16.0,
+            text_color: [0.0, 0.0, 0.0, 1.0],
+            disabled_text_color: [0.5, 0.5, 0.5, 1.0],
+            background: [0.9, 0.9, 0.9, 1.0],
+            highlight: [0.6, 0.75, 0.9, 1.0],
+            separator_color: [0.6, 0.6, 0.6, 1.0],
+            check_mark: "x".to_string(),
+            item_height: 24.0,
+            separator_height: 8.0,
+            popup_width: 200.0,
+            bar_item_width: 80.0,
+        }
+    }
+}
+
+impl UiMenuStyle {
+    fn item_color<W>(&self, item: &UiMenuItem<W>) -> [f32; 4] {
+        if item.enabled {
+            self.text_color
+        } else {
+            self.disabled_text_color
+        }
+    }
+
+    fn popup_height<W>(&self, entries: &[UiMenuEntry<W>]) -> f32 {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                UiMenuEntry::Item(_) => self.item_height,
+                UiMenuEntry::Separator => self.separator_height,
+            })
+            .sum()
+    }
+}
+
+/// Event sent when an item of a `UiMenu` is activated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiMenuEvent<W> {
+    /// The entity of the `UiMenu`.
+    pub menu: Entity,
+    /// The id of the activated item.
+    pub item: W,
+    /// The new state of a check item.
+    pub checked: Option<bool>,
+}
+
+impl<W> TargetedEvent for UiMenuEvent<W> {
+    fn get_target(&self) -> Entity {
+        self.menu
+    }
+}
+
+/// A popup shown by the `UiMenuSystem`.
+#[derive(Debug)]
+struct Popup {
+    entity: Entity,
+    /// The path of the item whose submenu is listed. Empty for the top level of a context menu.
+    path: Vec<usize>,
+    /// The entities of the items, with the index of their entry.
+    items: Vec<(Entity, usize)>,
+    /// Every entity making up the popup, including `entity`.
+    parts: Vec<Entity>,
+    /// The index of the hovered entry.
+    hovered: Option<usize>,
+}
+
+/// The menu the `UiMenuSystem` currently shows.
+#[derive(Debug)]
+struct OpenMenu {
+    menu: Entity,
+    /// The index of the entry of the open bar item.
+    bar_item: Option<usize>,
+    /// The root of the ui hierarchy of the menu. The popups are its children, so that they are
+    /// drawn in the same canvas and with the same scale as the menu.
+    root: Entity,
+    /// The size of the space the root is laid out in, which the popups are kept inside of.
+    area: (f32, f32),
+    /// The popups, from the top level to the deepest submenu.
+    popups: Vec<Popup>,
+}
+
+impl OpenMenu {
+    fn new(menu: Entity, bar_item: Option<usize>, root: Entity, area: (f32, f32)) -> Self {
+        OpenMenu {
+            menu,
+            bar_item,
+            root,
+            area,
+            popups: Vec::new(),
+        }
+    }
+
+    /// Deletes the popups from `level` on.
+    fn close(&mut self, level: usize, entities: &Entities<'_>) {
+        let level = level.min(self.popups.len());
+        for popup in self.popups.drain(level..) {
+            for part in popup.parts {
+                let _ = entities.delete(part);
+            }
+        }
+    }
+}
+
+/// Where an entity is in the menus.
+#[derive(Debug, Clone, Copy)]
+enum MenuPart {
+    /// An item of a menu bar, as `(menu, entry index)`.
+    Bar(Entity, usize),
+    /// The background of a popup, with its level.
+    Popup(usize),
+    /// An item of a popup, as `(level, entry index)`.
+    Item(usize, usize),
+}
+
+/// Something a key does to the deepest popup of the open menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuAction {
+    /// Hovers the entry at this index.
+    Hover(usize),
+    /// Opens the submenu of the hovered item.
+    OpenSubmenu,
+    /// Closes the popup, going back to its parent.
+    Close,
+    /// Opens the next bar item, or the previous one.
+    SwitchBarItem(bool),
+    /// Activates the hovered item.
+    Activate,
+}
+
+/// An input the open menu reacts to.
+#[derive(Debug, Clone, Copy)]
+enum MenuInput<'a> {
+    Mouse(&'a UiEvent),
+    Key(VirtualKeyCode),
+}
+
+/// A change of the open menu, made with the mouse or the keyboard.
+#[derive(Debug, Clone, Copy)]
+enum MenuStep {
+    /// Opens the bar item of a menu, as `(menu, entry index)`.
+    OpenBar(Entity, usize),
+    /// Opens the context menu of a menu at the cursor.
+    OpenContext(Entity),
+    /// Opens the submenu of an item of a popup, as `(level, entry index)`.
+    OpenSubmenu(usize, usize),
+    /// Activates an item of a popup, as `(level, entry index)`.
+    Activate(usize, usize),
+}
+
+/// System creating the items of the menu bars and showing the popup menus.
+///
+/// While a popup is open, the up and down arrow keys move between its items, right opens the
+/// submenu of the hovered item, or the next menu of a bar, left goes back to the parent popup, or
+/// to the previous menu of a bar, and enter activates the hovered item.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiMenuSystemDesc))]
+pub struct UiMenuSystem<T, W>
+where
+    T: BindingTypes,
+    W: WidgetId,
+{
+    #[system_desc(event_channel_reader)]
+    window_reader_id: ReaderId<Event>,
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+    #[system_desc(skip)]
+    open: Option<OpenMenu>,
+    #[system_desc(skip)]
+    default_font: Option<FontHandle>,
+    phantom: PhantomData<(T, W)>,
+}
+
+impl<T, W> UiMenuSystem<T, W>
+where
+    T: BindingTypes,
+    W: WidgetId,
+{
+    /// Creates a new `UiMenuSystem`.
+    pub fn new(window_reader_id: ReaderId<Event>, ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            window_reader_id,
+            ui_reader_id,
+            open: None,
+            default_font: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Closes the popups from `level` on. Closing the top level closes the menu.
+    fn close(&mut self, level: usize, entities: &Entities<'_>) {
+        if let Some(open) = self.open.as_mut() {
+            open.close(level, entities);
+        }
+        if level == 0 {
+            self.open = None;
+        }
+    }
+
+    /// Finds where `entity` is in the open menu, or in the menu bars.
+    fn part_of(
+        &self,
+        entity: Entity,
+        entities: &Entities<'_>,
+        menus: &WriteStorage<'_, UiMenu<W>>,
+    ) -> Option<MenuPart> {
+        if let Some(open) = self.open.as_ref() {
+            for (level, popup) in open.popups.iter().enumerate() {
+                if popup.entity == entity {
+                    return Some(MenuPart::Popup(level));
+                }
+                if let Some((_, index)) = popup.items.iter().find(|(item, _)| *item == entity) {
+                    return Some(MenuPart::Item(level, *index));
+                }
+            }
+        }
+        (&**entities, menus).join().find_map(|(menu_entity, menu)| {
+            menu.bar_items
+                .iter()
+                .find(|(item, _)| *item == entity)
+                .map(|(_, index)| MenuPart::Bar(menu_entity, *index))
+        })
+    }
+
+    /// Returns what the ui event `event` does to the menus.
+    fn mouse_step(
+        &mut self,
+        event: &UiEvent,
+        entities: &Entities<'_>,
+        hierarchy: &ParentHierarchy,
+        menus: &mut WriteStorage<'_, UiMenu<W>>,
+        menu_events: &mut EventChannel<UiMenuEvent<W>>,
+    ) -> Option<MenuStep> {
+        match (
+            &event.event_type,
+            self.part_of(event.target, entities, menus),
+        ) {
+            (UiEventType::Click(MouseButton::Left), Some(MenuPart::Bar(menu, index))) => {
+                let was_open = self.open.as_ref().map_or(false, |open| {
+                    open.menu == menu && open.bar_item == Some(index)
+                });
+                self.close(0, entities);
+                if was_open || activate(menu, &[index], menus, menu_events) {
+                    None
+                } else {
+                    Some(MenuStep::OpenBar(menu, index))
+                }
+            }
+            (UiEventType::HoverStart, Some(MenuPart::Bar(menu, index))) => {
+                let switch = self.open.as_ref().map_or(false, |open| {
+                    open.menu == menu && open.bar_item != Some(index)
+                });
+                if switch {
+                    Some(MenuStep::OpenBar(menu, index))
+                } else {
+                    None
+                }
+            }
+            (UiEventType::HoverStart, Some(MenuPart::Item(level, index))) => {
+                let open = self.open.as_mut()?;
+                let popup = &mut open.popups[level];
+                popup.hovered = Some(index);
+                let path = [&popup.path[..], &[index]].concat();
+                if open.popups.get(level + 1).map(|sub| &sub.path) == Some(&path) {
+                    None
+                } else {
+                    Some(MenuStep::OpenSubmenu(level, index))
+                }
+            }
+            (UiEventType::HoverStop, Some(MenuPart::Item(level, index))) => {
+                let popup = self.open.as_mut()?.popups.get_mut(level)?;
+                if popup.hovered == Some(index) {
+                    popup.hovered = None;
+                }
+                None
+            }
+            (UiEventType::Click(MouseButton::Left), Some(MenuPart::Item(level, index))) => {
+                Some(MenuStep::Activate(level, index))
+            }
+            (UiEventType::Click(MouseButton::Right), None) => {
+                context_menu_of(event.target, hierarchy, menus).map(MenuStep::OpenContext)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns what `key` does to the deepest popup of the open menu.
+    fn key_step(
+        &mut self,
+        key: VirtualKeyCode,
+        menus: &WriteStorage<'_, UiMenu<W>>,
+        entities: &Entities<'_>,
+    ) -> Option<MenuStep> {
+        let open = self.open.as_mut()?;
+        let menu = menus.get(open.menu)?;
+        let level = open.popups.len().checked_sub(1)?;
+        let popup = &mut open.popups[level];
+        let entries = entries_at(&menu.entries, &popup.path)?;
+        let bar = open.bar_item.is_some();
+        match key_action(entries, popup.hovered, level, bar, key)? {
+            MenuAction::Hover(index) => {
+                popup.hovered = Some(index);
+                None
+            }
+            MenuAction::OpenSubmenu => Some(MenuStep::OpenSubmenu(level, popup.hovered?)),
+            MenuAction::Close => {
+                open.close(level, entities);
+                None
+            }
+            MenuAction::SwitchBarItem(forward) => {
+                let index = next_bar_item(&menu.bar_items, open.bar_item, forward)?;
+                Some(MenuStep::OpenBar(open.menu, index))
+            }
+            MenuAction::Activate => Some(MenuStep::Activate(level, popup.hovered?)),
+        }
+    }
+
+    /// Opens the submenu of the item at `path` of the open menu, with its top left corner at
+    /// `(x, y)`, or with its top right corner at `(left, y)` if it doesn't fit. The position is in
+    /// the layout space of the root of the menu.
+    #[allow(clippy::too_many_arguments)]
+    fn open_popup(
+        &mut self,
+        path: Vec<usize>,
+        (x, y, left): (f32, f32, f32),
+        menus: &WriteStorage<'_, UiMenu<W>>,
+        style: &UiMenuStyle,
+        font: &FontHandle,
+        entities: &Entities<'_>,
+        ui_transforms: &mut WriteStorage<'_, UiTransform>,
+        texts: &mut WriteStorage<'_, UiText>,
+        images: &mut WriteStorage<'_, UiImage>,
+        parents: &mut WriteStorage<'_, Parent>,
+        interactables: &mut WriteStorage<'_, Interactable>,
+    ) {
+        let open = match self.open.as_mut() {
+            Some(open) => open,
+            None => return,
+        };
+        let entries = match menus
+            .get(open.menu)
+            .and_then(|menu| entries_at(&menu.entries, &path))
+        {
+            Some(entries) if !entries.is_empty() => entries,
+            _ => return,
+        };
+        let (root_left, root_bottom) = match ui_transforms.get(open.root) {
+            Some(root) => (
+                root.pixel_x() - root.pixel_width() / 2.0,
+                root.pixel_y() - root.pixel_height() / 2.0,
+            ),
+            None => return,
+        };
+
+        let level = open.popups.len();
+        let id = format!("{}_menu_{}", transform_id(open.menu, ui_transforms), level);
+        let (width, height) = (style.popup_width, style.popup_height(entries));
+        let (x, y) = place_popup((x, y, left), (width, height), open.area);
+
+        // The popup is placed from the bottom left corner of the root.
+        let entity = entities
+            .build_entity()
+            .with(
+                UiTransform::new(
+                    id.clone(),
+                    Anchor::BottomLeft,
+                    Anchor::TopLeft,
+                    x - root_left,
+                    y - root_bottom,
+                    MENU_Z + level as f32,
+                    width,
+                    height,
+                ),
+                ui_transforms,
+            )
+            .with(UiImage::SolidColor(style.background), images)
+            .with(Parent { entity: open.root }, parents)
+            .build();
+        let mut popup = Popup {
+            entity,
+            path,
+            items: Vec::new(),
+            parts: vec![entity],
+            hovered: None,
+        };
+        let mut top = 0.0;
+        for (index, entry) in entries.iter().enumerate() {
+            let item = match entry {
+                UiMenuEntry::Item(item) => item,
+                UiMenuEntry::Separator => {
+                    let separator = entities
+                        .build_entity()
+                        .with(
+                            UiTransform::new(
+                                format!("{}_separator_{}", id, index),
+                                Anchor::TopLeft,
+                                Anchor::MiddleLeft,
+                                style.item_height / 4.0,
+                                -top - style.separator_height / 2.0,
+                                1.0,
+                                width - style.item_height / 2.0,
+                                1.0,
+                            )
+                            .into_transparent(),
+                            ui_transforms,
+                        )
+                        .with(UiImage::SolidColor(style.separator_color), images)
+                        .with(Parent { entity }, parents)
+                        .build();
+                    popup.parts.push(separator);
+                    top += style.separator_height;
+                    continue;
+                }
+            };
+
+            let item_id = format!("{}_item_{}", id, index);
+            let item_entity = entities
+                .build_entity()
+                .with(
+                    UiTransform::new(
+                        item_id.clone(),
+                        Anchor::TopLeft,
+                        Anchor::TopLeft,
+                        0.0,
+                        -top,
+                        1.0,
+                        width,
+                        style.item_height,
+                    ),
+                    ui_transforms,
+                )
+                .with(Interactable, interactables)
+                .with(Parent { entity }, parents)
+                .build();
+            popup.items.push((item_entity, index));
+            popup.parts.push(item_entity);
+
+            // The check mark, the label and the accelerator or submenu arrow.
+            let check = if item.checked == Some(true) {
+                style.check_mark.clone()
+            } else {
+                String::new()
+            };
+            let right = if item.submenu.is_empty() {
+                item.accelerator.clone().unwrap_or_default()
+            } else {
+                SUBMENU_ARROW.to_string()
+            };
+            let columns = vec![
+                ("check", check, Anchor::MiddleLeft, 0.0, style.item_height),
+                (
+                    "label",
+                    item.label.clone(),
+                    Anchor::MiddleLeft,
+                    style.item_height,
+                    width - 2.0 * style.item_height,
+                ),
+                (
+                    "accelerator",
+                    right,
+                    Anchor::MiddleRight,
+                    -style.item_height / 2.0,
+                    width / 2.0,
+                ),
+            ];
+            for (name, text, anchor, x, text_width) in columns {
+                let text_entity = entities
+                    .build_entity()
+                    .with(
+                        UiTransform::new(
+                            format!("{}_{}", item_id, name),
+                            anchor,
+                            anchor,
+                            x,
+                            0.0,
+                            1.0,
+                            text_width,
+                            style.item_height,
+                        )
+                        .into_transparent(),
+                        ui_transforms,
+                    )
+                    .with(
+                        UiText::new(
+                            font.clone(),
+                            text,
+                            style.item_color(item),
+                            style.font_size,
+                            LineMode::Single,
+                            anchor,
+                        ),
+                        texts,
+                    )
+                    .with(
+                        Parent {
+                            entity: item_entity,
+                        },
+                        parents,
+                    )
+                    .build();
+                popup.parts.push(text_entity);
+            }
+            top += style.item_height;
+        }
+        open.popups.push(popup);
+    }
+}
+
+impl<'a, T, W> System<'a> for UiMenuSystem<T, W>
+where
+    T: BindingTypes,
+    W: WidgetId,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<FontAsset>>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, InputHandler<T>>,
+        Read<'a, UiMenuStyle>,
+        Read<'a, EventChannel<Event>>,
+        Read<'a, EventChannel<UiEvent>>,
+        Write<'a, EventChannel<UiMenuEvent<W>>>,
+        WriteStorage<'a, UiMenu<W>>,
+        ReadStorage<'a, UiCanvas>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, Interactable>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            font_assets,
+            hierarchy,
+            screen_dimensions,
+            input,
+            style,
+            window_events,
+            ui_events,
+            mut menu_events,
+            mut menus,
+            canvases,
+            mut ui_transforms,
+            mut texts,
+            mut images,
+            mut parents,
+            mut interactables,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_menu_system");
+
+        let mut escape = false;
+        let mut pressed = false;
+        let mut keys = Vec::new();
+        for event in window_events.read(&mut self.window_reader_id) {
+            match *event {
+                Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput {
+                            input:
+                                KeyboardInput {
+                                    state: ElementState::Pressed,
+                                    virtual_keycode: Some(key),
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } => {
+                    if key == VirtualKeyCode::Escape {
+                        escape = true;
+                    } else {
+                        keys.push(key);
+                    }
+                }
+                Event::WindowEvent {
+                    event:
+                        WindowEvent::MouseInput {
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => pressed = true,
+                _ => {}
+            }
+        }
+        let events = ui_events
+            .read(&mut self.ui_reader_id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Escape, a click outside of the open menu or a change of its entries close it.
+        if let Some(open_menu) = self.open.as_ref().map(|open| open.menu) {
+            let inside = events.iter().any(|event| match event.event_type {
+                UiEventType::ClickStart(_) => match self.part_of(event.target, &entities, &menus) {
+                    Some(MenuPart::Bar(menu, _)) => menu == open_menu,
+                    Some(_) => true,
+                    None => false,
+                },
+                _ => false,
+            });
+            let changed = menus.get(open_menu).map_or(true, |menu| menu.dirty);
+            if escape || changed || (pressed && !inside) {
+                self.close(0, &entities);
+            }
+        }
+
+        if (&menus).join().next().is_none() {
+            return;
+        }
+        let font = match style.font {
+            Some(ref font) => font.clone(),
+            None => self
+                .default_font
+                .get_or_insert_with(|| get_default_font(&loader, &font_assets))
+                .clone(),
+        };
+
+        // Create the items of the menu bars.
+        for (menu_entity, menu) in (&*entities, &mut menus).join() {
+            if !menu.dirty {
+                continue;
+            }
+            menu.dirty = false;
+            for (item, _) in menu.bar_items.drain(..) {
+                let _ = entities.delete(item);
+            }
+            if menu.kind != UiMenuKind::Bar {
+                continue;
+            }
+            let id = transform_id(menu_entity, &ui_transforms);
+            let items = menu
+                .entries
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| match entry {
+                    UiMenuEntry::Item(item) => Some((index, item)),
+                    UiMenuEntry::Separator => None,
+                });
+            for (position, (index, item)) in items.enumerate() {
+                let entity = entities
+                    .build_entity()
+                    .with(
+                        UiTransform::new(
+                            format!("{}_menu_{}", id, index),
+                            Anchor::TopLeft,
+                            Anchor::TopLeft,
+                            position as f32 * style.bar_item_width,
+                            0.0,
+                            1.0,
+                            style.bar_item_width,
+                            0.0,
+                        )
+                        .with_stretch(Stretch::Y { y_margin: 0.0 }),
+                        &mut ui_transforms,
+                    )
+                    .with(
+                        UiText::new(
+                            font.clone(),
+                            item.label.clone(),
+                            style.item_color(item),
+                            style.font_size,
+                            LineMode::Single,
+                            Anchor::Middle,
+                        ),
+                        &mut texts,
+                    )
+                    .with(Interactable, &mut interactables)
+                    .with(
+                        Parent {
+                            entity: menu_entity,
+                        },
+                        &mut parents,
+                    )
+                    .build();
+                menu.bar_items.push((entity, index));
+            }
+        }
+
+        // The mouse, then the keyboard, change the open menu.
+        let inputs = events
+            .iter()
+            .map(MenuInput::Mouse)
+            .chain(keys.iter().cloned().map(MenuInput::Key));
+        for menu_input in inputs {
+            let step = match menu_input {
+                MenuInput::Mouse(event) => {
+                    self.mouse_step(event, &entities, &hierarchy, &mut menus, &mut menu_events)
+                }
+                MenuInput::Key(key) => self.key_step(key, &menus, &entities),
+            };
+            let step = match step {
+                Some(step) => step,
+                None => continue,
+            };
+
+            match step {
+                MenuStep::Activate(level, index) => {
+                    let (menu, path) = match self.open.as_ref() {
+                        Some(open) => {
+                            let mut path = open.popups[level].path.clone();
+                            path.push(index);
+                            (open.menu, path)
+                        }
+                        None => continue,
+                    };
+                    if activate(menu, &path, &mut menus, &mut menu_events) {
+                        self.close(0, &entities);
+                    }
+                }
+                MenuStep::OpenBar(menu, index) => {
+                    // Show the submenu of the bar item below it.
+                    self.close(0, &entities);
+                    let root = root_of(menu, &hierarchy);
+                    let area = root_area(root, &canvases, &ui_transforms, &screen_dimensions);
+                    self.open = Some(OpenMenu::new(menu, Some(index), root, area));
+                    let bar_item = menus.get(menu).and_then(|menu| {
+                        menu.bar_items
+                            .iter()
+                            .find(|(_, item_index)| *item_index == index)
+                            .map(|(item, _)| *item)
+                    });
+                    let rect = bar_item.and_then(|item| rect_in(item, root, &ui_transforms));
+                    if let Some((left, bottom, right, _)) = rect {
+                        self.open_popup(
+                            vec![index],
+                            (left, bottom, right),
+                            &menus,
+                            &style,
+                            &font,
+                            &entities,
+                            &mut ui_transforms,
+                            &mut texts,
+                            &mut images,
+                            &mut parents,
+                            &mut interactables,
+                        );
+                    }
+                }
+                MenuStep::OpenSubmenu(level, index) => {
+                    // Show the submenu on the right of the item.
+                    self.close(level + 1, &entities);
+                    let (path, item, root) = match self.open.as_ref() {
+                        Some(open) => {
+                            let popup = &open.popups[level];
+                            let item = popup
+                                .items
+                                .iter()
+                                .find(|(_, item_index)| *item_index == index)
+                                .map(|(item, _)| *item);
+                            ([&popup.path[..], &[index]].concat(), item, open.root)
+                        }
+                        None => continue,
+                    };
+                    let rect = item.and_then(|item| rect_in(item, root, &ui_transforms));
+                    if let Some((left, _, right, top)) = rect {
+                        self.open_popup(
+                            path,
+                            (right, top, left),
+                            &menus,
+                            &style,
+                            &font,
+                            &entities,
+                            &mut ui_transforms,
+                            &mut texts,
+                            &mut images,
+                            &mut parents,
+                            &mut interactables,
+                        );
+                    }
+                }
+                MenuStep::OpenContext(menu) => {
+                    let root = root_of(menu, &hierarchy);
+                    let cursor = input.mouse_position().and_then(|(x, y)| {
+                        let y = screen_dimensions.height() - y;
+                        cursor_in(root, (x, y), &entities, &canvases, &ui_transforms, &images)
+                    });
+                    let (x, y) = match cursor {
+                        Some(cursor) => cursor,
+                        None => continue,
+                    };
+                    self.close(0, &entities);
+                    let area = root_area(root, &canvases, &ui_transforms, &screen_dimensions);
+                    self.open = Some(OpenMenu::new(menu, None, root, area));
+                    self.open_popup(
+                        Vec::new(),
+                        (x, y, x),
+                        &menus,
+                        &style,
+                        &font,
+                        &entities,
+                        &mut ui_transforms,
+                        &mut texts,
+                        &mut images,
+                        &mut parents,
+                        &mut interactables,
+                    );
+                }
+            }
+
+            // The keyboard hovers the first item of the popups it opens.
+            if let MenuInput::Key(_) = menu_input {
+                if let Some(open) = self.open.as_mut() {
+                    let entries = menus.get(open.menu).map(|menu| &menu.entries[..]);
+                    if let Some(popup) = open.popups.last_mut() {
+                        let first = entries
+                            .and_then(|entries| entries_at(entries, &popup.path))
+                            .and_then(|entries| next_enabled(entries, None, true));
+                        popup.hovered = popup.hovered.or(first);
+                    }
+                }
+            }
+        }
+
+        // Highlight the open bar item, the hovered items and the items whose submenu is open.
+        let mut highlights = Vec::new();
+        for (menu_entity, menu) in (&*entities, &menus).join() {
+            for (item, index) in menu.bar_items.iter() {
+                let open = self.open.as_ref().map_or(false, |open| {
+                    open.menu == menu_entity
+                        && open.bar_item == Some(*index)
+                        && !open.popups.is_empty()
+                });
+                highlights.push((*item, open));
+            }
+        }
+        if let Some(open) = self.open.as_ref() {
+            for (level, popup) in open.popups.iter().enumerate() {
+                let submenu = open
+                    .popups
+                    .get(level + 1)
+                    .and_then(|submenu| submenu.path.last().cloned());
+                for (item, index) in popup.items.iter() {
+                    let path = [&popup.path[..], &[*index]].concat();
+                    let enabled = menus
+                        .get(open.menu)
+                        .and_then(|menu| item_at(&menu.entries, &path))
+                        .map_or(false, |item| item.enabled);
+                    let highlighted = popup.hovered == Some(*index) || submenu == Some(*index);
+                    highlights.push((*item, enabled && highlighted));
+                }
+            }
+        }
+        for (entity, highlighted) in highlights {
+            if !highlighted {
+                images.remove(entity);
+            } else if !images.contains(entity) {
+                let _ = images.insert(entity, UiImage::SolidColor(style.highlight));
+            }
+        }
+    }
+}
+
+/// Activates the enabled item at `path`, toggling it if it is a check item. Returns whether the
+/// item was activated.
+fn activate<W: WidgetId>(
+    menu: Entity,
+    path: &[usize],
+    menus: &mut WriteStorage<'_, UiMenu<W>>,
+    menu_events: &mut EventChannel<UiMenuEvent<W>>,
+) -> bool {
+    let item = match menus
+        .get_mut(menu)
+        .and_then(|menu| item_at_mut(&mut menu.entries, path))
+    {
+        Some(item) if item.enabled && item.submenu.is_empty() => item,
+        _ => return false,
+    };
+    if let Some(checked) = item.checked.as_mut() {
+        *checked = !*checked;
+    }
+    menu_events.single_write(UiMenuEvent {
+        menu,
+        item: item.id.clone(),
+        checked: item.checked,
+    });
+    true
+}
+
+/// Finds the context menu opened by right clicking `entity`, which can be on `entity` or on one
+/// of its ancestors.
+fn context_menu_of<W: WidgetId>(
+    entity: Entity,
+    hierarchy: &ParentHierarchy,
+    menus: &WriteStorage<'_, UiMenu<W>>,
+) -> Option<Entity> {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if menus
+            .get(entity)
+            .map_or(false, |menu| menu.kind == UiMenuKind::Context)
+        {
+            return Some(entity);
+        }
+        current = hierarchy.parent(entity);
+    }
+    None
+}
+
+/// Returns the entries listed by the popup of the item at `path`, or the top level entries if
+/// `path` is empty. Returns `None` if the item is disabled.
+fn entries_at<'a, W>(
+    entries: &'a [UiMenuEntry<W>],
+    path: &[usize],
+) -> Option<&'a [UiMenuEntry<W>]> {
+    if path.is_empty() {
+        return Some(entries);
+    }
+    match item_at(entries, path) {
+        Some(item) if item.enabled => Some(&item.submenu),
+        _ => None,
+    }
+}
+
+/// Returns the index of the first enabled item after `from`, or before it if `forward` is false,
+/// wrapping around. Without `from`, starts from the first or the last entry.
+fn next_enabled<W>(
+    entries: &[UiMenuEntry<W>],
+    from: Option<usize>,
+    forward: bool,
+) -> Option<usize> {
+    let len = entries.len();
+    (1..=len)
+        .map(|step| match (from, forward) {
+            (Some(from), true) => (from + step) % len,
+            (Some(from), false) => (from + len - step % len) % len,
+            (None, true) => step - 1,
+            (None, false) => len - step,
+        })
+        .find(|index| match entries[*index] {
+            UiMenuEntry::Item(ref item) => item.enabled,
+            UiMenuEntry::Separator => false,
+        })
+}
+
+/// Returns what `key` does to a popup listing `entries`, at `level` of a menu bar if `bar` is
+/// true or of a context menu.
+fn key_action<W>(
+    entries: &[UiMenuEntry<W>],
+    hovered: Option<usize>,
+    level: usize,
+    bar: bool,
+    key: VirtualKeyCode,
+) -> Option<MenuAction> {
+    // Whether the hovered item has a submenu, if it is enabled.
+    let submenu = hovered.and_then(|index| match entries.get(index) {
+        Some(UiMenuEntry::Item(item)) if item.enabled => Some(!item.submenu.is_empty()),
+        _ => None,
+    });
+    match key {
+        VirtualKeyCode::Down => next_enabled(entries, hovered, true).map(MenuAction::Hover),
+        VirtualKeyCode::Up => next_enabled(entries, hovered, false).map(MenuAction::Hover),
+        VirtualKeyCode::Right if submenu == Some(true) => Some(MenuAction::OpenSubmenu),
+        VirtualKeyCode::Right if bar => Some(MenuAction::SwitchBarItem(true)),
+        VirtualKeyCode::Left if level > 0 => Some(MenuAction::Close),
+        VirtualKeyCode::Left if bar => Some(MenuAction::SwitchBarItem(false)),
+        VirtualKeyCode::Return => submenu.map(|submenu| {
+            if submenu {
+                MenuAction::OpenSubmenu
+            } else {
+                MenuAction::Activate
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the entry index of the bar item next to the one of the entry `index`, or before it if
+/// `forward` is false, wrapping around.
+fn next_bar_item(
+    bar_items: &[(Entity, usize)],
+    index: Option<usize>,
+    forward: bool,
+) -> Option<usize> {
+    let position = bar_items
+        .iter()
+        .position(|(_, item)| Some(*item) == index)?;
+    let len = bar_items.len();
+    let next = if forward {
+        (position + 1) % len
+    } else {
+        (position + len - 1) % len
+    };
+    Some(bar_items[next].1)
+}
+
+/// Returns the top left corner of a popup of `size` opened at `(x, y)`, or with its top right
+/// corner at `(left, y)` if it doesn't fit on the right, keeping it inside of `area`.
+fn place_popup(
+    (x, y, left): (f32, f32, f32),
+    (width, height): (f32, f32),
+    (area_width, area_height): (f32, f32),
+) -> (f32, f32) {
+    let x = if x + width > area_width {
+        left - width
+    } else {
+        x
+    };
+    let x = x.min(area_width - width).max(0.0);
+    let y = y.max(height).min(area_height);
+    (x, y)
+}
+
+/// Returns the root of the ui hierarchy containing `entity`, which can be `entity` itself.
+pub(crate) fn root_of(entity: Entity, hierarchy: &ParentHierarchy) -> Entity {
+    let mut root = entity;
+    while let Some(parent) = hierarchy.parent(root) {
+        root = parent;
+    }
+    root
+}
+
+/// Returns the size of the space the root `root` is laid out in: its canvas, or the screen in ui
+/// pixels.
+pub(crate) fn root_area(
+    root: Entity,
+    canvases: &ReadStorage<'_, UiCanvas>,
+    ui_transforms: &WriteStorage<'_, UiTransform>,
+    screen_dimensions: &ScreenDimensions,
+) -> (f32, f32) {
+    match canvases.get(root) {
+        Some(canvas) => (canvas.width, canvas.height),
+        None => {
+            let scale_factor = ui_transforms.get(root).map_or(1.0, |t| t.scale_factor);
+            (
+                screen_dimensions.width() / scale_factor,
+                screen_dimensions.height() / scale_factor,
+            )
+        }
+    }
+}
+
+/// Returns the `(left, bottom, right, top)` edges of the bounds of `entity` in the layout space
+/// of `root`.
+pub(crate) fn rect_in(
+    entity: Entity,
+    root: Entity,
+    ui_transforms: &WriteStorage<'_, UiTransform>,
+) -> Option<(f32, f32, f32, f32)> {
+    bounds_in(ui_transforms.get(entity)?, ui_transforms.get(root)?)
+}
+
+/// Returns the `(left, bottom, right, top)` edges of the bounds of `transform` in the layout
+/// space of `space`, taking the scale and rotation of both into account.
+fn bounds_in(transform: &UiTransform, space: &UiTransform) -> Option<(f32, f32, f32, f32)> {
+    let (half_width, half_height) = (
+        transform.pixel_width() / 2.0,
+        transform.pixel_height() / 2.0,
+    );
+    let mut bounds = (
+        std::f32::INFINITY,
+        std::f32::INFINITY,
+        std::f32::NEG_INFINITY,
+        std::f32::NEG_INFINITY,
+    );
+    for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter() {
+        let (x, y) = transform.to_screen_space(
+            transform.pixel_x() + dx * half_width,
+            transform.pixel_y() + dy * half_height,
+        );
+        let (x, y) = space.to_layout_space(x, y)?;
+        bounds = (
+            bounds.0.min(x),
+            bounds.1.min(y),
+            bounds.2.max(x),
+            bounds.3.max(y),
+        );
+    }
+    Some(bounds)
+}
+
+/// Returns the position of the cursor, at `(x, y)` on the screen, in the layout space of `root`.
+/// The cursor reaches a `UiCanvas` through the image showing it.
+pub(crate) fn cursor_in(
+    root: Entity,
+    (x, y): (f32, f32),
+    entities: &Entities<'_>,
+    canvases: &ReadStorage<'_, UiCanvas>,
+    ui_transforms: &WriteStorage<'_, UiTransform>,
+    images: &WriteStorage<'_, UiImage>,
+) -> Option<(f32, f32)> {
+    let (x, y) = match canvases.get(root) {
+        Some(canvas) => (&**entities, images).join().find_map(|(entity, _)| {
+            let under_cursor = ui_transforms
+                .get(entity)
+                .map_or(false, |transform| transform.position_inside(x, y));
+            canvas_position((x, y), entity, ui_transforms, images, canvases)
+                .filter(|(target, _)| under_cursor && *target == canvas.target)
+                .map(|(_, position)| position)
+        })?,
+        None => (x, y),
+    };
+    ui_transforms.get(root)?.to_layout_space(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::{
+        ecs::prelude::{Builder, World, WorldExt},
+        math::Matrix3,
+    };
+
+    fn entries() -> Vec<UiMenuEntry<u32>> {
+        vec![
+            UiMenuItem::new(1, "a").into(),
+            UiMenuEntry::Separator,
+            UiMenuItem::new(2, "b").disabled().into(),
+            UiMenuItem::new(3, "c")
+                .with_submenu(vec![UiMenuItem::new(4, "d").into()])
+                .into(),
+            UiMenuItem::new(5, "e").into(),
+        ]
+    }
+
+    #[test]
+    fn next_enabled_skips_separators_and_disabled_items() {
+        let entries = entries();
+        assert_eq!(next_enabled(&entries, None, true), Some(0));
+        assert_eq!(next_enabled(&entries, None, false), Some(4));
+        assert_eq!(next_enabled(&entries, Some(0), true), Some(3));
+        assert_eq!(next_enabled(&entries, Some(3), false), Some(0));
+        assert_eq!(next_enabled(&entries, Some(4), true), Some(0));
+        assert_eq!(next_enabled(&entries, Some(0), false), Some(4));
+
+        let disabled: Vec<UiMenuEntry<u32>> = vec![UiMenuItem::new(1, "a").disabled().into()];
+        assert_eq!(next_enabled(&disabled, None, true), None);
+        assert_eq!(next_enabled::<u32>(&[], None, true), None);
+    }
+
+    #[test]
+    fn arrows_move_between_the_items() {
+        let entries = entries();
+        let action = |hovered, key| key_action(&entries, hovered, 0, false, key);
+        assert_eq!(
+            action(None, VirtualKeyCode::Down),
+            Some(MenuAction::Hover(0))
+        );
+        assert_eq!(
+            action(Some(0), VirtualKeyCode::Down),
+            Some(MenuAction::Hover(3))
+        );
+        assert_eq!(
+            action(Some(0), VirtualKeyCode::Up),
+            Some(MenuAction::Hover(4))
+        );
+        assert_eq!(
+            action(Some(0), VirtualKeyCode::Return),
+            Some(MenuAction::Activate)
+        );
+        assert_eq!(action(Some(2), VirtualKeyCode::Return), None);
+        assert_eq!(action(None, VirtualKeyCode::Return), None);
+    }
+
+    #[test]
+    fn right_and_left_open_and_close_submenus() {
+        let entries = entries();
+        let action = |hovered, level, bar, key| key_action(&entries, hovered, level, bar, key);
+        assert_eq!(
+            action(Some(3), 0, false, VirtualKeyCode::Right),
+            Some(MenuAction::OpenSubmenu)
+        );
+        assert_eq!(
+            action(Some(3), 0, false, VirtualKeyCode::Return),
+            Some(MenuAction::OpenSubmenu)
+        );
+        assert_eq!(action(Some(0), 0, false, VirtualKeyCode::Right), None);
+        assert_eq!(action(Some(0), 0, false, VirtualKeyCode::Left), None);
+        assert_eq!(
+            action(Some(0), 1, false, VirtualKeyCode::Left),
+            Some(MenuAction::Close)
+        );
+
+        // In a menu bar, right and left switch to the next menus from the first popups.
+        assert_eq!(
+            action(Some(0), 0, true, VirtualKeyCode::Right),
+            Some(MenuAction::SwitchBarItem(true))
+        );
+        assert_eq!(
+            action(Some(0), 0, true, VirtualKeyCode::Left),
+            Some(MenuAction::SwitchBarItem(false))
+        );
+        assert_eq!(
+            action(Some(0), 1, true, VirtualKeyCode::Left),
+            Some(MenuAction::Close)
+        );
+    }
+
+    #[test]
+    fn next_bar_item_wraps_around() {
+        let entity = World::new().create_entity().build();
+        let bar_items = vec![(entity, 0), (entity, 2), (entity, 3)];
+        assert_eq!(next_bar_item(&bar_items, Some(0), true), Some(2));
+        assert_eq!(next_bar_item(&bar_items, Some(3), true), Some(0));
+        assert_eq!(next_bar_item(&bar_items, Some(0), false), Some(3));
+        assert_eq!(next_bar_item(&bar_items, Some(1), true), None);
+    }
+
+    #[test]
+    fn popups_stay_inside_of_the_area() {
+        let area = (300.0, 200.0);
+        let size = (100.0, 50.0);
+        assert_eq!(place_popup((50.0, 150.0, 50.0), size, area), (50.0, 150.0));
+        // Opens on the left of the item when there is no room on its right.
+        assert_eq!(
+            place_popup((250.0, 150.0, 240.0), size, area),
+            (140.0, 150.0)
+        );
+        assert_eq!(place_popup((250.0, 150.0, 50.0), size, area), (0.0, 150.0));
+        assert_eq!(place_popup((50.0, 20.0, 50.0), size, area), (50.0, 50.0));
+        assert_eq!(place_popup((50.0, 250.0, 50.0), size, area), (50.0, 200.0));
+    }
+
+    #[test]
+    fn bounds_are_in_the_space_of_the_root() {
+        // A root scaled by the `UiScaler`, with a child scaled twice around the origin.
+        let mut root = UiTransform::new(
+            "root".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            100.,
+            100.,
+            0.,
+            200.,
+            200.,
+        );
+        root.global_matrix = Matrix3::new_scaling(1.5);
+        let mut item = UiTransform::new(
+            "item".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            20.,
+            45.,
+            0.,
+            20.,
+            10.,
+        );
+        item.global_matrix = root.global_matrix * Matrix3::new_scaling(2.0);
+
+        let (left, bottom, right, top) = bounds_in(&item, &root).unwrap();
+        assert!((left - 20.0).abs() < 1e-4);
+        assert!((bottom - 80.0).abs() < 1e-4);
+        assert!((right - 60.0).abs() < 1e-4);
+        assert!((top - 100.0).abs() < 1e-4);
+
+        root.global_matrix = Matrix3::new_scaling(0.0);
+        assert_eq!(bounds_in(&item, &root), None);
+    }
+
+    #[test]
+    fn closing_deletes_the_popups_from_a_level() {
+        let mut world = World::new();
+        let mut popup = |path: Vec<usize>| {
+            let entity = world.create_entity().build();
+            let item = world.create_entity().build();
+            Popup {
+                entity,
+                path,
+                items: vec![(item, 0)],
+                parts: vec![entity, item],
+                hovered: None,
+            }
+        };
+        let (first, second) = (popup(vec![0]), popup(vec![0, 0]));
+        let root = world.create_entity().build();
+        let parts = [first.parts.clone(), second.parts.clone()];
+        let mut open = OpenMenu::new(root, Some(0), root, (100.0, 100.0));
+        open.popups = vec![first, second];
+
+        open.close(1, &world.entities());
+        world.maintain();
+        assert_eq!(open.popups.len(), 1);
+        assert!(parts[0].iter().all(|part| world.is_alive(*part)));
+        assert!(parts[1].iter().all(|part| !world.is_alive(*part)));
+
+        open.close(0, &world.entities());
+        world.maintain();
+        assert!(open.popups.is_empty());
+        assert!(parts[0].iter().all(|part| !world.is_alive(*part)));
+        assert!(world.is_alive(root));
+    }
+}
@@ -0,0 +1,402 @@
+use derive_new::new;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
+    Hidden, HiddenPropagate, ParentHierarchy,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{Anchor, Selected, Stretch, UiEvent, UiEventType, UiImage, UiTransform};
+
+/// How far below the modal the backdrop is placed on the z axis.
+const BACKDROP_Z_OFFSET: f32 = 0.01;
+
+/// Component turning a ui entity into a modal focus scope.
+///
+/// While the entity is visible (not `Hidden` nor `HiddenPropagate`), only the entity and its
+/// descendants receive mouse events, keyboard selection and text input.
+/// Modals stack: when a modal closes, the focus goes back to the elements that were selected
+/// before it opened.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, new)]
+pub struct UiModal {
+    /// Linear RGBA colour of a backdrop covering the whole screen underneath the modal.
+    /// The backdrop is opaque to the mouse, so nothing underneath can be hovered.
+    /// If `None`, no backdrop is created, but the elements outside of the modal still ignore input.
+    #[new(default)]
+    #[serde(default)]
+    pub backdrop: Option<[f32; 4]>,
+}
+
+impl UiModal {
+    /// Creates a modal with a backdrop of the given linear RGBA colour.
+    pub fn with_backdrop(backdrop: [f32; 4]) -> Self {
+        UiModal {
+            backdrop: Some(backdrop),
+        }
+    }
+}
+
+impl Component for UiModal {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(Debug, Clone)]
+struct UiModalLayer {
+    root: Entity,
+    backdrop: Option<Entity>,
+    previous_focus: Vec<Entity>,
+}
+
+/// Resource holding the currently open modals, ordered from the bottom-most to the top-most.
+///
+/// Maintained by the `UiModalSystem`.
+#[derive(Debug, Default)]
+pub struct UiModalStack {
+    layers: Vec<UiModalLayer>,
+}
+
+impl UiModalStack {
+    /// Returns the root entity of the top-most open modal, if any.
+    pub fn top(&self) -> Option<Entity> {
+        self.layers.last().map(|layer| layer.root)
+    }
+
+    /// Returns true if no modal is currently open.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Returns the root entities of all open modals, from the bottom-most to the top-most.
+    pub fn roots(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.layers.iter().map(|layer| layer.root)
+    }
+
+    /// Checks if `entity` is allowed to receive input, which is the case when no modal is open
+    /// or when the entity is the top-most modal or one of its descendants.
+    pub fn allows(&self, entity: Entity, hierarchy: &ParentHierarchy) -> bool {
+        let root = match self.top() {
+            Some(root) => root,
+            None => return true,
+        };
+
+        let mut current = Some(entity);
+        while let Some(e) = current {
+            if e == root {
+                return true;
+            }
+            current = hierarchy.parent(e);
+        }
+        false
+    }
+}
+
+/// System keeping the `UiModalStack` in sync with the visible `UiModal` entities.
+///
+/// When a modal opens, the selected elements lose focus and a backdrop is created if requested.
+/// When it closes, its backdrop is deleted and the previously selected elements regain focus.
+#[derive(Debug, Default, new)]
+pub struct UiModalSystem;
+
+impl<'a> System<'a> for UiModalSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, UiModal>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, Selected>,
+        Write<'a, UiModalStack>,
+        Write<'a, EventChannel<UiEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            modals,
+            hiddens,
+            hidden_props,
+            mut transforms,
+            mut images,
+            mut selecteds,
+            mut stack,
+            mut ui_events,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_modal_system");
+
+        let active = (&*entities, &modals, !&hiddens, !&hidden_props)
+            .join()
+            .map(|(entity, modal, _, _)| (entity, modal.backdrop))
+            .collect::<Vec<_>>();
+
+        // Close the modals that were hidden, removed or deleted.
+        let mut index = 0;
+        while index < stack.layers.len() {
+            let root = stack.layers[index].root;
+            if active.iter().any(|(entity, _)| *entity == root) {
+                index += 1;
+                continue;
+            }
+
+            let layer = stack.layers.remove(index);
+            if let Some(backdrop) = layer.backdrop {
+                if let Err(err) = entities.delete(backdrop) {
+                    warn!(
+                        "Failed to delete the backdrop of a closed ui modal: {}",
+                        err
+                    );
+                }
+            }
+
+            if let Some(above) = stack.layers.get_mut(index) {
+                // The modal above now returns focus to what was focused before the closed one.
+                above.previous_focus = layer.previous_focus;
+            } else {
+                for (entity, _) in (&*entities, &selecteds).join() {
+                    ui_events.single_write(UiEvent::new(UiEventType::Blur, entity));
+                }
+                selecteds.clear();
+                for entity in layer.previous_focus {
+                    if entities.is_alive(entity) {
+                        selecteds
+                            .insert(entity, Selected)
+                            .expect("unreachable: We are inserting");
+                        ui_events.single_write(UiEvent::new(UiEventType::Focus, entity));
+                    }
+                }
+            }
+        }
+
+        // Open the modals that just became visible, the ones drawn on top last.
+        let mut opened = active
+            .into_iter()
+            .filter(|(entity, _)| stack.layers.iter().all(|layer| layer.root != *entity))
+            .filter_map(|(entity, backdrop)| {
+                transforms
+                    .get(entity)
+                    .map(|transform| (entity, backdrop, transform.global_z))
+            })
+            .collect::<Vec<_>>();
+        opened.sort_by(|(_, _, z1), (_, _, z2)| z1.partial_cmp(z2).expect("Unexpected NaN"));
+
+        for (root, backdrop_color, global_z) in opened {
+            let previous_focus = (&*entities, &selecteds)
+                .join()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>();
+            for entity in &previous_focus {
+                ui_events.single_write(UiEvent::new(UiEventType::Blur, *entity));
+            }
+            selecteds.clear();
+
+            let backdrop = backdrop_color.map(|color| {
+                let id = transforms
+                    .get(root)
+                    .map(|t| format!("{}_backdrop", t.id))
+                    .unwrap_or_default();
+                entities
+                    .build_entity()
+                    .with(
+                        UiTransform::new(
+                            id,
+                            Anchor::Middle,
+                            Anchor::Middle,
+                            0.,
+                            0.,
+                            global_z - BACKDROP_Z_OFFSET,
+                            0.,
+                            0.,
+                        )
+                        .with_stretch(Stretch::XY {
+                            x_margin: 0.,
+                            y_margin: 0.,
+                            keep_aspect_ratio: false,
+                        }),
+                        &mut transforms,
+                    )
+                    .with(UiImage::SolidColor(color), &mut images)
+                    .build()
+            });
+
+            stack.layers.push(UiModalLayer {
+                root,
+                backdrop,
+                previous_focus,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs_hierarchy::HierarchySystem;
+
+    use amethyst_core::{
+        ecs::prelude::{Builder, RunNow, World, WorldExt},
+        Parent,
+    };
+
+    use super::*;
+
+    struct Setup {
+        world: World,
+        modal_system: UiModalSystem,
+        hierarchy_system: HierarchySystem<Parent>,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let mut world = World::new();
+            let hierarchy_system = HierarchySystem::<Parent>::new(&mut world);
+            let mut modal_system = UiModalSystem::new();
+            RunNow::setup(&mut modal_system, &mut world);
+            world.register::<Parent>();
+            Setup {
+                world,
+                modal_system,
+                hierarchy_system,
+            }
+        }
+
+        fn run(&mut self) {
+            self.hierarchy_system.run_now(&self.world);
+            self.modal_system.run_now(&self.world);
+            self.world.maintain();
+        }
+
+        fn element(&mut self, z: f32, parent: Option<Entity>) -> Entity {
+            let transform = UiTransform::new(
+                String::new(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                0.,
+                z,
+                10.,
+                10.,
+            );
+            let mut builder = self.world.create_entity().with(transform);
+            if let Some(entity) = parent {
+                builder = builder.with(Parent { entity });
+            }
+            builder.build()
+        }
+
+        fn modal(&mut self, z: f32, backdrop: Option<[f32; 4]>) -> Entity {
+            let modal = self.element(z, None);
+            self.world
+                .write_storage()
+                .insert(modal, UiModal { backdrop })
+                .unwrap();
+            modal
+        }
+
+        fn hide(&mut self, entity: Entity) {
+            self.world
+                .write_storage()
+                .insert(entity, HiddenPropagate::new())
+                .unwrap();
+            self.run();
+        }
+
+        fn select(&mut self, entity: Entity) {
+            self.world.write_storage().insert(entity, Selected).unwrap();
+        }
+
+        fn selected(&self) -> Vec<Entity> {
+            (
+                &*self.world.entities(),
+                &self.world.read_storage::<Selected>(),
+            )
+                .join()
+                .map(|(entity, _)| entity)
+                .collect()
+        }
+
+        fn roots(&self) -> Vec<Entity> {
+            self.world.read_resource::<UiModalStack>().roots().collect()
+        }
+    }
+
+    #[test]
+    fn modals_stack_by_z_and_give_the_focus_back_when_closed() {
+        let mut setup = Setup::new();
+        let field = setup.element(0., None);
+        setup.select(field);
+        let first = setup.modal(1., Some([0., 0., 0., 0.5]));
+        let inside_first = setup.element(1.1, Some(first));
+        setup.run();
+        assert_eq!(setup.roots(), vec![first]);
+        assert!(setup.selected().is_empty());
+        let backdrop = setup.world.read_resource::<UiModalStack>().layers[0]
+            .backdrop
+            .unwrap();
+
+        setup.select(inside_first);
+        // Modals opened together are stacked from the lowest to the highest.
+        let third = setup.modal(3., None);
+        let second = setup.modal(2., None);
+        setup.run();
+        assert_eq!(setup.roots(), vec![first, second, third]);
+        assert_eq!(
+            setup.world.read_resource::<UiModalStack>().top(),
+            Some(third)
+        );
+        assert!(setup.selected().is_empty());
+
+        // Closing a modal under the top one hands its focus over to the modal above it.
+        setup.hide(second);
+        assert_eq!(setup.roots(), vec![first, third]);
+        assert!(setup.selected().is_empty());
+        setup.hide(third);
+        assert_eq!(setup.roots(), vec![first]);
+        assert_eq!(setup.selected(), vec![inside_first]);
+
+        setup.hide(first);
+        assert!(setup.world.read_resource::<UiModalStack>().is_empty());
+        assert_eq!(setup.selected(), vec![field]);
+        assert!(!setup.world.is_alive(backdrop));
+    }
+
+    #[test]
+    fn only_the_top_modal_receives_input() {
+        let mut setup = Setup::new();
+        let outside = setup.element(0., None);
+        let first = setup.modal(1., None);
+        let inside_first = setup.element(1.1, Some(first));
+        setup.run();
+        let allows = |setup: &Setup, entity| {
+            let hierarchy = setup.world.read_resource::<ParentHierarchy>();
+            setup
+                .world
+                .read_resource::<UiModalStack>()
+                .allows(entity, &hierarchy)
+        };
+        assert!(allows(&setup, first));
+        assert!(allows(&setup, inside_first));
+        assert!(!allows(&setup, outside));
+
+        let second = setup.modal(2., None);
+        let inside_second = setup.element(2.1, Some(second));
+        setup.run();
+        assert!(allows(&setup, inside_second));
+        assert!(!allows(&setup, inside_first));
+        assert!(!allows(&setup, first));
+        assert!(!allows(&setup, outside));
+
+        setup.hide(second);
+        setup.hide(first);
+        assert!(allows(&setup, outside));
+    }
+}
@@ -0,0 +1,154 @@
+//! Module for the UiOpacity component.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use amethyst_core::{
+    ecs::prelude::{Component, DenseVecStorage, Entities, Entity, Join, ReadStorage},
+    ParentHierarchy,
+};
+
+/// Multiplies the alpha of a ui element and of all its descendants.
+///
+/// Opacities multiply down the hierarchy: a child with an opacity of `0.5` inside a parent with an
+/// opacity of `0.5` is drawn with a quarter of its own alpha. This applies to the colour of
+/// `UiImage`s, `Tint`s and `UiText`s alike.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiOpacity(pub f32);
+
+impl Default for UiOpacity {
+    fn default() -> Self {
+        UiOpacity(1.0)
+    }
+}
+
+impl Component for UiOpacity {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Resource configuring how opacity affects the ui.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiOpacityConfig {
+    /// If true, elements with a global opacity of zero are not targeted by mouse events and
+    /// don't block the elements underneath them, so that fading menus don't eat clicks.
+    pub ignore_transparent_input: bool,
+}
+
+/// The opacities the ui elements are drawn with, computed once for the whole hierarchy.
+///
+/// Use it instead of `global_opacity` when the opacity of many elements is needed.
+#[derive(Debug, Clone, Default)]
+pub struct UiGlobalOpacities {
+    /// The elements that aren't fully opaque.
+    opacities: HashMap<Entity, f32>,
+}
+
+impl UiGlobalOpacities {
+    /// Propagates the `UiOpacity`s down the hierarchy, from the parents to their children.
+    pub fn compute(
+        entities: &Entities<'_>,
+        opacities: &ReadStorage<'_, UiOpacity>,
+        hierarchy: &ParentHierarchy,
+    ) -> Self {
+        let roots = (&**entities, opacities)
+            .join()
+            .filter(|(entity, _)| hierarchy.parent(*entity).is_none())
+            .map(|(entity, opacity)| (entity, opacity.0));
+        Self::propagate(
+            roots,
+            hierarchy.all(),
+            |entity| hierarchy.parent(entity),
+            |entity| opacities.get(entity).map(|opacity| opacity.0),
+        )
+    }
+
+    /// `sorted` holds the children, after their parents.
+    fn propagate(
+        roots: impl Iterator<Item = (Entity, f32)>,
+        sorted: &[Entity],
+        parent: impl Fn(Entity) -> Option<Entity>,
+        opacity: impl Fn(Entity) -> Option<f32>,
+    ) -> Self {
+        let mut global = roots
+            .map(|(entity, opacity)| (entity, clamp(opacity)))
+            .filter(|&(_, opacity)| opacity < 1.0)
+            .collect::<HashMap<_, _>>();
+        for &entity in sorted {
+            let inherited = parent(entity)
+                .and_then(|parent| global.get(&parent))
+                .cloned()
+                .unwrap_or(1.0);
+            let opacity = inherited * opacity(entity).map_or(1.0, clamp);
+            if opacity < 1.0 {
+                global.insert(entity, opacity);
+            }
+        }
+        UiGlobalOpacities { opacities: global }
+    }
+
+    /// The opacity `entity` is drawn with.
+    pub fn get(&self, entity: Entity) -> f32 {
+        self.opacities.get(&entity).cloned().unwrap_or(1.0)
+    }
+}
+
+fn clamp(opacity: f32) -> f32 {
+    opacity.max(0.0).min(1.0)
+}
+
+/// Computes the opacity `entity` is drawn with, which is the product of its own `UiOpacity` and
+/// the ones of all its ancestors.
+pub fn global_opacity(
+    entity: Entity,
+    opacities: &ReadStorage<'_, UiOpacity>,
+    hierarchy: &ParentHierarchy,
+) -> f32 {
+    let mut opacity = 1.0;
+    let mut current = Some(entity);
+    while let Some(e) = current {
+        if let Some(UiOpacity(o)) = opacities.get(e) {
+            opacity *= clamp(*o);
+        }
+        current = hierarchy.parent(e);
+    }
+    opacity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::prelude::{Builder, World, WorldExt};
+
+    #[test]
+    fn opacities_multiply_down_the_hierarchy() {
+        let mut world = World::new();
+        let mut entity = || world.create_entity().build();
+        let (root, child, grandchild, opaque_root, opaque_child) =
+            (entity(), entity(), entity(), entity(), entity());
+        let parents = [
+            (child, root),
+            (grandchild, child),
+            (opaque_child, opaque_root),
+        ]
+        .iter()
+        .cloned()
+        .collect::<HashMap<_, _>>();
+        let locals = [(root, 0.5), (grandchild, 0.5), (opaque_child, 2.0)]
+            .iter()
+            .cloned()
+            .collect::<HashMap<_, _>>();
+
+        let global = UiGlobalOpacities::propagate(
+            vec![(root, 0.5)].into_iter(),
+            &[child, opaque_child, grandchild],
+            |entity| parents.get(&entity).cloned(),
+            |entity| locals.get(&entity).cloned(),
+        );
+        assert_eq!(global.get(root), 0.5);
+        assert_eq!(global.get(child), 0.5);
+        assert_eq!(global.get(grandchild), 0.25);
+        assert_eq!(global.get(opaque_root), 1.0);
+        assert_eq!(global.get(opaque_child), 1.0);
+    }
+}
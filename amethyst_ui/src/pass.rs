@@ -1,6 +1,11 @@
 use crate::{
+    canvas::{target_of, UiCanvas},
     glyphs::{UiGlyphs, UiGlyphsResource},
-    Selected, TextEditing, UiGlyphsSystemDesc, UiImage, UiTransform,
+    image::linear_to_srgba,
+    opacity::{UiGlobalOpacities, UiOpacity},
+    vector::{UiVectorSystemDesc, UiVectorTexture},
+    Selected, TextEditing, UiGlyphsSystemDesc, UiImage, UiImageFit, UiImageLayout, UiShape,
+    UiShapeFill, UiTransform,
 };
 use amethyst_assets::{AssetStorage, Handle, Loader};
 use amethyst_core::{
@@ -8,12 +13,13 @@ use amethyst_core::{
         hibitset::BitSet, DispatcherBuilder, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
         SystemData, World,
     },
-    Hidden, HiddenPropagate, SystemDesc,
+    math::{Matrix3, Point2},
+    Hidden, HiddenPropagate, ParentHierarchy, SystemDesc,
 };
 use amethyst_error::Error;
 use amethyst_rendy::{
     batch::OrderedOneLevelBatch,
-    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage},
     palette,
     pipeline::{PipelineDescBuilder, PipelinesBuilder},
     rendy::{
@@ -21,7 +27,7 @@ use amethyst_rendy::{
         factory::Factory,
         graph::{
             render::{PrepareResult, RenderGroup, RenderGroupDesc},
-            GraphContext, NodeBuffer, NodeImage,
+            GraphContext, ImageAccess, NodeBuffer, NodeImage,
         },
         hal::{
             self,
@@ -30,6 +36,10 @@ use amethyst_rendy::{
             pso::{self, ShaderStageFlags},
         },
         mesh::{AsVertex, VertexFormat},
+        resource::{
+            DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle, ImageView,
+            ImageViewInfo, Sampler,
+        },
         shader::{Shader, SpirvShader},
         texture::palette::load_from_srgba,
     },
@@ -37,28 +47,40 @@ use amethyst_rendy::{
     simple_shader_set,
     submodules::{DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub},
     types::{Backend, Texture},
-    ChangeDetection, SpriteSheet,
+    util, ChangeDetection, SpriteSheet,
 };
 use amethyst_window::ScreenDimensions;
 use derivative::Derivative;
-use glsl_layout::{vec2, vec4, AsStd140};
+use glsl_layout::{uvec2, uvec4, vec2, vec4, AsStd140};
+use log::warn;
 use std::cmp::Ordering;
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
 /// A [RenderPlugin] for rendering UI elements.
+///
+/// Several plugins can be added to draw `UiCanvas`es into other render targets.
 #[derive(Debug, Default)]
 pub struct RenderUi {
     target: Target,
+    images: Vec<TargetImage>,
 }
 
 impl RenderUi {
     /// Select render target on which UI should be rendered.
+    /// Only the UI elements inside a `UiCanvas` with the same target are rendered, or the ones
+    /// outside of any canvas for the main target.
     pub fn with_target(mut self, target: Target) -> Self {
         self.target = target;
         self
     }
+
+    /// Makes an image of another render target available to `UiImage::RenderTarget`.
+    pub fn with_image(mut self, image: TargetImage) -> Self {
+        self.images.push(image);
+        self
+    }
 }
 
 impl<B: Backend> RenderPlugin<B> for RenderUi {
@@ -67,11 +89,19 @@ impl<B: Backend> RenderPlugin<B> for RenderUi {
         world: &mut World,
         builder: &mut DispatcherBuilder<'a, 'b>,
     ) -> Result<(), Error> {
-        builder.add(
-            UiGlyphsSystemDesc::<B>::default().build(world),
-            "ui_glyphs_system",
-            &[],
-        );
+        // The glyphs are shared by all the ui render targets.
+        if !world.has_value::<UiGlyphsResource>() {
+            builder.add(
+                UiGlyphsSystemDesc::<B>::default().build(world),
+                "ui_glyphs_system",
+                &[],
+            );
+            builder.add(
+                UiVectorSystemDesc::<B>::default().build(world),
+                "ui_vector_system",
+                &[],
+            );
+        }
         Ok(())
     }
 
@@ -81,14 +111,29 @@ impl<B: Backend> RenderPlugin<B> for RenderUi {
         _factory: &mut Factory<B>,
         _world: &World,
     ) -> Result<(), Error> {
-        plan.extend_target(self.target, |ctx| {
-            ctx.add(RenderOrder::Overlay, DrawUiDesc::new().builder())?;
+        let target = self.target;
+        let images = self.images.clone();
+        plan.extend_target(self.target, move |ctx| {
+            let mut builder = DrawUiDesc::new()
+                .with_target(target)
+                .with_images(images.clone())
+                .builder();
+            for image in images {
+                builder = builder.with_image(ctx.get_image(image)?);
+            }
+            ctx.add(RenderOrder::Overlay, builder)?;
             Ok(())
         });
         Ok(())
     }
 }
 
+/// The `UiArgs::transform` of quads that are neither rotated nor scaled.
+pub(crate) const IDENTITY_TRANSFORM: [f32; 4] = [1., 0., 0., 1.];
+
+/// The `UiArgs::shape` of quads which are not a `UiShape`.
+pub(crate) const NO_SHAPE: [u32; 2] = [0, 0];
+
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, AsStd140)]
 #[repr(C, align(4))]
 pub(crate) struct UiArgs {
@@ -97,6 +142,21 @@ pub(crate) struct UiArgs {
     pub(crate) tex_coord_bounds: vec4,
     pub(crate) color: vec4,
     pub(crate) color_bias: vec4,
+    /// Column-major 2x2 matrix rotating and scaling the quad around its center.
+    pub(crate) transform: vec4,
+    /// What the quad is, `0` for a texture, `1` for a `UiShape` and `2` for a
+    /// `UiImage::Gradient`, then the border width, blur and gradient kind of shapes.
+    /// The rest of the fields are only used by shapes and gradients, so they are packed by
+    /// `pack_halves` and `pack_color` to keep the instances of other quads small.
+    pub(crate) shape: uvec2,
+    /// The radius of the top left, top right, bottom right and bottom left corners of a shape.
+    pub(crate) radii: uvec2,
+    /// The start and end of a linear gradient, or the center and radius of a radial one.
+    pub(crate) gradient: uvec2,
+    /// The color at the end of the gradient of a shape, which starts with `color`, and the color
+    /// of its border. The top left, top right, bottom left and bottom right colors of a
+    /// `UiImage::Gradient`.
+    pub(crate) shape_colors: uvec4,
 }
 
 impl AsVertex for UiArgs {
@@ -107,6 +167,11 @@ impl AsVertex for UiArgs {
             (Format::Rgba32Sfloat, "tex_coord_bounds"),
             (Format::Rgba32Sfloat, "color"),
             (Format::Rgba32Sfloat, "color_bias"),
+            (Format::Rgba32Sfloat, "transform"),
+            (Format::Rg32Uint, "shape"),
+            (Format::Rg32Uint, "radii"),
+            (Format::Rg32Uint, "gradient"),
+            (Format::Rgba32Uint, "shape_colors"),
         ))
     }
 }
@@ -132,19 +197,47 @@ lazy_static::lazy_static! {
 
 /// A UI drawing pass that draws UI elements and text in screen-space
 #[derive(Clone, Debug, Default, PartialEq)]
-pub struct DrawUiDesc;
+pub struct DrawUiDesc {
+    target: Target,
+    images: Vec<TargetImage>,
+}
 
 impl DrawUiDesc {
     /// Create new DrawUI pass description
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Only draw the UI elements of the `UiCanvas`es drawn into `target`.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Sets the images of other render targets available to `UiImage::RenderTarget`.
+    /// They must be added to the builder of this pass, in the same order.
+    pub fn with_images(mut self, images: Vec<TargetImage>) -> Self {
+        self.images = images;
+        self
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        self.images
+            .iter()
+            .map(|_| ImageAccess {
+                access: hal::image::Access::SHADER_READ,
+                usage: hal::image::Usage::SAMPLED,
+                layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                stages: hal::pso::PipelineStage::FRAGMENT_SHADER,
+            })
+            .collect()
+    }
+
     fn build(
         self,
-        _ctx: &GraphContext<B>,
+        ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
         resources: &World,
@@ -152,7 +245,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
         _buffers: Vec<NodeBuffer>,
-        _images: Vec<NodeImage>,
+        images: Vec<NodeImage>,
     ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
         #[cfg(feature = "profiler")]
         profile_scope!("build");
@@ -161,6 +254,56 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
+        let target_layout: RendyHandle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::CombinedImageSampler,
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let sampler = factory.get_sampler(hal::image::SamplerInfo::new(
+            hal::image::Filter::Linear,
+            hal::image::WrapMode::Clamp,
+        ))?;
+        let target_images = self
+            .images
+            .iter()
+            .zip(images)
+            .map(|(target_image, node_image)| {
+                let image = ctx
+                    .get_image(node_image.id)
+                    .ok_or_else(|| failure::format_err!("Missing image {:?}", target_image))?;
+                let extent = image.kind().extent();
+                let view = factory.create_image_view(
+                    image.clone(),
+                    ImageViewInfo {
+                        view_kind: hal::image::ViewKind::D2,
+                        format: image.format(),
+                        swizzle: hal::format::Swizzle::NO,
+                        range: node_image.range.clone(),
+                    },
+                )?;
+                let set = factory.create_descriptor_set(target_layout.clone())?;
+                unsafe {
+                    factory.write_descriptor_sets(vec![util::desc_write(
+                        set.raw(),
+                        0,
+                        pso::Descriptor::CombinedImageSampler(
+                            view.raw(),
+                            hal::image::Layout::ShaderReadOnlyOptimal,
+                            sampler.raw(),
+                        ),
+                    )]);
+                }
+                Ok(TargetImageSet {
+                    image: *target_image,
+                    size: [extent.width as f32, extent.height as f32],
+                    set,
+                    _view: view,
+                })
+            })
+            .collect::<Result<Vec<_>, failure::Error>>()?;
+
         let (pipeline, pipeline_layout) = build_ui_pipeline(
             factory,
             subpass,
@@ -178,6 +321,10 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
         );
 
         Ok(Box::new(DrawUi::<B> {
+            target: self.target,
+            framebuffer_size: (framebuffer_width as f32, framebuffer_height as f32),
+            target_images,
+            _sampler: sampler,
             pipeline,
             pipeline_layout,
             env,
@@ -191,15 +338,38 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
     }
 }
 
+/// Key of the draw batches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UiTextureId {
+    /// A texture asset.
+    Texture(TextureId),
+    /// The index of an image of another render target.
+    Target(usize),
+}
+
+/// An image of another render target, ready to be sampled.
+#[derive(Debug)]
+struct TargetImageSet<B: Backend> {
+    image: TargetImage,
+    /// The size of the image in pixels, as the pass is rebuilt when the targets are resized.
+    size: [f32; 2],
+    set: Escape<DescriptorSet<B>>,
+    _view: Escape<ImageView<B>>,
+}
+
 /// A UI drawing pass that draws UI elements and text in screen-space
 #[derive(Debug)]
 pub struct DrawUi<B: Backend> {
+    target: Target,
+    framebuffer_size: (f32, f32),
+    target_images: Vec<TargetImageSet<B>>,
+    _sampler: RendyHandle<Sampler<B>>,
     pipeline: B::GraphicsPipeline,
     pipeline_layout: B::PipelineLayout,
     env: DynamicUniform<B, UiViewArgs>,
     textures: TextureSub<B>,
     vertex: DynamicVertexBuffer<B, UiArgs>,
-    batches: OrderedOneLevelBatch<TextureId, UiArgs>,
+    batches: OrderedOneLevelBatch<UiTextureId, UiArgs>,
     change: ChangeDetection,
     cached_draw_order: CachedDrawOrder,
     white_tex: Handle<Texture>,
@@ -227,24 +397,34 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
         let (
             entities,
             images,
+            image_layouts,
+            vector_textures,
             transforms,
             text_editings,
             hiddens,
             hidden_propagates,
             selected,
             tints,
+            opacities,
+            canvases,
+            hierarchy,
             glyphs,
             glyphs_res,
             screen_dimesnions,
         ) = <(
             Entities<'_>,
             ReadStorage<'_, UiImage>,
+            ReadStorage<'_, UiImageLayout>,
+            ReadStorage<'_, UiVectorTexture>,
             ReadStorage<'_, UiTransform>,
             ReadStorage<'_, TextEditing>,
             ReadStorage<'_, Hidden>,
             ReadStorage<'_, HiddenPropagate>,
             ReadStorage<'_, Selected>,
             ReadStorage<'_, Tint>,
+            ReadStorage<'_, UiOpacity>,
+            ReadStorage<'_, UiCanvas>,
+            ReadExpect<'_, ParentHierarchy>,
             ReadStorage<'_, UiGlyphs>,
             ReadExpect<'_, UiGlyphsResource>,
             ReadExpect<'_, ScreenDimensions>,
@@ -331,12 +511,24 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
             .cache
             .sort_unstable_by(|&(z1, _), &(z2, _)| z1.partial_cmp(&z2).unwrap_or(Ordering::Equal));
 
+        let global_opacities = UiGlobalOpacities::compute(&entities, &opacities, &hierarchy);
         for &(_z, entity) in &self.cached_draw_order.cache {
             // Skip hidden entities
             if hiddens.contains(entity) || hidden_propagates.contains(entity) {
                 continue;
             }
 
+            // Skip entities drawn into other render targets
+            if target_of(entity, &hierarchy, &canvases) != self.target {
+                continue;
+            }
+
+            // Skip fully transparent entities
+            let opacity = global_opacities.get(entity);
+            if opacity <= 0.0 {
+                continue;
+            }
+
             let transform = transforms
                 .get(entity)
                 .expect("Unreachable: Entity is guaranteed to be present based on earlier actions");
@@ -345,6 +537,14 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                 let (r, g, b, a) = t.0.into_components();
                 [r, g, b, a]
             });
+            let tint = if opacity < 1.0 {
+                Some(mul_blend(
+                    &tint.unwrap_or([1., 1., 1., 1.]),
+                    &[1., 1., 1., opacity],
+                ))
+            } else {
+                tint
+            };
 
             let image = images.get(entity);
             if let Some(image) = image {
@@ -353,8 +553,11 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                     resources,
                     transform,
                     image,
+                    image_layouts.get(entity),
+                    vector_textures.get(entity).map(|vector| &vector.texture),
                     &tint,
                     white_tex_id,
+                    &self.target_images,
                     &mut self.textures,
                     &mut self.batches,
                 );
@@ -363,8 +566,12 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
 
             if let Some(glyph_data) = glyphs.get(entity) {
                 if !glyph_data.sel_vertices.is_empty() {
-                    self.batches
-                        .insert(white_tex_id, glyph_data.sel_vertices.iter().cloned());
+                    self.batches.insert(
+                        UiTextureId::Texture(white_tex_id),
+                        glyph_data.sel_vertices.iter().map(|args| {
+                            with_matrix(with_opacity(*args, opacity), transform.global_matrix())
+                        }),
+                    );
                 }
 
                 // blinking cursor
@@ -400,21 +607,33 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                         let h = bottom - top;
 
                         self.batches.insert(
-                            white_tex_id,
-                            Some(UiArgs {
-                                coords: [x, y].into(),
-                                dimensions: [w, h].into(),
-                                tex_coord_bounds: [0., 0., 1., 1.].into(),
-                                color: tint.unwrap_or([1., 1., 1., 1.]).into(),
-                                color_bias: [0., 0., 0., 0.].into(),
-                            }),
+                            UiTextureId::Texture(white_tex_id),
+                            Some(with_matrix(
+                                UiArgs {
+                                    coords: [x, y].into(),
+                                    dimensions: [w, h].into(),
+                                    tex_coord_bounds: [0., 0., 1., 1.].into(),
+                                    color: tint.unwrap_or([1., 1., 1., 1.]).into(),
+                                    color_bias: [0., 0., 0., 0.].into(),
+                                    transform: IDENTITY_TRANSFORM.into(),
+                                    shape: NO_SHAPE.into(),
+                                    radii: [0, 0].into(),
+                                    gradient: [0, 0].into(),
+                                    shape_colors: [0, 0, 0, 0].into(),
+                                },
+                                transform.global_matrix(),
+                            )),
                         )
                     }
                 }
 
                 if !glyph_data.vertices.is_empty() {
-                    self.batches
-                        .insert(glyph_tex_id, glyph_data.vertices.iter().cloned());
+                    self.batches.insert(
+                        UiTextureId::Texture(glyph_tex_id),
+                        glyph_data.vertices.iter().map(|args| {
+                            with_matrix(with_opacity(*args, opacity), transform.global_matrix())
+                        }),
+                    );
                 }
             }
         }
@@ -433,12 +652,13 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                 Some(self.batches.data()),
             );
 
+            // Canvases are laid out in the size of their target.
+            let (width, height) = match self.target {
+                Target::Main => (screen_dimesnions.width(), screen_dimesnions.height()),
+                _ => self.framebuffer_size,
+            };
             let view_args = UiViewArgs {
-                inverse_window_size: [
-                    1.0 / screen_dimesnions.width() as f32,
-                    1.0 / screen_dimesnions.height() as f32,
-                ]
-                .into(),
+                inverse_window_size: [1.0 / width, 1.0 / height].into(),
             };
             changed = self.env.write(factory, index, view_args.std140()) || changed;
         }
@@ -462,7 +682,17 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
             self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
             self.vertex.bind(index, 0, 0, &mut encoder);
             for (&tex, range) in self.batches.iter() {
-                self.textures.bind(layout, 1, tex, &mut encoder);
+                match tex {
+                    UiTextureId::Texture(tex) => self.textures.bind(layout, 1, tex, &mut encoder),
+                    UiTextureId::Target(index) => unsafe {
+                        encoder.bind_graphics_descriptor_sets(
+                            layout,
+                            1,
+                            Some(self.target_images[index].set.raw()),
+                            std::iter::empty(),
+                        );
+                    },
+                }
                 unsafe {
                     encoder.draw(0..4, range);
                 }
@@ -532,16 +762,467 @@ fn mul_blend(a: &[f32; 4], b: &[f32; 4]) -> [f32; 4] {
     [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
 }
 
+fn with_opacity(mut args: UiArgs, opacity: f32) -> UiArgs {
+    if opacity < 1.0 {
+        let color: [f32; 4] = args.color.into();
+        args.color = mul_blend(&color, &[1., 1., 1., opacity]).into();
+    }
+    args
+}
+
+/// A quad of an image, in the layout space of its `UiTransform`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ImageQuad {
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    /// The texture coordinates of the left, top, right and bottom edges.
+    tex_coords: [f32; 4],
+}
+
+/// Fits an image of `native_size` pixels showing `tex_coords` into the rect of `center` and
+/// `dimensions`. Images of unknown size are stretched.
+fn fit_image(
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    tex_coords: [f32; 4],
+    native_size: [f32; 2],
+    fit: UiImageFit,
+) -> Vec<ImageQuad> {
+    let stretched = ImageQuad {
+        center,
+        dimensions,
+        tex_coords,
+    };
+    if native_size[0] <= 0.0 || native_size[1] <= 0.0 {
+        return vec![stretched];
+    }
+    match fit {
+        UiImageFit::Stretch => vec![stretched],
+        UiImageFit::Contain => {
+            let scale = (dimensions[0] / native_size[0]).min(dimensions[1] / native_size[1]);
+            vec![ImageQuad {
+                dimensions: [native_size[0] * scale, native_size[1] * scale],
+                ..stretched
+            }]
+        }
+        UiImageFit::Cover => {
+            let scale = (dimensions[0] / native_size[0]).max(dimensions[1] / native_size[1]);
+            // Keeps the visible part of the texture coordinates, around their center.
+            let crop = |start: f32, end: f32, visible: f32| {
+                let middle = (start + end) / 2.0;
+                let half = (end - start) * visible / 2.0;
+                (middle - half, middle + half)
+            };
+            let (left, right) = crop(
+                tex_coords[0],
+                tex_coords[2],
+                dimensions[0] / (native_size[0] * scale),
+            );
+            let (top, bottom) = crop(
+                tex_coords[1],
+                tex_coords[3],
+                dimensions[1] / (native_size[1] * scale),
+            );
+            vec![ImageQuad {
+                tex_coords: [left, top, right, bottom],
+                ..stretched
+            }]
+        }
+        UiImageFit::Tile => tile_quads(center, dimensions, tex_coords, native_size),
+        UiImageFit::NativeSize => vec![ImageQuad {
+            dimensions: native_size,
+            ..stretched
+        }],
+    }
+}
+
+/// Covers the rect of `center` and `dimensions` with tiles of `tile_size`, from its top left
+/// corner. The tiles on the right and bottom edges are cropped.
+fn tile_quads(
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    tex_coords: [f32; 4],
+    tile_size: [f32; 2],
+) -> Vec<ImageQuad> {
+    if tile_size[0] <= 0.0 || tile_size[1] <= 0.0 {
+        return vec![ImageQuad {
+            center,
+            dimensions,
+            tex_coords,
+        }];
+    }
+    let left = center[0] - dimensions[0] / 2.0;
+    let top = center[1] + dimensions[1] / 2.0;
+    let columns = (dimensions[0] / tile_size[0]).ceil().max(0.0) as usize;
+    let rows = (dimensions[1] / tile_size[1]).ceil().max(0.0) as usize;
+    let mut quads = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        let y = row as f32 * tile_size[1];
+        let height = (dimensions[1] - y).min(tile_size[1]);
+        let bottom = tex_coords[1] + (tex_coords[3] - tex_coords[1]) * height / tile_size[1];
+        for column in 0..columns {
+            let x = column as f32 * tile_size[0];
+            let width = (dimensions[0] - x).min(tile_size[0]);
+            let right = tex_coords[0] + (tex_coords[2] - tex_coords[0]) * width / tile_size[0];
+            quads.push(ImageQuad {
+                center: [left + x + width / 2.0, top - y - height / 2.0],
+                dimensions: [width, height],
+                tex_coords: [tex_coords[0], tex_coords[1], right, bottom],
+            });
+        }
+    }
+    quads
+}
+
+/// Fits a nine-slice image into the rect of `center` and `dimensions`, like `fit_image` does for
+/// other images: the slices are laid out over the fitted rect, whose corners keep their native
+/// size.
+///
+/// `tex_coords` are the texture coordinates of the edges of the slices, from the left and the top,
+/// and `native` the sizes in pixels of the three columns and the three rows.
+fn fit_nine_slice(
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    tex_coords: ([f32; 4], [f32; 4]),
+    native: ([f32; 3], [f32; 3]),
+    fit: UiImageFit,
+) -> Vec<ImageQuad> {
+    let native_size = [native.0.iter().sum::<f32>(), native.1.iter().sum::<f32>()];
+    let scale = |larger: bool| {
+        let x = dimensions[0] / native_size[0];
+        let y = dimensions[1] / native_size[1];
+        let scale = if larger { x.max(y) } else { x.min(y) };
+        [native_size[0] * scale, native_size[1] * scale]
+    };
+    let size = if native_size[0] <= 0.0 || native_size[1] <= 0.0 {
+        dimensions
+    } else {
+        match fit {
+            UiImageFit::Stretch | UiImageFit::Tile => dimensions,
+            UiImageFit::Contain => scale(false),
+            UiImageFit::Cover => scale(true),
+            UiImageFit::NativeSize => native_size,
+        }
+    };
+    let quads = nine_slice_quads(center, size, tex_coords, native, fit == UiImageFit::Tile);
+    if fit == UiImageFit::Cover {
+        crop_quads(quads, center, dimensions)
+    } else {
+        quads
+    }
+}
+
+/// Lays the nine slices of an image out over the rect of `center` and `dimensions`, from left to
+/// right and top to bottom. The corners keep their native size, while the edges and the center
+/// are stretched, or repeated if `tile` is set.
+fn nine_slice_quads(
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    tex_coords: ([f32; 4], [f32; 4]),
+    native: ([f32; 3], [f32; 3]),
+    tile: bool,
+) -> Vec<ImageQuad> {
+    let sizes =
+        |length: f32, native: [f32; 3]| [native[0], length - native[0] - native[2], native[2]];
+    let widths = sizes(dimensions[0], native.0);
+    let heights = sizes(dimensions[1], native.1);
+    let mut quads = Vec::with_capacity(9);
+    let mut left = center[0] - dimensions[0] / 2.0;
+    for x in 0..3 {
+        let mut top = center[1] + dimensions[1] / 2.0;
+        for y in 0..3 {
+            let slice = ImageQuad {
+                center: [left + widths[x] / 2.0, top - heights[y] / 2.0],
+                dimensions: [widths[x], heights[y]],
+                tex_coords: [
+                    tex_coords.0[x],
+                    tex_coords.1[y],
+                    tex_coords.0[x + 1],
+                    tex_coords.1[y + 1],
+                ],
+            };
+            if tile {
+                quads.extend(tile_quads(
+                    slice.center,
+                    slice.dimensions,
+                    slice.tex_coords,
+                    [native.0[x], native.1[y]],
+                ));
+            } else {
+                quads.push(slice);
+            }
+            top -= heights[y];
+        }
+        left += widths[x];
+    }
+    quads
+}
+
+/// Crops `quads` to the rect of `center` and `dimensions`, along with their texture coordinates.
+/// The quads outside of the rect are dropped.
+fn crop_quads(quads: Vec<ImageQuad>, center: [f32; 2], dimensions: [f32; 2]) -> Vec<ImageQuad> {
+    let (left, right) = (
+        center[0] - dimensions[0] / 2.0,
+        center[0] + dimensions[0] / 2.0,
+    );
+    let (bottom, top) = (
+        center[1] - dimensions[1] / 2.0,
+        center[1] + dimensions[1] / 2.0,
+    );
+    quads
+        .into_iter()
+        .filter_map(|quad| {
+            let quad_left = quad.center[0] - quad.dimensions[0] / 2.0;
+            let quad_top = quad.center[1] + quad.dimensions[1] / 2.0;
+            let new_left = quad_left.max(left);
+            let new_right = (quad.center[0] + quad.dimensions[0] / 2.0).min(right);
+            let new_bottom = (quad.center[1] - quad.dimensions[1] / 2.0).max(bottom);
+            let new_top = quad_top.min(top);
+            if new_left >= new_right || new_bottom >= new_top {
+                return None;
+            }
+            let [u_start, v_start, u_end, v_end] = quad.tex_coords;
+            let u = |x: f32| u_start + (u_end - u_start) * (x - quad_left) / quad.dimensions[0];
+            let v = |y: f32| v_start + (v_end - v_start) * (quad_top - y) / quad.dimensions[1];
+            Some(ImageQuad {
+                center: [(new_left + new_right) / 2.0, (new_bottom + new_top) / 2.0],
+                dimensions: [new_right - new_left, new_top - new_bottom],
+                tex_coords: [u(new_left), v(new_top), u(new_right), v(new_bottom)],
+            })
+        })
+        .collect()
+}
+
+/// Mirrors `quads` around `center`, along with their texture coordinates.
+fn flip_quads(quads: &mut [ImageQuad], center: [f32; 2], flip_x: bool, flip_y: bool) {
+    for quad in quads {
+        if flip_x {
+            quad.center[0] = 2.0 * center[0] - quad.center[0];
+            quad.tex_coords.swap(0, 2);
+        }
+        if flip_y {
+            quad.center[1] = 2.0 * center[1] - quad.center[1];
+            quad.tex_coords.swap(1, 3);
+        }
+    }
+}
+
+/// The instances drawing `quads` with the color of `args`, in screen space.
+fn quad_args(
+    args: UiArgs,
+    quads: Vec<ImageQuad>,
+    matrix: &Matrix3<f32>,
+) -> impl Iterator<Item = UiArgs> + '_ {
+    quads.into_iter().map(move |quad| {
+        let mut quad_args = args;
+        quad_args.coords = quad.center.into();
+        quad_args.dimensions = quad.dimensions.into();
+        quad_args.tex_coord_bounds = quad.tex_coords.into();
+        with_matrix(quad_args, matrix)
+    })
+}
+
+/// The instances drawing `shape` over the rect of `center` and `dimensions`, in layout space:
+/// its shadow if it has one, then the shape itself. Every color is multiplied by `tint`.
+fn shape_args(
+    shape: &UiShape,
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    tint: &Option<[f32; 4]>,
+) -> Vec<UiArgs> {
+    let tinted = |color: &[f32; 4]| match tint {
+        Some(t) => mul_blend(color, t),
+        None => *color,
+    };
+    let (color, gradient_color, gradient, gradient_kind) = match shape.fill {
+        UiShapeFill::Solid(ref color) => (color, color, [0., 0., 0., 0.], 0.),
+        UiShapeFill::Linear {
+            start,
+            end,
+            ref start_color,
+            ref end_color,
+        } => (
+            start_color,
+            end_color,
+            [start[0], start[1], end[0], end[1]],
+            1.,
+        ),
+        UiShapeFill::Radial {
+            center,
+            radius,
+            ref inner_color,
+            ref outer_color,
+        } => (
+            inner_color,
+            outer_color,
+            [center[0], center[1], radius, 0.],
+            2.,
+        ),
+    };
+    let args = UiArgs {
+        coords: center.into(),
+        dimensions: dimensions.into(),
+        tex_coord_bounds: [0., 0., 1., 1.].into(),
+        color: tinted(color).into(),
+        color_bias: [0., 0., 0., 0.].into(),
+        transform: IDENTITY_TRANSFORM.into(),
+        shape: pack_halves([1., shape.border_width, 0., gradient_kind]).into(),
+        radii: pack_halves(shape.corner_radii).into(),
+        gradient: pack_halves(gradient).into(),
+        shape_colors: [
+            pack_color(tinted(gradient_color)),
+            pack_color(tinted(&shape.border_color)),
+            0,
+            0,
+        ]
+        .into(),
+    };
+
+    let mut instances = Vec::with_capacity(2);
+    if let Some(ref shadow) = shape.shadow {
+        // The quad of the shadow grows by the blur on every side, so that it can fade out.
+        let blur = shadow.blur.max(0.);
+        let shadow_color = tinted(&shadow.color);
+        instances.push(UiArgs {
+            coords: [center[0] + shadow.offset[0], center[1] + shadow.offset[1]].into(),
+            dimensions: [dimensions[0] + blur * 2., dimensions[1] + blur * 2.].into(),
+            color: shadow_color.into(),
+            shape: pack_halves([1., 0., blur, 0.]).into(),
+            gradient: [0, 0].into(),
+            shape_colors: [0, 0, 0, 0].into(),
+            ..args
+        });
+    }
+    instances.push(args);
+    instances
+}
+
+/// The size of `tex` in pixels, or zero while it is loading.
+fn texture_size<B: Backend>(resources: &World, tex: &Handle<Texture>) -> [f32; 2] {
+    resources
+        .fetch::<AssetStorage<Texture>>()
+        .get(tex)
+        .and_then(B::unwrap_texture)
+        .map_or([0., 0.], |texture| {
+            let extent = texture.image().kind().extent();
+            [extent.width as f32, extent.height as f32]
+        })
+}
+
+/// The instance drawing a gradient between the linear `[top_left, top_right, bottom_left,
+/// bottom_right]` colors over the rect of `center` and `dimensions`, in layout space. The corners
+/// are tinted, then packed in sRGB so that the fragment shader interpolates them in sRGB.
+fn corner_gradient_args(
+    corners: &[[f32; 4]; 4],
+    center: [f32; 2],
+    dimensions: [f32; 2],
+    tint: &Option<[f32; 4]>,
+) -> UiArgs {
+    let corner = |index: usize| match tint {
+        Some(t) => pack_color(mul_blend(&corners[index], t)),
+        None => pack_color(corners[index]),
+    };
+    UiArgs {
+        coords: center.into(),
+        dimensions: dimensions.into(),
+        tex_coord_bounds: [0., 0., 1., 1.].into(),
+        color: [1., 1., 1., 1.].into(),
+        color_bias: [0., 0., 0., 0.].into(),
+        transform: IDENTITY_TRANSFORM.into(),
+        shape: pack_halves([2., 0., 0., 0.]).into(),
+        radii: [0, 0].into(),
+        gradient: [0, 0].into(),
+        shape_colors: [corner(0), corner(1), corner(2), corner(3)].into(),
+    }
+}
+
+/// Packs four floats into two integers, as half precision floats read back by `unpackHalf2x16`
+/// in the ui shaders.
+fn pack_halves([x, y, z, w]: [f32; 4]) -> [u32; 2] {
+    let pair = |low: f32, high: f32| u32::from(to_half(low)) | (u32::from(to_half(high)) << 16);
+    [pair(x, y), pair(z, w)]
+}
+
+/// The bits of the half precision float closest to `value`. Values too large for it become
+/// infinite.
+fn to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // Infinite, or not a number.
+        return sign | 0x7c00 | if mantissa == 0 { 0 } else { 0x200 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // Subnormal, with the implicit leading bit of the mantissa shifted in.
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        sign | ((mantissa >> shift) + round) as u16
+    } else {
+        // Rounding can carry into the exponent, up to infinity, which is still the closest.
+        let round = (mantissa >> 12) & 1;
+        sign | ((((exponent as u32) << 10) | (mantissa >> 13)) + round) as u16
+    }
+}
+
+/// Packs a linear RGBA color into an integer, as the sRGB bytes read back by `unpackUnorm4x8` in
+/// the ui fragment shader.
+fn pack_color(color: [f32; 4]) -> u32 {
+    linear_to_srgba(color)
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| ((channel.max(0.0).min(1.0) * 255.0).round() as u32) << (i * 8))
+        .sum()
+}
+
+/// Moves `args` from the layout space of a `UiTransform` to screen space.
+///
+/// The matrices of world canvases are projective: their quads are drawn with the affine
+/// approximation of the projection at their center.
+fn with_matrix(mut args: UiArgs, matrix: &Matrix3<f32>) -> UiArgs {
+    if matrix.is_identity(0.0) {
+        return args;
+    }
+    let coords: [f32; 2] = args.coords.into();
+    let w = matrix[(2, 0)] * coords[0] + matrix[(2, 1)] * coords[1] + matrix[(2, 2)];
+    let center = matrix.transform_point(&Point2::new(coords[0], coords[1]));
+    // The derivatives of the projection at the center, which for affine matrices are their
+    // linear part.
+    let derivative =
+        |axis: usize, of: usize| (matrix[(of, axis)] - center[of] * matrix[(2, axis)]) / w;
+    args.coords = [center.x, center.y].into();
+    args.transform = [
+        derivative(0, 0),
+        derivative(0, 1),
+        derivative(1, 0),
+        derivative(1, 1),
+    ]
+    .into();
+    args
+}
+
 fn render_image<B: Backend>(
     factory: &Factory<B>,
     resources: &World,
     transform: &UiTransform,
     raw_image: &UiImage,
+    layout: Option<&UiImageLayout>,
+    vector_texture: Option<&Handle<Texture>>,
     tint: &Option<[f32; 4]>,
     white_tex_id: TextureId,
+    target_images: &[TargetImageSet<B>],
     textures: &mut TextureSub<B>,
-    batches: &mut OrderedOneLevelBatch<TextureId, UiArgs>,
+    batches: &mut OrderedOneLevelBatch<UiTextureId, UiArgs>,
 ) -> bool {
+    let layout = layout.cloned().unwrap_or_default();
     let color = match (raw_image, tint.as_ref()) {
         (UiImage::SolidColor(color), Some(t)) => mul_blend(color, t),
         (UiImage::SolidColor(color), None) => *color,
@@ -580,6 +1261,19 @@ fn render_image<B: Backend>(
         tex_coord_bounds: tex_coords.into(),
         color: color.into(),
         color_bias: [0., 0., 0., 0.].into(),
+        transform: IDENTITY_TRANSFORM.into(),
+        shape: NO_SHAPE.into(),
+        radii: [0, 0].into(),
+        gradient: [0, 0].into(),
+        shape_colors: [0, 0, 0, 0].into(),
+    };
+
+    let center = [transform.pixel_x(), transform.pixel_y()];
+    let dimensions = [transform.pixel_width, transform.pixel_height];
+    let fit = |native_size: [f32; 2]| {
+        let mut quads = fit_image(center, dimensions, tex_coords, native_size, layout.fit);
+        flip_quads(&mut quads, center, layout.flip_x, layout.flip_y);
+        quads
     };
 
     match raw_image {
@@ -590,20 +1284,35 @@ fn render_image<B: Backend>(
                 tex,
                 hal::image::Layout::ShaderReadOnlyOptimal,
             ) {
-                batches.insert(tex_id, Some(args));
+                let quads = fit(texture_size::<B>(resources, tex));
+                batches.insert(
+                    UiTextureId::Texture(tex_id),
+                    quad_args(args, quads, transform.global_matrix()),
+                );
                 this_changed
             } else {
                 false
             }
         }
-        UiImage::PartialTexture { tex, .. } => {
+        UiImage::PartialTexture {
+            tex,
+            left,
+            right,
+            bottom,
+            top,
+        } => {
             if let Some((tex_id, this_changed)) = textures.insert(
                 factory,
                 resources,
                 tex,
                 hal::image::Layout::ShaderReadOnlyOptimal,
             ) {
-                batches.insert(tex_id, Some(args));
+                let [width, height] = texture_size::<B>(resources, tex);
+                let quads = fit([width * (right - left).abs(), height * (bottom - top).abs()]);
+                batches.insert(
+                    UiTextureId::Texture(tex_id),
+                    quad_args(args, quads, transform.global_matrix()),
+                );
                 this_changed
             } else {
                 false
@@ -618,7 +1327,12 @@ fn render_image<B: Backend>(
                     &sprite_sheet.texture,
                     hal::image::Layout::ShaderReadOnlyOptimal,
                 ) {
-                    batches.insert(tex_id, Some(args));
+                    let sprite = &sprite_sheet.sprites[sprite_renderer.sprite_number];
+                    let quads = fit([sprite.width, sprite.height]);
+                    batches.insert(
+                        UiTextureId::Texture(tex_id),
+                        quad_args(args, quads, transform.global_matrix()),
+                    );
                     this_changed
                 } else {
                     false
@@ -659,55 +1373,503 @@ fn render_image<B: Backend>(
                     (*y_start + *height) as f32 / texture_dimensions[1] as f32,
                 ];
 
-                //The dimensions of each slice
-                let x_dimensions = [
+                //The size of each slice in the texture
+                let x_native = [
                     *left_dist as f32,
-                    transform.pixel_width - (*right_dist + *left_dist) as f32,
+                    (*width - *left_dist - *right_dist) as f32,
                     *right_dist as f32,
                 ];
-                let y_dimensions = [
+                let y_native = [
                     *top_dist as f32,
-                    transform.pixel_height - (*top_dist + *bottom_dist) as f32,
+                    (*height - *top_dist - *bottom_dist) as f32,
                     *bottom_dist as f32,
                 ];
 
-                //The center location of each slice on the screen
-                let x_coords = [
-                    transform.pixel_x() - ((transform.pixel_width - *left_dist as f32) / 2.0),
-                    transform.pixel_x() + (*left_dist as f32 - *right_dist as f32) / 2.0,
-                    transform.pixel_x() + ((transform.pixel_width - *right_dist as f32) / 2.0),
-                ];
-                let y_coords = [
-                    transform.pixel_y() + ((transform.pixel_height - *top_dist as f32) / 2.0),
-                    transform.pixel_y() + (*bottom_dist as f32 - *top_dist as f32) / 2.0,
-                    transform.pixel_y() - ((transform.pixel_height - *bottom_dist as f32) / 2.0),
-                ];
-
-                // loop through left to right, the top to bottom and batch each slice to render
-                for x in 0..3 {
-                    for y in 0..3 {
-                        let mut temp_args = args;
-                        temp_args.tex_coord_bounds = [
-                            x_tex_coord_bound[x],
-                            y_tex_coord_bound[y],
-                            x_tex_coord_bound[x + 1],
-                            y_tex_coord_bound[y + 1],
-                        ]
-                        .into();
-                        temp_args.dimensions = [x_dimensions[x], y_dimensions[y]].into();
-                        temp_args.coords = [x_coords[x], y_coords[y]].into();
-                        batches.insert(tex_id, Some(temp_args));
-                    }
-                }
+                let mut quads = fit_nine_slice(
+                    center,
+                    dimensions,
+                    (x_tex_coord_bound, y_tex_coord_bound),
+                    (x_native, y_native),
+                    layout.fit,
+                );
+                flip_quads(&mut quads, center, layout.flip_x, layout.flip_y);
+                batches.insert(
+                    UiTextureId::Texture(tex_id),
+                    quad_args(args, quads, transform.global_matrix()),
+                );
 
                 this_changed
             } else {
                 false
             }
         }
+        UiImage::Vector(_) => {
+            // Nothing is drawn until the image is rasterised by `UiVectorSystem`.
+            match vector_texture.and_then(|tex| {
+                textures.insert(
+                    factory,
+                    resources,
+                    tex,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                )
+            }) {
+                Some((tex_id, this_changed)) => {
+                    // The texture already has the size of the transform.
+                    let quads = fit([0., 0.]);
+                    batches.insert(
+                        UiTextureId::Texture(tex_id),
+                        quad_args(args, quads, transform.global_matrix()),
+                    );
+                    this_changed
+                }
+                None => false,
+            }
+        }
+        UiImage::Shape(shape) => {
+            batches.insert(
+                UiTextureId::Texture(white_tex_id),
+                shape_args(shape, center, dimensions, tint)
+                    .into_iter()
+                    .map(|args| with_matrix(args, transform.global_matrix())),
+            );
+            false
+        }
+        UiImage::Gradient {
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+        } => {
+            let corners = [*top_left, *top_right, *bottom_left, *bottom_right];
+            batches.insert(
+                UiTextureId::Texture(white_tex_id),
+                Some(with_matrix(
+                    corner_gradient_args(&corners, center, dimensions, tint),
+                    transform.global_matrix(),
+                )),
+            );
+            false
+        }
+        UiImage::RenderTarget(image) => {
+            match target_images.iter().position(|set| set.image == *image) {
+                Some(index) => {
+                    let quads = fit(target_images[index].size);
+                    batches.insert(
+                        UiTextureId::Target(index),
+                        quad_args(args, quads, transform.global_matrix()),
+                    );
+                }
+                None => warn!(
+                    "{:?} is not available to this ui pass, add it with `RenderUi::with_image`",
+                    image
+                ),
+            }
+            false
+        }
         _ => {
-            batches.insert(white_tex_id, Some(args));
+            batches.insert(
+                UiTextureId::Texture(white_tex_id),
+                Some(with_matrix(args, transform.global_matrix())),
+            );
             false
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UiShadow;
+
+    const FULL: [f32; 4] = [0., 0., 1., 1.];
+
+    #[test]
+    fn stretch_fills_the_rect() {
+        let quads = fit_image(
+            [0., 0.],
+            [200., 100.],
+            FULL,
+            [50., 50.],
+            UiImageFit::Stretch,
+        );
+        assert_eq!(
+            quads,
+            vec![ImageQuad {
+                center: [0., 0.],
+                dimensions: [200., 100.],
+                tex_coords: FULL,
+            }]
+        );
+    }
+
+    #[test]
+    fn contain_letterboxes() {
+        let quads = fit_image(
+            [0., 0.],
+            [200., 100.],
+            FULL,
+            [50., 50.],
+            UiImageFit::Contain,
+        );
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].dimensions, [100., 100.]);
+        assert_eq!(quads[0].tex_coords, FULL);
+    }
+
+    #[test]
+    fn cover_crops_around_the_center() {
+        let quads = fit_image([0., 0.], [200., 100.], FULL, [50., 50.], UiImageFit::Cover);
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].dimensions, [200., 100.]);
+        assert_eq!(quads[0].tex_coords, [0., 0.25, 1., 0.75]);
+    }
+
+    #[test]
+    fn native_size_is_centered() {
+        let quads = fit_image(
+            [10., 20.],
+            [200., 100.],
+            FULL,
+            [50., 30.],
+            UiImageFit::NativeSize,
+        );
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].center, [10., 20.]);
+        assert_eq!(quads[0].dimensions, [50., 30.]);
+    }
+
+    #[test]
+    fn unknown_size_stretches() {
+        let quads = fit_image([0., 0.], [200., 100.], FULL, [0., 0.], UiImageFit::Tile);
+        assert_eq!(quads.len(), 1);
+        assert_eq!(quads[0].dimensions, [200., 100.]);
+    }
+
+    #[test]
+    fn tiles_are_cropped_on_the_right_and_bottom() {
+        // A 100x50 rect with its top left corner at (0, 50).
+        let quads = fit_image([50., 25.], [100., 50.], FULL, [40., 40.], UiImageFit::Tile);
+        assert_eq!(quads.len(), 6);
+        assert_eq!(
+            quads[0],
+            ImageQuad {
+                center: [20., 30.],
+                dimensions: [40., 40.],
+                tex_coords: FULL,
+            }
+        );
+        assert_eq!(
+            quads[2],
+            ImageQuad {
+                center: [90., 30.],
+                dimensions: [20., 40.],
+                tex_coords: [0., 0., 0.5, 1.],
+            }
+        );
+        assert_eq!(
+            quads[5],
+            ImageQuad {
+                center: [90., 5.],
+                dimensions: [20., 10.],
+                tex_coords: [0., 0., 0.5, 0.25],
+            }
+        );
+    }
+
+    #[test]
+    fn tiles_keep_partial_texture_bounds() {
+        let quads = tile_quads([10., 10.], [30., 20.], [0.5, 0.25, 0.75, 0.5], [20., 20.]);
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[0].tex_coords, [0.5, 0.25, 0.75, 0.5]);
+        assert_eq!(quads[1].tex_coords, [0.5, 0.25, 0.625, 0.5]);
+    }
+
+    /// The slices of a 40x40 image with 10 pixel borders.
+    const SLICE_TEX_COORDS: ([f32; 4], [f32; 4]) = ([0., 0.25, 0.75, 1.], [0., 0.25, 0.75, 1.]);
+    const SLICE_NATIVE: ([f32; 3], [f32; 3]) = ([10., 20., 10.], [10., 20., 10.]);
+
+    fn fit_slices(fit: UiImageFit) -> Vec<ImageQuad> {
+        fit_nine_slice([0., 0.], [200., 100.], SLICE_TEX_COORDS, SLICE_NATIVE, fit)
+    }
+
+    #[test]
+    fn stretched_nine_slices_keep_their_corners() {
+        let quads = fit_slices(UiImageFit::Stretch);
+        assert_eq!(quads.len(), 9);
+        assert_eq!(
+            quads[0],
+            ImageQuad {
+                center: [-95., 45.],
+                dimensions: [10., 10.],
+                tex_coords: [0., 0., 0.25, 0.25],
+            }
+        );
+        assert_eq!(quads[4].center, [0., 0.]);
+        assert_eq!(quads[4].dimensions, [180., 80.]);
+        assert_eq!(quads[8].center, [95., -45.]);
+    }
+
+    #[test]
+    fn nine_slices_fit_like_other_images() {
+        let native = fit_slices(UiImageFit::NativeSize);
+        assert_eq!(native[0].center, [-15., 15.]);
+        assert_eq!(native[4].dimensions, [20., 20.]);
+
+        let contained = fit_slices(UiImageFit::Contain);
+        assert_eq!(contained[0].center, [-45., 45.]);
+        assert_eq!(contained[0].dimensions, [10., 10.]);
+        assert_eq!(contained[4].dimensions, [80., 80.]);
+    }
+
+    #[test]
+    fn covered_nine_slices_are_cropped() {
+        // Laid out over 200x200, the top and bottom rows are outside of the 200x100 rect.
+        let quads = fit_slices(UiImageFit::Cover);
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[1].center, [0., 0.]);
+        assert_eq!(quads[1].dimensions, [180., 100.]);
+        let cropped = 0.5 * 40. / 180.;
+        let [left, top, right, bottom] = quads[1].tex_coords;
+        assert_eq!([left, right], [0.25, 0.75]);
+        assert!((top - (0.25 + cropped)).abs() < 1e-6);
+        assert!((bottom - (0.75 - cropped)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tiled_nine_slices_repeat_their_edges_and_center() {
+        let quads = fit_nine_slice(
+            [0., 0.],
+            [60., 60.],
+            SLICE_TEX_COORDS,
+            SLICE_NATIVE,
+            UiImageFit::Tile,
+        );
+        // 4 corners, 2 tiles on each edge and 2x2 in the center.
+        assert_eq!(quads.len(), 16);
+        assert!(quads.iter().all(|quad| quad.dimensions == [10., 10.]
+            || quad.dimensions == [20., 10.]
+            || quad.dimensions == [10., 20.]
+            || quad.dimensions == [20., 20.]));
+    }
+
+    #[test]
+    fn flip_mirrors_quads_and_tex_coords() {
+        let mut quads = fit_image([50., 25.], [100., 50.], FULL, [40., 40.], UiImageFit::Tile);
+        flip_quads(&mut quads, [50., 25.], true, false);
+        assert_eq!(quads[2].center, [10., 30.]);
+        assert_eq!(quads[2].tex_coords, [0.5, 0., 0., 1.]);
+
+        let mut quads = fit_image([0., 0.], [200., 100.], FULL, [0., 0.], UiImageFit::Stretch);
+        flip_quads(&mut quads, [0., 0.], true, true);
+        assert_eq!(quads[0].center, [0., 0.]);
+        assert_eq!(quads[0].tex_coords, [1., 1., 0., 0.]);
+    }
+
+    fn components(v: vec4) -> [f32; 4] {
+        v.into()
+    }
+
+    /// The float of the bits of a half precision float, like `unpackHalf2x16`.
+    fn from_half(half: u16) -> f32 {
+        let sign = if half & 0x8000 == 0 { 1.0 } else { -1.0 };
+        let exponent = i32::from((half >> 10) & 0x1f);
+        let mantissa = f32::from(half & 0x3ff);
+        sign * match exponent {
+            0 => mantissa * 2f32.powi(-24),
+            0x1f => std::f32::INFINITY,
+            _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    fn halves(packed: uvec2) -> [f32; 4] {
+        let [low, high]: [u32; 2] = packed.into();
+        [
+            from_half(low as u16),
+            from_half((low >> 16) as u16),
+            from_half(high as u16),
+            from_half((high >> 16) as u16),
+        ]
+    }
+
+    fn colors(args: &UiArgs) -> [u32; 4] {
+        args.shape_colors.into()
+    }
+
+    #[test]
+    fn halves_are_rounded_to_the_nearest() {
+        assert_eq!(to_half(0.), 0);
+        assert_eq!(to_half(-0.), 0x8000);
+        assert_eq!(to_half(1.), 0x3c00);
+        assert_eq!(to_half(-2.), 0xc000);
+        assert_eq!(to_half(0.5), 0x3800);
+        assert_eq!(to_half(0.1), 0x2e66);
+        assert_eq!(to_half(65504.), 0x7bff);
+        assert_eq!(to_half(1e6), 0x7c00);
+        assert_eq!(to_half(std::f32::INFINITY), 0x7c00);
+        assert_eq!(to_half(std::f32::NAN) & 0x7e00, 0x7e00);
+        // Subnormals, down to the smallest one.
+        assert_eq!(to_half(2f32.powi(-24)), 1);
+        assert_eq!(to_half(2f32.powi(-15)), 0x200);
+        assert_eq!(to_half(1e-10), 0);
+        for &value in &[0.25, 1.5, 12., 300.75, -7.125] {
+            assert_eq!(from_half(to_half(value)), value);
+        }
+    }
+
+    #[test]
+    fn colors_are_packed_as_srgb_bytes() {
+        assert_eq!(pack_color([1., 0., 0., 0.5]), 0xff | 128 << 24);
+        assert_eq!(pack_color([0., 0., 1., 1.]), 0xff << 16 | 0xff << 24);
+        // Linear 0.5 is 188 in sRGB.
+        assert_eq!(pack_color([0.5, 2., -1., 0.]), 188 | 0xff << 8);
+    }
+
+    #[test]
+    fn shape_without_shadow_is_one_instance() {
+        let shape = UiShape::new([1., 0., 0., 1.])
+            .with_corner_radii([1., 2., 3., 4.])
+            .with_border(2., [0., 0., 0., 1.]);
+        let instances = shape_args(&shape, [10., 20.], [100., 50.], &None);
+        assert_eq!(instances.len(), 1);
+        let args = instances[0];
+        let coords: [f32; 2] = args.coords.into();
+        let dimensions: [f32; 2] = args.dimensions.into();
+        assert_eq!(coords, [10., 20.]);
+        assert_eq!(dimensions, [100., 50.]);
+        assert_eq!(halves(args.shape), [1., 2., 0., 0.]);
+        assert_eq!(halves(args.radii), [1., 2., 3., 4.]);
+        assert_eq!(components(args.color), [1., 0., 0., 1.]);
+        assert_eq!(colors(&args)[0], pack_color([1., 0., 0., 1.]));
+        assert_eq!(colors(&args)[1], pack_color([0., 0., 0., 1.]));
+    }
+
+    /// The position of a vertex of `args` in normalized device coordinates, like `ui.vert`.
+    fn vertex_position(args: &UiArgs, pos: [f32; 2], window: [f32; 2]) -> [f32; 2] {
+        let coords: [f32; 2] = args.coords.into();
+        let dimensions: [f32; 2] = args.dimensions.into();
+        let [a, b, c, d] = components(args.transform);
+        let center = [coords[0] / window[0], 1.0 - coords[1] / window[1]];
+        let corner = [dimensions[0] * pos[0], dimensions[1] * -pos[1]];
+        let offset = [
+            a * corner[0] + c * corner[1],
+            -(b * corner[0] + d * corner[1]),
+        ];
+        [
+            (center[0] + offset[0] / window[0]) * 2.0 - 1.0,
+            (center[1] + offset[1] / window[1]) * 2.0 - 1.0,
+        ]
+    }
+
+    /// The radius `rounded_box` of `ui.frag` uses at `p`, with y up.
+    fn shader_radius(p: [f32; 2], radii: [f32; 4]) -> f32 {
+        match (p[0] > 0.0, p[1] > 0.0) {
+            (true, true) => radii[1],
+            (true, false) => radii[2],
+            (false, true) => radii[0],
+            (false, false) => radii[3],
+        }
+    }
+
+    #[test]
+    fn corner_radii_are_drawn_on_their_corners() {
+        let shape = UiShape::new([1., 1., 1., 1.]).with_corner_radii([1., 2., 3., 4.]);
+        let args = shape_args(&shape, [400., 300.], [100., 50.], &None)[0];
+        let dimensions: [f32; 2] = args.dimensions.into();
+        let radii = halves(args.radii);
+        // The vertices of `ui.vert`, with the local position it gives them.
+        for &pos in &[[0.5, -0.5], [-0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]] {
+            let local = [dimensions[0] * pos[0], dimensions[1] * -pos[1]];
+            // The y of normalized device coordinates goes down.
+            let [x, y] = vertex_position(&args, pos, [800., 600.]);
+            let expected = match (x > 0.0, y < 0.0) {
+                (false, true) => 1.,
+                (true, true) => 2.,
+                (true, false) => 3.,
+                (false, false) => 4.,
+            };
+            assert_eq!(shader_radius(local, radii), expected, "{:?}", pos);
+        }
+    }
+
+    #[test]
+    fn shadow_is_drawn_first_and_grows_by_its_blur() {
+        let shape = UiShape::new([1., 1., 1., 1.])
+            .with_corner_radius(8.)
+            .with_shadow(UiShadow::new([0., 0., 0., 0.5], [4., -4.], 6.));
+        let instances = shape_args(&shape, [0., 0.], [100., 50.], &None);
+        assert_eq!(instances.len(), 2);
+        let shadow = instances[0];
+        let coords: [f32; 2] = shadow.coords.into();
+        let dimensions: [f32; 2] = shadow.dimensions.into();
+        assert_eq!(coords, [4., -4.]);
+        assert_eq!(dimensions, [112., 62.]);
+        assert_eq!(halves(shadow.shape), [1., 0., 6., 0.]);
+        assert_eq!(halves(shadow.radii), [8., 8., 8., 8.]);
+        assert_eq!(components(shadow.color), [0., 0., 0., 0.5]);
+        assert_eq!(halves(instances[1].shape), [1., 0., 0., 0.]);
+    }
+
+    #[test]
+    fn gradients_are_packed_by_kind() {
+        let linear = UiShape::new([0., 0., 0., 1.]).with_fill(UiShapeFill::Linear {
+            start: [0., 0.],
+            end: [0., 1.],
+            start_color: [1., 0., 0., 1.],
+            end_color: [0., 0., 1., 1.],
+        });
+        let args = shape_args(&linear, [0., 0.], [10., 10.], &None)[0];
+        assert_eq!(halves(args.shape)[3], 1.);
+        assert_eq!(halves(args.gradient), [0., 0., 0., 1.]);
+        assert_eq!(components(args.color), [1., 0., 0., 1.]);
+        assert_eq!(colors(&args)[0], pack_color([0., 0., 1., 1.]));
+
+        let radial = UiShape::new([0., 0., 0., 1.]).with_fill(UiShapeFill::Radial {
+            center: [0.5, 0.5],
+            radius: 0.75,
+            inner_color: [1., 1., 1., 1.],
+            outer_color: [0., 0., 0., 0.],
+        });
+        let args = shape_args(&radial, [0., 0.], [10., 10.], &None)[0];
+        assert_eq!(halves(args.shape)[3], 2.);
+        assert_eq!(halves(args.gradient), [0.5, 0.5, 0.75, 0.]);
+    }
+
+    #[test]
+    fn tint_applies_to_every_color() {
+        let shape = UiShape::new([1., 1., 1., 1.])
+            .with_border(1., [1., 1., 1., 1.])
+            .with_shadow(UiShadow::new([1., 1., 1., 1.], [0., 0.], 2.));
+        let tint = Some([0.5, 0.5, 0.5, 0.5]);
+        let instances = shape_args(&shape, [0., 0.], [10., 10.], &tint);
+        for args in &instances {
+            assert_eq!(components(args.color), [0.5, 0.5, 0.5, 0.5]);
+        }
+        let half = pack_color([0.5, 0.5, 0.5, 0.5]);
+        assert_eq!(colors(&instances[1])[..2], [half, half]);
+    }
+
+    #[test]
+    fn corner_gradient_is_one_instance_with_srgb_corners() {
+        let corners = [
+            [1., 1., 1., 1.],
+            [1., 0., 0., 1.],
+            [0., 0., 0., 1.],
+            [0., 1., 0., 0.],
+        ];
+        let args = corner_gradient_args(&corners, [10., 20.], [100., 50.], &None);
+        let coords: [f32; 2] = args.coords.into();
+        let dimensions: [f32; 2] = args.dimensions.into();
+        assert_eq!(coords, [10., 20.]);
+        assert_eq!(dimensions, [100., 50.]);
+        assert_eq!(halves(args.shape)[0], 2.);
+        assert_eq!(
+            colors(&args),
+            [0xffff_ffff, 0xff00_00ff, 0xff00_0000, 0x0000_ff00]
+        );
+
+        // Tinting happens in linear space, before the conversion to sRGB.
+        let tint = Some([0.5, 0.5, 0.5, 0.5]);
+        let args = corner_gradient_args(&corners, [0., 0.], [1., 1.], &tint);
+        assert_eq!(colors(&args)[0], pack_color([0.5, 0.5, 0.5, 0.5]));
+    }
+}
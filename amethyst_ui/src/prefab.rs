@@ -1,5 +1,5 @@
 use derivative::Derivative;
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserializer, Serializer};
 use std::{
     fmt::{Debug, Formatter},
     marker::PhantomData,
@@ -24,10 +24,38 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     get_default_font, Anchor, Draggable, FontAsset, Interactable, LineMode, Selectable, Stretch,
-    TextEditing, UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionType, UiImage,
-    UiPlaySoundAction, UiSoundRetrigger, UiText, UiTransform, WidgetId, Widgets,
+    TextEditing, UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionType, UiClass,
+    UiImage, UiPlaySoundAction, UiSoundRetrigger, UiStyleOverrides, UiTabs, UiText, UiTooltip,
+    UiTooltipData, UiTransform, UiWindow, UiWindowEdge, UiWindowFrame, UiWindowPart, WidgetId,
+    Widgets,
 };
 
+/// Font size used when neither the prefab nor the theme sets one.
+const DEFAULT_FONT_SIZE: f32 = 32.;
+/// Text colour used when neither the prefab nor the theme sets one.
+const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+// Optional prefab values that are written without `Some(...)`, so that making a value optional
+// keeps the existing `ron` files valid.
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+fn serialize_some<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    match value {
+        Some(value) => value.serialize(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
 /// Loadable `UiTransform` data.
 /// By default z is equal to one.
 #[derive(Debug, Clone, Deserialize, Serialize, Derivative)]
@@ -81,6 +109,13 @@ pub struct UiTransformData<G> {
     pub selectable: Option<u32>,
     /// Makes the UiTransform draggable through mouse inputs.
     pub draggable: bool,
+    /// Shows a tooltip when the element is hovered: a text, or another element of the same ui.
+    pub tooltip: Option<UiTooltipData>,
+    /// Applies the style of the same name from the active `UiTheme`.
+    pub class: Option<String>,
+    /// The stretch was generated rather than set in the prefab, so a theme may pad it.
+    #[serde(skip)]
+    themed_stretch: bool,
     #[serde(skip)]
     _phantom: PhantomData<G>,
 }
@@ -134,11 +169,38 @@ impl<G> UiTransformData<G> {
         self
     }
 
+    /// Set pivot
+    pub fn with_pivot(mut self, pivot: Anchor) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
     /// Set stretch
     pub fn with_stretch(mut self, stretch: Stretch) -> Self {
         self.stretch = Some(stretch);
         self
     }
+
+    /// Set tooltip
+    pub fn with_tooltip(mut self, tooltip: UiTooltipData) -> Self {
+        self.tooltip = Some(tooltip);
+        self
+    }
+
+    /// Set theme class
+    pub fn with_class<S>(mut self, class: S) -> Self
+    where
+        S: ToString,
+    {
+        self.class = Some(class.to_string());
+        self
+    }
+
+    /// Lets the padding of a theme replace the stretch margins.
+    pub(crate) fn with_themed_stretch(mut self) -> Self {
+        self.themed_stretch = true;
+        self
+    }
 }
 
 impl<'a, G> PrefabData<'a> for UiTransformData<G>
@@ -151,6 +213,9 @@ where
         WriteStorage<'a, HiddenPropagate>,
         WriteStorage<'a, Selectable<G>>,
         WriteStorage<'a, Draggable>,
+        WriteStorage<'a, UiTooltip>,
+        WriteStorage<'a, UiClass>,
+        WriteStorage<'a, UiStyleOverrides<UiTransform>>,
     );
     type Result = ();
 
@@ -195,7 +260,19 @@ where
         }
 
         if self.draggable {
-            system_data.4.insert(entity, Draggable)?;
+            system_data.4.insert(entity, Draggable::default())?;
+        }
+
+        if let Some(ref tooltip) = self.tooltip {
+            system_data.5.insert(entity, tooltip.to_tooltip())?;
+        }
+
+        if let Some(ref class) = self.class {
+            system_data.6.insert(entity, UiClass(class.clone()))?;
+        }
+
+        if self.stretch.is_some() && !self.themed_stretch {
+            UiStyleOverrides::of(&mut system_data.7, entity)?.padding = true;
         }
 
         Ok(())
@@ -212,11 +289,23 @@ where
 pub struct UiTextData {
     /// Text to display
     pub text: String,
-    /// Font size
-    pub font_size: f32,
-    /// Font color
-    pub color: [f32; 4],
-    /// Font
+    /// Font size. Falls back to the theme, then to 32.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub font_size: Option<f32>,
+    /// Font color. Falls back to the theme, then to black.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub color: Option<[f32; 4]>,
+    /// Font. Falls back to the theme, then to the default font.
     pub font: Option<AssetPrefab<FontAsset>>,
     /// Should the text be shown as dots instead of the proper characters?
     #[serde(default)]
@@ -281,6 +370,7 @@ impl<'a> PrefabData<'a> for UiTextData {
     type SystemData = (
         WriteStorage<'a, UiText>,
         WriteStorage<'a, TextEditing>,
+        WriteStorage<'a, UiStyleOverrides<UiText>>,
         <AssetPrefab<FontAsset> as PrefabData<'a>>::SystemData,
     );
     type Result = ();
@@ -292,12 +382,19 @@ impl<'a> PrefabData<'a> for UiTextData {
         _: &[Entity],
         _: &[Entity],
     ) -> Result<(), Error> {
-        let (ref mut texts, ref mut editables, ref mut fonts) = system_data;
-        let font_handle = self
-            .font
-            .as_ref()
-            .ok_or_else(|| format_err!("did not load sub assets"))?
-            .add_to_entity(entity, fonts, &[], &[])?;
+        let (ref mut texts, ref mut editables, ref mut overrides, ref mut fonts) = system_data;
+        let font_handle = match self.font {
+            Some(ref font) => font.add_to_entity(entity, fonts, &[], &[])?,
+            None => {
+                let (ref loader, _, ref storage) = fonts;
+                get_default_font(loader, storage)
+            }
+        };
+
+        let overrides = UiStyleOverrides::of(overrides, entity)?;
+        overrides.font = self.font.is_some();
+        overrides.font_size = self.font_size.is_some();
+        overrides.text_color = self.color.is_some();
 
         let mut ui_text_align = Anchor::Middle;
         if let Some(align) = self.align {
@@ -311,8 +408,8 @@ impl<'a> PrefabData<'a> for UiTextData {
         let mut ui_text = UiText::new(
             font_handle,
             self.text.clone(),
-            self.color,
-            self.font_size,
+            self.color.unwrap_or(DEFAULT_TXT_COLOR),
+            self.font_size.unwrap_or(DEFAULT_FONT_SIZE),
             ui_text_line_mode,
             ui_text_align,
         );
@@ -338,14 +435,8 @@ impl<'a> PrefabData<'a> for UiTextData {
         progress: &mut ProgressCounter,
         system_data: &mut Self::SystemData,
     ) -> Result<bool, Error> {
-        let (_, _, ref mut fonts) = system_data;
-
-        self.font
-            .get_or_insert_with(|| {
-                let (ref loader, _, ref storage) = fonts;
-                AssetPrefab::Handle(get_default_font(loader, storage))
-            })
-            .load_sub_assets(progress, fonts)
+        let (_, _, _, ref mut fonts) = system_data;
+        self.font.load_sub_assets(progress, fonts)
     }
 }
 
@@ -408,6 +499,7 @@ impl<'a> PrefabData<'a> for UiImagePrefab {
     type SystemData = (
         <UiImageLoadPrefab as PrefabData<'a>>::SystemData,
         WriteStorage<'a, UiImage>,
+        WriteStorage<'a, UiStyleOverrides<UiImage>>,
     );
 
     type Result = ();
@@ -415,18 +507,19 @@ impl<'a> PrefabData<'a> for UiImagePrefab {
     fn add_to_entity(
         &self,
         entity: Entity,
-        (ref mut inner, ref mut images): &mut Self::SystemData,
+        (ref mut inner, ref mut images, ref mut overrides): &mut Self::SystemData,
         entities: &[Entity],
         children: &[Entity],
     ) -> Result<(), Error> {
         let image = self.0.add_to_entity(entity, inner, entities, children)?;
         images.insert(entity, image)?;
+        UiStyleOverrides::of(overrides, entity)?.normal_image = true;
         Ok(())
     }
     fn load_sub_assets(
         &mut self,
         progress: &mut ProgressCounter,
-        (ref mut inner, _): &mut Self::SystemData,
+        (ref mut inner, _, _): &mut Self::SystemData,
     ) -> Result<bool, Error> {
         self.0.load_sub_assets(progress, inner)
     }
@@ -515,12 +608,24 @@ pub struct UiButtonData<W: WidgetId = u32> {
     pub id: Option<W>,
     /// Text to display
     pub text: String,
-    /// Font size
-    pub font_size: f32,
-    /// Font
+    /// Font size. Falls back to the theme, then to 32.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub font_size: Option<f32>,
+    /// Font. Falls back to the theme, then to the default font.
     pub font: Option<AssetPrefab<FontAsset>>,
-    /// Default text color
-    pub normal_text_color: [f32; 4],
+    /// Default text color. Falls back to the theme, then to black.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub normal_text_color: Option<[f32; 4]>,
     // this `normal_image` is "transplanted" into UiImagePrefab at the top level
     // of ui widegt. This happens inside `walk_ui_tree` function. It means that
     // it will always be `None` during `add_to_entity`.
@@ -577,6 +682,7 @@ where
         WriteStorage<'a, UiSoundRetrigger>,
         WriteStorage<'a, UiButtonActionRetrigger>,
         Write<'a, Widgets<UiButton, W>>,
+        WriteStorage<'a, UiStyleOverrides<UiButton>>,
         <UiImageLoadPrefab as PrefabData<'a>>::SystemData,
         <AssetPrefab<Audio> as PrefabData<'a>>::SystemData,
     );
@@ -593,10 +699,18 @@ where
             ref mut sound_retrigger,
             ref mut button_action_retrigger,
             ref mut widgets,
+            ref mut overrides,
             ref mut images,
             ref mut sounds,
         ) = system_data;
 
+        let overrides = UiStyleOverrides::of(overrides, entity)?;
+        overrides.hover_image = self.hover_image.is_some();
+        overrides.press_image = self.press_image.is_some();
+        overrides.hover_sound = self.hover_sound.is_some();
+        overrides.press_sound = self.press_sound.is_some();
+        overrides.release_sound = self.release_sound.is_some();
+
         let text_entity = children.get(0).expect("Invalid: Should have text child");
         let widget = UiButton::new(entity, *text_entity);
         if let Some(id) = &self.id {
@@ -712,7 +826,7 @@ where
         progress: &mut ProgressCounter,
         system_data: &mut Self::SystemData,
     ) -> Result<bool, Error> {
-        let (_, _, _, ref mut images, ref mut sounds) = system_data;
+        let (_, _, _, _, ref mut images, ref mut sounds) = system_data;
         self.normal_image.load_sub_assets(progress, images)?;
         self.hover_image.load_sub_assets(progress, images)?;
         self.press_image.load_sub_assets(progress, images)?;
@@ -722,6 +836,233 @@ where
     }
 }
 
+/// Loadable `UiWindow` data
+///
+/// ### Type parameters:
+///
+/// - `W`: Type used for Widget IDs
+#[derive(Deserialize, Serialize, Clone)]
+pub struct UiWindowData<W: WidgetId = u32> {
+    /// Id for the widget
+    pub id: Option<W>,
+    /// Title of the window
+    #[serde(default)]
+    pub title: String,
+    /// Font size of the title and buttons. Falls back to the theme, then to 32.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub font_size: Option<f32>,
+    /// Font of the title and buttons. Falls back to the theme, then to the default font.
+    pub font: Option<AssetPrefab<FontAsset>>,
+    /// Color of the title and buttons. Falls back to the theme, then to black.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub title_color: Option<[f32; 4]>,
+    // Like the `normal_image` of buttons, this is moved to the title bar entity in `walk_ui_tree`.
+    /// Image drawn behind the title bar
+    pub title_image: Option<UiImageLoadPrefab>,
+    /// Title bar height, size limits and persistence of the window
+    #[serde(default)]
+    pub frame: UiWindowFrame,
+    /// Adds a button closing the window. Defaults to true.
+    #[serde(default = "default_true")]
+    pub closable: bool,
+    /// Adds a button minimising the window. Defaults to true.
+    #[serde(default = "default_true")]
+    pub minimizable: bool,
+    /// Adds handles resizing the window on its edges and corners. Defaults to true.
+    #[serde(default = "default_true")]
+    pub resizable: bool,
+}
+
+impl<W: WidgetId + Debug> Debug for UiWindowData<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let font = match self.font.as_ref() {
+            Some(AssetPrefab::File(path, _)) => format!("<Font:{}>", path),
+            _ => "<Font>".to_string(),
+        };
+
+        f.debug_struct("UiWindowData")
+            .field("id", &self.id)
+            .field("title", &self.title)
+            .field("font_size", &self.font_size)
+            .field("font", &font)
+            .field("title_color", &self.title_color)
+            .field("title_image", &self.title_image)
+            .field("frame", &self.frame)
+            .field("closable", &self.closable)
+            .field("minimizable", &self.minimizable)
+            .field("resizable", &self.resizable)
+            .finish()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Loadable window components, added by `UiWidget::Window` to the window and to its parts.
+///
+/// ### Type parameters:
+///
+/// - `W`: Type used for Widget IDs
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum UiWindowPrefab<W: WidgetId = u32> {
+    /// The window itself. Its first two children must be its title bar and its content.
+    Frame {
+        /// Id for the widget
+        id: Option<W>,
+        /// The window component
+        frame: UiWindowFrame,
+    },
+    /// A part of a window
+    Part(UiWindowPart),
+}
+
+impl<'a, W> PrefabData<'a> for UiWindowPrefab<W>
+where
+    W: WidgetId,
+{
+    type SystemData = (
+        WriteStorage<'a, UiWindowFrame>,
+        WriteStorage<'a, UiWindowPart>,
+        Write<'a, Widgets<UiWindow, W>>,
+    );
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        _: &[Entity],
+        children: &[Entity],
+    ) -> Result<(), Error> {
+        let (ref mut frames, ref mut parts, ref mut widgets) = system_data;
+        match self {
+            UiWindowPrefab::Frame { id, frame } => {
+                let title_entity = children.get(0).expect("Invalid: Should have title child");
+                let content_entity = children.get(1).expect("Invalid: Should have content child");
+                let widget = UiWindow::new(entity, *title_entity, *content_entity);
+                if let Some(id) = id {
+                    widgets.add_with_id(id.clone(), widget);
+                } else {
+                    widgets.add(widget);
+                }
+                frames.insert(entity, frame.clone())?;
+            }
+            UiWindowPrefab::Part(part) => {
+                parts.insert(entity, *part)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loadable `UiTabs` data, describing the tab strip of a `UiWidget::Tabs`
+#[derive(Deserialize, Serialize, Clone, Derivative)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct UiTabsData {
+    /// Width of the tab buttons
+    #[derivative(Default(value = "120."))]
+    pub tab_width: f32,
+    /// Height of the tab strip
+    #[derivative(Default(value = "32."))]
+    pub tab_height: f32,
+    /// Font size of the labels. Falls back to the theme, then to 32.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub font_size: Option<f32>,
+    /// Font of the labels. Falls back to the theme, then to the default font.
+    pub font: Option<AssetPrefab<FontAsset>>,
+    /// Color of the labels. Falls back to the theme, then to black.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub text_color: Option<[f32; 4]>,
+    /// Color of the inactive tab buttons
+    #[derivative(Default(value = "crate::tabs::DEFAULT_TAB_COLOR"))]
+    pub tab_color: [f32; 4],
+    /// Color of the active tab button
+    #[derivative(Default(value = "crate::tabs::DEFAULT_ACTIVE_TAB_COLOR"))]
+    pub active_tab_color: [f32; 4],
+}
+
+impl Debug for UiTabsData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let font = match self.font.as_ref() {
+            Some(AssetPrefab::File(path, _)) => format!("<Font:{}>", path),
+            _ => "<Font>".to_string(),
+        };
+
+        f.debug_struct("UiTabsData")
+            .field("tab_width", &self.tab_width)
+            .field("tab_height", &self.tab_height)
+            .field("font_size", &self.font_size)
+            .field("font", &font)
+            .field("text_color", &self.text_color)
+            .field("tab_color", &self.tab_color)
+            .field("active_tab_color", &self.active_tab_color)
+            .finish()
+    }
+}
+
+/// Loadable `UiTabs` component, added by `UiWidget::Tabs` to the tab container. The children of
+/// the container must be the tab buttons and the pages, alternately, so there must be an even
+/// number of them.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UiTabsPrefab {
+    /// The tab active at first
+    pub active: usize,
+    /// Color of the inactive tab buttons
+    pub tab_color: [f32; 4],
+    /// Color of the active tab button
+    pub active_tab_color: [f32; 4],
+}
+
+impl<'a> PrefabData<'a> for UiTabsPrefab {
+    type SystemData = WriteStorage<'a, UiTabs>;
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        tabs: &mut Self::SystemData,
+        _: &[Entity],
+        children: &[Entity],
+    ) -> Result<(), Error> {
+        if children.len() % 2 != 0 {
+            return Err(format_err!(
+                "The {} children of a tab container are not pairs of a tab button and a page",
+                children.len()
+            ));
+        }
+        let pairs = children.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        tabs.insert(
+            entity,
+            UiTabs::new(pairs)
+                .with_active(self.active)
+                .with_colors(self.tab_color, self.active_tab_color),
+        )?;
+        Ok(())
+    }
+}
+
 /// Loadable ui components
 ///
 /// ### Type parameters:
@@ -765,6 +1106,34 @@ where
         /// Button
         button: UiButtonData<W>,
     },
+    /// Window widget
+    Window {
+        /// Spatial information for the window
+        transform: UiTransformData<G>,
+        /// Background image
+        #[serde(default = "default_container_image")]
+        background: Option<UiImagePrefab>,
+        /// Window
+        window: UiWindowData<W>,
+        /// Child widgets, placed in the content of the window
+        children: Vec<UiWidget<C, W>>,
+    },
+    /// Tab container widget
+    Tabs {
+        /// Spatial information for the tab container
+        transform: UiTransformData<G>,
+        /// Background image
+        #[serde(default = "default_container_image")]
+        background: Option<UiImagePrefab>,
+        /// Index of the tab active at first
+        #[serde(default)]
+        active: usize,
+        /// Look of the tab strip
+        #[serde(default)]
+        style: UiTabsData,
+        /// Label and content of each tab
+        tabs: Vec<(String, UiWidget<C, W>)>,
+    },
     /// Custom UI widget
     Custom(Box<C>),
 }
@@ -781,6 +1150,8 @@ where
             UiWidget::Image { ref transform, .. } => Some(transform),
             UiWidget::Label { ref transform, .. } => Some(transform),
             UiWidget::Button { ref transform, .. } => Some(transform),
+            UiWidget::Window { ref transform, .. } => Some(transform),
+            UiWidget::Tabs { ref transform, .. } => Some(transform),
             UiWidget::Custom(_) => None,
         }
     }
@@ -800,6 +1171,12 @@ where
             UiWidget::Button {
                 ref mut transform, ..
             } => Some(transform),
+            UiWidget::Window {
+                ref mut transform, ..
+            } => Some(transform),
+            UiWidget::Tabs {
+                ref mut transform, ..
+            } => Some(transform),
             UiWidget::Custom(_) => None,
         }
     }
@@ -807,7 +1184,9 @@ where
     /// Convenience function to access widgets `UiImagePrefab`
     pub fn image(&self) -> Option<&UiImagePrefab> {
         match self {
-            UiWidget::Container { ref background, .. } => background.as_ref(),
+            UiWidget::Container { ref background, .. }
+            | UiWidget::Window { ref background, .. }
+            | UiWidget::Tabs { ref background, .. } => background.as_ref(),
             UiWidget::Image { ref image, .. } => Some(image),
             _ => None,
         }
@@ -818,6 +1197,12 @@ where
         match self {
             UiWidget::Container {
                 ref mut background, ..
+            }
+            | UiWidget::Window {
+                ref mut background, ..
+            }
+            | UiWidget::Tabs {
+                ref mut background, ..
             } => background.as_mut(),
             UiWidget::Image { ref mut image, .. } => Some(image),
             _ => None,
@@ -862,11 +1247,13 @@ fn default_container_image() -> Option<UiImagePrefab> {
     None
 }
 
-type UiPrefabData<D = <NoCustomUi as ToNativeWidget>::PrefabData, W = u32, G = ()> = (
+pub(crate) type UiPrefabData<D = <NoCustomUi as ToNativeWidget>::PrefabData, W = u32, G = ()> = (
     Option<UiTransformData<G>>,
     Option<UiImagePrefab>,
     Option<UiTextData>,
     Option<UiButtonData<W>>,
+    Option<UiWindowPrefab<W>>,
+    Option<UiTabsPrefab>,
     D,
 );
 
@@ -933,14 +1320,30 @@ fn walk_ui_tree<C, W>(
             prefab
                 .entity(current_index)
                 .expect("Unreachable: `Prefab` entity should always be set when walking ui tree")
-                .set_data((Some(transform), Some(image), None, None, custom_data));
+                .set_data((
+                    Some(transform),
+                    Some(image),
+                    None,
+                    None,
+                    None,
+                    None,
+                    custom_data,
+                ));
         }
 
         UiWidget::Label { transform, text } => {
             prefab
                 .entity(current_index)
                 .expect("Unreachable: `Prefab` entity should always be set when walking ui tree")
-                .set_data((Some(transform), None, Some(text), None, custom_data));
+                .set_data((
+                    Some(transform),
+                    None,
+                    Some(text),
+                    None,
+                    None,
+                    None,
+                    custom_data,
+                ));
         }
 
         UiWidget::Container {
@@ -951,7 +1354,15 @@ fn walk_ui_tree<C, W>(
             prefab
                 .entity(current_index)
                 .expect("Unreachable: `Prefab` entity should always be set when walking ui tree")
-                .set_data((Some(transform), background, None, None, custom_data));
+                .set_data((
+                    Some(transform),
+                    background,
+                    None,
+                    None,
+                    None,
+                    None,
+                    custom_data,
+                ));
 
             for child_widget in children {
                 let child_index = prefab.add(Some(current_index), None);
@@ -983,6 +1394,8 @@ fn walk_ui_tree<C, W>(
                     button.normal_image.take().map(UiImagePrefab),
                     None,
                     Some(button),
+                    None,
+                    None,
                     custom_data,
                 ));
 
@@ -993,9 +1406,229 @@ fn walk_ui_tree<C, W>(
                     None,
                     Some(text),
                     None,
+                    None,
+                    None,
+                    Default::default(),
+                )),
+            );
+        }
+
+        UiWidget::Window {
+            transform,
+            background,
+            mut window,
+            children,
+        } => {
+            let id = transform.id.clone();
+            let title_height = window.frame.title_height;
+            let title_image = window.title_image.take().map(UiImagePrefab);
+            let text = |text: String| UiTextData {
+                color: window.title_color,
+                editable: None,
+                font: window.font.clone(),
+                password: false,
+                align: None,
+                line_mode: None,
+                text,
+                font_size: window.font_size,
+            };
+            let title = text(window.title.clone());
+
+            prefab
+                .entity(current_index)
+                .expect("Unreachable: `Prefab` entity should always be set when walking ui tree")
+                .set_data((
+                    Some(transform),
+                    background,
+                    None,
+                    None,
+                    Some(UiWindowPrefab::Frame {
+                        id: window.id.clone(),
+                        frame: window.frame.clone(),
+                    }),
+                    None,
+                    custom_data,
+                ));
+
+            // The title bar and the content come first, as the widget is made from them.
+            prefab.add(
+                Some(current_index),
+                Some((
+                    Some(
+                        window_part_transform(&id, "title", Anchor::TopMiddle, 0., 1.)
+                            .with_size(0., title_height)
+                            .with_stretch(Stretch::X { x_margin: 0. })
+                            .transparent(),
+                    ),
+                    title_image,
+                    Some(title),
+                    None,
+                    Some(UiWindowPrefab::Part(UiWindowPart::TitleBar)),
+                    None,
                     Default::default(),
                 )),
             );
+            let content_index = prefab.add(
+                Some(current_index),
+                Some((
+                    Some(
+                        window_part_transform(&id, "content", Anchor::Middle, 0., 1.)
+                            .with_position(0., -title_height / 2., 1.)
+                            .with_stretch(Stretch::XY {
+                                x_margin: 0.,
+                                y_margin: title_height / 2.,
+                                keep_aspect_ratio: false,
+                            }),
+                    ),
+                    None,
+                    None,
+                    None,
+                    Some(UiWindowPrefab::Part(UiWindowPart::Content)),
+                    None,
+                    Default::default(),
+                )),
+            );
+            for child_widget in children {
+                let child_index = prefab.add(Some(content_index), None);
+                walk_ui_tree(child_widget, child_index, prefab, Default::default());
+            }
+
+            let mut buttons = Vec::new();
+            if window.closable {
+                buttons.push((UiWindowPart::Close, "x", "close"));
+            }
+            if window.minimizable {
+                buttons.push((UiWindowPart::Minimise, "_", "minimise"));
+            }
+            for (index, (part, label, name)) in buttons.into_iter().enumerate() {
+                let x = -(index as f32) * title_height;
+                prefab.add(
+                    Some(current_index),
+                    Some((
+                        Some(
+                            window_part_transform(&id, name, Anchor::TopRight, x, 2.)
+                                .with_size(title_height, title_height)
+                                .reactive(),
+                        ),
+                        None,
+                        Some(text(label.to_string())),
+                        None,
+                        Some(UiWindowPrefab::Part(part)),
+                        None,
+                        Default::default(),
+                    )),
+                );
+            }
+
+            if window.resizable {
+                for edge in UiWindowEdge::ALL.iter() {
+                    let (anchor, stretch, width, height) = edge.handle_layout();
+                    let name = format!("resize_{:?}", edge);
+                    prefab.add(
+                        Some(current_index),
+                        Some((
+                            Some(
+                                window_part_transform(&id, &name, anchor, 0., 3.)
+                                    .with_size(width, height)
+                                    .with_stretch(stretch)
+                                    .reactive(),
+                            ),
+                            None,
+                            None,
+                            None,
+                            Some(UiWindowPrefab::Part(UiWindowPart::ResizeHandle(*edge))),
+                            None,
+                            Default::default(),
+                        )),
+                    );
+                }
+            }
+        }
+
+        UiWidget::Tabs {
+            transform,
+            background,
+            active,
+            style,
+            tabs,
+        } => {
+            let id = transform.id.clone();
+            let tab_height = style.tab_height;
+
+            prefab
+                .entity(current_index)
+                .expect("Unreachable: `Prefab` entity should always be set when walking ui tree")
+                .set_data((
+                    Some(transform),
+                    background,
+                    None,
+                    None,
+                    None,
+                    Some(UiTabsPrefab {
+                        active,
+                        tab_color: style.tab_color,
+                        active_tab_color: style.active_tab_color,
+                    }),
+                    custom_data,
+                ));
+
+            // The tab buttons and the pages alternate, as the `UiTabs` is made from them.
+            for (index, (label, content)) in tabs.into_iter().enumerate() {
+                let text = UiTextData {
+                    color: style.text_color,
+                    editable: None,
+                    font: style.font.clone(),
+                    password: false,
+                    align: None,
+                    line_mode: None,
+                    text: label,
+                    font_size: style.font_size,
+                };
+                prefab.add(
+                    Some(current_index),
+                    Some((
+                        Some(
+                            UiTransformData::default()
+                                .with_id(format!("{}_tab_{}", id, index))
+                                .with_position(index as f32 * style.tab_width, 0., 1.)
+                                .with_anchor(Anchor::TopLeft)
+                                .with_pivot(Anchor::TopLeft)
+                                .with_size(style.tab_width, tab_height)
+                                .reactive(),
+                        ),
+                        None,
+                        Some(text),
+                        None,
+                        None,
+                        None,
+                        Default::default(),
+                    )),
+                );
+                let page_index = prefab.add(
+                    Some(current_index),
+                    Some((
+                        Some(
+                            UiTransformData::default()
+                                .with_id(format!("{}_page_{}", id, index))
+                                .with_position(0., -tab_height / 2., 1.)
+                                .with_anchor(Anchor::Middle)
+                                .with_stretch(Stretch::XY {
+                                    x_margin: 0.,
+                                    y_margin: tab_height / 2.,
+                                    keep_aspect_ratio: false,
+                                }),
+                        ),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        Default::default(),
+                    )),
+                );
+                let content_index = prefab.add(Some(page_index), None);
+                walk_ui_tree(content, content_index, prefab, Default::default());
+            }
         }
     }
 }
@@ -1126,5 +1759,20 @@ fn button_text_transform<G>(mut id: String) -> UiTransformData<G> {
             y_margin: 0.,
             keep_aspect_ratio: false,
         })
+        .with_themed_stretch()
         .transparent()
 }
+
+fn window_part_transform<G>(
+    id: &str,
+    part: &str,
+    anchor: Anchor,
+    x: f32,
+    z: f32,
+) -> UiTransformData<G> {
+    UiTransformData::default()
+        .with_id(format!("{}_window_{}", id, part))
+        .with_position(x, 0., z)
+        .with_anchor(anchor)
+        .with_pivot(anchor)
+}
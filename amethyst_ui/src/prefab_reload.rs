@@ -0,0 +1,560 @@
+//! Module containing the system applying hot reloaded `UiPrefab`s to the ui created from them.
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use derivative::Derivative;
+
+use amethyst_assets::{AssetStorage, Handle, PrefabData, PrefabTag};
+use amethyst_core::{
+    ecs::prelude::{
+        BitSet, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, WriteStorage,
+    },
+    HiddenPropagate, Parent, ParentHierarchy,
+};
+use amethyst_error::Error;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{prefab::UiPrefabData, Draggable, TextEditing, UiPrefab, UiWindowFrame, WidgetId};
+
+/// Applies the `UiPrefab`s reloaded through the `HotReloadStrategy` to the entities that the
+/// `UiLoaderSystem` created from them, so that layouts can be changed while the game runs.
+///
+/// The nodes of the reloaded prefab are matched with the live entities by `UiTransform::id`, in
+/// order when an id is repeated. Matched entities get the transform, text, image, style and widget
+/// data of their node in place and lose the components their node no longer has, nodes added to the
+/// prefab are spawned, and the entities whose node was removed are deleted with their children and
+/// their widgets.
+///
+/// The state changed at runtime is kept: the contents and cursor of editable texts, the focus, the
+/// visibility, the active tab, the position, size, minimised state and `Draggable` of windows, and
+/// the entities created by the widgets themselves, like the rows and scroll offset of lists.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Default(bound = ""))]
+pub struct UiPrefabReloadSystem<CD, W = u32> {
+    /// The id, version and tag of the prefab each ui root was last built from.
+    versions: HashMap<Entity, (u32, u32, Option<u64>)>,
+    #[derivative(Debug = "ignore")]
+    marker: PhantomData<(CD, W)>,
+}
+
+impl<'a, CD, W> System<'a> for UiPrefabReloadSystem<CD, W>
+where
+    CD: PrefabData<'a> + Send + Sync + 'static,
+    W: WidgetId,
+{
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, AssetStorage<UiPrefab<CD, W>>>,
+        ReadStorage<'a, Handle<UiPrefab<CD, W>>>,
+        ReadExpect<'a, ParentHierarchy>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, PrefabTag<UiPrefabData<CD, W>>>,
+        <UiPrefabData<CD, W> as PrefabData<'a>>::SystemData,
+    );
+
+    fn run(
+        &mut self,
+        (entities, prefab_storage, prefab_handles, hierarchy, mut parents, mut tags, mut data): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_prefab_reload_system");
+
+        self.versions.retain(|root, _| entities.is_alive(*root));
+
+        let mut reloaded = Vec::new();
+        for (root, handle) in (&*entities, &prefab_handles).join() {
+            // The prefab is still loading.
+            let (prefab, version) = match prefab_storage.get_with_version(handle) {
+                Some((prefab, version)) => (prefab, *version),
+                None => continue,
+            };
+            let built = (handle.id(), version, prefab.tag());
+            // A new handle is instantiated by the `UiLoaderSystem`.
+            if let Some((id, previous, tag)) = self.versions.insert(root, built) {
+                if id == handle.id() && previous != version {
+                    reloaded.push((root, prefab, tag));
+                }
+            }
+        }
+
+        for (root, prefab, previous_tag) in reloaded {
+            if let Err(err) = reload(
+                root,
+                prefab,
+                previous_tag,
+                &entities,
+                &hierarchy,
+                &mut parents,
+                &mut tags,
+                &mut data,
+            ) {
+                log::error!("Failed to apply the reloaded ui prefab: {}", err);
+            }
+        }
+    }
+}
+
+/// Rebuilds the ui under `root` from `prefab`, reusing the entities matching its nodes.
+fn reload<'a, CD, W>(
+    root: Entity,
+    prefab: &UiPrefab<CD, W>,
+    previous_tag: Option<u64>,
+    entities: &Entities<'a>,
+    hierarchy: &ParentHierarchy,
+    parents: &mut WriteStorage<'a, Parent>,
+    tags: &mut WriteStorage<'a, PrefabTag<UiPrefabData<CD, W>>>,
+    data: &mut <UiPrefabData<CD, W> as PrefabData<'a>>::SystemData,
+) -> Result<(), Error>
+where
+    CD: PrefabData<'a> + Send + Sync + 'static,
+    W: WidgetId,
+{
+    // The entities created from the previous prefab, by id and in creation order. The entities
+    // created at runtime by the widgets, and by other prefabs, have no `PrefabTag` of its tag.
+    let mut live: HashMap<String, Vec<Entity>> = HashMap::new();
+    let descendants = hierarchy.all_children(root);
+    let created = (&**entities, &descendants, &*tags)
+        .join()
+        .filter(|(_, _, tag)| Some(tag.tag()) == previous_tag)
+        .map(|(entity, _, _)| entity)
+        .collect::<Vec<_>>();
+    for entity in created {
+        let id = (data.0)
+            .0
+            .get(entity)
+            .map_or_else(String::new, |t| t.id.clone());
+        live.entry(id).or_insert_with(Vec::new).push(entity);
+    }
+
+    let nodes = prefab.entities().collect::<Vec<_>>();
+    let tag = prefab
+        .tag()
+        .expect("Unreachable: Every loaded prefab should have a `PrefabTag`");
+    let mut node_entities = Vec::with_capacity(nodes.len());
+    let mut matched = Vec::with_capacity(nodes.len());
+    for (index, node) in nodes.iter().enumerate() {
+        if index == 0 {
+            node_entities.push(root);
+            matched.push(true);
+            continue;
+        }
+        let id = node
+            .data()
+            .and_then(|data| data.0.as_ref())
+            .map_or("", |transform| transform.id.as_str());
+        let entity = match live.get_mut(id).filter(|same_id| !same_id.is_empty()) {
+            Some(same_id) => {
+                matched.push(true);
+                same_id.remove(0)
+            }
+            None => {
+                matched.push(false);
+                entities.create()
+            }
+        };
+        // The next reload finds the entities by the tag of this prefab.
+        tags.insert(entity, PrefabTag::new(tag))?;
+        node_entities.push(entity);
+    }
+
+    // Nodes can also move to another parent.
+    let mut children = HashMap::new();
+    for (index, node) in nodes.iter().enumerate().skip(1) {
+        if let Some(parent) = node.parent() {
+            let entity = node_entities[index];
+            let parent_entity = node_entities[parent];
+            if parents.get(entity).map(|parent| parent.entity) != Some(parent_entity) {
+                parents.insert(
+                    entity,
+                    Parent {
+                        entity: parent_entity,
+                    },
+                )?;
+            }
+            children.entry(parent).or_insert_with(Vec::new).push(entity);
+        }
+    }
+
+    // The remaining entities lost their node, their children moved elsewhere are kept.
+    let mut kept = BitSet::new();
+    for entity in &node_entities {
+        kept.add(entity.id());
+    }
+    let mut deleted = BitSet::new();
+    for entity in live.values().flatten() {
+        for (child, _) in (&**entities, &hierarchy.all_children(*entity)).join() {
+            if !kept.contains(child.id()) {
+                let _ = entities.delete(child);
+                deleted.add(child.id());
+            }
+        }
+        let _ = entities.delete(*entity);
+        deleted.add(entity.id());
+    }
+    (data.3)
+        .2
+        .retain(|_, button| !deleted.contains(button.image_entity.id()));
+    (data.4)
+        .2
+        .retain(|_, window| !deleted.contains(window.window_entity.id()));
+
+    for (index, node) in nodes.iter().enumerate() {
+        let node_data = match node.data() {
+            Some(node_data) => node_data,
+            None => continue,
+        };
+        let entity = node_entities[index];
+        let node_children = children.get(&index).map_or(&[][..], |c| &c[..]);
+        if matched[index] {
+            let state = RuntimeState::take(entity, data);
+            node_data.add_to_entity(entity, data, &node_entities, node_children)?;
+            state.restore(entity, data)?;
+        } else {
+            node_data.add_to_entity(entity, data, &node_entities, node_children)?;
+        }
+    }
+    Ok(())
+}
+
+/// The state of an entity changed at runtime, kept when its prefab node is applied again.
+#[derive(Debug, Default)]
+struct RuntimeState {
+    hidden: Option<HiddenPropagate>,
+    text: Option<String>,
+    editing: Option<TextEditing>,
+    active_tab: Option<usize>,
+    window: Option<(UiWindowFrame, [f32; 4])>,
+    /// The `Draggable` of a window, inserted by the `UiWindowSystem` unless the prefab has one.
+    window_draggable: Option<Draggable>,
+}
+
+impl RuntimeState {
+    /// Saves the runtime state of `entity`, and removes the components that the prefab node adds, so
+    /// that those it no longer declares are gone once it is applied again.
+    fn take<'a, CD, W>(
+        entity: Entity,
+        data: &mut <UiPrefabData<CD, W> as PrefabData<'a>>::SystemData,
+    ) -> Self
+    where
+        CD: PrefabData<'a> + Send + Sync + 'static,
+        W: WidgetId,
+    {
+        let (
+            (
+                ref transforms,
+                ref mut interactables,
+                ref hiddens,
+                ref mut selectables,
+                ref mut draggables,
+                ref mut tooltips,
+                ref mut classes,
+                ref mut transform_overrides,
+            ),
+            (_, ref mut images, ref mut image_overrides),
+            (ref mut texts, ref mut editings, ref mut text_overrides, _),
+            (
+                ref mut sound_retriggers,
+                ref mut action_retriggers,
+                ref mut buttons,
+                ref mut button_overrides,
+                _,
+                _,
+            ),
+            (ref mut frames, ref mut parts, ref mut windows),
+            ref mut tabs,
+            _,
+        ) = data;
+
+        let editing = editings.get(entity).cloned();
+        let state = RuntimeState {
+            hidden: hiddens.get(entity).cloned(),
+            text: editing.and(texts.get(entity)).map(|text| text.text.clone()),
+            editing,
+            active_tab: tabs.get(entity).map(|tabs| tabs.active()),
+            window: frames.get(entity).cloned().and_then(|frame| {
+                transforms.get(entity).map(|transform| {
+                    let layout = [
+                        transform.local_x,
+                        transform.local_y,
+                        transform.width,
+                        transform.height,
+                    ];
+                    (frame, layout)
+                })
+            }),
+            window_draggable: frames
+                .get(entity)
+                .and_then(|_| draggables.get(entity).cloned()),
+        };
+
+        interactables.remove(entity);
+        selectables.remove(entity);
+        draggables.remove(entity);
+        tooltips.remove(entity);
+        classes.remove(entity);
+        transform_overrides.remove(entity);
+        image_overrides.remove(entity);
+        text_overrides.remove(entity);
+        button_overrides.remove(entity);
+        images.remove(entity);
+        texts.remove(entity);
+        editings.remove(entity);
+        sound_retriggers.remove(entity);
+        action_retriggers.remove(entity);
+        frames.remove(entity);
+        parts.remove(entity);
+        tabs.remove(entity);
+        // The button and window widgets are registered again.
+        buttons.retain(|_, button| button.image_entity != entity);
+        windows.retain(|_, window| window.window_entity != entity);
+        state
+    }
+
+    /// Restores the runtime state of `entity` over the components added by its prefab node.
+    fn restore<'a, CD, W>(
+        self,
+        entity: Entity,
+        data: &mut <UiPrefabData<CD, W> as PrefabData<'a>>::SystemData,
+    ) -> Result<(), Error>
+    where
+        CD: PrefabData<'a> + Send + Sync + 'static,
+        W: WidgetId,
+    {
+        let (
+            (ref mut transforms, _, ref mut hiddens, _, ref mut draggables, _, _, _),
+            _,
+            (ref mut texts, ref mut editings, _, _),
+            _,
+            (ref mut frames, _, _),
+            ref mut tabs,
+            _,
+        ) = data;
+
+        match self.hidden {
+            Some(hidden) => {
+                hiddens.insert(entity, hidden)?;
+            }
+            None => {
+                hiddens.remove(entity);
+            }
+        }
+        // Texts stay editable only if the prefab still allows it.
+        if let (Some(previous), Some(editing)) = (self.editing, editings.get_mut(entity)) {
+            if let (Some(contents), Some(text)) = (self.text, texts.get_mut(entity)) {
+                text.text = contents;
+            }
+            editing.cursor_position = previous.cursor_position;
+            editing.highlight_vector = previous.highlight_vector;
+        }
+        if let (Some(active), Some(tabs)) = (self.active_tab, tabs.get_mut(entity)) {
+            tabs.set_active(active);
+        }
+        if let Some((previous, [x, y, width, height])) = self.window {
+            if let Some(frame) = frames.get_mut(entity) {
+                frame.keep_state_of(&previous);
+                // The `UiWindowSystem` does not set up a kept window again.
+                if let Some(draggable) = self.window_draggable {
+                    if !draggables.contains(entity) {
+                        draggables.insert(entity, draggable)?;
+                    }
+                }
+            }
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.local_x = x;
+                transform.local_y = y;
+                transform.width = width;
+                transform.height = height;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+    use specs_hierarchy::HierarchySystem;
+
+    use amethyst_assets::{Loader, Prefab};
+    use amethyst_core::{
+        ecs::prelude::{Builder, RunNow, World, WorldExt},
+        SystemDesc, Time,
+    };
+
+    use super::*;
+    use crate::{
+        drag::DragBounds, Selected, TextEditingPrefab, UiLoaderSystemDesc, UiText, UiTextData,
+        UiTransform, UiTransformData, UiWindow, UiWindowPrefab, Widgets,
+    };
+
+    type Data = UiPrefabData<(), u32>;
+
+    fn transform(id: &str, x: f32) -> Option<UiTransformData<()>> {
+        Some(UiTransformData {
+            id: id.to_string(),
+            x,
+            width: 200.,
+            height: 100.,
+            ..Default::default()
+        })
+    }
+
+    fn text(editable: bool) -> Option<UiTextData> {
+        Some(UiTextData {
+            text: "prefab".to_string(),
+            font_size: None,
+            color: None,
+            font: None,
+            password: false,
+            line_mode: None,
+            align: None,
+            editable: if editable {
+                Some(TextEditingPrefab::default())
+            } else {
+                None
+            },
+        })
+    }
+
+    fn add_window(prefab: &mut Prefab<Data>, id: u32, name: &str) {
+        let frame = UiWindowPrefab::Frame {
+            id: Some(id),
+            frame: UiWindowFrame::default(),
+        };
+        let window = prefab.add(
+            Some(0),
+            Some((transform(name, 0.), None, None, None, Some(frame), None, ())),
+        );
+        for part in &["title", "content"] {
+            let part_id = format!("{}_{}", name, part);
+            let data = (transform(&part_id, 0.), None, None, None, None, None, ());
+            prefab.add(Some(window), Some(data));
+        }
+    }
+
+    /// The reloaded prefab moves the field, takes the text of the label away and removes the
+    /// dialog window.
+    fn prefab(reloaded: bool) -> Prefab<Data> {
+        let mut prefab = Prefab::new();
+        let field_x = if reloaded { 50. } else { 0. };
+        let field = (
+            transform("field", field_x),
+            None,
+            text(true),
+            None,
+            None,
+            None,
+            (),
+        );
+        prefab.add(Some(0), Some(field));
+        let label_text = if reloaded { None } else { text(false) };
+        let label = (
+            transform("label", 0.),
+            None,
+            label_text,
+            None,
+            None,
+            None,
+            (),
+        );
+        prefab.add(Some(0), Some(label));
+        add_window(&mut prefab, 1, "window");
+        if !reloaded {
+            add_window(&mut prefab, 2, "dialog");
+        }
+        prefab
+    }
+
+    fn find(world: &World, id: &str) -> Option<Entity> {
+        (&*world.entities(), &world.read_storage::<UiTransform>())
+            .join()
+            .find(|(_, transform)| transform.id == id)
+            .map(|(entity, _)| entity)
+    }
+
+    #[test]
+    fn reloading_applies_the_layout_and_keeps_the_runtime_state() {
+        let mut world = World::new();
+        let pool = Arc::new(ThreadPoolBuilder::default().build().unwrap());
+        world.insert(pool.clone());
+        world.insert(Loader::new(".", pool));
+        world.insert(Time::default());
+        world.register::<Selected>();
+        let mut hierarchy = HierarchySystem::<Parent>::new(&mut world);
+        let mut loader = UiLoaderSystemDesc::<(), u32>::default().build(&mut world);
+        RunNow::setup(&mut loader, &mut world);
+        let mut reload = UiPrefabReloadSystem::<(), u32>::default();
+        RunNow::setup(&mut reload, &mut world);
+        let mut run = |world: &mut World| {
+            loader.run_now(world);
+            hierarchy.run_now(world);
+            reload.run_now(world);
+            world.maintain();
+        };
+
+        let load = |world: &World, prefab| {
+            world.read_resource::<Loader>().load_from_data(
+                prefab,
+                (),
+                &world.read_resource::<AssetStorage<UiPrefab<(), u32>>>(),
+            )
+        };
+        let handle = load(&world, prefab(false));
+        let root = world.create_entity().with(handle.clone()).build();
+        run(&mut world);
+
+        let field = find(&world, "field").unwrap();
+        let label = find(&world, "label").unwrap();
+        let window = find(&world, "window").unwrap();
+        world.write_storage().insert(field, Selected).unwrap();
+        world.write_storage::<UiText>().get_mut(field).unwrap().text = "typed".to_string();
+        // What the `UiWindowSystem` and dragging the window do.
+        let draggable = Draggable::new().with_bounds(DragBounds::Parent);
+        world
+            .write_storage()
+            .insert(window, draggable.clone())
+            .unwrap();
+        world
+            .write_storage::<UiTransform>()
+            .get_mut(window)
+            .unwrap()
+            .local_x = 120.;
+
+        // A hot reload gets the prefab processed by the loader, which tags it.
+        let reloaded = load(&world, prefab(true));
+        run(&mut world);
+        {
+            let mut storage = world.write_resource::<AssetStorage<UiPrefab<(), u32>>>();
+            let reloaded = storage.replace(&reloaded, Prefab::new());
+            storage.replace(&handle, reloaded);
+        }
+        run(&mut world);
+
+        assert!(world.is_alive(root));
+        assert_eq!(find(&world, "field"), Some(field));
+        assert!(world.read_storage::<Selected>().contains(field));
+        let transforms = world.read_storage::<UiTransform>();
+        assert_eq!(transforms.get(field).unwrap().local_x, 50.);
+        assert_eq!(
+            world.read_storage::<UiText>().get(field).unwrap().text,
+            "typed"
+        );
+        assert!(!world.read_storage::<UiText>().contains(label));
+
+        assert_eq!(transforms.get(window).unwrap().local_x, 120.);
+        assert_eq!(
+            world.read_storage::<Draggable>().get(window),
+            Some(&draggable)
+        );
+        assert!(find(&world, "dialog").is_none());
+        assert!(find(&world, "dialog_title").is_none());
+        let windows = world.read_resource::<Widgets<UiWindow, u32>>();
+        assert_eq!(windows.get(1).map(|w| w.window_entity), Some(window));
+        assert!(windows.get(2).is_none());
+    }
+}
@@ -0,0 +1,298 @@
+//! Propagation of ui events through the ui hierarchy.
+
+use std::{fmt, sync::Arc};
+
+use amethyst_core::{
+    ecs::prelude::{Component, DenseVecStorage, Entity, ReaderId, RunNow, World, WorldExt},
+    shrev::EventChannel,
+    ParentHierarchy,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{UiEvent, UiEventPhase, UiEventType};
+
+type Handler = Arc<dyn Fn(&World, &UiEvent) -> UiPropagation + Send + Sync>;
+
+/// Tells whether a `UiEvent` keeps propagating after a handler saw it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiPropagation {
+    /// Let the event reach the next entity of its path.
+    Continue,
+    /// Stop the event here. The other handlers of the current entity are still called.
+    Stop,
+}
+
+/// Handles the `UiEvent`s targeting this entity or any of its descendants.
+///
+/// Events travel like in the DOM: they are captured from the root of the hierarchy down to their
+/// target, then bubble back up to the root. `UiEvent::current_target` is the entity whose handler
+/// is called, while `UiEvent::target` stays the entity on which the event happened, so a list can
+/// handle the clicks on all of its items with a single handler:
+///
+/// ```rust,ignore
+/// let handler = UiEventHandler::new().on_bubble(|world, event| {
+///     if event.event_type == UiEventType::Click(MouseButton::Left) {
+///         world.write_resource::<Selection>().item = Some(event.target);
+///         UiPropagation::Stop
+///     } else {
+///         UiPropagation::Continue
+///     }
+/// });
+/// ```
+///
+/// When several elements on top of each other are targeted by the same mouse event, like a
+/// button and its text, the event only propagates once, from the deepest of them.
+#[derive(Default, Clone)]
+pub struct UiEventHandler {
+    capture: Option<Handler>,
+    bubble: Option<Handler>,
+}
+
+impl fmt::Debug for UiEventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UiEventHandler")
+            .field("capture", &self.capture.is_some())
+            .field("bubble", &self.bubble.is_some())
+            .finish()
+    }
+}
+
+impl UiEventHandler {
+    /// Creates a handler ignoring all events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the handler called while the event goes down to its target, and at the target.
+    pub fn on_capture<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&World, &UiEvent) -> UiPropagation + Send + Sync + 'static,
+    {
+        self.capture = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets the handler called at the target, and while the event goes back up to the root.
+    pub fn on_bubble<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&World, &UiEvent) -> UiPropagation + Send + Sync + 'static,
+    {
+        self.bubble = Some(Arc::new(handler));
+        self
+    }
+}
+
+impl Component for UiEventHandler {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Thread local system propagating the `UiEvent`s to the `UiEventHandler`s.
+///
+/// The handlers get the whole `World`, so it is added with `add_thread_local`.
+#[derive(Debug, Default)]
+pub struct UiEventHandlerSystem {
+    ui_reader_id: Option<ReaderId<UiEvent>>,
+}
+
+impl UiEventHandlerSystem {
+    /// Creates a new `UiEventHandlerSystem`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a> RunNow<'a> for UiEventHandlerSystem {
+    fn run_now(&mut self, world: &'a World) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_event_handler_system");
+
+        let events = {
+            let reader_id = self
+                .ui_reader_id
+                .as_mut()
+                .expect("`UiEventHandlerSystem::setup` was not called before being run.");
+            let events = world
+                .fetch::<EventChannel<UiEvent>>()
+                .read(reader_id)
+                .cloned()
+                .collect::<Vec<_>>();
+            let hierarchy = world.fetch::<ParentHierarchy>();
+            deepest_events(&events, |entity| hierarchy.parent(entity))
+        };
+
+        for event in events {
+            // Collect the handlers first, so that they are free to access any storage.
+            let calls = {
+                let hierarchy = world.fetch::<ParentHierarchy>();
+                let handlers = world.read_storage::<UiEventHandler>();
+                propagation_path(event.target, &hierarchy)
+                    .into_iter()
+                    .flat_map(|(entity, phase)| {
+                        let handler = handlers.get(entity);
+                        let capture = handler
+                            .and_then(|h| h.capture.clone())
+                            .filter(|_| phase != UiEventPhase::Bubble);
+                        let bubble = handler
+                            .and_then(|h| h.bubble.clone())
+                            .filter(|_| phase != UiEventPhase::Capture);
+                        capture
+                            .into_iter()
+                            .chain(bubble)
+                            .map(move |handler| (entity, phase, handler))
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            let mut stopped_at = None;
+            for (entity, phase, handler) in calls {
+                if stopped_at.map_or(false, |stopped| stopped != entity) {
+                    break;
+                }
+                if handler(world, &event.propagated(entity, phase)) == UiPropagation::Stop {
+                    stopped_at = Some(entity);
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        world.register::<UiEventHandler>();
+        self.ui_reader_id = Some(
+            world
+                .entry::<EventChannel<UiEvent>>()
+                .or_insert_with(Default::default)
+                .register_reader(),
+        );
+    }
+}
+
+/// Returns the entities an event targeting `target` goes through, in order.
+fn propagation_path(target: Entity, hierarchy: &ParentHierarchy) -> Vec<(Entity, UiEventPhase)> {
+    let mut ancestors = Vec::new();
+    let mut current = hierarchy.parent(target);
+    while let Some(entity) = current {
+        ancestors.push(entity);
+        current = hierarchy.parent(entity);
+    }
+
+    let mut path = ancestors
+        .iter()
+        .rev()
+        .map(|entity| (*entity, UiEventPhase::Capture))
+        .collect::<Vec<_>>();
+    path.push((target, UiEventPhase::Target));
+    path.extend(
+        ancestors
+            .iter()
+            .map(|entity| (*entity, UiEventPhase::Bubble)),
+    );
+    path
+}
+
+/// Drops the duplicated events, and the pointer events whose propagation is covered by the same
+/// event targeting a descendant, as the mouse targets all the elements under the cursor.
+fn deepest_events(events: &[UiEvent], parent: impl Fn(Entity) -> Option<Entity>) -> Vec<UiEvent> {
+    let is_ancestor = |ancestor: Entity, mut entity: Entity| {
+        while let Some(parent) = parent(entity) {
+            if parent == ancestor {
+                return true;
+            }
+            entity = parent;
+        }
+        false
+    };
+    let mut deepest: Vec<UiEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        let duplicated = deepest
+            .iter()
+            .any(|other| other.target == event.target && other.event_type == event.event_type);
+        let covered = is_pointer_event(&event.event_type)
+            && events.iter().any(|other| {
+                other.event_type == event.event_type && is_ancestor(event.target, other.target)
+            });
+        if !duplicated && !covered {
+            deepest.push(event.clone());
+        }
+    }
+    deepest
+}
+
+/// Whether the `UiMouseSystem` sends events of this type to all the elements under the cursor.
+fn is_pointer_event(event_type: &UiEventType) -> bool {
+    match event_type {
+        UiEventType::Click(_)
+        | UiEventType::ClickStart(_)
+        | UiEventType::ClickStop(_)
+        | UiEventType::DoubleClick(_)
+        | UiEventType::LongPress(_)
+        | UiEventType::Scroll { .. }
+        | UiEventType::HoverStart
+        | UiEventType::HoverStop => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::prelude::Builder;
+    use winit::MouseButton;
+
+    fn entities(count: usize) -> Vec<Entity> {
+        let mut world = World::new();
+        (0..count).map(|_| world.create_entity().build()).collect()
+    }
+
+    fn targets(events: &[UiEvent]) -> Vec<(UiEventType, Entity)> {
+        events
+            .iter()
+            .map(|event| (event.event_type.clone(), event.target))
+            .collect()
+    }
+
+    #[test]
+    fn pointer_events_only_propagate_from_the_deepest_target() {
+        let e = entities(3);
+        let (button, text, other) = (e[0], e[1], e[2]);
+        let parent = |entity| if entity == text { Some(button) } else { None };
+        let click = UiEventType::Click(MouseButton::Left);
+        let events = [
+            UiEvent::new(click.clone(), button),
+            UiEvent::new(click.clone(), text),
+            UiEvent::new(click.clone(), other),
+        ];
+        assert_eq!(
+            targets(&deepest_events(&events, parent)),
+            vec![(click.clone(), text), (click, other)]
+        );
+    }
+
+    #[test]
+    fn other_events_of_ancestors_are_kept() {
+        let e = entities(2);
+        let (field, spin_box) = (e[0], e[1]);
+        let parent = |entity| {
+            if entity == field {
+                Some(spin_box)
+            } else {
+                None
+            }
+        };
+        let events = [
+            UiEvent::new(UiEventType::ValueCommit, field),
+            UiEvent::new(UiEventType::ValueCommit, spin_box),
+            UiEvent::new(UiEventType::Focus, field),
+            UiEvent::new(UiEventType::Focus, field),
+        ];
+        assert_eq!(
+            targets(&deepest_events(&events, parent)),
+            vec![
+                (UiEventType::ValueCommit, field),
+                (UiEventType::ValueCommit, spin_box),
+                (UiEventType::Focus, field),
+            ]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! The recycled rows of the virtualised views, `UiListView` and `UiTreeView`.
+
+use amethyst_core::{
+    ecs::prelude::{Entities, Entity, Join, WriteStorage},
+    HiddenPropagate, Parent, ParentHierarchy,
+};
+
+use crate::{Anchor, Interactable, Selectable, Selected, UiImage, UiTransform};
+
+/// An entity showing one of the items of a view, bound to other items while scrolling.
+#[derive(Debug)]
+pub(crate) struct Row<K, E> {
+    pub(crate) entity: Entity,
+    /// The children of the row specific to the view.
+    pub(crate) elements: E,
+    /// The item shown, if any.
+    pub(crate) bound: Option<K>,
+    /// Whether the entity was `Selected` at the end of the last update.
+    pub(crate) selected: bool,
+}
+
+impl<K, E> Row<K, E> {
+    pub(crate) fn new(entity: Entity, elements: E) -> Self {
+        Row {
+            entity,
+            elements,
+            bound: None,
+            selected: false,
+        }
+    }
+}
+
+/// Creates the entity of a row of `view`, named `{id}_row_{index}`, with the tab order
+/// `tab_order + index`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_row<G>(
+    view: Entity,
+    id: &str,
+    index: usize,
+    tab_order: u32,
+    entities: &Entities<'_>,
+    ui_transforms: &mut WriteStorage<'_, UiTransform>,
+    interactables: &mut WriteStorage<'_, Interactable>,
+    selectables: &mut WriteStorage<'_, Selectable<G>>,
+    parents: &mut WriteStorage<'_, Parent>,
+) -> Entity
+where
+    G: Send + Sync + 'static,
+{
+    let entity = entities.create();
+    let _ = ui_transforms.insert(
+        entity,
+        UiTransform::new(
+            format!("{}_row_{}", id, index),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            0.,
+            0.,
+            1.,
+            0.,
+            0.,
+        ),
+    );
+    let _ = interactables.insert(entity, Interactable);
+    let _ = selectables.insert(entity, Selectable::new(tab_order + index as u32));
+    let _ = parents.insert(entity, Parent { entity: view });
+    entity
+}
+
+/// Binds the row at `index` to `item` and shows it. Returns whether it showed another item.
+pub(crate) fn show_row<K, E, G>(
+    row: &mut Row<K, E>,
+    (index, item): (usize, K),
+    tab_order: u32,
+    hiddens: &mut WriteStorage<'_, HiddenPropagate>,
+    selectables: &mut WriteStorage<'_, Selectable<G>>,
+) -> bool
+where
+    K: Copy + PartialEq,
+    G: Send + Sync + 'static,
+{
+    hiddens.remove(row.entity);
+    // The tab order of the view may have changed since the row was created.
+    let order = tab_order + index as u32;
+    if selectables
+        .get(row.entity)
+        .map_or(false, |selectable| selectable.order != order)
+    {
+        if let Some(selectable) = selectables.get_mut(row.entity) {
+            selectable.order = order;
+        }
+    }
+    let rebound = row.bound != Some(item);
+    row.bound = Some(item);
+    rebound
+}
+
+/// Hides a row which has no item to show.
+pub(crate) fn hide_row<K, E>(
+    row: &mut Row<K, E>,
+    hiddens: &mut WriteStorage<'_, HiddenPropagate>,
+    selecteds: &mut WriteStorage<'_, Selected>,
+) {
+    if row.bound.take().is_some() || !hiddens.contains(row.entity) {
+        let _ = hiddens.insert(row.entity, HiddenPropagate::new());
+        selecteds.remove(row.entity);
+    }
+}
+
+/// Applies the selection changes made with the mouse or the keyboard on the rows to `selected`,
+/// and returns the newly selected item, if any.
+///
+/// `focused` tracks whether the focus is on the view. The view keeps it while its selected row is
+/// scrolled out of view, and loses it when another element is selected.
+pub(crate) fn sync_selection<K, E>(
+    rows: &[Row<K, E>],
+    selected: &mut Option<K>,
+    focused: &mut bool,
+    entities: &Entities<'_>,
+    selecteds: &WriteStorage<'_, Selected>,
+) -> Option<K>
+where
+    K: Copy + PartialEq,
+{
+    let mut newly_selected = None;
+    for row in rows {
+        let row_selected = selecteds.contains(row.entity);
+        if row_selected && !row.selected {
+            newly_selected = row.bound.or(newly_selected);
+        } else if !row_selected && row.selected && *selected == row.bound {
+            *selected = None;
+        }
+    }
+    if rows.iter().any(|row| selecteds.contains(row.entity)) {
+        *focused = true;
+    } else if (&**entities, selecteds)
+        .join()
+        .any(|(entity, _)| rows.iter().all(|row| row.entity != entity))
+    {
+        *focused = false;
+    }
+    let changed = newly_selected.filter(|_| newly_selected != *selected);
+    if newly_selected.is_some() {
+        *selected = newly_selected;
+    }
+    changed
+}
+
+/// Shows the selection on the row bound to the `selected` item: the row gets the
+/// `selected_image`, and the focus when the view has it.
+pub(crate) fn show_selection<K, E>(
+    rows: &mut [Row<K, E>],
+    (selected, focused): (Option<K>, bool),
+    (row_image, selected_image): (Option<&UiImage>, Option<&UiImage>),
+    selecteds: &mut WriteStorage<'_, Selected>,
+    images: &mut WriteStorage<'_, UiImage>,
+) where
+    K: Copy + PartialEq,
+{
+    for row in rows.iter_mut() {
+        let is_selected = row.bound.is_some() && row.bound == selected;
+        if !is_selected && row.bound.is_some() {
+            selecteds.remove(row.entity);
+        } else if is_selected && focused && !selecteds.contains(row.entity) {
+            let _ = selecteds.insert(row.entity, Selected);
+        }
+        row.selected = selecteds.contains(row.entity);
+        let image = if is_selected {
+            selected_image.or(row_image)
+        } else {
+            row_image
+        };
+        match image {
+            Some(image) if images.get(row.entity) != Some(image) => {
+                let _ = images.insert(row.entity, image.clone());
+            }
+            None => {
+                images.remove(row.entity);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Finds the view `entity` is part of, which can be `entity` itself.
+pub(crate) fn view_of(
+    entity: Entity,
+    hierarchy: &ParentHierarchy,
+    is_view: impl Fn(Entity) -> bool,
+) -> Option<Entity> {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if is_view(entity) {
+            return Some(entity);
+        }
+        current = hierarchy.parent(entity);
+    }
+    None
+}
@@ -0,0 +1,152 @@
+//! Module for the UiScaler resource.
+
+use serde::{Deserialize, Serialize};
+
+use amethyst_window::ScreenDimensions;
+
+/// How the ui is scaled to fit the screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UiScaleMode {
+    /// One ui pixel is one pixel of the screen, whatever its size or density.
+    ConstantPixelSize,
+    /// The ui is designed for a reference resolution and scaled up or down with the screen.
+    ScaleWithScreen {
+        /// Width of the screen the ui was designed for.
+        reference_width: f32,
+        /// Height of the screen the ui was designed for.
+        reference_height: f32,
+        /// Whether the scale follows the width (`0.0`), the height (`1.0`) or a mix of both when
+        /// the aspect ratio of the screen differs from the reference one.
+        match_width_or_height: f32,
+    },
+    /// One ui pixel is one logical pixel of the window, so the ui keeps the same physical size
+    /// on high density displays.
+    ConstantPhysicalSize,
+}
+
+impl Default for UiScaleMode {
+    fn default() -> Self {
+        UiScaleMode::ConstantPixelSize
+    }
+}
+
+/// Resource scaling the ui elements laid out directly on the screen.
+///
+/// Elements inside a `UiCanvas` or a `UiWorldCanvas` are not affected, as they have their own
+/// resolution. Layout, text rasterisation and mouse input all take the scale into account, so
+/// `UiTransform`s can keep using ui pixels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiScaler {
+    /// How the ui follows the size of the screen.
+    pub mode: UiScaleMode,
+    /// Multiplier applied on top of the mode, for example from an accessibility setting.
+    pub ui_scale: f32,
+}
+
+impl Default for UiScaler {
+    fn default() -> Self {
+        UiScaler {
+            mode: UiScaleMode::default(),
+            ui_scale: 1.0,
+        }
+    }
+}
+
+impl UiScaler {
+    /// Creates a new `UiScaler` using `mode`.
+    pub fn new(mode: UiScaleMode) -> Self {
+        UiScaler {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the multiplier applied on top of the mode.
+    pub fn with_ui_scale(mut self, ui_scale: f32) -> Self {
+        self.ui_scale = ui_scale;
+        self
+    }
+
+    /// Returns how many screen pixels make up a ui pixel.
+    pub fn scale_factor(&self, screen_dimensions: &ScreenDimensions) -> f32 {
+        let factor = match self.mode {
+            UiScaleMode::ConstantPixelSize => 1.0,
+            UiScaleMode::ScaleWithScreen {
+                reference_width,
+                reference_height,
+                match_width_or_height,
+            } => {
+                // Interpolating in log space keeps doubling the width and halving the height
+                // neutral when matching both halfway.
+                let log_width = (screen_dimensions.width() / reference_width).log2();
+                let log_height = (screen_dimensions.height() / reference_height).log2();
+                let t = match_width_or_height.max(0.0).min(1.0);
+                (log_width + (log_height - log_width) * t).exp2()
+            }
+            UiScaleMode::ConstantPhysicalSize => screen_dimensions.hidpi_factor() as f32,
+        };
+        let factor = factor * self.ui_scale;
+        if factor.is_finite() && factor > 0.0 {
+            factor
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_screen(mode: UiScaleMode) -> f32 {
+        UiScaler::new(mode).scale_factor(&ScreenDimensions::new(2560, 1080, 2.0))
+    }
+
+    fn scale_with_screen(match_width_or_height: f32) -> UiScaleMode {
+        UiScaleMode::ScaleWithScreen {
+            reference_width: 1280.,
+            reference_height: 720.,
+            match_width_or_height,
+        }
+    }
+
+    #[test]
+    fn constant_pixel_size_ignores_the_screen() {
+        assert_eq!(with_screen(UiScaleMode::ConstantPixelSize), 1.0);
+    }
+
+    #[test]
+    fn constant_physical_size_follows_the_hidpi_factor() {
+        assert_eq!(with_screen(UiScaleMode::ConstantPhysicalSize), 2.0);
+    }
+
+    #[test]
+    fn scale_with_screen_matches_the_width_the_height_or_both() {
+        // The screen is twice as wide and one and a half times as high as the reference.
+        assert!((with_screen(scale_with_screen(0.0)) - 2.0).abs() < 1e-5);
+        assert!((with_screen(scale_with_screen(1.0)) - 1.5).abs() < 1e-5);
+        assert!((with_screen(scale_with_screen(0.5)) - 3f32.sqrt()).abs() < 1e-5);
+        // Out of range matches are clamped.
+        assert!((with_screen(scale_with_screen(4.0)) - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ui_scale_multiplies_the_mode() {
+        let screen = ScreenDimensions::new(2560, 1080, 2.0);
+        let scaler = UiScaler::new(UiScaleMode::ConstantPhysicalSize).with_ui_scale(1.5);
+        assert_eq!(scaler.scale_factor(&screen), 3.0);
+    }
+
+    #[test]
+    fn invalid_factors_fall_back_to_one() {
+        let screen = ScreenDimensions::new(2560, 1080, 2.0);
+        let zero_scale = UiScaler::default().with_ui_scale(0.0);
+        assert_eq!(zero_scale.scale_factor(&screen), 1.0);
+        let no_reference = UiScaler::new(UiScaleMode::ScaleWithScreen {
+            reference_width: 0.,
+            reference_height: 0.,
+            match_width_or_height: 0.,
+        });
+        assert_eq!(no_reference.scale_factor(&screen), 1.0);
+    }
+}
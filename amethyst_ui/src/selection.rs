@@ -3,20 +3,20 @@ use std::marker::PhantomData;
 use derivative::Derivative;
 use derive_new::new;
 use serde::{Deserialize, Serialize};
-use winit::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
 
 use amethyst_core::{
     ecs::{
-        Component, DenseVecStorage, Entities, FlaggedStorage, Join, Read, ReadStorage, ReaderId,
-        System, SystemData, World, Write, WriteStorage,
+        Component, DenseVecStorage, Entities, Entity, FlaggedStorage, Join, Read, ReadExpect,
+        ReadStorage, ReaderId, System, SystemData, World, Write, WriteStorage,
     },
     shrev::EventChannel,
-    SystemDesc,
+    ParentHierarchy, SystemDesc,
 };
 use amethyst_derive::SystemDesc;
 use amethyst_input::{BindingTypes, InputHandler};
 
-use crate::{CachedSelectionOrder, UiEvent, UiEventType};
+use crate::{CachedSelectionOrder, UiEvent, UiEventType, UiModalStack};
 
 // TODO: If none selected and there is a Selectable in the World, select the lower ordered one automatically?
 
@@ -93,10 +93,12 @@ where
         WriteStorage<'a, Selected>,
         Write<'a, EventChannel<UiEvent>>,
         Entities<'a>,
+        Read<'a, UiModalStack>,
+        ReadExpect<'a, ParentHierarchy>,
     );
     fn run(
         &mut self,
-        (window_events, cached, mut selecteds, mut ui_events, entities): Self::SystemData,
+        (window_events, cached, mut selecteds, mut ui_events, entities, modal_stack, hierarchy): Self::SystemData,
     ) {
         /*
         Algorithm in use:
@@ -136,50 +138,64 @@ where
                 ..
             } = *event
             {
-                // Get index of highest selected ui element
-                let highest = cached.highest_order_selected_index(&selecteds);
-
-                if let Some(highest) = highest {
-                    // If Some, an element was currently selected. We move the cursor to the next or previous element depending if Shift was pressed.
+                // While a modal is open, only cycle through the elements inside of it.
+                let target = tab_target(
+                    &cached.cache,
+                    |entity| modal_stack.allows(entity, &hierarchy),
+                    |entity| selecteds.contains(entity),
+                    !modifiers.shift,
+                );
+
+                if let Some(target) = target {
                     // Select Replace
                     for (entity, _) in (&*entities, &selecteds).join() {
                         ui_events.single_write(UiEvent::new(UiEventType::Blur, entity));
                     }
                     selecteds.clear();
 
-                    let target = if !modifiers.shift {
-                        // Up
-                        if highest > 0 {
-                            cached.cache.get(highest - 1).unwrap_or_else(|| cached.cache.last()
-                                .expect("unreachable: A highest ui element was selected, but none exist in the cache."))
-                        } else {
-                            cached.cache.last()
-                                .expect("unreachable: A highest ui element was selected, but none exist in the cache.")
-                        }
-                    } else {
-                        // Down
-                        cached.cache.get(highest + 1).unwrap_or_else(|| cached.cache.first()
-                        .expect("unreachable: A highest ui element was selected, but none exist in the cache."))
-                    };
-
-                    selecteds
-                        .insert(target.1, Selected)
-                        .expect("unreachable: We are inserting");
-
-                    ui_events.single_write(UiEvent::new(UiEventType::Focus, target.1));
-                } else if let Some(lowest) = cached.cache.first() {
-                    // If None, nothing was selected. Try to take lowest if it exists.
                     selecteds
-                        .insert(lowest.1, Selected)
+                        .insert(target, Selected)
                         .expect("unreachable: We are inserting");
 
-                    ui_events.single_write(UiEvent::new(UiEventType::Focus, lowest.1));
+                    ui_events.single_write(UiEvent::new(UiEventType::Focus, target));
                 }
             }
         }
     }
 }
 
+/// The entity selected when pressing Tab, among the `allowed` entities of the selection `cache`.
+///
+/// If one of them is `selected`, the one before the highest selected one is returned when
+/// `previous` is true, or the one after it otherwise, wrapping around. If none of them is, the
+/// lowest one is returned.
+fn tab_target(
+    cache: &[(u32, Entity)],
+    allowed: impl Fn(Entity) -> bool,
+    selected: impl Fn(Entity) -> bool,
+    previous: bool,
+) -> Option<Entity> {
+    let in_scope = |entry: &&(u32, Entity)| allowed(entry.1);
+    let highest = cache
+        .iter()
+        .rposition(|&(_, entity)| allowed(entity) && selected(entity));
+    let target = match highest {
+        // Up
+        Some(highest) if previous => cache[..highest]
+            .iter()
+            .rev()
+            .find(in_scope)
+            .or_else(|| cache[highest..].iter().rev().find(in_scope)),
+        // Down
+        Some(highest) => cache[highest + 1..]
+            .iter()
+            .find(in_scope)
+            .or_else(|| cache[..=highest].iter().find(in_scope)),
+        None => cache.iter().find(in_scope),
+    };
+    target.map(|&(_, entity)| entity)
+}
+
 /// Builds a `SelectionMouseSystem`.
 #[derive(Derivative, Debug)]
 #[derivative(Default(bound = ""))]
@@ -253,7 +269,7 @@ where
 
         // Add clicked elements to clicked buffer
         for ev in ui_events.read(&mut self.ui_reader_id) {
-            if let UiEventType::ClickStart = ev.event_type {
+            if let UiEventType::ClickStart(MouseButton::Left) = ev.event_type {
                 if !selectables.contains(ev.target) {
                     for (entity, _) in (&*entities, &selecteds).join() {
                         emitted.push(UiEvent::new(UiEventType::Blur, entity));
@@ -360,3 +376,65 @@ where
         ui_events.iter_write(emitted.into_iter());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, WorldExt};
+
+    fn cache(world: &mut World, count: u32) -> Vec<(u32, Entity)> {
+        (0..count)
+            .map(|order| (order, world.create_entity().build()))
+            .collect()
+    }
+
+    #[test]
+    fn lowest_is_selected_first() {
+        let mut world = World::new();
+        let cache = cache(&mut world, 3);
+        let target = tab_target(&cache, |_| true, |_| false, true);
+        assert_eq!(target, Some(cache[0].1));
+    }
+
+    #[test]
+    fn cycles_from_the_highest_selected() {
+        let mut world = World::new();
+        let cache = cache(&mut world, 3);
+        let (first, second, third) = (cache[0].1, cache[1].1, cache[2].1);
+        let selected = |entity| entity == first || entity == second;
+        assert_eq!(tab_target(&cache, |_| true, selected, false), Some(third));
+        assert_eq!(tab_target(&cache, |_| true, selected, true), Some(first));
+        // Wraps around both ends.
+        assert_eq!(
+            tab_target(&cache, |_| true, |e| e == third, false),
+            Some(first)
+        );
+        assert_eq!(
+            tab_target(&cache, |_| true, |e| e == first, true),
+            Some(third)
+        );
+    }
+
+    #[test]
+    fn skips_the_entities_outside_of_the_scope() {
+        let mut world = World::new();
+        let cache = cache(&mut world, 4);
+        let (first, third) = (cache[0].1, cache[2].1);
+        let allowed = |entity| entity == first || entity == third;
+        assert_eq!(tab_target(&cache, allowed, |_| false, false), Some(first));
+        assert_eq!(
+            tab_target(&cache, allowed, |e| e == first, false),
+            Some(third)
+        );
+        assert_eq!(
+            tab_target(&cache, allowed, |e| e == third, false),
+            Some(first)
+        );
+        // Selected entities outside of the scope are ignored.
+        assert_eq!(
+            tab_target(&cache, allowed, |e| e == cache[3].1, false),
+            Some(first)
+        );
+        assert_eq!(tab_target(&cache, |_| false, |_| false, false), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Procedural shapes drawn by the ui pass.
+
+/// A rectangle with rounded corners, a border, a gradient fill and a drop shadow, drawn by the
+/// fragment shader of `DrawUi` without any texture. Use it with `UiImage::Shape`.
+///
+/// Colors are in linear RGBA, like `UiImage::SolidColor`, and sizes are in the units of the
+/// `UiTransform` of the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiShape {
+    /// The radius of the top left, top right, bottom right and bottom left corners.
+    pub corner_radii: [f32; 4],
+    /// The width of the border, inside the shape. The border is not drawn if it is zero.
+    pub border_width: f32,
+    /// The color of the border.
+    pub border_color: [f32; 4],
+    /// How the inside of the shape is filled.
+    pub fill: UiShapeFill,
+    /// The shadow drawn under the shape.
+    pub shadow: Option<UiShadow>,
+}
+
+impl UiShape {
+    /// Creates a rectangle filled with `color`, without border nor shadow.
+    pub fn new(color: [f32; 4]) -> Self {
+        UiShape {
+            corner_radii: [0.0; 4],
+            border_width: 0.0,
+            border_color: [0.0; 4],
+            fill: UiShapeFill::Solid(color),
+            shadow: None,
+        }
+    }
+
+    /// Sets the same radius to every corner.
+    pub fn with_corner_radius(self, radius: f32) -> Self {
+        self.with_corner_radii([radius; 4])
+    }
+
+    /// Sets the radius of the top left, top right, bottom right and bottom left corners.
+    pub fn with_corner_radii(mut self, corner_radii: [f32; 4]) -> Self {
+        self.corner_radii = corner_radii;
+        self
+    }
+
+    /// Sets the width and the color of the border.
+    pub fn with_border(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.border_width = width;
+        self.border_color = color;
+        self
+    }
+
+    /// Sets how the inside of the shape is filled.
+    pub fn with_fill(mut self, fill: UiShapeFill) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Sets the shadow drawn under the shape.
+    pub fn with_shadow(mut self, shadow: UiShadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+}
+
+/// How the inside of a `UiShape` is filled.
+///
+/// Gradient positions are relative to the shape, from `[0, 0]` at its top left corner to `[1, 1]`
+/// at its bottom right corner.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiShapeFill {
+    /// A single color.
+    Solid([f32; 4]),
+    /// A gradient along the line from `start` to `end`.
+    Linear {
+        /// Where the gradient starts.
+        start: [f32; 2],
+        /// Where the gradient ends.
+        end: [f32; 2],
+        /// The color at `start` and before it.
+        start_color: [f32; 4],
+        /// The color at `end` and after it.
+        end_color: [f32; 4],
+    },
+    /// A gradient around `center`. As positions are relative, the gradient is elliptic in shapes
+    /// which are not square.
+    Radial {
+        /// The center of the gradient.
+        center: [f32; 2],
+        /// The distance from the center where the gradient ends.
+        radius: f32,
+        /// The color at the center.
+        inner_color: [f32; 4],
+        /// The color at `radius` and beyond it.
+        outer_color: [f32; 4],
+    },
+}
+
+/// The blurred shadow drawn under a `UiShape`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiShadow {
+    /// The color of the shadow.
+    pub color: [f32; 4],
+    /// How far the shadow is moved from the shape, with y going up like in `UiTransform`.
+    pub offset: [f32; 2],
+    /// The distance over which the edges of the shadow fade out, on both sides of the edges.
+    pub blur: f32,
+}
+
+impl UiShadow {
+    /// Creates a shadow of `color`, moved by `offset` and blurred over `blur`.
+    pub fn new(color: [f32; 4], offset: [f32; 2], blur: f32) -> Self {
+        UiShadow {
+            color,
+            offset,
+            blur,
+        }
+    }
+}
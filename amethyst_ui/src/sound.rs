@@ -8,6 +8,7 @@ use amethyst_core::{
     shrev::{EventChannel, ReaderId},
 };
 use amethyst_derive::SystemDesc;
+use winit::MouseButton;
 
 use crate::{
     event::{UiEvent, UiEventType::*},
@@ -57,8 +58,8 @@ impl EventRetrigger for UiSoundRetrigger {
         R: EventReceiver<Self::Out>,
     {
         let event_to_trigger = match &event.event_type {
-            ClickStart => &self.on_click_start,
-            ClickStop => &self.on_click_stop,
+            ClickStart(MouseButton::Left) => &self.on_click_start,
+            ClickStop(MouseButton::Left) => &self.on_click_stop,
             HoverStart => &self.on_hover_start,
             HoverStop => &self.on_hover_stop,
             _ => return,
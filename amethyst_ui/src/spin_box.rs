@@ -0,0 +1,569 @@
+//! Numeric spin boxes.
+
+use std::{collections::HashMap, fmt, marker::PhantomData, sync::Arc};
+
+use winit::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        ReaderId, System, SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+
+use crate::{
+    font::default::get_default_font,
+    list::{set_layout, transform_id},
+    rows::view_of,
+    Anchor, FontAsset, FontHandle, Interactable, LineMode, Selectable, Selected, TextEditing,
+    UiEvent, UiEventType, UiImage, UiText, UiTransform,
+};
+
+const DEFAULT_BUTTON_WIDTH: f32 = 24.0;
+const DEFAULT_FONT_SIZE: f32 = 18.0;
+const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const DEFAULT_BUTTON_COLOR: [f32; 4] = [0.82, 0.83, 0.83, 1.0];
+/// The longest text which can be typed in a spin box.
+const MAX_LENGTH: usize = 32;
+
+type FormatFn = Arc<dyn Fn(f64) -> String + Send + Sync>;
+
+/// The entities making up a spin box.
+#[derive(Debug, Clone, Copy)]
+struct SpinBoxParts {
+    field: Entity,
+    increment: Entity,
+    decrement: Entity,
+}
+
+/// Component showing an editable number in the `UiTransform` of its entity, next to buttons
+/// incrementing and decrementing it.
+///
+/// The number can be typed in the text field, and is parsed, clamped and rounded to `precision`
+/// decimals when the edit is committed with enter, or when the field stops being `Selected`.
+/// While the field is `Selected`, the mouse wheel and the up and down arrow keys step the value.
+/// Every change made by the user sends a `NumberChanged` event to the spin box.
+///
+/// The value is shown with `precision` decimals, or with the format function, which can add units
+/// like `"75 %"`. Units are ignored when parsing the typed text, and hidden while it is edited.
+pub struct UiSpinBox {
+    value: f64,
+    /// The smallest value.
+    pub min: f64,
+    /// The largest value.
+    pub max: f64,
+    /// How much the buttons, the mouse wheel and the arrow keys change the value.
+    pub step: f64,
+    /// The number of decimals the value is rounded to.
+    pub precision: usize,
+    format: Option<FormatFn>,
+    /// The font of the text. Defaults to the default font.
+    pub font: Option<FontHandle>,
+    /// The font size of the text.
+    pub font_size: f32,
+    /// The color of the text.
+    pub text_color: [f32; 4],
+    /// The width of the increment and decrement buttons.
+    pub button_width: f32,
+    /// The color of the increment and decrement buttons.
+    pub button_color: [f32; 4],
+    /// The selection order of the text field, see `Selectable`.
+    pub selection_order: u32,
+    parts: Option<SpinBoxParts>,
+    /// Whether the text has to be written again.
+    dirty: bool,
+    /// Whether the field was `Selected` at the end of the last update.
+    editing: bool,
+}
+
+impl fmt::Debug for UiSpinBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UiSpinBox")
+            .field("value", &self.value)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("step", &self.step)
+            .field("precision", &self.precision)
+            .field("format", &self.format.is_some())
+            .field("parts", &self.parts)
+            .finish()
+    }
+}
+
+impl UiSpinBox {
+    /// Creates a spin box showing `value`, between `min` and `max`.
+    pub fn new(value: f64, min: f64, max: f64, step: f64) -> Self {
+        UiSpinBox {
+            value,
+            min,
+            max,
+            step,
+            precision: 0,
+            format: None,
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            text_color: DEFAULT_TXT_COLOR,
+            button_width: DEFAULT_BUTTON_WIDTH,
+            button_color: DEFAULT_BUTTON_COLOR,
+            selection_order: 0,
+            parts: None,
+            dirty: true,
+            editing: false,
+        }
+    }
+
+    /// Sets the number of decimals of the value.
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self.set_value(self.value);
+        self
+    }
+
+    /// Sets the function formatting the value, for example to add units.
+    pub fn with_format<F>(mut self, format: F) -> Self
+    where
+        F: Fn(f64) -> String + Send + Sync + 'static,
+    {
+        self.format = Some(Arc::new(format));
+        self.dirty = true;
+        self
+    }
+
+    /// Sets the font of the text.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of the text.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Sets the color of the text.
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    /// Sets the width and the color of the increment and decrement buttons.
+    pub fn with_buttons(mut self, button_width: f32, button_color: [f32; 4]) -> Self {
+        self.button_width = button_width;
+        self.button_color = button_color;
+        self
+    }
+
+    /// Sets the selection order of the text field.
+    pub fn with_selection_order(mut self, selection_order: u32) -> Self {
+        self.selection_order = selection_order;
+        self
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Sets the value, clamped and rounded. No `NumberChanged` event is sent.
+    pub fn set_value(&mut self, value: f64) {
+        let scale = 10f64.powi(self.precision as i32);
+        let value = (value.max(self.min).min(self.max) * scale).round() / scale;
+        if value.is_finite() {
+            self.value = value;
+        }
+        self.dirty = true;
+    }
+
+    /// Returns the value as shown, using the format function if there is one.
+    pub fn formatted(&self) -> String {
+        match self.format {
+            Some(ref format) => format(self.value),
+            None => self.raw(),
+        }
+    }
+
+    /// Returns the value with `precision` decimals, as shown while it is edited.
+    fn raw(&self) -> String {
+        format!("{:.*}", self.precision, self.value)
+    }
+
+    /// Moves the value by `steps` steps.
+    fn step_by(&mut self, steps: f64) {
+        self.set_value(self.value + steps * self.step);
+    }
+}
+
+impl Component for UiSpinBox {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Reads the number at the start of `text`, ignoring the units after it, like `"12.5 px"`.
+/// Returns `None` if there is no number, or if what follows it starts like another number.
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+    let mut end = match bytes.first() {
+        Some(b'+') | Some(b'-') => 1,
+        _ => 0,
+    };
+    let integer = digits(end);
+    end += integer;
+    let mut fraction = 0;
+    if bytes.get(end) == Some(&b'.') {
+        fraction = digits(end + 1);
+        end += 1 + fraction;
+    }
+    match bytes.get(end) {
+        _ if integer + fraction == 0 => None,
+        Some(b'+') | Some(b'-') | Some(b'.') => None,
+        _ => text[..end].parse().ok(),
+    }
+}
+
+/// System creating the parts of the `UiSpinBox`es and handling their input.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiSpinBoxSystemDesc))]
+pub struct UiSpinBoxSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    #[system_desc(event_channel_reader)]
+    window_reader_id: ReaderId<Event>,
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+    phantom: PhantomData<G>,
+}
+
+impl<G> UiSpinBoxSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    /// Creates a new `UiSpinBoxSystem`.
+    pub fn new(window_reader_id: ReaderId<Event>, ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            window_reader_id,
+            ui_reader_id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, G> System<'a> for UiSpinBoxSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<FontAsset>>,
+        ReadExpect<'a, ParentHierarchy>,
+        Read<'a, EventChannel<Event>>,
+        Write<'a, EventChannel<UiEvent>>,
+        WriteStorage<'a, UiSpinBox>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, TextEditing>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, Interactable>,
+        WriteStorage<'a, Selectable<G>>,
+        ReadStorage<'a, Selected>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            font_assets,
+            hierarchy,
+            window_events,
+            mut ui_events,
+            mut spin_boxes,
+            mut ui_transforms,
+            mut texts,
+            mut editables,
+            mut images,
+            mut parents,
+            mut interactables,
+            mut selectables,
+            selecteds,
+        ): Self::SystemData,
+    ) {
+        // The arrow keys step the spin box whose field is selected.
+        let key_steps = window_events
+            .read(&mut self.window_reader_id)
+            .filter_map(|event| match *event {
+                Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput {
+                            input:
+                                KeyboardInput {
+                                    state: ElementState::Pressed,
+                                    virtual_keycode: Some(key),
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } => match key {
+                    VirtualKeyCode::Up => Some(1.0),
+                    VirtualKeyCode::Down => Some(-1.0),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .sum::<f64>();
+
+        // The input on the spin boxes, as steps and commits.
+        let mut steps = HashMap::new();
+        let mut scrolls = HashMap::new();
+        let mut commits = Vec::new();
+        let events = ui_events
+            .read(&mut self.ui_reader_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        for event in events {
+            let spin_box_entity =
+                match view_of(event.target, &hierarchy, |e| spin_boxes.contains(e)) {
+                    Some(spin_box_entity) => spin_box_entity,
+                    None => continue,
+                };
+            let spin_box = spin_boxes
+                .get(spin_box_entity)
+                .expect("unreachable: We just found this spin box");
+            let parts = match spin_box.parts {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let step = match event.event_type {
+                UiEventType::Click(MouseButton::Left) if event.target == parts.increment => 1.0,
+                UiEventType::Click(MouseButton::Left) if event.target == parts.decrement => -1.0,
+                // Every part of the spin box under the mouse receives the same scroll.
+                UiEventType::Scroll { delta } if selecteds.contains(parts.field) => {
+                    scrolls
+                        .entry(spin_box_entity)
+                        .or_insert_with(|| f64::from(delta[1]).round());
+                    continue;
+                }
+                UiEventType::ValueCommit if event.target == parts.field => {
+                    commits.push(spin_box_entity);
+                    continue;
+                }
+                _ => continue,
+            };
+            *steps.entry(spin_box_entity).or_insert(0.0) += step;
+        }
+
+        for (spin_box_entity, spin_box) in (&*entities, &mut spin_boxes).join() {
+            let font = spin_box
+                .font
+                .get_or_insert_with(|| get_default_font(&loader, &font_assets))
+                .clone();
+
+            // Create the text field and the buttons.
+            let parts = match spin_box.parts {
+                Some(parts) => parts,
+                None => {
+                    let id = transform_id(spin_box_entity, &ui_transforms);
+                    let mut create_part = |name: &str, text: &str, align: Anchor| {
+                        let part = entities.create();
+                        let _ = ui_transforms.insert(
+                            part,
+                            UiTransform::new(
+                                format!("{}_{}", id, name),
+                                Anchor::TopLeft,
+                                Anchor::TopLeft,
+                                0.,
+                                0.,
+                                1.,
+                                0.,
+                                0.,
+                            ),
+                        );
+                        let _ = texts.insert(
+                            part,
+                            UiText::new(
+                                font.clone(),
+                                text.to_string(),
+                                spin_box.text_color,
+                                spin_box.font_size,
+                                LineMode::Single,
+                                align,
+                            ),
+                        );
+                        let _ = interactables.insert(part, Interactable);
+                        let _ = parents.insert(
+                            part,
+                            Parent {
+                                entity: spin_box_entity,
+                            },
+                        );
+                        part
+                    };
+                    let parts = SpinBoxParts {
+                        field: create_part("field", "", Anchor::MiddleLeft),
+                        increment: create_part("increment", "+", Anchor::Middle),
+                        decrement: create_part("decrement", "-", Anchor::Middle),
+                    };
+                    let _ = editables.insert(
+                        parts.field,
+                        TextEditing::new(MAX_LENGTH, [1., 1., 1., 1.], [0., 0., 0., 1.], false),
+                    );
+                    let _ =
+                        selectables.insert(parts.field, Selectable::new(spin_box.selection_order));
+                    for button in [parts.increment, parts.decrement].iter() {
+                        let _ = images.insert(*button, UiImage::SolidColor(spin_box.button_color));
+                    }
+                    spin_box.parts = Some(parts);
+                    // The value given to `UiSpinBox::new` is rounded once all the settings are known.
+                    spin_box.set_value(spin_box.value);
+                    parts
+                }
+            };
+
+            // The text field fills the spin box, except for the buttons on the right.
+            if let Some((width, height)) = ui_transforms
+                .get(spin_box_entity)
+                .map(|transform| (transform.pixel_width(), transform.pixel_height()))
+            {
+                let button_width = spin_box.button_width.min(width);
+                let button_x = width - button_width;
+                set_layout(
+                    parts.field,
+                    (0.0, 0.0, button_x, height),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    parts.increment,
+                    (button_x, 0.0, button_width, height / 2.0),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    parts.decrement,
+                    (button_x, height / 2.0, button_width, height / 2.0),
+                    &mut ui_transforms,
+                );
+            }
+
+            let editing = selecteds.contains(parts.field);
+            let previous = spin_box.value;
+            if commits.contains(&spin_box_entity) || (spin_box.editing && !editing) {
+                // Invalid text shows the current value again.
+                let typed = texts
+                    .get(parts.field)
+                    .and_then(|text| parse_number(&text.text));
+                spin_box.set_value(typed.unwrap_or(spin_box.value));
+            }
+            let mut steps = steps.get(&spin_box_entity).cloned().unwrap_or(0.0)
+                + scrolls.get(&spin_box_entity).cloned().unwrap_or(0.0);
+            if editing {
+                steps += key_steps;
+            }
+            if steps != 0.0 {
+                spin_box.step_by(steps);
+            }
+            if spin_box.value != previous {
+                ui_events.single_write(UiEvent::new(
+                    UiEventType::NumberChanged {
+                        value: spin_box.value,
+                    },
+                    spin_box_entity,
+                ));
+            }
+
+            // The units are hidden while the value is edited.
+            if spin_box.dirty || spin_box.editing != editing {
+                spin_box.dirty = false;
+                let text = if editing {
+                    spin_box.raw()
+                } else {
+                    spin_box.formatted()
+                };
+                if let Some(ui_text) = texts.get_mut(parts.field) {
+                    if ui_text.text != text {
+                        if let Some(edit) = editables.get_mut(parts.field) {
+                            edit.cursor_position = text.chars().count() as isize;
+                            edit.highlight_vector = 0;
+                        }
+                        ui_text.text = text;
+                    }
+                }
+            }
+            spin_box.editing = editing;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_number_ignores_units() {
+        assert_eq!(parse_number("12"), Some(12.0));
+        assert_eq!(parse_number(" -3.5 px "), Some(-3.5));
+        assert_eq!(parse_number("+4"), Some(4.0));
+        assert_eq!(parse_number(".5"), Some(0.5));
+        assert_eq!(parse_number("7."), Some(7.0));
+        assert_eq!(parse_number("50%"), Some(50.0));
+    }
+
+    #[test]
+    fn parse_number_rejects_malformed_numbers() {
+        for text in &[
+            "", "px", "-", "+", ".", "--", "-.", "1-2", "1+", "1.2.3", "+-1",
+        ] {
+            assert_eq!(parse_number(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn set_value_clamps_and_rounds_to_the_precision() {
+        let mut spin_box = UiSpinBox::new(2.345, -10., 10., 1.).with_precision(1);
+        assert_eq!(spin_box.value(), 2.3);
+        spin_box.set_value(3.14159);
+        assert_eq!(spin_box.value(), 3.1);
+        spin_box.set_value(12.);
+        assert_eq!(spin_box.value(), 10.);
+        spin_box.set_value(-12.);
+        assert_eq!(spin_box.value(), -10.);
+
+        let mut spin_box = UiSpinBox::new(0., -10., 10., 1.);
+        spin_box.set_value(2.5);
+        assert_eq!(spin_box.value(), 3.);
+    }
+
+    #[test]
+    fn step_by_stays_between_min_and_max() {
+        let mut spin_box = UiSpinBox::new(0., 0., 1., 0.1).with_precision(1);
+        spin_box.step_by(3.);
+        assert_eq!(spin_box.value(), 0.3);
+        spin_box.step_by(-1.);
+        assert_eq!(spin_box.value(), 0.2);
+        spin_box.step_by(20.);
+        assert_eq!(spin_box.value(), 1.);
+        spin_box.step_by(-20.);
+        assert_eq!(spin_box.value(), 0.);
+    }
+
+    #[test]
+    fn formatted_uses_the_format_function() {
+        let spin_box = UiSpinBox::new(0.5, 0., 1., 0.1).with_precision(2);
+        assert_eq!(spin_box.formatted(), "0.50");
+        let spin_box = spin_box.with_format(|value| format!("{:.0} %", value * 100.));
+        assert_eq!(spin_box.formatted(), "50 %");
+        assert_eq!(spin_box.raw(), "0.50");
+    }
+}
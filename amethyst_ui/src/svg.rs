@@ -0,0 +1,1738 @@
+//! Parsing and rasterisation of the subset of SVG supported by `UiImage::Vector`.
+//!
+//! Paths, basic shapes, solid and gradient fills and strokes are supported, in groups with
+//! transforms. Text, clipping, masks, filters, patterns, `<use>` and CSS style sheets are ignored.
+
+use std::{collections::HashMap, f32::consts::PI};
+
+use amethyst_error::{format_err, Error};
+
+type Point = [f32; 2];
+
+/// The number of sub-scanlines sampled in each row of pixels.
+const SAMPLES: usize = 4;
+/// The largest distance in pixels between a curve and the lines it is flattened to.
+const TOLERANCE: f32 = 0.2;
+
+/// An affine transform `[a, b, c, d, e, f]`, as in the SVG `matrix()` function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Affine([f32; 6]);
+
+impl Affine {
+    const IDENTITY: Affine = Affine([1., 0., 0., 1., 0., 0.]);
+
+    fn apply(&self, [x, y]: Point) -> Point {
+        let m = &self.0;
+        [m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5]]
+    }
+
+    /// The transform applying `other`, then `self`.
+    fn then_apply(&self, other: &Affine) -> Affine {
+        let (a, b) = (&self.0, &other.0);
+        Affine([
+            a[0] * b[0] + a[2] * b[1],
+            a[1] * b[0] + a[3] * b[1],
+            a[0] * b[2] + a[2] * b[3],
+            a[1] * b[2] + a[3] * b[3],
+            a[0] * b[4] + a[2] * b[5] + a[4],
+            a[1] * b[4] + a[3] * b[5] + a[5],
+        ])
+    }
+
+    /// How much the transform scales lengths, on average.
+    fn scale(&self) -> f32 {
+        let m = &self.0;
+        (m[0] * m[3] - m[1] * m[2]).abs().sqrt()
+    }
+}
+
+/// A segment of a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+impl Segment {
+    fn transformed(self, transform: &Affine) -> Segment {
+        let t = |p| transform.apply(p);
+        match self {
+            Segment::MoveTo(p) => Segment::MoveTo(t(p)),
+            Segment::LineTo(p) => Segment::LineTo(t(p)),
+            Segment::QuadTo(c, p) => Segment::QuadTo(t(c), t(p)),
+            Segment::CubicTo(c1, c2, p) => Segment::CubicTo(t(c1), t(c2), t(p)),
+            Segment::Close => Segment::Close,
+        }
+    }
+}
+
+/// How a shape is filled or stroked.
+#[derive(Debug, Clone, PartialEq)]
+enum Paint {
+    None,
+    /// An sRGB color with straight alpha.
+    Color([f32; 4]),
+    /// The id of a gradient.
+    Gradient(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum GradientKind {
+    /// `x1`, `y1`, `x2` and `y2`.
+    Linear([f32; 4]),
+    /// `cx`, `cy` and `r`.
+    Radial([f32; 3]),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Gradient {
+    kind: GradientKind,
+    /// Whether the coordinates are relative to the bounding box of the shape.
+    bbox_units: bool,
+    transform: Affine,
+    /// The offsets and sRGB colors of the stops, sorted by offset.
+    stops: Vec<(f32, [f32; 4])>,
+    /// The gradient the stops are taken from, if this one has none.
+    href: Option<String>,
+}
+
+/// A shape, with its segments in the coordinates of the root element.
+#[derive(Debug, Clone, PartialEq)]
+struct Shape {
+    segments: Vec<Segment>,
+    /// The transform from the coordinates of the shape to the ones of the root element.
+    transform: Affine,
+    fill: Paint,
+    fill_opacity: f32,
+    even_odd: bool,
+    stroke: Paint,
+    stroke_opacity: f32,
+    /// The stroke width in the coordinates of the root element.
+    stroke_width: f32,
+    line_cap: LineCap,
+}
+
+/// The inherited presentation attributes of an element.
+#[derive(Debug, Clone)]
+struct Style {
+    transform: Affine,
+    fill: Paint,
+    fill_opacity: f32,
+    even_odd: bool,
+    stroke: Paint,
+    stroke_opacity: f32,
+    stroke_width: f32,
+    line_cap: LineCap,
+    opacity: f32,
+    /// Whether the element is not rendered, like the content of `<defs>`.
+    hidden: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            transform: Affine::IDENTITY,
+            fill: Paint::Color([0., 0., 0., 1.]),
+            fill_opacity: 1.,
+            even_odd: false,
+            stroke: Paint::None,
+            stroke_opacity: 1.,
+            stroke_width: 1.,
+            line_cap: LineCap::Butt,
+            opacity: 1.,
+            hidden: false,
+        }
+    }
+}
+
+/// A parsed SVG image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgImage {
+    /// The size of the image, from the `width` and `height` of the root element.
+    size: [f32; 2],
+    /// The `x`, `y`, `width` and `height` of the view box.
+    view_box: [f32; 4],
+    shapes: Vec<Shape>,
+    gradients: HashMap<String, Gradient>,
+}
+
+impl SvgImage {
+    /// Parses the supported subset of an SVG document.
+    pub fn parse(text: &str) -> Result<SvgImage, Error> {
+        Parser::default().parse(text)
+    }
+
+    /// The size of the image given by the document, in pixels.
+    pub fn size(&self) -> [f32; 2] {
+        self.size
+    }
+
+    /// Rasterises the image into `width` by `height` sRGB pixels with straight alpha, from the
+    /// bottom row to the top one like the textures of amethyst. The image keeps its aspect ratio
+    /// and is centered, as with the default `preserveAspectRatio` of SVG.
+    pub fn rasterize(&self, width: u32, height: u32) -> Vec<[u8; 4]> {
+        let (w, h) = (width as usize, height as usize);
+        // Premultiplied sRGB.
+        let mut pixels = vec![[0.0f32; 4]; w * h];
+
+        let [vx, vy, vw, vh] = self.view_box;
+        let scale = (width as f32 / vw).min(height as f32 / vh);
+        let view = Affine([
+            scale,
+            0.,
+            0.,
+            scale,
+            (width as f32 - vw * scale) / 2. - vx * scale,
+            (height as f32 - vh * scale) / 2. - vy * scale,
+        ]);
+
+        for shape in &self.shapes {
+            let segments = shape
+                .segments
+                .iter()
+                .map(|segment| segment.transformed(&view))
+                .collect::<Vec<_>>();
+            let polylines = flatten(&segments);
+            let transform = view.then_apply(&shape.transform);
+            if let Some(paint) = self.resolve(&shape.fill, &polylines, &transform) {
+                let polygons = polylines
+                    .iter()
+                    .map(|(points, _)| points.clone())
+                    .collect::<Vec<_>>();
+                let coverage = coverage(&polygons, w, h, shape.even_odd);
+                composite(&mut pixels, &coverage, &paint, shape.fill_opacity, w);
+            }
+            let stroke_width = shape.stroke_width * scale;
+            if stroke_width > 0. {
+                if let Some(paint) = self.resolve(&shape.stroke, &polylines, &transform) {
+                    let polygons = stroke(&polylines, stroke_width / 2., shape.line_cap);
+                    let coverage = coverage(&polygons, w, h, false);
+                    composite(&mut pixels, &coverage, &paint, shape.stroke_opacity, w);
+                }
+            }
+        }
+
+        let mut data = Vec::with_capacity(w * h);
+        for row in pixels.chunks(w.max(1)).rev() {
+            data.extend(row.iter().map(|&[r, g, b, a]| {
+                let byte = |c: f32| (c.max(0.).min(1.) * 255.).round() as u8;
+                if a > 0. {
+                    [byte(r / a), byte(g / a), byte(b / a), byte(a)]
+                } else {
+                    [0, 0, 0, 0]
+                }
+            }));
+        }
+        data
+    }
+
+    /// Resolves `paint` for a shape made of `polylines` in pixel space, where `transform` moves
+    /// the coordinates of the shape to pixel space.
+    fn resolve(
+        &self,
+        paint: &Paint,
+        polylines: &[(Vec<Point>, bool)],
+        transform: &Affine,
+    ) -> Option<PixelPaint> {
+        match paint {
+            Paint::None => None,
+            Paint::Color(color) => Some(PixelPaint::Color(*color)),
+            Paint::Gradient(id) => {
+                let gradient = self.gradients.get(id)?;
+                let stops = self.stops(gradient);
+                match stops.len() {
+                    0 => return None,
+                    1 => return Some(PixelPaint::Color(stops[0].1)),
+                    _ => {}
+                }
+                // The transform from the coordinates of the gradient to pixel space.
+                let to_pixels = if gradient.bbox_units {
+                    let [min_x, min_y, max_x, max_y] = bounds(polylines)?;
+                    Affine([max_x - min_x, 0., 0., max_y - min_y, min_x, min_y])
+                } else {
+                    *transform
+                }
+                .then_apply(&gradient.transform);
+                Some(match gradient.kind {
+                    GradientKind::Linear([x1, y1, x2, y2]) => PixelPaint::Linear {
+                        start: to_pixels.apply([x1, y1]),
+                        end: to_pixels.apply([x2, y2]),
+                        stops,
+                    },
+                    GradientKind::Radial([cx, cy, r]) => PixelPaint::Radial {
+                        center: to_pixels.apply([cx, cy]),
+                        radius: r * to_pixels.scale(),
+                        stops,
+                    },
+                })
+            }
+        }
+    }
+
+    /// The stops of `gradient`, or of the gradient it references if it has none.
+    fn stops(&self, gradient: &Gradient) -> Vec<(f32, [f32; 4])> {
+        let mut gradient = gradient;
+        // Bounded, in case the references are cyclic.
+        for _ in 0..8 {
+            if !gradient.stops.is_empty() {
+                break;
+            }
+            match gradient.href.as_ref().and_then(|id| self.gradients.get(id)) {
+                Some(referenced) => gradient = referenced,
+                None => break,
+            }
+        }
+        gradient.stops.clone()
+    }
+}
+
+/// A paint resolved in pixel space.
+#[derive(Debug, Clone)]
+enum PixelPaint {
+    Color([f32; 4]),
+    Linear {
+        start: Point,
+        end: Point,
+        stops: Vec<(f32, [f32; 4])>,
+    },
+    Radial {
+        center: Point,
+        radius: f32,
+        stops: Vec<(f32, [f32; 4])>,
+    },
+}
+
+impl PixelPaint {
+    /// The sRGB color with straight alpha at `point`.
+    fn color_at(&self, point: Point) -> [f32; 4] {
+        match self {
+            PixelPaint::Color(color) => *color,
+            PixelPaint::Linear { start, end, stops } => {
+                let d = [end[0] - start[0], end[1] - start[1]];
+                let length = d[0] * d[0] + d[1] * d[1];
+                let t = if length > 0. {
+                    ((point[0] - start[0]) * d[0] + (point[1] - start[1]) * d[1]) / length
+                } else {
+                    1.
+                };
+                stop_color(stops, t)
+            }
+            PixelPaint::Radial {
+                center,
+                radius,
+                stops,
+            } => {
+                let d = [point[0] - center[0], point[1] - center[1]];
+                let t = if *radius > 0. {
+                    (d[0] * d[0] + d[1] * d[1]).sqrt() / radius
+                } else {
+                    1.
+                };
+                stop_color(stops, t)
+            }
+        }
+    }
+}
+
+/// The color of the gradient of `stops` at `t`, padded outside of the stops.
+fn stop_color(stops: &[(f32, [f32; 4])], t: f32) -> [f32; 4] {
+    let first = stops[0];
+    if t <= first.0 {
+        return first.1;
+    }
+    for pair in stops.windows(2) {
+        let ((start, from), (end, to)) = (pair[0], pair[1]);
+        if t <= end {
+            let f = if end > start {
+                (t - start) / (end - start)
+            } else {
+                1.
+            };
+            let mut color = [0.; 4];
+            for i in 0..4 {
+                color[i] = from[i] + (to[i] - from[i]) * f;
+            }
+            return color;
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+/// Blends `paint` over premultiplied `pixels` where the shape covers them.
+fn composite(
+    pixels: &mut [[f32; 4]],
+    coverage: &[f32],
+    paint: &PixelPaint,
+    opacity: f32,
+    width: usize,
+) {
+    for (index, (pixel, &covered)) in pixels.iter_mut().zip(coverage.iter()).enumerate() {
+        if covered <= 0. {
+            continue;
+        }
+        let point = [(index % width) as f32 + 0.5, (index / width) as f32 + 0.5];
+        let [r, g, b, a] = paint.color_at(point);
+        let alpha = a * opacity * covered.min(1.);
+        for (channel, source) in pixel
+            .iter_mut()
+            .zip([r * alpha, g * alpha, b * alpha, alpha].iter())
+        {
+            *channel = source + *channel * (1. - alpha);
+        }
+    }
+}
+
+/// The bounds `[min_x, min_y, max_x, max_y]` of `polylines`.
+fn bounds(polylines: &[(Vec<Point>, bool)]) -> Option<[f32; 4]> {
+    let mut points = polylines.iter().flat_map(|(points, _)| points.iter());
+    let first = points.next()?;
+    Some(points.fold(
+        [first[0], first[1], first[0], first[1]],
+        |[min_x, min_y, max_x, max_y], p| {
+            [
+                min_x.min(p[0]),
+                min_y.min(p[1]),
+                max_x.max(p[0]),
+                max_y.max(p[1]),
+            ]
+        },
+    ))
+}
+
+/// Flattens `segments` to polylines, and whether each of them is closed.
+fn flatten(segments: &[Segment]) -> Vec<(Vec<Point>, bool)> {
+    let mut polylines = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+    let mut finish = |current: &mut Vec<Point>, closed: bool| {
+        if current.len() > 1 {
+            polylines.push((std::mem::take(current), closed));
+        } else {
+            current.clear();
+        }
+    };
+    for segment in segments {
+        let last = current.last().cloned().unwrap_or([0., 0.]);
+        match *segment {
+            Segment::MoveTo(p) => {
+                finish(&mut current, false);
+                current.push(p);
+            }
+            Segment::LineTo(p) => current.push(p),
+            Segment::QuadTo(c, p) => {
+                let n = subdivisions(&[last, c, p]);
+                for i in 1..=n {
+                    let t = i as f32 / n as f32;
+                    let u = 1. - t;
+                    current.push(mix(&[(u * u, last), (2. * u * t, c), (t * t, p)]));
+                }
+            }
+            Segment::CubicTo(c1, c2, p) => {
+                let n = subdivisions(&[last, c1, c2, p]);
+                for i in 1..=n {
+                    let t = i as f32 / n as f32;
+                    let u = 1. - t;
+                    current.push(mix(&[
+                        (u * u * u, last),
+                        (3. * u * u * t, c1),
+                        (3. * u * t * t, c2),
+                        (t * t * t, p),
+                    ]));
+                }
+            }
+            Segment::Close => {
+                let start = current.first().cloned();
+                finish(&mut current, true);
+                // Drawing can go on from the start of the closed path.
+                if let Some(start) = start {
+                    current.push(start);
+                }
+            }
+        }
+        if current.is_empty() {
+            current.push(last);
+        }
+    }
+    finish(&mut current, false);
+    polylines
+}
+
+/// The number of lines a curve with these control points is flattened to.
+fn subdivisions(points: &[Point]) -> usize {
+    let bend = points
+        .windows(3)
+        .map(|w| {
+            let d = [
+                w[0][0] - 2. * w[1][0] + w[2][0],
+                w[0][1] - 2. * w[1][1] + w[2][1],
+            ];
+            (d[0] * d[0] + d[1] * d[1]).sqrt()
+        })
+        .fold(0., f32::max);
+    ((bend / TOLERANCE).sqrt().ceil() as usize).max(1).min(64)
+}
+
+fn mix(weighted: &[(f32, Point)]) -> Point {
+    weighted.iter().fold([0., 0.], |sum, (weight, p)| {
+        [sum[0] + weight * p[0], sum[1] + weight * p[1]]
+    })
+}
+
+/// The polygons covering the stroke of `polylines`, all turning the same way so that their union
+/// is filled with the non-zero rule. Joins are round.
+fn stroke(polylines: &[(Vec<Point>, bool)], half_width: f32, line_cap: LineCap) -> Vec<Vec<Point>> {
+    let mut polygons = Vec::new();
+    for (points, closed) in polylines {
+        let mut points = points.clone();
+        if *closed && points.first() != points.last() {
+            let first = points[0];
+            points.push(first);
+        }
+        let count = points.len();
+        for (i, pair) in points.windows(2).enumerate() {
+            let (mut p, mut q) = (pair[0], pair[1]);
+            let d = [q[0] - p[0], q[1] - p[1]];
+            let length = (d[0] * d[0] + d[1] * d[1]).sqrt();
+            if length <= 0. {
+                continue;
+            }
+            let d = [d[0] / length * half_width, d[1] / length * half_width];
+            if line_cap == LineCap::Square && !*closed {
+                if i == 0 {
+                    p = [p[0] - d[0], p[1] - d[1]];
+                }
+                if i + 2 == count {
+                    q = [q[0] + d[0], q[1] + d[1]];
+                }
+            }
+            let n = [-d[1], d[0]];
+            polygons.push(vec![
+                [p[0] + n[0], p[1] + n[1]],
+                [q[0] + n[0], q[1] + n[1]],
+                [q[0] - n[0], q[1] - n[1]],
+                [p[0] - n[0], p[1] - n[1]],
+            ]);
+        }
+        for (i, point) in points.iter().enumerate() {
+            let end = !*closed && (i == 0 || i + 1 == count);
+            if !end || line_cap == LineCap::Round {
+                polygons.push(circle(*point, half_width));
+            }
+        }
+    }
+    for polygon in &mut polygons {
+        if signed_area(polygon) < 0. {
+            polygon.reverse();
+        }
+    }
+    polygons
+}
+
+fn circle(center: Point, radius: f32) -> Vec<Point> {
+    let sides = ((radius * PI).ceil() as usize).max(8).min(32);
+    (0..sides)
+        .map(|i| {
+            let angle = i as f32 / sides as f32 * 2. * PI;
+            [
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+            ]
+        })
+        .collect()
+}
+
+fn signed_area(polygon: &[Point]) -> f32 {
+    let mut area = 0.;
+    for i in 0..polygon.len() {
+        let (p, q) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        area += p[0] * q[1] - q[0] * p[1];
+    }
+    area / 2.
+}
+
+/// How much each of the `width` by `height` pixels is covered by `polygons`, from the top row.
+fn coverage(polygons: &[Vec<Point>], width: usize, height: usize, even_odd: bool) -> Vec<f32> {
+    // The edges going down, with +1, and up, with -1.
+    let mut edges = Vec::new();
+    for polygon in polygons {
+        for i in 0..polygon.len() {
+            let (p, q) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            if p[1] < q[1] {
+                edges.push((p, q, 1));
+            } else if p[1] > q[1] {
+                edges.push((q, p, -1));
+            }
+        }
+    }
+
+    let mut coverage = vec![0.; width * height];
+    let weight = 1. / SAMPLES as f32;
+    let mut crossings = Vec::new();
+    for row in 0..height {
+        let pixels = &mut coverage[row * width..(row + 1) * width];
+        for sample in 0..SAMPLES {
+            let y = row as f32 + (sample as f32 + 0.5) * weight;
+            crossings.clear();
+            for &(p, q, direction) in &edges {
+                if p[1] <= y && y < q[1] {
+                    let x = p[0] + (y - p[1]) * (q[0] - p[0]) / (q[1] - p[1]);
+                    crossings.push((x, direction));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = if even_odd {
+                    winding % 2 != 0
+                } else {
+                    winding != 0
+                };
+                if inside {
+                    add_span(pixels, pair[0].0, pair[1].0, weight);
+                }
+            }
+        }
+    }
+    coverage
+}
+
+/// Adds `weight` times the part of each pixel of the row between `start` and `end`.
+fn add_span(pixels: &mut [f32], start: f32, end: f32, weight: f32) {
+    let width = pixels.len() as f32;
+    let (start, end) = (start.max(0.).min(width), end.max(0.).min(width));
+    if start >= end {
+        return;
+    }
+    let (first, last) = (start.floor() as usize, end.floor() as usize);
+    if first == last {
+        pixels[first] += (end - start) * weight;
+        return;
+    }
+    pixels[first] += (first as f32 + 1. - start) * weight;
+    for pixel in &mut pixels[first + 1..last] {
+        *pixel += weight;
+    }
+    if last < pixels.len() {
+        pixels[last] += (end - last as f32) * weight;
+    }
+}
+
+/// A tag of an XML document.
+#[derive(Debug)]
+struct Tag<'a> {
+    /// The name without namespace prefix.
+    name: &'a str,
+    attributes: Vec<(&'a str, String)>,
+    closing: bool,
+    self_closing: bool,
+}
+
+impl<'a> Tag<'a> {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number(&self, name: &str, default: f32) -> f32 {
+        self.attribute(name).and_then(length).unwrap_or(default)
+    }
+}
+
+/// Splits an XML document in tags, skipping the text, comments, declarations and instructions.
+fn tags(text: &str) -> Result<Vec<Tag<'_>>, Error> {
+    let mut tags = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let skip_to = |rest: &str, end: &str| {
+            rest.find(end)
+                .map(|index| index + end.len())
+                .ok_or_else(|| format_err!("Unterminated XML markup"))
+        };
+        if rest.starts_with("<!--") {
+            rest = &rest[skip_to(rest, "-->")?..];
+            continue;
+        }
+        if rest.starts_with("<![CDATA[") {
+            rest = &rest[skip_to(rest, "]]>")?..];
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[skip_to(rest, ">")?..];
+            continue;
+        }
+
+        let closing = rest.starts_with("</");
+        let mut body = &rest[if closing { 2 } else { 1 }..];
+        let name_end = body
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .ok_or_else(|| format_err!("Unterminated XML tag"))?;
+        let full_name = &body[..name_end];
+        let name = full_name.rsplit(':').next().unwrap_or(full_name);
+        body = &body[name_end..];
+
+        let mut attributes = Vec::new();
+        let mut self_closing = false;
+        loop {
+            body = body.trim_start();
+            if body.starts_with("/>") {
+                self_closing = true;
+                body = &body[2..];
+                break;
+            }
+            if body.starts_with('>') {
+                body = &body[1..];
+                break;
+            }
+            let equals = body
+                .find('=')
+                .ok_or_else(|| format_err!("Invalid XML attribute in <{}>", full_name))?;
+            let attribute = body[..equals].trim();
+            body = body[equals + 1..].trim_start();
+            let quote = body
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| format_err!("Unquoted XML attribute {}", attribute))?;
+            let value_end = body[1..]
+                .find(quote)
+                .ok_or_else(|| format_err!("Unterminated XML attribute {}", attribute))?;
+            attributes.push((attribute, unescape(&body[1..=value_end])));
+            body = &body[value_end + 2..];
+        }
+        tags.push(Tag {
+            name,
+            attributes,
+            closing,
+            self_closing,
+        });
+        rest = body;
+    }
+    Ok(tags)
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Builds an `SvgImage` from the tags of a document.
+#[derive(Debug, Default)]
+struct Parser {
+    size: Option<[f32; 2]>,
+    view_box: Option<[f32; 4]>,
+    shapes: Vec<Shape>,
+    gradients: HashMap<String, Gradient>,
+}
+
+impl Parser {
+    fn parse(mut self, text: &str) -> Result<SvgImage, Error> {
+        let tags = tags(text)?;
+        // The styles of the open elements, and the gradient whose stops are being read.
+        let mut styles: Vec<Style> = Vec::new();
+        let mut gradient: Option<String> = None;
+        for tag in &tags {
+            if tag.closing {
+                styles.pop();
+                if tag.name.ends_with("Gradient") {
+                    gradient = None;
+                }
+                continue;
+            }
+            if styles.is_empty() && tag.name != "svg" {
+                return Err(format_err!("The root element is <{}>, not <svg>", tag.name));
+            }
+            let parent = styles.last().cloned().unwrap_or_default();
+            let style = self.element(tag, &parent, &mut gradient);
+            if !tag.self_closing {
+                styles.push(style);
+            }
+        }
+
+        let view_box = self
+            .view_box
+            .or_else(|| self.size.map(|[w, h]| [0., 0., w, h]));
+        let view_box = view_box
+            .filter(|[_, _, w, h]| *w > 0. && *h > 0.)
+            .ok_or_else(|| format_err!("The SVG image has no size nor view box"))?;
+        Ok(SvgImage {
+            size: self.size.unwrap_or([view_box[2], view_box[3]]),
+            view_box,
+            shapes: self.shapes,
+            gradients: self.gradients,
+        })
+    }
+
+    /// Reads an element, and returns the style of its children.
+    fn element(&mut self, tag: &Tag<'_>, parent: &Style, gradient: &mut Option<String>) -> Style {
+        let mut style = parent.clone();
+        apply_style(tag, &mut style);
+        match tag.name {
+            "svg" if self.view_box.is_none() && self.size.is_none() => {
+                let width = tag.attribute("width").and_then(length);
+                let height = tag.attribute("height").and_then(length);
+                self.view_box = tag.attribute("viewBox").and_then(|text| {
+                    let numbers = numbers(text);
+                    if numbers.len() == 4 {
+                        Some([numbers[0], numbers[1], numbers[2], numbers[3]])
+                    } else {
+                        None
+                    }
+                });
+                self.size = match (width, height, self.view_box) {
+                    (Some(w), Some(h), _) => Some([w, h]),
+                    // A missing dimension keeps the aspect ratio of the view box.
+                    (Some(w), None, Some([_, _, vw, vh])) => Some([w, w * vh / vw]),
+                    (None, Some(h), Some([_, _, vw, vh])) => Some([h * vw / vh, h]),
+                    _ => None,
+                };
+            }
+            "defs" | "clipPath" | "mask" | "symbol" | "pattern" | "marker" | "text" | "style"
+            | "title" | "desc" | "metadata" | "switch" => style.hidden = true,
+            "linearGradient" | "radialGradient" => {
+                style.hidden = true;
+                if let Some(id) = tag.attribute("id") {
+                    self.gradients.insert(id.to_string(), read_gradient(tag));
+                    if !tag.self_closing {
+                        *gradient = Some(id.to_string());
+                    }
+                }
+            }
+            "stop" => {
+                if let Some(gradient) = gradient.as_ref().and_then(|id| self.gradients.get_mut(id))
+                {
+                    let (offset, color) = read_stop(tag);
+                    // Offsets never decrease.
+                    let offset = gradient
+                        .stops
+                        .last()
+                        .map_or(offset, |(last, _)| offset.max(*last));
+                    gradient.stops.push((offset, color));
+                }
+            }
+            _ => {
+                if let Some(segments) = shape_segments(tag) {
+                    if !style.hidden {
+                        self.push_shape(segments, &style);
+                    }
+                }
+            }
+        }
+        style
+    }
+
+    fn push_shape(&mut self, segments: Vec<Segment>, style: &Style) {
+        let transform = style.transform;
+        self.shapes.push(Shape {
+            segments: segments
+                .into_iter()
+                .map(|segment| segment.transformed(&transform))
+                .collect(),
+            transform,
+            fill: style.fill.clone(),
+            fill_opacity: style.fill_opacity * style.opacity,
+            even_odd: style.even_odd,
+            stroke: style.stroke.clone(),
+            stroke_opacity: style.stroke_opacity * style.opacity,
+            stroke_width: style.stroke_width * transform.scale(),
+            line_cap: style.line_cap,
+        });
+    }
+}
+
+/// Applies the presentation attributes of `tag`, then its `style` attribute.
+fn apply_style(tag: &Tag<'_>, style: &mut Style) {
+    if let Some(transform) = tag.attribute("transform") {
+        style.transform = style.transform.then_apply(&parse_transform(transform));
+    }
+    let mut properties = tag
+        .attributes
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect::<Vec<_>>();
+    if let Some(declarations) = tag.attribute("style") {
+        properties.extend(declarations.split(';').filter_map(|declaration| {
+            let mut parts = declaration.splitn(2, ':');
+            Some((parts.next()?.trim(), parts.next()?.trim()))
+        }));
+    }
+    for (name, value) in properties {
+        match name {
+            "fill" => style.fill = parse_paint(value).unwrap_or_else(|| style.fill.clone()),
+            "stroke" => style.stroke = parse_paint(value).unwrap_or_else(|| style.stroke.clone()),
+            "fill-opacity" => style.fill_opacity = opacity(value).unwrap_or(style.fill_opacity),
+            "stroke-opacity" => {
+                style.stroke_opacity = opacity(value).unwrap_or(style.stroke_opacity)
+            }
+            "opacity" => style.opacity *= opacity(value).unwrap_or(1.),
+            "stroke-width" => style.stroke_width = length(value).unwrap_or(style.stroke_width),
+            "fill-rule" => style.even_odd = value == "evenodd",
+            "stroke-linecap" => {
+                style.line_cap = match value {
+                    "round" => LineCap::Round,
+                    "square" => LineCap::Square,
+                    _ => LineCap::Butt,
+                }
+            }
+            "display" if value == "none" => style.hidden = true,
+            "visibility" if value == "hidden" => style.hidden = true,
+            _ => {}
+        }
+    }
+}
+
+fn read_gradient(tag: &Tag<'_>) -> Gradient {
+    let bbox_units = tag.attribute("gradientUnits") != Some("userSpaceOnUse");
+    // Percentages are read as fractions, which is only right relative to the bounding box.
+    let coordinate = |name: &str, default: f32| {
+        tag.attribute(name)
+            .and_then(|value| percentage(value).or_else(|| length(value)))
+            .unwrap_or(default)
+    };
+    let kind = if tag.name == "linearGradient" {
+        GradientKind::Linear([
+            coordinate("x1", 0.),
+            coordinate("y1", 0.),
+            coordinate("x2", 1.),
+            coordinate("y2", 0.),
+        ])
+    } else {
+        GradientKind::Radial([
+            coordinate("cx", 0.5),
+            coordinate("cy", 0.5),
+            coordinate("r", 0.5),
+        ])
+    };
+    Gradient {
+        kind,
+        bbox_units,
+        transform: tag
+            .attribute("gradientTransform")
+            .map_or(Affine::IDENTITY, parse_transform),
+        stops: Vec::new(),
+        href: tag
+            .attribute("href")
+            .or_else(|| tag.attribute("xlink:href"))
+            .map(|href| href.trim_start_matches('#').to_string()),
+    }
+}
+
+fn read_stop(tag: &Tag<'_>) -> (f32, [f32; 4]) {
+    let mut color = [0., 0., 0., 1.];
+    let mut stop_opacity = 1.;
+    let mut offset = 0.;
+    let style = tag
+        .attribute("style")
+        .map(|declarations| {
+            declarations
+                .split(';')
+                .filter_map(|declaration| {
+                    let mut parts = declaration.splitn(2, ':');
+                    Some((parts.next()?.trim(), parts.next()?.trim()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let properties = tag
+        .attributes
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .chain(style);
+    for (name, value) in properties {
+        match name {
+            "offset" => offset = opacity(value).unwrap_or(offset),
+            "stop-color" => color = parse_color(value).unwrap_or(color),
+            "stop-opacity" => stop_opacity = opacity(value).unwrap_or(stop_opacity),
+            _ => {}
+        }
+    }
+    color[3] *= stop_opacity;
+    (offset, color)
+}
+
+/// The segments of a shape element, or `None` if `tag` is not a shape.
+fn shape_segments(tag: &Tag<'_>) -> Option<Vec<Segment>> {
+    let segments = match tag.name {
+        "path" => parse_path(tag.attribute("d").unwrap_or("")),
+        "rect" => {
+            let (x, y) = (tag.number("x", 0.), tag.number("y", 0.));
+            let (w, h) = (tag.number("width", 0.), tag.number("height", 0.));
+            let rx = tag.attribute("rx").and_then(length);
+            let ry = tag.attribute("ry").and_then(length);
+            let (rx, ry) = match (rx, ry) {
+                (Some(rx), Some(ry)) => (rx, ry),
+                (Some(r), None) | (None, Some(r)) => (r, r),
+                (None, None) => (0., 0.),
+            };
+            rect(x, y, w, h, rx.min(w / 2.), ry.min(h / 2.))
+        }
+        "circle" => {
+            let r = tag.number("r", 0.);
+            ellipse(tag.number("cx", 0.), tag.number("cy", 0.), r, r)
+        }
+        "ellipse" => ellipse(
+            tag.number("cx", 0.),
+            tag.number("cy", 0.),
+            tag.number("rx", 0.),
+            tag.number("ry", 0.),
+        ),
+        "line" => vec![
+            Segment::MoveTo([tag.number("x1", 0.), tag.number("y1", 0.)]),
+            Segment::LineTo([tag.number("x2", 0.), tag.number("y2", 0.)]),
+        ],
+        "polyline" | "polygon" => {
+            let numbers = numbers(tag.attribute("points").unwrap_or(""));
+            let mut segments = numbers
+                .chunks_exact(2)
+                .enumerate()
+                .map(|(i, p)| {
+                    if i == 0 {
+                        Segment::MoveTo([p[0], p[1]])
+                    } else {
+                        Segment::LineTo([p[0], p[1]])
+                    }
+                })
+                .collect::<Vec<_>>();
+            if tag.name == "polygon" {
+                segments.push(Segment::Close);
+            }
+            segments
+        }
+        _ => return None,
+    };
+    Some(segments)
+}
+
+fn rect(x: f32, y: f32, w: f32, h: f32, rx: f32, ry: f32) -> Vec<Segment> {
+    if w <= 0. || h <= 0. {
+        return Vec::new();
+    }
+    if rx <= 0. || ry <= 0. {
+        return vec![
+            Segment::MoveTo([x, y]),
+            Segment::LineTo([x + w, y]),
+            Segment::LineTo([x + w, y + h]),
+            Segment::LineTo([x, y + h]),
+            Segment::Close,
+        ];
+    }
+    let mut segments = vec![Segment::MoveTo([x + rx, y])];
+    let corner = |from: Point, to: Point, segments: &mut Vec<Segment>| {
+        arc_to_cubics(from, rx, ry, 0., false, true, to, segments);
+    };
+    segments.push(Segment::LineTo([x + w - rx, y]));
+    corner([x + w - rx, y], [x + w, y + ry], &mut segments);
+    segments.push(Segment::LineTo([x + w, y + h - ry]));
+    corner([x + w, y + h - ry], [x + w - rx, y + h], &mut segments);
+    segments.push(Segment::LineTo([x + rx, y + h]));
+    corner([x + rx, y + h], [x, y + h - ry], &mut segments);
+    segments.push(Segment::LineTo([x, y + ry]));
+    corner([x, y + ry], [x + rx, y], &mut segments);
+    segments.push(Segment::Close);
+    segments
+}
+
+fn ellipse(cx: f32, cy: f32, rx: f32, ry: f32) -> Vec<Segment> {
+    if rx <= 0. || ry <= 0. {
+        return Vec::new();
+    }
+    let mut segments = vec![Segment::MoveTo([cx + rx, cy])];
+    arc_to_cubics(
+        [cx + rx, cy],
+        rx,
+        ry,
+        0.,
+        false,
+        true,
+        [cx - rx, cy],
+        &mut segments,
+    );
+    arc_to_cubics(
+        [cx - rx, cy],
+        rx,
+        ry,
+        0.,
+        false,
+        true,
+        [cx + rx, cy],
+        &mut segments,
+    );
+    segments.push(Segment::Close);
+    segments
+}
+
+/// Parses the `d` attribute of a path. Arcs are converted to cubic curves, and parsing stops at
+/// the first error, as specified by SVG.
+fn parse_path(d: &str) -> Vec<Segment> {
+    let mut scanner = Scanner::new(d);
+    let mut segments = Vec::new();
+    let (mut current, mut start) = ([0., 0.], [0., 0.]);
+    // The last control point of a curve, reflected by the smooth curves.
+    let mut last_control: Option<(u8, Point)> = None;
+    let mut command = None;
+    while !scanner.at_end() {
+        let next = match scanner.command() {
+            Some(next) => next,
+            // Commands repeat, a move being followed by lines.
+            None => match command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(previous) => previous,
+                None => break,
+            },
+        };
+        command = Some(next);
+        let relative = next.is_ascii_lowercase();
+        let offset = if relative { current } else { [0., 0.] };
+        let point = |scanner: &mut Scanner<'_>| -> Option<Point> {
+            Some([scanner.number()? + offset[0], scanner.number()? + offset[1]])
+        };
+        let upper = next.to_ascii_uppercase();
+        let parsed = match upper {
+            b'M' => point(&mut scanner).map(|p| {
+                start = p;
+                (Segment::MoveTo(p), p, None)
+            }),
+            b'L' => point(&mut scanner).map(|p| (Segment::LineTo(p), p, None)),
+            b'H' => scanner.number().map(|x| {
+                let p = [x + offset[0], current[1]];
+                (Segment::LineTo(p), p, None)
+            }),
+            b'V' => scanner.number().map(|y| {
+                let p = [current[0], y + offset[1]];
+                (Segment::LineTo(p), p, None)
+            }),
+            b'C' => (|| {
+                let c1 = point(&mut scanner)?;
+                let c2 = point(&mut scanner)?;
+                let p = point(&mut scanner)?;
+                Some((Segment::CubicTo(c1, c2, p), p, Some((b'C', c2))))
+            })(),
+            b'S' => (|| {
+                let c1 = reflect(last_control, b'C', current);
+                let c2 = point(&mut scanner)?;
+                let p = point(&mut scanner)?;
+                Some((Segment::CubicTo(c1, c2, p), p, Some((b'C', c2))))
+            })(),
+            b'Q' => (|| {
+                let c = point(&mut scanner)?;
+                let p = point(&mut scanner)?;
+                Some((Segment::QuadTo(c, p), p, Some((b'Q', c))))
+            })(),
+            b'T' => point(&mut scanner).map(|p| {
+                let c = reflect(last_control, b'Q', current);
+                (Segment::QuadTo(c, p), p, Some((b'Q', c)))
+            }),
+            b'A' => (|| {
+                let rx = scanner.number()?;
+                let ry = scanner.number()?;
+                let rotation = scanner.number()?;
+                let large_arc = scanner.flag()?;
+                let sweep = scanner.flag()?;
+                let p = point(&mut scanner)?;
+                let mut arc = Vec::new();
+                arc_to_cubics(current, rx, ry, rotation, large_arc, sweep, p, &mut arc);
+                segments.append(&mut arc);
+                Some((Segment::LineTo(p), p, None))
+            })(),
+            b'Z' => Some((Segment::Close, start, None)),
+            _ => None,
+        };
+        match parsed {
+            Some((segment, end, control)) => {
+                // Arcs are already pushed as curves ending at `end`.
+                if upper != b'A' {
+                    segments.push(segment);
+                }
+                current = end;
+                last_control = control;
+            }
+            None => break,
+        }
+    }
+    segments
+}
+
+/// The reflection of the last control point of a curve of the same `kind` around `current`.
+fn reflect(last_control: Option<(u8, Point)>, kind: u8, current: Point) -> Point {
+    match last_control {
+        Some((last_kind, c)) if last_kind == kind => {
+            [2. * current[0] - c[0], 2. * current[1] - c[1]]
+        }
+        _ => current,
+    }
+}
+
+/// Converts an SVG arc to cubic curves, at most a quarter turn each.
+#[allow(clippy::too_many_arguments)]
+fn arc_to_cubics(
+    from: Point,
+    rx: f32,
+    ry: f32,
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Point,
+    segments: &mut Vec<Segment>,
+) {
+    if from == to {
+        return;
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0. || ry == 0. {
+        segments.push(Segment::LineTo(to));
+        return;
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((from[0] - to[0]) / 2., (from[1] - to[1]) / 2.);
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1. {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1. } else { 1. };
+    let coefficient = sign * (numerator / denominator).max(0.).sqrt();
+    let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+    let cx = cos * cx1 - sin * cy1 + (from[0] + to[0]) / 2.;
+    let cy = sin * cx1 + cos * cy1 + (from[1] + to[1]) / 2.;
+
+    let angle = |u: Point, v: Point| (u[0] * v[1] - u[1] * v[0]).atan2(u[0] * v[0] + u[1] * v[1]);
+    let u = [(x1 - cx1) / rx, (y1 - cy1) / ry];
+    let v = [(-x1 - cx1) / rx, (-y1 - cy1) / ry];
+    let start = angle([1., 0.], u);
+    let mut delta = angle(u, v);
+    if !sweep && delta > 0. {
+        delta -= 2. * PI;
+    } else if sweep && delta < 0. {
+        delta += 2. * PI;
+    }
+
+    let count = (delta.abs() / (PI / 2.)).ceil().max(1.) as usize;
+    let step = delta / count as f32;
+    let k = 4. / 3. * (step / 4.).tan();
+    let point = |t: f32| {
+        let (s, c) = t.sin_cos();
+        [
+            cx + rx * c * cos - ry * s * sin,
+            cy + rx * c * sin + ry * s * cos,
+        ]
+    };
+    let derivative = |t: f32| {
+        let (s, c) = t.sin_cos();
+        [-rx * s * cos - ry * c * sin, -rx * s * sin + ry * c * cos]
+    };
+    for i in 0..count {
+        let (t1, t2) = (start + step * i as f32, start + step * (i + 1) as f32);
+        let (p1, d1) = (point(t1), derivative(t1));
+        let (p2, d2) = (point(t2), derivative(t2));
+        let end = if i + 1 == count { to } else { p2 };
+        segments.push(Segment::CubicTo(
+            [p1[0] + k * d1[0], p1[1] + k * d1[1]],
+            [p2[0] - k * d2[0], p2[1] - k * d2[1]],
+            end,
+        ));
+    }
+}
+
+/// Parses a list of transform functions, applied from the last to the first.
+fn parse_transform(text: &str) -> Affine {
+    let mut transform = Affine::IDENTITY;
+    for function in text.split(')') {
+        let mut parts = function.splitn(2, '(');
+        let (name, arguments) = match (parts.next(), parts.next()) {
+            (Some(name), Some(arguments)) => (name.trim().trim_start_matches(','), arguments),
+            _ => continue,
+        };
+        let a = numbers(arguments);
+        let arg = |i: usize, default: f32| a.get(i).cloned().unwrap_or(default);
+        let next = match name.trim() {
+            "matrix" if a.len() == 6 => Affine([a[0], a[1], a[2], a[3], a[4], a[5]]),
+            "translate" => Affine([1., 0., 0., 1., arg(0, 0.), arg(1, 0.)]),
+            "scale" => {
+                let sx = arg(0, 1.);
+                Affine([sx, 0., 0., arg(1, sx), 0., 0.])
+            }
+            "rotate" => {
+                let (sin, cos) = arg(0, 0.).to_radians().sin_cos();
+                let (cx, cy) = (arg(1, 0.), arg(2, 0.));
+                Affine([1., 0., 0., 1., cx, cy])
+                    .then_apply(&Affine([cos, sin, -sin, cos, 0., 0.]))
+                    .then_apply(&Affine([1., 0., 0., 1., -cx, -cy]))
+            }
+            "skewX" => Affine([1., 0., arg(0, 0.).to_radians().tan(), 1., 0., 0.]),
+            "skewY" => Affine([1., arg(0, 0.).to_radians().tan(), 0., 1., 0., 0.]),
+            _ => continue,
+        };
+        transform = transform.then_apply(&next);
+    }
+    transform
+}
+
+fn parse_paint(text: &str) -> Option<Paint> {
+    let text = text.trim();
+    if text == "none" {
+        return Some(Paint::None);
+    }
+    if text.starts_with("url(") {
+        let end = text.find(')')?;
+        let id = text[4..end].trim().trim_matches(|c| c == '\'' || c == '"');
+        return Some(Paint::Gradient(id.trim_start_matches('#').to_string()));
+    }
+    if text == "currentColor" {
+        return Some(Paint::Color([0., 0., 0., 1.]));
+    }
+    parse_color(text).map(Paint::Color)
+}
+
+/// Parses a color to sRGB with straight alpha.
+fn parse_color(text: &str) -> Option<[f32; 4]> {
+    let text = text.trim();
+    if text.starts_with('#') {
+        let hex = &text[1..];
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..=i)?, 16).ok();
+        return match hex.len() {
+            3 => Some([
+                f32::from(digit(0)? * 17) / 255.,
+                f32::from(digit(1)? * 17) / 255.,
+                f32::from(digit(2)? * 17) / 255.,
+                1.,
+            ]),
+            6 => {
+                let byte = |i: usize| Some(f32::from(digit(i)? * 16 + digit(i + 1)?) / 255.);
+                Some([byte(0)?, byte(2)?, byte(4)?, 1.])
+            }
+            _ => None,
+        };
+    }
+    if text.starts_with("rgb") {
+        let start = text.find('(')?;
+        let end = text.find(')')?;
+        let channels = text[start + 1..end]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                if part.ends_with('%') {
+                    percentage(part)
+                } else {
+                    part.parse::<f32>().ok().map(|c| c / 255.)
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if channels.len() < 3 {
+            return None;
+        }
+        let alpha = text[start + 1..end]
+            .split(',')
+            .nth(3)
+            .and_then(opacity)
+            .unwrap_or(1.);
+        return Some([channels[0], channels[1], channels[2], alpha]);
+    }
+    let rgb = match text.to_ascii_lowercase().as_str() {
+        "black" => 0x00_00_00,
+        "white" => 0xff_ff_ff,
+        "red" => 0xff_00_00,
+        "green" => 0x00_80_00,
+        "lime" => 0x00_ff_00,
+        "blue" => 0x00_00_ff,
+        "yellow" => 0xff_ff_00,
+        "cyan" | "aqua" => 0x00_ff_ff,
+        "magenta" | "fuchsia" => 0xff_00_ff,
+        "gray" | "grey" => 0x80_80_80,
+        "silver" => 0xc0_c0_c0,
+        "maroon" => 0x80_00_00,
+        "olive" => 0x80_80_00,
+        "teal" => 0x00_80_80,
+        "navy" => 0x00_00_80,
+        "purple" => 0x80_00_80,
+        "orange" => 0xff_a5_00,
+        "transparent" => return Some([0., 0., 0., 0.]),
+        _ => return None,
+    };
+    let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.;
+    Some([channel(16), channel(8), channel(0), 1.])
+}
+
+/// Parses an opacity or an offset, as a number or a percentage, clamped between 0 and 1.
+fn opacity(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let value = if text.ends_with('%') {
+        percentage(text)?
+    } else {
+        text.parse::<f32>().ok()?
+    };
+    Some(value.max(0.).min(1.))
+}
+
+/// Parses a percentage to a fraction.
+fn percentage(text: &str) -> Option<f32> {
+    let text = text.trim();
+    if !text.ends_with('%') {
+        return None;
+    }
+    text[..text.len() - 1]
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .map(|percent| percent / 100.)
+}
+
+/// Parses a length, ignoring its unit.
+fn length(text: &str) -> Option<f32> {
+    Scanner::new(text).number()
+}
+
+fn numbers(text: &str) -> Vec<f32> {
+    let mut scanner = Scanner::new(text);
+    let mut numbers = Vec::new();
+    while let Some(number) = scanner.number() {
+        numbers.push(number);
+    }
+    numbers
+}
+
+/// Reads the numbers and commands of path data and of number lists.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Scanner {
+            bytes: text.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).cloned()
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') | Some(b',') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.position >= self.bytes.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() && c != b'e' && c != b'E' => {
+                self.position += 1;
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads an arc flag, which does not need to be followed by a separator.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.position;
+        let digits = |scanner: &mut Scanner<'_>| {
+            let from = scanner.position;
+            while let Some(b'0'..=b'9') = scanner.peek() {
+                scanner.position += 1;
+            }
+            scanner.position > from
+        };
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.position += 1;
+        }
+        let mut any = digits(self);
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            any |= digits(self);
+        }
+        if !any {
+            self.position = start;
+            return None;
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            if !digits(self) {
+                self.position = mantissa_end;
+            }
+        }
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()?
+            .parse()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Point, b: Point) {
+        assert!(
+            (a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    fn end_of(segment: &Segment) -> Point {
+        match *segment {
+            Segment::MoveTo(p) | Segment::LineTo(p) | Segment::QuadTo(_, p) => p,
+            Segment::CubicTo(_, _, p) => p,
+            Segment::Close => panic!("A close has no end point"),
+        }
+    }
+
+    #[test]
+    fn path_commands_are_absolute_or_relative() {
+        assert_eq!(
+            parse_path("M10 20 L30 40 h5 v-5 H0 V0 z"),
+            vec![
+                Segment::MoveTo([10., 20.]),
+                Segment::LineTo([30., 40.]),
+                Segment::LineTo([35., 40.]),
+                Segment::LineTo([35., 35.]),
+                Segment::LineTo([0., 35.]),
+                Segment::LineTo([0., 0.]),
+                Segment::Close,
+            ]
+        );
+        // A move is followed by implicit lines, relative to the current point.
+        assert_eq!(
+            parse_path("m1,2 3,4 z l1 1"),
+            vec![
+                Segment::MoveTo([1., 2.]),
+                Segment::LineTo([4., 6.]),
+                Segment::Close,
+                Segment::LineTo([2., 3.]),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_numbers_can_be_packed() {
+        assert_eq!(
+            parse_path("M1.5.5L-1e1-2"),
+            vec![Segment::MoveTo([1.5, 0.5]), Segment::LineTo([-10., -2.])]
+        );
+    }
+
+    #[test]
+    fn smooth_curves_reflect_the_last_control_point() {
+        assert_eq!(
+            parse_path("M0 0 C1 1 2 1 3 0 S5 -1 6 0"),
+            vec![
+                Segment::MoveTo([0., 0.]),
+                Segment::CubicTo([1., 1.], [2., 1.], [3., 0.]),
+                Segment::CubicTo([4., -1.], [5., -1.], [6., 0.]),
+            ]
+        );
+        assert_eq!(
+            parse_path("M0 0 Q1 1 2 0 T4 0"),
+            vec![
+                Segment::MoveTo([0., 0.]),
+                Segment::QuadTo([1., 1.], [2., 0.]),
+                Segment::QuadTo([3., -1.], [4., 0.]),
+            ]
+        );
+        // Without a previous curve of the same kind, the control point is the current point.
+        assert_eq!(
+            parse_path("M0 0 Q1 1 2 0 S3 1 4 0")[2],
+            Segment::CubicTo([2., 0.], [3., 1.], [4., 0.])
+        );
+    }
+
+    #[test]
+    fn malformed_paths_stop_at_the_error() {
+        assert_eq!(parse_path("M0 0 L1"), vec![Segment::MoveTo([0., 0.])]);
+        assert_eq!(parse_path("M0 0 X 1 1"), vec![Segment::MoveTo([0., 0.])]);
+        assert_eq!(parse_path("10 10"), vec![]);
+    }
+
+    #[test]
+    fn arcs_are_converted_to_quarter_turns() {
+        // A half circle from (0, 0) to (2, 0), around (1, 0).
+        for &(sweep, middle) in &[(true, [1., -1.]), (false, [1., 1.])] {
+            let mut segments = Vec::new();
+            arc_to_cubics([0., 0.], 1., 1., 0., false, sweep, [2., 0.], &mut segments);
+            assert_eq!(segments.len(), 2);
+            assert_close(end_of(&segments[0]), middle);
+            assert_eq!(end_of(&segments[1]), [2., 0.]);
+            if let Segment::CubicTo(c1, _, _) = segments[0] {
+                // The handles of a quarter circle are 0.5523 times its radius long.
+                let k = 4. / 3. * (PI / 8.).tan();
+                assert_close(c1, [0., middle[1] * k]);
+            } else {
+                panic!("Arcs are cubic curves");
+            }
+        }
+    }
+
+    #[test]
+    fn arcs_with_small_radii_are_scaled_up() {
+        let mut segments = Vec::new();
+        arc_to_cubics([0., 0.], 0.5, 0.5, 0., false, true, [2., 0.], &mut segments);
+        assert_eq!(segments.len(), 2);
+        assert_close(end_of(&segments[0]), [1., -1.]);
+    }
+
+    #[test]
+    fn degenerate_arcs() {
+        let mut segments = Vec::new();
+        arc_to_cubics([1., 1.], 1., 1., 0., false, true, [1., 1.], &mut segments);
+        assert!(segments.is_empty());
+        arc_to_cubics([0., 0.], 0., 1., 0., false, true, [2., 0.], &mut segments);
+        assert_eq!(segments, vec![Segment::LineTo([2., 0.])]);
+        // In path data, arcs end where the next segment starts.
+        let path = parse_path("M0 0 A1 1 0 1 0 2 0 L2 2");
+        assert_eq!(path.len(), 4);
+        assert_eq!(end_of(&path[2]), [2., 0.]);
+    }
+
+    #[test]
+    fn colors_are_parsed_to_srgb() {
+        assert_eq!(parse_color("#f80"), Some([1., 136. / 255., 0., 1.]));
+        assert_eq!(parse_color(" #FF8000 "), Some([1., 128. / 255., 0., 1.]));
+        assert_eq!(parse_color("rgb(255, 0, 51)"), Some([1., 0., 0.2, 1.]));
+        assert_eq!(parse_color("rgb(100%,50%,0%)"), Some([1., 0.5, 0., 1.]));
+        assert_eq!(parse_color("rgba(0, 0, 0, 0.5)"), Some([0., 0., 0., 0.5]));
+        assert_eq!(parse_color("Navy"), Some([0., 0., 128. / 255., 1.]));
+        assert_eq!(parse_color("transparent"), Some([0., 0., 0., 0.]));
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        for text in &[
+            "",
+            "#",
+            "#12",
+            "#1234",
+            "#ggg",
+            "#ff00é",
+            "rgb(1, 2)",
+            "rgb(a, b, c)",
+            "bleu",
+        ] {
+            assert_eq!(parse_color(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn transforms_apply_from_the_last() {
+        let transform = parse_transform("translate(10 20) scale(2)");
+        assert_eq!(transform.apply([1., 1.]), [12., 22.]);
+        let transform = parse_transform("scale(2, 3),translate(1)");
+        assert_eq!(transform.apply([0., 1.]), [2., 3.]);
+        assert_eq!(
+            parse_transform("matrix(1 2 3 4 5 6)"),
+            Affine([1., 2., 3., 4., 5., 6.])
+        );
+    }
+
+    #[test]
+    fn rotations_and_skews() {
+        assert_close(parse_transform("rotate(90)").apply([1., 0.]), [0., 1.]);
+        assert_close(
+            parse_transform("rotate(90 10 10)").apply([10., 0.]),
+            [20., 10.],
+        );
+        assert_close(parse_transform("skewX(45)").apply([0., 1.]), [1., 1.]);
+        assert_close(parse_transform("skewY(45)").apply([1., 0.]), [1., 1.]);
+    }
+
+    #[test]
+    fn unknown_transforms_are_ignored() {
+        assert_eq!(parse_transform("perspective(2)"), Affine::IDENTITY);
+        assert_eq!(parse_transform("matrix(1 2)"), Affine::IDENTITY);
+        assert_eq!(
+            parse_transform("foo(1) translate(1,0)").apply([0., 0.]),
+            [1., 0.]
+        );
+    }
+
+    fn square(left: f32, top: f32, size: f32) -> Vec<Point> {
+        vec![
+            [left, top],
+            [left + size, top],
+            [left + size, top + size],
+            [left, top + size],
+        ]
+    }
+
+    #[test]
+    fn coverage_of_whole_pixels() {
+        let pixels = coverage(&[square(1., 1., 2.)], 4, 4, false);
+        for row in 0..4 {
+            for column in 0..4 {
+                let inside = (1..3).contains(&row) && (1..3).contains(&column);
+                let expected = if inside { 1. } else { 0. };
+                assert!((pixels[row * 4 + column] - expected).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn coverage_of_partial_pixels() {
+        let pixels = coverage(&[square(0.5, 0., 1.)], 2, 1, false);
+        assert!((pixels[0] - 0.5).abs() < 1e-5);
+        assert!((pixels[1] - 0.5).abs() < 1e-5);
+        // Shapes are clipped to the image.
+        let pixels = coverage(&[square(-1., -1., 2.)], 2, 2, false);
+        assert_eq!(pixels, vec![1., 0., 0., 0.]);
+    }
+
+    #[test]
+    fn coverage_fill_rules() {
+        let nested = [square(0., 0., 3.), square(1., 1., 1.)];
+        let center = 3 + 1;
+        assert!((coverage(&nested, 3, 3, false)[center] - 1.).abs() < 1e-5);
+        assert!(coverage(&nested, 3, 3, true)[center].abs() < 1e-5);
+        // A hole going the other way is empty with both rules.
+        let mut hole = square(1., 1., 1.);
+        hole.reverse();
+        let nested = [square(0., 0., 3.), hole];
+        assert!(coverage(&nested, 3, 3, false)[center].abs() < 1e-5);
+    }
+}
@@ -0,0 +1,394 @@
+//! Tab containers.
+
+use derivative::Derivative;
+use std::marker::PhantomData;
+
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        ReaderId, System, SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    Hidden, HiddenPropagate, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use winit::MouseButton;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{rows::view_of, Selected, UiEvent, UiEventType, UiImage};
+
+pub(crate) const DEFAULT_TAB_COLOR: [f32; 4] = [0.6, 0.62, 0.65, 1.0];
+pub(crate) const DEFAULT_ACTIVE_TAB_COLOR: [f32; 4] = [0.82, 0.83, 0.83, 1.0];
+
+/// Component turning its entity into a tab container.
+///
+/// Each tab is made of a tab button, usually in a strip along the top of the container, and of a
+/// page. Only the page of the active tab is shown, the others are hidden with `HiddenPropagate`
+/// so that nothing in them can be interacted with. Clicking a tab button activates its tab, and
+/// the actions of the `UiTabsConfig` switch the tabs of the innermost container of the focused
+/// element. The container receives an `ActiveTabChanged` event whenever the active tab changes.
+///
+/// Use a `UiWidget::Tabs` prefab to create one.
+#[derive(Debug, Clone)]
+pub struct UiTabs {
+    /// The tab buttons and their pages.
+    tabs: Vec<(Entity, Entity)>,
+    active: usize,
+    /// The linear RGBA colour of the inactive tab buttons.
+    pub tab_color: [f32; 4],
+    /// The linear RGBA colour of the active tab button.
+    pub active_tab_color: [f32; 4],
+    /// The active tab last reported with an `ActiveTabChanged` event.
+    reported: Option<usize>,
+    /// The tab shown by the `UiTabsSystem`.
+    shown: Option<usize>,
+}
+
+impl UiTabs {
+    /// Creates a tab container from its tab buttons and pages, with the first tab active.
+    pub fn new(tabs: Vec<(Entity, Entity)>) -> Self {
+        UiTabs {
+            tabs,
+            active: 0,
+            tab_color: DEFAULT_TAB_COLOR,
+            active_tab_color: DEFAULT_ACTIVE_TAB_COLOR,
+            reported: None,
+            shown: None,
+        }
+    }
+
+    /// Sets the tab active at first.
+    pub fn with_active(mut self, active: usize) -> Self {
+        self.set_active(active);
+        self
+    }
+
+    /// Sets the colours of the inactive tab buttons and of the active one.
+    pub fn with_colors(mut self, tab_color: [f32; 4], active_tab_color: [f32; 4]) -> Self {
+        self.tab_color = tab_color;
+        self.active_tab_color = active_tab_color;
+        self
+    }
+
+    /// Returns the tab buttons and their pages.
+    pub fn tabs(&self) -> &[(Entity, Entity)] {
+        &self.tabs
+    }
+
+    /// Returns the index of the active tab.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Activates the tab at `index`. Indices out of range are ignored.
+    pub fn set_active(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active = index;
+        }
+    }
+
+    /// Activates the tab whose button is among the `clicked` elements, then moves the active tab
+    /// by `step`, wrapping around.
+    fn switch(&mut self, clicked: &[Entity], step: isize) {
+        for target in clicked {
+            if let Some(index) = self.tabs.iter().position(|(button, _)| button == target) {
+                self.active = index;
+            }
+        }
+        if step != 0 {
+            let count = self.tabs.len() as isize;
+            self.active = (self.active as isize + step).rem_euclid(count) as usize;
+        }
+        self.active = self.active.min(self.tabs.len() - 1);
+    }
+
+    /// Reports a change of the active tab to the container `entity`, and shows the page of the
+    /// active tab while hiding the others.
+    ///
+    /// Only the pages of a `visible` container are updated, as showing a page of a hidden
+    /// container would show it inside of the hidden container. They are updated once it is shown.
+    fn update_pages(
+        &mut self,
+        entity: Entity,
+        visible: bool,
+        images: &mut WriteStorage<'_, UiImage>,
+        hidden_props: &mut WriteStorage<'_, HiddenPropagate>,
+        ui_events: &mut EventChannel<UiEvent>,
+    ) {
+        if self.reported != Some(self.active) {
+            if self.reported.is_some() {
+                ui_events.single_write(UiEvent::new(
+                    UiEventType::ActiveTabChanged { index: self.active },
+                    entity,
+                ));
+            }
+            self.reported = Some(self.active);
+        }
+
+        if !visible || self.shown == Some(self.active) {
+            return;
+        }
+        self.shown = Some(self.active);
+        for (index, (button, page)) in self.tabs.iter().enumerate() {
+            if index == self.active {
+                let _ = images.insert(*button, UiImage::SolidColor(self.active_tab_color));
+                hidden_props.remove(*page);
+            } else {
+                let _ = images.insert(*button, UiImage::SolidColor(self.tab_color));
+                let _ = hidden_props.insert(*page, HiddenPropagate::new());
+            }
+        }
+    }
+}
+
+impl Component for UiTabs {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Resource setting the input actions switching the tabs of the `UiTabs` containing the `Selected`
+/// element, like the shoulder buttons of a gamepad or a key. Both are unset by default.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Clone(bound = ""), Default(bound = ""))]
+pub struct UiTabsConfig<T: BindingTypes> {
+    /// The action activating the previous tab, wrapping around to the last one.
+    pub previous_action: Option<T::Action>,
+    /// The action activating the next tab, wrapping around to the first one.
+    pub next_action: Option<T::Action>,
+}
+
+/// System switching the active tab of the `UiTabs` and showing its page.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiTabsSystemDesc))]
+pub struct UiTabsSystem<T: BindingTypes> {
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+    /// Whether the previous and next actions were down during the last update.
+    #[system_desc(skip)]
+    actions_down: (bool, bool),
+    phantom: PhantomData<T>,
+}
+
+impl<T: BindingTypes> UiTabsSystem<T> {
+    /// Creates a new `UiTabsSystem`.
+    pub fn new(ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            ui_reader_id,
+            actions_down: (false, false),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: BindingTypes> System<'a> for UiTabsSystem<T> {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, ParentHierarchy>,
+        Read<'a, InputHandler<T>>,
+        Read<'a, UiTabsConfig<T>>,
+        Write<'a, EventChannel<UiEvent>>,
+        WriteStorage<'a, UiTabs>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, HiddenPropagate>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, Selected>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            hierarchy,
+            input,
+            config,
+            mut ui_events,
+            mut tabs,
+            mut images,
+            mut hidden_props,
+            hiddens,
+            selecteds,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_tabs_system");
+
+        let is_down = |action: &Option<T::Action>| {
+            action
+                .as_ref()
+                .and_then(|action| input.action_is_down(action))
+                .unwrap_or(false)
+        };
+        let actions_down = (
+            is_down(&config.previous_action),
+            is_down(&config.next_action),
+        );
+        let step = match (
+            actions_down.0 && !self.actions_down.0,
+            actions_down.1 && !self.actions_down.1,
+        ) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => 0,
+        };
+        self.actions_down = actions_down;
+
+        // The actions switch the innermost tabs containing the focused elements.
+        let focused = if step != 0 {
+            (&*entities, &selecteds)
+                .join()
+                .filter_map(|(entity, _)| view_of(entity, &hierarchy, |e| tabs.contains(e)))
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        let clicked = ui_events
+            .read(&mut self.ui_reader_id)
+            .filter(|event| event.event_type == UiEventType::Click(MouseButton::Left))
+            .map(|event| event.target)
+            .collect::<Vec<_>>();
+
+        for (entity, tabs) in (&*entities, &mut tabs).join() {
+            if tabs.tabs.is_empty() {
+                continue;
+            }
+            // The actions only switch the tabs which are shown.
+            let visible = !hiddens.contains(entity) && !hidden_props.contains(entity);
+            let step = if visible && focused.contains(&entity) {
+                step
+            } else {
+                0
+            };
+            tabs.switch(&clicked, step);
+            tabs.update_pages(
+                entity,
+                visible,
+                &mut images,
+                &mut hidden_props,
+                &mut ui_events,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::prelude::{Builder, World, WorldExt};
+
+    struct Setup {
+        world: World,
+        container: Entity,
+        tabs: UiTabs,
+        events: EventChannel<UiEvent>,
+        reader: ReaderId<UiEvent>,
+    }
+
+    impl Setup {
+        fn new(count: usize) -> Self {
+            let mut world = World::new();
+            world.register::<UiImage>();
+            world.register::<HiddenPropagate>();
+            let container = world.create_entity().build();
+            let tabs = (0..count)
+                .map(|_| (world.create_entity().build(), world.create_entity().build()))
+                .collect();
+            let mut events = EventChannel::new();
+            let reader = events.register_reader();
+            Setup {
+                world,
+                container,
+                tabs: UiTabs::new(tabs),
+                events,
+                reader,
+            }
+        }
+
+        fn update(&mut self, visible: bool) -> Vec<UiEventType> {
+            let (mut images, mut hidden_props) = self
+                .world
+                .system_data::<(WriteStorage<'_, UiImage>, WriteStorage<'_, HiddenPropagate>)>();
+            self.tabs.update_pages(
+                self.container,
+                visible,
+                &mut images,
+                &mut hidden_props,
+                &mut self.events,
+            );
+            let container = self.container;
+            self.events
+                .read(&mut self.reader)
+                .map(|event| {
+                    assert_eq!(event.target, container);
+                    event.event_type.clone()
+                })
+                .collect()
+        }
+
+        fn hidden_pages(&self) -> Vec<bool> {
+            let hidden_props = self.world.read_storage::<HiddenPropagate>();
+            self.tabs
+                .tabs()
+                .iter()
+                .map(|(_, page)| hidden_props.contains(*page))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn clicking_a_tab_button_activates_its_tab() {
+        let mut setup = Setup::new(3);
+        let button = setup.tabs.tabs()[2].0;
+        setup.tabs.switch(&[setup.container, button], 0);
+        assert_eq!(setup.tabs.active(), 2);
+    }
+
+    #[test]
+    fn previous_and_next_wrap_around() {
+        let mut setup = Setup::new(3);
+        setup.tabs.switch(&[], -1);
+        assert_eq!(setup.tabs.active(), 2);
+        setup.tabs.switch(&[], 1);
+        assert_eq!(setup.tabs.active(), 0);
+        setup.tabs.switch(&[], 1);
+        assert_eq!(setup.tabs.active(), 1);
+    }
+
+    #[test]
+    fn only_the_active_page_is_shown_and_changes_are_reported() {
+        let mut setup = Setup::new(3);
+        // The first update shows the initial tab without reporting it.
+        assert_eq!(setup.update(true), vec![]);
+        assert_eq!(setup.hidden_pages(), vec![false, true, true]);
+
+        setup.tabs.set_active(1);
+        assert_eq!(
+            setup.update(true),
+            vec![UiEventType::ActiveTabChanged { index: 1 }]
+        );
+        assert_eq!(setup.hidden_pages(), vec![true, false, true]);
+        let images = setup.world.read_storage::<UiImage>();
+        let color = |tab: usize| images.get(setup.tabs.tabs()[tab].0).cloned();
+        assert_eq!(color(0), Some(UiImage::SolidColor(DEFAULT_TAB_COLOR)));
+        assert_eq!(
+            color(1),
+            Some(UiImage::SolidColor(DEFAULT_ACTIVE_TAB_COLOR))
+        );
+    }
+
+    #[test]
+    fn pages_of_a_hidden_container_are_updated_once_it_is_shown() {
+        let mut setup = Setup::new(2);
+        setup.update(true);
+        setup.tabs.set_active(1);
+        assert_eq!(
+            setup.update(false),
+            vec![UiEventType::ActiveTabChanged { index: 1 }]
+        );
+        assert_eq!(setup.hidden_pages(), vec![false, true]);
+        assert_eq!(setup.update(true), vec![]);
+        assert_eq!(setup.hidden_pages(), vec![true, false]);
+    }
+}
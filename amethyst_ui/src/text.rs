@@ -1,6 +1,6 @@
 //! Module holding the components related to text and text editing.
 
-use crate::Anchor;
+use crate::{Anchor, UiTransform};
 
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
@@ -177,6 +177,7 @@ impl<'a> System<'a> for TextEditingMouseSystem {
         WriteStorage<'a, UiText>,
         WriteStorage<'a, TextEditing>,
         ReadStorage<'a, Selected>,
+        ReadStorage<'a, UiTransform>,
         Read<'a, EventChannel<Event>>,
         ReadExpect<'a, ScreenDimensions>,
         Read<'a, Time>,
@@ -184,7 +185,15 @@ impl<'a> System<'a> for TextEditingMouseSystem {
 
     fn run(
         &mut self,
-        (mut texts, mut text_editings, selecteds, events, screen_dimensions, time): Self::SystemData,
+        (
+            mut texts,
+            mut text_editings,
+            selecteds,
+            transforms,
+            events,
+            screen_dimensions,
+            time,
+        ): Self::SystemData,
     ) {
         // Normalize text to ensure we can properly count the characters.
         // TODO: Possible improvement to be made if this can be moved only when inserting characters into ui text.
@@ -216,10 +225,11 @@ impl<'a> System<'a> for TextEditingMouseSystem {
                     event: WindowEvent::CursorMoved { position, .. },
                     ..
                 } => {
+                    // Same physical pixels as the `InputHandler`, with y going up.
                     let hidpi = screen_dimensions.hidpi_factor() as f32;
                     self.mouse_position = (
                         position.x as f32 * hidpi,
-                        (screen_dimensions.height() - position.y as f32) * hidpi,
+                        screen_dimensions.height() - position.y as f32 * hidpi,
                     );
                     if self.left_mouse_button_pressed {
                         moved_while_pressed = true;
@@ -246,9 +256,18 @@ impl<'a> System<'a> for TextEditingMouseSystem {
             }
         }
 
-        for (ref mut text, ref mut text_editing, selected) in
-            (&mut texts, &mut text_editings, selecteds.maybe()).join()
+        for (ref mut text, ref mut text_editing, selected, transform) in (
+            &mut texts,
+            &mut text_editings,
+            selecteds.maybe(),
+            transforms.maybe(),
+        )
+            .join()
         {
+            // The glyphs are laid out before the element is rotated and scaled.
+            let (mouse_x, mouse_y) = transform
+                .and_then(|t| t.to_layout_space(self.mouse_position.0, self.mouse_position.1))
+                .unwrap_or(self.mouse_position);
             if selected.is_none() {
                 // If an editable text field is no longer selected, we should reset
                 // the highlight vector.
@@ -256,7 +275,6 @@ impl<'a> System<'a> for TextEditingMouseSystem {
             } else if just_pressed {
                 // If we focused an editable text field be sure to position the cursor
                 // in it.
-                let (mouse_x, mouse_y) = self.mouse_position;
                 text_editing.highlight_vector = 0;
                 text_editing.cursor_position =
                     closest_glyph_index_to_mouse(mouse_x, mouse_y, &text.cached_glyphs);
@@ -270,7 +288,6 @@ impl<'a> System<'a> for TextEditingMouseSystem {
                     text_editing.cursor_position += 1;
                 }
             } else if moved_while_pressed {
-                let (mouse_x, mouse_y) = self.mouse_position;
                 text_editing.highlight_vector =
                     closest_glyph_index_to_mouse(mouse_x, mouse_y, &text.cached_glyphs)
                         - text_editing.cursor_position;
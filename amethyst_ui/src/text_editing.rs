@@ -7,12 +7,15 @@ use unicode_segmentation::UnicodeSegmentation;
 use winit::{ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent};
 
 use amethyst_core::{
-    ecs::prelude::{Entities, Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
+    ecs::prelude::{
+        Entities, Join, Read, ReadExpect, ReadStorage, System, SystemData, Write, WriteStorage,
+    },
     shrev::{EventChannel, ReaderId},
+    ParentHierarchy,
 };
 use amethyst_derive::SystemDesc;
 
-use crate::{LineMode, Selected, TextEditing, UiEvent, UiEventType, UiText};
+use crate::{LineMode, Selected, TextEditing, UiEvent, UiEventType, UiModalStack, UiText};
 
 /// System managing the keyboard inputs for the editable text fields.
 /// ## Features
@@ -42,11 +45,22 @@ impl<'a> System<'a> for TextEditingInputSystem {
         ReadStorage<'a, Selected>,
         Read<'a, EventChannel<Event>>,
         Write<'a, EventChannel<UiEvent>>,
+        Read<'a, UiModalStack>,
+        ReadExpect<'a, ParentHierarchy>,
     );
 
     fn run(
         &mut self,
-        (entities, mut texts, mut editables, selecteds, events, mut edit_events): Self::SystemData,
+        (
+            entities,
+            mut texts,
+            mut editables,
+            selecteds,
+            events,
+            mut edit_events,
+            modal_stack,
+            hierarchy,
+        ): Self::SystemData,
     ) {
         for text in (&mut texts).join() {
             if (*text.text).chars().any(is_combining_mark) {
@@ -60,7 +74,7 @@ impl<'a> System<'a> for TextEditingInputSystem {
             if let Some((entity, ref mut focused_text, ref mut focused_edit, _)) =
                 (&*entities, &mut texts, &mut editables, &selecteds)
                     .join()
-                    .next()
+                    .find(|(entity, _, _, _)| modal_stack.allows(*entity, &hierarchy))
             {
                 match *event {
                     Event::WindowEvent {
@@ -0,0 +1,820 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+};
+
+use amethyst_assets::{Asset, AssetPrefab, AssetStorage, Handle, PrefabData, ProgressCounter};
+use amethyst_audio::Source as Audio;
+use amethyst_core::{
+    ecs::prelude::{
+        BitSet, Component, ComponentEvent, DenseVecStorage, Entities, Entity, FlaggedStorage, Join,
+        Read, ReadExpect, ReadStorage, ReaderId, System, SystemData, VecStorage, WriteStorage,
+    },
+    ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_error::Error;
+use derivative::Derivative;
+use log::error;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    FontAsset, FontHandle, Stretch, UiButton, UiButtonAction, UiButtonActionRetrigger,
+    UiButtonActionType, UiImage, UiImageLoadPrefab, UiPlaySoundAction, UiSoundRetrigger, UiText,
+    UiTransform,
+};
+
+/// A named set of style values, part of a `UiTheme`.
+/// Every value is optional; missing values keep the value the widget had before being styled.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UiStyle {
+    /// Colour of the text.
+    pub text_color: Option<[f32; 4]>,
+    /// Font of the text.
+    pub font: Option<AssetPrefab<FontAsset>>,
+    /// Font size of the text.
+    pub font_size: Option<f32>,
+    /// Default image.
+    pub normal_image: Option<UiImageLoadPrefab>,
+    /// Image used when the mouse hovers over the widget.
+    pub hover_image: Option<UiImageLoadPrefab>,
+    /// Image used when the widget is pressed.
+    pub press_image: Option<UiImageLoadPrefab>,
+    /// Sound made when the widget is hovered over.
+    pub hover_sound: Option<AssetPrefab<Audio>>,
+    /// Sound made when the widget is pressed.
+    pub press_sound: Option<AssetPrefab<Audio>>,
+    /// Sound made when the widget is released.
+    pub release_sound: Option<AssetPrefab<Audio>>,
+    /// Horizontal and vertical padding, in pixels, applied as the stretch margins of the
+    /// stretched children without a class of their own, like the text of a button.
+    pub padding: Option<(f32, f32)>,
+}
+
+impl Debug for UiStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let font = match self.font.as_ref() {
+            Some(AssetPrefab::File(path, _)) => Some(format!("<Font:{}>", path)),
+            Some(_) => Some("<Font>".to_string()),
+            None => None,
+        };
+
+        f.debug_struct("UiStyle")
+            .field("text_color", &self.text_color)
+            .field("font", &font)
+            .field("font_size", &self.font_size)
+            .field("normal_image", &self.normal_image)
+            .field("hover_image", &self.hover_image)
+            .field("press_image", &self.press_image)
+            .field("hover_sound", &self.hover_sound)
+            .field("press_sound", &self.press_sound)
+            .field("release_sound", &self.release_sound)
+            .field("padding", &self.padding)
+            .finish()
+    }
+}
+
+/// A set of named `UiStyle`s applied to the widgets having a matching `UiClass`.
+///
+/// Load it from a `ron` file with `RonFormat` and make it active with the `UiActiveTheme`
+/// resource.
+///
+/// ### Example:
+///
+/// ```ron
+/// (
+///     styles: {
+///         "primary": (
+///             text_color: Some((1.0, 1.0, 1.0, 1.0)),
+///             font_size: Some(24.0),
+///             normal_image: Some(SolidColor(0.1, 0.3, 0.8, 1.0)),
+///             hover_image: Some(SolidColor(0.2, 0.4, 0.9, 1.0)),
+///             padding: Some((8.0, 4.0)),
+///         ),
+///     },
+/// )
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UiTheme {
+    /// The styles, by class name.
+    pub styles: HashMap<String, UiStyle>,
+}
+
+impl Asset for UiTheme {
+    const NAME: &'static str = "ui::Theme";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+/// Resource selecting the `UiTheme` applied to the widgets.
+/// Changing it restyles all the widgets having a `UiClass`.
+#[derive(Debug, Default, Clone)]
+pub struct UiActiveTheme {
+    /// The active theme, if any.
+    pub theme: Option<Handle<UiTheme>>,
+}
+
+/// Opts a ui entity in to the style of the same name in the active `UiTheme`.
+///
+/// The text style is applied to the `UiText` of the entity, or, if it has none, to the `UiText`s of
+/// its direct children without a class of their own.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UiClass(pub String);
+
+impl Component for UiClass {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Records the style values explicitly set by a prefab on an entity.
+/// The `UiThemeSystem` never overwrites those values.
+///
+/// The parts of a ui prefab can't share a storage, so each records the values it sets in the
+/// overrides of its own component `C`: `UiTransform`, `UiText`, `UiImage` or `UiButton`.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Clone(bound = ""), Default(bound = ""))]
+pub struct UiStyleOverrides<C> {
+    /// The text colour was set explicitly.
+    pub text_color: bool,
+    /// The font was set explicitly.
+    pub font: bool,
+    /// The font size was set explicitly.
+    pub font_size: bool,
+    /// The normal image was set explicitly.
+    pub normal_image: bool,
+    /// The hover image was set explicitly.
+    pub hover_image: bool,
+    /// The press image was set explicitly.
+    pub press_image: bool,
+    /// The hover sound was set explicitly.
+    pub hover_sound: bool,
+    /// The press sound was set explicitly.
+    pub press_sound: bool,
+    /// The release sound was set explicitly.
+    pub release_sound: bool,
+    /// The stretch margins were set explicitly, so the padding of a style doesn't apply.
+    pub padding: bool,
+    #[derivative(Debug = "ignore")]
+    _part: PhantomData<C>,
+}
+
+impl<C> UiStyleOverrides<C>
+where
+    C: Send + Sync + 'static,
+{
+    /// Returns the overrides of `entity`, inserting empty ones if it has none yet.
+    pub fn of<'s>(
+        storage: &'s mut WriteStorage<'_, UiStyleOverrides<C>>,
+        entity: Entity,
+    ) -> Result<&'s mut UiStyleOverrides<C>, Error> {
+        Ok(storage.entry(entity)?.or_insert_with(Default::default))
+    }
+}
+
+impl<C> Component for UiStyleOverrides<C>
+where
+    C: Send + Sync + 'static,
+{
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The values of an entity before a style changed them. They are put back before the entity is
+/// styled again, so that the values set by a previous theme don't outlive it.
+#[derive(Debug, Clone, Default)]
+struct Unthemed {
+    text: Option<(FontHandle, [f32; 4], f32)>,
+    stretch: Option<Stretch>,
+    image: Option<Option<UiImage>>,
+    actions: Option<Option<UiButtonActionRetrigger>>,
+    sounds: Option<Option<UiSoundRetrigger>>,
+}
+
+impl Unthemed {
+    /// Puts the recorded values back on `entity`.
+    fn restore(
+        self,
+        entity: Entity,
+        transforms: &mut WriteStorage<'_, UiTransform>,
+        texts: &mut WriteStorage<'_, UiText>,
+        images: &mut WriteStorage<'_, UiImage>,
+        button_retriggers: &mut WriteStorage<'_, UiButtonActionRetrigger>,
+        sound_retriggers: &mut WriteStorage<'_, UiSoundRetrigger>,
+    ) -> Result<(), Error> {
+        if let (Some((font, color, font_size)), Some(text)) = (self.text, texts.get_mut(entity)) {
+            text.font = font;
+            text.color = color;
+            text.font_size = font_size;
+        }
+        if let (Some(stretch), Some(transform)) = (self.stretch, transforms.get_mut(entity)) {
+            transform.stretch = stretch;
+        }
+        if let Some(image) = self.image {
+            put(images, entity, image)?;
+        }
+        if let Some(actions) = self.actions {
+            put(button_retriggers, entity, actions)?;
+        }
+        if let Some(sounds) = self.sounds {
+            put(sound_retriggers, entity, sounds)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inserts `component` on `entity`, or removes the component of `entity` if it is `None`.
+fn put<C: Component>(
+    storage: &mut WriteStorage<'_, C>,
+    entity: Entity,
+    component: Option<C>,
+) -> Result<(), Error> {
+    match component {
+        Some(component) => {
+            storage.insert(entity, component)?;
+        }
+        None => {
+            storage.remove(entity);
+        }
+    }
+    Ok(())
+}
+
+/// System applying the active `UiTheme` to the entities having a `UiClass`.
+///
+/// Entities are restyled when they get a class, when their class changes or is removed and when
+/// the active theme changes. The values set by the previous style of an entity are reset first.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiThemeSystemDesc))]
+pub struct UiThemeSystem {
+    #[system_desc(flagged_storage_reader(UiClass))]
+    class_events_id: ReaderId<ComponentEvent>,
+    #[system_desc(skip)]
+    applied_theme: Option<Handle<UiTheme>>,
+    #[system_desc(skip)]
+    resolved: Option<UiTheme>,
+    #[system_desc(skip)]
+    dirty: BitSet,
+    #[system_desc(skip)]
+    unthemed: HashMap<Entity, Unthemed>,
+}
+
+impl UiThemeSystem {
+    /// Creates a new `UiThemeSystem` listening to the given `UiClass` events.
+    pub fn new(class_events_id: ReaderId<ComponentEvent>) -> Self {
+        UiThemeSystem {
+            class_events_id,
+            applied_theme: None,
+            resolved: None,
+            dirty: BitSet::new(),
+            unthemed: HashMap::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for UiThemeSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, UiClass>,
+        ReadStorage<'a, UiStyleOverrides<UiTransform>>,
+        ReadStorage<'a, UiStyleOverrides<UiText>>,
+        ReadStorage<'a, UiStyleOverrides<UiImage>>,
+        ReadStorage<'a, UiStyleOverrides<UiButton>>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, UiButtonActionRetrigger>,
+        WriteStorage<'a, UiSoundRetrigger>,
+        ReadExpect<'a, ParentHierarchy>,
+        Read<'a, UiActiveTheme>,
+        Read<'a, AssetStorage<UiTheme>>,
+        <AssetPrefab<FontAsset> as PrefabData<'a>>::SystemData,
+        <UiImageLoadPrefab as PrefabData<'a>>::SystemData,
+        <AssetPrefab<Audio> as PrefabData<'a>>::SystemData,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            classes,
+            transform_overrides,
+            text_overrides,
+            image_overrides,
+            button_overrides,
+            mut transforms,
+            mut texts,
+            mut images,
+            mut button_retriggers,
+            mut sound_retriggers,
+            hierarchy,
+            active_theme,
+            themes,
+            mut fonts,
+            mut textures,
+            mut sounds,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_theme_system");
+
+        for event in classes.channel().read(&mut self.class_events_id) {
+            match event {
+                ComponentEvent::Inserted(id)
+                | ComponentEvent::Modified(id)
+                | ComponentEvent::Removed(id) => {
+                    self.dirty.add(*id);
+                }
+            }
+        }
+
+        // Resolve the sub assets of the theme once it is loaded, then restyle everything.
+        // The previous theme stays applied while the new one is loading.
+        let loaded = active_theme.theme.as_ref().map(|handle| themes.get(handle));
+        let ready = loaded.map_or(true, |theme| theme.is_some());
+        if self.applied_theme != active_theme.theme && ready {
+            self.resolved = loaded.flatten().map(|theme| {
+                let mut theme = theme.clone();
+                let mut progress = ProgressCounter::new();
+                for style in theme.styles.values_mut() {
+                    if let Err(err) = load_style_assets(
+                        style,
+                        &mut progress,
+                        &mut fonts,
+                        &mut textures,
+                        &mut sounds,
+                    ) {
+                        error!("Failed to load the assets of a ui style: {}", err);
+                    }
+                }
+                theme
+            });
+            self.dirty = classes.mask().clone();
+            self.applied_theme = active_theme.theme.clone();
+        }
+
+        self.unthemed.retain(|entity, _| entities.is_alive(*entity));
+        let theme = self.resolved.as_ref();
+
+        for (entity, _) in (&*entities, &self.dirty).join() {
+            // The previous style is reset, including the values the new one doesn't set.
+            let unclassed_children = hierarchy
+                .children(entity)
+                .iter()
+                .cloned()
+                .filter(|child| !classes.contains(*child));
+            for target in std::iter::once(entity).chain(unclassed_children) {
+                if let Some(unthemed) = self.unthemed.remove(&target) {
+                    if let Err(err) = unthemed.restore(
+                        target,
+                        &mut transforms,
+                        &mut texts,
+                        &mut images,
+                        &mut button_retriggers,
+                        &mut sound_retriggers,
+                    ) {
+                        error!("Failed to reset a ui style: {}", err);
+                    }
+                }
+            }
+
+            let style = match (classes.get(entity), theme) {
+                (Some(class), Some(theme)) => match theme.styles.get(&class.0) {
+                    Some(style) => style,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let image_overridden = image_overrides
+                .get(entity)
+                .map_or(false, |overrides| overrides.normal_image);
+            let own = button_overrides.get(entity).cloned().unwrap_or_default();
+
+            // Text style goes on the entity itself, or on its unclassed children.
+            let text_targets = if texts.contains(entity) {
+                vec![entity]
+            } else {
+                hierarchy
+                    .children(entity)
+                    .iter()
+                    .cloned()
+                    .filter(|child| !classes.contains(*child) && texts.contains(*child))
+                    .collect()
+            };
+            for target in text_targets {
+                let target_overrides = text_overrides.get(target).cloned().unwrap_or_default();
+                let text = texts.get_mut(target).expect("unreachable: filtered above");
+                let unthemed = self.unthemed.entry(target).or_default();
+                unthemed
+                    .text
+                    .get_or_insert_with(|| (text.font.clone(), text.color, text.font_size));
+                if let (Some(color), false) = (style.text_color, target_overrides.text_color) {
+                    text.color = color;
+                }
+                if let (Some(size), false) = (style.font_size, target_overrides.font_size) {
+                    text.font_size = size;
+                }
+                if let (Some(AssetPrefab::Handle(ref font)), false) =
+                    (&style.font, target_overrides.font)
+                {
+                    text.font = font.clone();
+                }
+            }
+
+            if let Some((x_margin, y_margin)) = style.padding {
+                for child in hierarchy.children(entity) {
+                    let overridden = transform_overrides
+                        .get(*child)
+                        .map_or(false, |overrides| overrides.padding);
+                    if classes.contains(*child) || overridden {
+                        continue;
+                    }
+                    if let Some(transform) = transforms.get_mut(*child) {
+                        if let Some(stretch) = padded(&transform.stretch, x_margin, y_margin) {
+                            let unthemed = self.unthemed.entry(*child).or_default();
+                            unthemed
+                                .stretch
+                                .get_or_insert_with(|| transform.stretch.clone());
+                            transform.stretch = stretch;
+                        }
+                    }
+                }
+            }
+
+            let unthemed = self.unthemed.entry(entity).or_default();
+            if let Err(err) = apply_images(
+                entity,
+                style,
+                image_overridden,
+                &own,
+                unthemed,
+                &mut images,
+                &mut button_retriggers,
+                &mut textures,
+            ) {
+                error!("Failed to apply a ui style image: {}", err);
+            }
+            apply_sounds(entity, style, &own, unthemed, &mut sound_retriggers);
+        }
+
+        self.dirty.clear();
+    }
+}
+
+fn load_style_assets<'a>(
+    style: &mut UiStyle,
+    progress: &mut ProgressCounter,
+    fonts: &mut <AssetPrefab<FontAsset> as PrefabData<'a>>::SystemData,
+    textures: &mut <UiImageLoadPrefab as PrefabData<'a>>::SystemData,
+    sounds: &mut <AssetPrefab<Audio> as PrefabData<'a>>::SystemData,
+) -> Result<(), Error> {
+    style.font.load_sub_assets(progress, fonts)?;
+    style.normal_image.load_sub_assets(progress, textures)?;
+    style.hover_image.load_sub_assets(progress, textures)?;
+    style.press_image.load_sub_assets(progress, textures)?;
+    style.hover_sound.load_sub_assets(progress, sounds)?;
+    style.press_sound.load_sub_assets(progress, sounds)?;
+    style.release_sound.load_sub_assets(progress, sounds)?;
+    Ok(())
+}
+
+/// The stretch of a child of an entity styled with the padding `x_margin` and `y_margin`, or
+/// `None` if the child is not stretched.
+fn padded(stretch: &Stretch, x_margin: f32, y_margin: f32) -> Option<Stretch> {
+    match *stretch {
+        Stretch::NoStretch => None,
+        Stretch::X { .. } => Some(Stretch::X { x_margin }),
+        Stretch::Y { .. } => Some(Stretch::Y { y_margin }),
+        Stretch::XY {
+            keep_aspect_ratio, ..
+        } => Some(Stretch::XY {
+            x_margin,
+            y_margin,
+            keep_aspect_ratio,
+        }),
+    }
+}
+
+fn apply_images<'a>(
+    entity: Entity,
+    style: &UiStyle,
+    image_overridden: bool,
+    overrides: &UiStyleOverrides<UiButton>,
+    unthemed: &mut Unthemed,
+    images: &mut WriteStorage<'a, UiImage>,
+    retriggers: &mut WriteStorage<'a, UiButtonActionRetrigger>,
+    textures: &mut <UiImageLoadPrefab as PrefabData<'a>>::SystemData,
+) -> Result<(), Error> {
+    if let (Some(ref image), false) = (&style.normal_image, image_overridden) {
+        let image = image.add_to_entity(entity, textures, &[], &[])?;
+        unthemed
+            .image
+            .get_or_insert_with(|| images.get(entity).cloned());
+        images.insert(entity, image)?;
+    }
+
+    let hover = match (&style.hover_image, overrides.hover_image) {
+        (Some(ref image), false) => Some(image.add_to_entity(entity, textures, &[], &[])?),
+        _ => None,
+    };
+    let press = match (&style.press_image, overrides.press_image) {
+        (Some(ref image), false) => Some(image.add_to_entity(entity, textures, &[], &[])?),
+        _ => None,
+    };
+    if hover.is_none() && press.is_none() {
+        return Ok(());
+    }
+
+    unthemed
+        .actions
+        .get_or_insert_with(|| retriggers.get(entity).cloned());
+    let retrigger = retriggers
+        .entry(entity)?
+        .or_insert_with(UiButtonActionRetrigger::default);
+    add_image_actions(retrigger, entity, hover, press);
+    Ok(())
+}
+
+/// Adds the actions setting the `hover` and `press` images of `entity` to its `retrigger`, which
+/// only has the actions of the entity before it was styled.
+fn add_image_actions(
+    retrigger: &mut UiButtonActionRetrigger,
+    entity: Entity,
+    hover: Option<UiImage>,
+    press: Option<UiImage>,
+) {
+    let actions = |image: UiImage| {
+        (
+            UiButtonAction {
+                target: entity,
+                event_type: UiButtonActionType::SetImage(image.clone()),
+            },
+            UiButtonAction {
+                target: entity,
+                event_type: UiButtonActionType::UnsetTexture(image),
+            },
+        )
+    };
+    if let Some((set, unset)) = hover.map(actions) {
+        retrigger.on_hover_start.push(set);
+        retrigger.on_hover_stop.push(unset);
+    }
+    if let Some((set, unset)) = press.map(actions) {
+        retrigger.on_click_start.push(set);
+        retrigger.on_click_stop.push(unset);
+    }
+}
+
+fn apply_sounds(
+    entity: Entity,
+    style: &UiStyle,
+    overrides: &UiStyleOverrides<UiButton>,
+    unthemed: &mut Unthemed,
+    retriggers: &mut WriteStorage<'_, UiSoundRetrigger>,
+) {
+    let handle = |sound: &Option<AssetPrefab<Audio>>, overridden: bool| match (sound, overridden) {
+        (Some(AssetPrefab::Handle(ref handle)), false) => Some(UiPlaySoundAction(handle.clone())),
+        _ => None,
+    };
+    let hover = handle(&style.hover_sound, overrides.hover_sound);
+    let press = handle(&style.press_sound, overrides.press_sound);
+    let release = handle(&style.release_sound, overrides.release_sound);
+    if hover.is_none() && press.is_none() && release.is_none() {
+        return;
+    }
+
+    unthemed
+        .sounds
+        .get_or_insert_with(|| retriggers.get(entity).cloned());
+    let retrigger = match retriggers.entry(entity) {
+        Ok(entry) => entry.or_insert_with(|| UiSoundRetrigger {
+            on_click_start: None,
+            on_click_stop: None,
+            on_hover_start: None,
+            on_hover_stop: None,
+        }),
+        Err(_) => return,
+    };
+    if hover.is_some() {
+        retrigger.on_hover_start = hover;
+    }
+    if press.is_some() {
+        retrigger.on_click_start = press;
+    }
+    if release.is_some() {
+        retrigger.on_click_stop = release;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+    use specs_hierarchy::HierarchySystem;
+
+    use super::*;
+    use crate::{font::default::test_font, Anchor, LineMode};
+    use amethyst_assets::{Loader, ProcessingState};
+    use amethyst_core::{
+        ecs::{Builder, RunNow, World, WorldExt},
+        Parent, SystemDesc,
+    };
+
+    const RED: [f32; 4] = [1., 0., 0., 1.];
+    const GREEN: [f32; 4] = [0., 1., 0., 1.];
+    const BLUE: [f32; 4] = [0., 0., 1., 1.];
+
+    fn set_image(entity: Entity, color: [f32; 4]) -> UiButtonAction {
+        UiButtonAction {
+            target: entity,
+            event_type: UiButtonActionType::SetImage(UiImage::SolidColor(color)),
+        }
+    }
+
+    fn image_colors(actions: &[UiButtonAction]) -> Vec<[f32; 4]> {
+        actions
+            .iter()
+            .filter_map(|action| match action.event_type {
+                UiButtonActionType::SetImage(UiImage::SolidColor(color))
+                | UiButtonActionType::UnsetTexture(UiImage::SolidColor(color)) => Some(color),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<UiTransform>();
+        world.register::<UiText>();
+        world.register::<UiImage>();
+        world.register::<UiButtonActionRetrigger>();
+        world.register::<UiSoundRetrigger>();
+        world
+    }
+
+    fn theme(color: [f32; 4]) -> UiTheme {
+        let style = UiStyle {
+            text_color: Some(color),
+            ..Default::default()
+        };
+        UiTheme {
+            styles: std::iter::once(("primary".to_string(), style)).collect(),
+        }
+    }
+
+    #[test]
+    fn a_loading_theme_is_applied_once_loaded() {
+        let mut world = world();
+        let pool = Arc::new(ThreadPoolBuilder::default().build().unwrap());
+        world.insert(pool.clone());
+        world.insert(Loader::new(".", pool.clone()));
+        let mut hierarchy = HierarchySystem::<Parent>::new(&mut world);
+        let mut system = UiThemeSystemDesc::default().build(&mut world);
+        RunNow::setup(&mut system, &mut world);
+        let mut run = |world: &mut World| {
+            hierarchy.run_now(world);
+            system.run_now(world);
+            world.maintain();
+        };
+
+        let loaded = world
+            .write_resource::<AssetStorage<UiTheme>>()
+            .insert(theme(RED));
+        let loading = world.read_resource::<Loader>().load_from_data(
+            theme(GREEN),
+            (),
+            &world.read_resource::<AssetStorage<UiTheme>>(),
+        );
+        let text = UiText::new(
+            test_font(),
+            String::new(),
+            BLUE,
+            10.,
+            LineMode::Single,
+            Anchor::Middle,
+        );
+        let entity = world
+            .create_entity()
+            .with(text)
+            .with(UiClass("primary".to_string()))
+            .build();
+        let color = |world: &World| world.read_storage::<UiText>().get(entity).unwrap().color;
+
+        world.write_resource::<UiActiveTheme>().theme = Some(loaded);
+        run(&mut world);
+        assert_eq!(color(&world), RED);
+
+        // The previous theme stays until the new one is loaded.
+        world.write_resource::<UiActiveTheme>().theme = Some(loading);
+        run(&mut world);
+        assert_eq!(color(&world), RED);
+
+        world.write_resource::<AssetStorage<UiTheme>>().process(
+            |theme| Ok(ProcessingState::Loaded(theme)),
+            0,
+            &pool,
+            None,
+        );
+        run(&mut world);
+        assert_eq!(color(&world), GREEN);
+
+        world.write_resource::<UiActiveTheme>().theme = None;
+        run(&mut world);
+        assert_eq!(color(&world), BLUE);
+    }
+
+    #[test]
+    fn padding_keeps_the_stretch_kind() {
+        assert!(padded(&Stretch::NoStretch, 4., 2.).is_none());
+        match padded(&Stretch::X { x_margin: 0. }, 4., 2.) {
+            Some(Stretch::X { x_margin }) => assert_eq!(x_margin, 4.),
+            other => panic!("Unexpected stretch {:?}", other),
+        }
+        let stretch = Stretch::XY {
+            x_margin: 0.,
+            y_margin: 0.,
+            keep_aspect_ratio: true,
+        };
+        match padded(&stretch, 4., 2.) {
+            Some(Stretch::XY {
+                x_margin,
+                y_margin,
+                keep_aspect_ratio,
+            }) => assert_eq!((x_margin, y_margin, keep_aspect_ratio), (4., 2., true)),
+            other => panic!("Unexpected stretch {:?}", other),
+        }
+    }
+
+    #[test]
+    fn image_actions_are_added_to_the_unstyled_ones() {
+        let mut world = world();
+        let entity = world.create_entity().build();
+        let mut retrigger = UiButtonActionRetrigger::default();
+        retrigger.on_click_start.push(set_image(entity, RED));
+
+        add_image_actions(
+            &mut retrigger,
+            entity,
+            Some(UiImage::SolidColor(GREEN)),
+            Some(UiImage::SolidColor(BLUE)),
+        );
+        assert_eq!(image_colors(&retrigger.on_hover_start), vec![GREEN]);
+        assert_eq!(image_colors(&retrigger.on_hover_stop), vec![GREEN]);
+        assert_eq!(image_colors(&retrigger.on_click_start), vec![RED, BLUE]);
+        assert_eq!(image_colors(&retrigger.on_click_stop), vec![BLUE]);
+    }
+
+    #[test]
+    fn restoring_removes_every_styled_value() {
+        let mut world = world();
+        let with_image = world.create_entity().with(UiImage::SolidColor(RED)).build();
+        let without_image = world.create_entity().build();
+        let (mut transforms, mut texts, mut images, mut button_retriggers, mut sound_retriggers) =
+            world.system_data::<(
+                WriteStorage<'_, UiTransform>,
+                WriteStorage<'_, UiText>,
+                WriteStorage<'_, UiImage>,
+                WriteStorage<'_, UiButtonActionRetrigger>,
+                WriteStorage<'_, UiSoundRetrigger>,
+            )>();
+
+        // Style both entities like `apply_images` does.
+        let mut unthemed = HashMap::new();
+        for &entity in &[with_image, without_image] {
+            let record: &mut Unthemed = unthemed.entry(entity).or_default();
+            record
+                .image
+                .get_or_insert_with(|| images.get(entity).cloned());
+            record
+                .actions
+                .get_or_insert_with(|| button_retriggers.get(entity).cloned());
+            images.insert(entity, UiImage::SolidColor(GREEN)).unwrap();
+            let retrigger = button_retriggers
+                .entry(entity)
+                .unwrap()
+                .or_insert_with(UiButtonActionRetrigger::default);
+            add_image_actions(retrigger, entity, Some(UiImage::SolidColor(BLUE)), None);
+        }
+
+        for (entity, record) in unthemed {
+            record
+                .restore(
+                    entity,
+                    &mut transforms,
+                    &mut texts,
+                    &mut images,
+                    &mut button_retriggers,
+                    &mut sound_retriggers,
+                )
+                .unwrap();
+        }
+        assert_eq!(images.get(with_image), Some(&UiImage::SolidColor(RED)));
+        assert_eq!(images.get(without_image), None);
+        assert!(button_retriggers.get(with_image).is_none());
+        assert!(button_retriggers.get(without_image).is_none());
+    }
+}
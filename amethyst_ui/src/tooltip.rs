@@ -0,0 +1,648 @@
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        ReaderId, System, SystemData, World, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    HiddenPropagate, Parent, ParentHierarchy, SystemDesc, Time,
+};
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_window::ScreenDimensions;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    get_default_font,
+    menu::{cursor_in, rect_in, root_area, root_of},
+    Anchor, FontAsset, FontHandle, LineMode, UiCanvas, UiEvent, UiEventType, UiImage, UiText,
+    UiTransform,
+};
+
+/// The z coordinate at which tooltips are shown, above all regular ui elements.
+const TOOLTIP_Z: f32 = 1000.0;
+
+/// What a `UiTooltip` shows.
+#[derive(Debug, Clone)]
+pub enum UiTooltipContent {
+    /// A text tooltip, drawn using the `UiTooltipStyle` resource.
+    Text(String),
+    /// An existing root ui entity, for example created from a prefab with the `UiCreator`.
+    /// The entity is kept hidden using `HiddenPropagate` until the tooltip is shown. It is then
+    /// added to the root of the ui of the target, and its `UiTransform` is moved next to the
+    /// cursor or the target.
+    /// It should not be opaque, otherwise it steals the hover from its target.
+    Widget(Entity),
+    /// The ui entity with this `UiTransform` id in the same ui tree as the target, like a hidden
+    /// container declared in the same prefab. It is replaced by `Widget` once found.
+    WidgetId(String),
+}
+
+/// Where a tooltip is placed relative to its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiTooltipPlacement {
+    /// Below and to the right of the mouse cursor, following it.
+    Cursor,
+    /// Above the target.
+    Above,
+    /// Below the target.
+    Below,
+    /// Left of the target.
+    Left,
+    /// Right of the target.
+    Right,
+}
+
+impl Default for UiTooltipPlacement {
+    fn default() -> Self {
+        UiTooltipPlacement::Cursor
+    }
+}
+
+/// Shows a tooltip when the cursor hovers this entity for longer than `delay`.
+/// The entity needs a `UiTransform`. The tooltip is hidden on click, on mouse scroll or when the
+/// cursor leaves the entity.
+#[derive(Debug, Clone)]
+pub struct UiTooltip {
+    /// What the tooltip shows.
+    pub content: UiTooltipContent,
+    /// The time in seconds the cursor has to stay on the entity before the tooltip appears.
+    pub delay: f32,
+    /// Where the tooltip is placed. If the tooltip doesn't fit on the screen there, it is flipped
+    /// to the opposite side and then clamped inside of the screen.
+    pub placement: UiTooltipPlacement,
+    /// The size of a text tooltip, in pixels. Ignored for widget tooltips, which use the size of
+    /// their `UiTransform`.
+    pub size: (f32, f32),
+}
+
+impl UiTooltip {
+    /// Creates a text tooltip with the default delay, placement and size.
+    pub fn text<S: ToString>(text: S) -> Self {
+        UiTooltip {
+            content: UiTooltipContent::Text(text.to_string()),
+            delay: 0.5,
+            placement: UiTooltipPlacement::Cursor,
+            size: (200.0, 30.0),
+        }
+    }
+
+    /// Creates a tooltip showing an existing ui entity, with the default delay and placement.
+    pub fn widget(entity: Entity) -> Self {
+        UiTooltip {
+            content: UiTooltipContent::Widget(entity),
+            ..UiTooltip::text("")
+        }
+    }
+
+    /// Creates a tooltip showing the ui entity with the `UiTransform` id `id`, found in the ui tree
+    /// of the target, with the default delay and placement.
+    pub fn widget_id<S: ToString>(id: S) -> Self {
+        UiTooltip {
+            content: UiTooltipContent::WidgetId(id.to_string()),
+            ..UiTooltip::text("")
+        }
+    }
+
+    /// Set the hover delay, in seconds.
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the placement.
+    pub fn with_placement(mut self, placement: UiTooltipPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Set the size of a text tooltip.
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.size = (width, height);
+        self
+    }
+}
+
+impl Component for UiTooltip {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Resource describing how text tooltips look.
+#[derive(Debug, Clone)]
+pub struct UiTooltipStyle {
+    /// The font of the text. If `None`, the default font is used.
+    pub font: Option<FontHandle>,
+    /// The font size of the text.
+    pub font_size: f32,
+    /// The linear RGBA colour of the text.
+    pub text_color: [f32; 4],
+    /// The linear RGBA colour of the background.
+    pub background: [f32; 4],
+    /// The gap in pixels between the tooltip and the cursor or the target.
+    pub gap: f32,
+}
+
+impl Default for UiTooltipStyle {
+    fn default() -> Self {
+        UiTooltipStyle {
+            font: None,
+            font_size: 16.0,
+            text_color: [1.0, 1.0, 1.0, 1.0],
+            background: [0.05, 0.05, 0.05, 0.9],
+            gap: 8.0,
+        }
+    }
+}
+
+/// Computes the center of a tooltip of size `size` placed next to `anchor_rect`, given as
+/// `(center_x, center_y, width, height)` in ui coordinates (origin at the bottom left of the
+/// screen, or of the canvas). The tooltip is flipped to the opposite side if it goes out of the
+/// screen, then clamped inside of it.
+pub fn place_tooltip(
+    placement: UiTooltipPlacement,
+    anchor_rect: (f32, f32, f32, f32),
+    size: (f32, f32),
+    gap: f32,
+    screen: (f32, f32),
+) -> (f32, f32) {
+    let (ax, ay, aw, ah) = anchor_rect;
+    let (w, h) = size;
+    let (half_w, half_h) = (w / 2.0, h / 2.0);
+
+    let right = ax + aw / 2.0 + gap + half_w;
+    let left = ax - aw / 2.0 - gap - half_w;
+    let above = ay + ah / 2.0 + gap + half_h;
+    let below = ay - ah / 2.0 - gap - half_h;
+
+    let fits_x = |x: f32| x - half_w >= 0.0 && x + half_w <= screen.0;
+    let fits_y = |y: f32| y - half_h >= 0.0 && y + half_h <= screen.1;
+    let pick = |preferred: f32, opposite: f32, fits: &dyn Fn(f32) -> bool| {
+        if !fits(preferred) && fits(opposite) {
+            opposite
+        } else {
+            preferred
+        }
+    };
+
+    let (x, y) = match placement {
+        UiTooltipPlacement::Cursor => (pick(right, left, &fits_x), pick(below, above, &fits_y)),
+        UiTooltipPlacement::Above => (ax, pick(above, below, &fits_y)),
+        UiTooltipPlacement::Below => (ax, pick(below, above, &fits_y)),
+        UiTooltipPlacement::Left => (pick(left, right, &fits_x), ay),
+        UiTooltipPlacement::Right => (pick(right, left, &fits_x), ay),
+    };
+
+    (
+        x.min(screen.0 - half_w).max(half_w),
+        y.min(screen.1 - half_h).max(half_h),
+    )
+}
+
+/// Loadable `UiTooltip` data, declared through the `tooltip` field of `UiTransformData`.
+#[derive(Debug, Clone, Deserialize, Serialize, Derivative)]
+#[serde(default)]
+#[derivative(Default)]
+pub struct UiTooltipData {
+    /// The text of the tooltip.
+    pub text: String,
+    /// The id of an element of the same ui to show instead of the text, usually a hidden
+    /// container declared in the same prefab. Its size is the one of its `UiTransform`.
+    pub widget: Option<String>,
+    /// The hover delay, in seconds.
+    #[derivative(Default(value = "0.5"))]
+    pub delay: f32,
+    /// Where the tooltip is placed.
+    pub placement: UiTooltipPlacement,
+    /// The width of the tooltip.
+    #[derivative(Default(value = "200.0"))]
+    pub width: f32,
+    /// The height of the tooltip.
+    #[derivative(Default(value = "30.0"))]
+    pub height: f32,
+}
+
+impl UiTooltipData {
+    /// Builds the `UiTooltip` component described by this data.
+    pub fn to_tooltip(&self) -> UiTooltip {
+        match self.widget {
+            Some(ref id) => UiTooltip::widget_id(id),
+            None => UiTooltip::text(&self.text),
+        }
+        .with_delay(self.delay)
+        .with_placement(self.placement)
+        .with_size(self.width, self.height)
+    }
+}
+
+/// Finds the entity with the `UiTransform` id `id` in the ui tree of `entity`.
+fn find_in_tree(
+    entity: Entity,
+    id: &str,
+    hierarchy: &ParentHierarchy,
+    transforms: &WriteStorage<'_, UiTransform>,
+) -> Option<Entity> {
+    let root = root_of(entity, hierarchy);
+    std::iter::once(root)
+        .chain(hierarchy.all_children_iter(root))
+        .find(|e| transforms.get(*e).map_or(false, |t| t.id == id))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ShownTooltip {
+    target: Entity,
+    entity: Entity,
+    owned: bool,
+}
+
+/// Builds a `UiTooltipSystem`.
+#[derive(Derivative, Debug)]
+#[derivative(Default(bound = ""))]
+pub struct UiTooltipSystemDesc<T: BindingTypes> {
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'b, T: BindingTypes> SystemDesc<'a, 'b, UiTooltipSystem<T>> for UiTooltipSystemDesc<T> {
+    fn build(self, world: &mut World) -> UiTooltipSystem<T> {
+        <UiTooltipSystem<T> as System<'_>>::SystemData::setup(world);
+
+        let ui_reader_id = world.fetch_mut::<EventChannel<UiEvent>>().register_reader();
+
+        UiTooltipSystem::new(ui_reader_id)
+    }
+}
+
+/// System showing and hiding the `UiTooltip`s of the hovered entities.
+#[derive(Debug)]
+pub struct UiTooltipSystem<T: BindingTypes> {
+    ui_reader_id: ReaderId<UiEvent>,
+    hovered: Option<(Entity, f32)>,
+    shown: Option<ShownTooltip>,
+    marker: PhantomData<T>,
+}
+
+impl<T: BindingTypes> UiTooltipSystem<T> {
+    /// Creates a new `UiTooltipSystem`.
+    pub fn new(ui_reader_id: ReaderId<UiEvent>) -> Self {
+        UiTooltipSystem {
+            ui_reader_id,
+            hovered: None,
+            shown: None,
+            marker: PhantomData,
+        }
+    }
+
+    fn hide(
+        &mut self,
+        entities: &Entities<'_>,
+        hidden_props: &mut WriteStorage<'_, HiddenPropagate>,
+    ) {
+        if let Some(shown) = self.shown.take() {
+            if shown.owned {
+                let _ = entities.delete(shown.entity);
+            } else if entities.is_alive(shown.entity) {
+                let _ = hidden_props.insert(shown.entity, HiddenPropagate::new());
+            }
+        }
+    }
+}
+
+impl<'a, T: BindingTypes> System<'a> for UiTooltipSystem<T> {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, UiTooltip>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, HiddenPropagate>,
+        WriteStorage<'a, Parent>,
+        ReadStorage<'a, UiCanvas>,
+        Read<'a, EventChannel<UiEvent>>,
+        Read<'a, InputHandler<T>>,
+        Read<'a, Time>,
+        Read<'a, UiTooltipStyle>,
+        ReadExpect<'a, ScreenDimensions>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<FontAsset>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut tooltips,
+            mut transforms,
+            mut texts,
+            mut images,
+            mut hidden_props,
+            mut parents,
+            canvases,
+            ui_events,
+            input,
+            time,
+            style,
+            screen_dimensions,
+            hierarchy,
+            loader,
+            font_storage,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_tooltip_system");
+
+        for (target, tooltip) in (&*entities, &mut tooltips).join() {
+            let found = match tooltip.content {
+                UiTooltipContent::WidgetId(ref id) => {
+                    find_in_tree(target, id, &hierarchy, &transforms)
+                }
+                _ => None,
+            };
+            if let Some(entity) = found {
+                tooltip.content = UiTooltipContent::Widget(entity);
+            }
+        }
+
+        for event in ui_events.read(&mut self.ui_reader_id) {
+            match event.event_type {
+                UiEventType::HoverStart if tooltips.contains(event.target) => {
+                    self.hovered = Some((event.target, 0.0));
+                }
+                UiEventType::HoverStop => {
+                    if self.hovered.map(|(e, _)| e) == Some(event.target) {
+                        self.hovered = None;
+                    }
+                    if self.shown.map(|s| s.target) == Some(event.target) {
+                        self.hide(&entities, &mut hidden_props);
+                    }
+                }
+                UiEventType::Click(_) | UiEventType::ClickStart(_) => {
+                    // Don't show the tooltip again until the cursor enters the target again.
+                    self.hovered = None;
+                    self.hide(&entities, &mut hidden_props);
+                }
+                _ => {}
+            }
+        }
+
+        if input.mouse_wheel_value(false) != 0.0 || input.mouse_wheel_value(true) != 0.0 {
+            self.hovered = None;
+            self.hide(&entities, &mut hidden_props);
+        }
+
+        // The target or the tooltip component may have been removed.
+        if let Some(shown) = self.shown {
+            if !tooltips.contains(shown.target) || !entities.is_alive(shown.entity) {
+                self.hide(&entities, &mut hidden_props);
+            }
+        }
+
+        if self.shown.is_none() {
+            if let Some((target, ref mut timer)) = self.hovered {
+                *timer += time.delta_real_seconds();
+                let delay = tooltips.get(target).map(|t| t.delay);
+                match delay {
+                    Some(delay) if *timer >= delay => {
+                        let tooltip = tooltips.get(target).expect("unreachable: checked above");
+                        self.shown = match tooltip.content {
+                            UiTooltipContent::Text(ref text) => {
+                                let font = style
+                                    .font
+                                    .clone()
+                                    .unwrap_or_else(|| get_default_font(&loader, &font_storage));
+                                let mut transform = UiTransform::new(
+                                    "tooltip".to_string(),
+                                    Anchor::BottomLeft,
+                                    Anchor::Middle,
+                                    0.0,
+                                    0.0,
+                                    TOOLTIP_Z,
+                                    tooltip.size.0,
+                                    tooltip.size.1,
+                                )
+                                .into_transparent();
+                                transform.pixel_width = tooltip.size.0;
+                                transform.pixel_height = tooltip.size.1;
+                                let entity = entities
+                                    .build_entity()
+                                    .with(transform, &mut transforms)
+                                    .with(UiImage::SolidColor(style.background), &mut images)
+                                    .with(
+                                        UiText::new(
+                                            font,
+                                            text.clone(),
+                                            style.text_color,
+                                            style.font_size,
+                                            LineMode::Wrap,
+                                            Anchor::Middle,
+                                        ),
+                                        &mut texts,
+                                    )
+                                    .build();
+                                Some(ShownTooltip {
+                                    target,
+                                    entity,
+                                    owned: true,
+                                })
+                            }
+                            // The widget is not found yet.
+                            UiTooltipContent::WidgetId(_) => None,
+                            UiTooltipContent::Widget(entity) => {
+                                hidden_props.remove(entity);
+                                if let Some(transform) = transforms.get_mut(entity) {
+                                    transform.anchor = Anchor::BottomLeft;
+                                    transform.pivot = Anchor::Middle;
+                                    transform.local_z = TOOLTIP_Z;
+                                }
+                                Some(ShownTooltip {
+                                    target,
+                                    entity,
+                                    owned: false,
+                                })
+                            }
+                        };
+                    }
+                    Some(_) => {}
+                    None => self.hovered = None,
+                }
+            }
+        }
+
+        // Widget tooltips stay hidden while they are not shown.
+        for tooltip in (&tooltips).join() {
+            if let UiTooltipContent::Widget(entity) = tooltip.content {
+                if self.shown.map(|s| s.entity) != Some(entity)
+                    && !hidden_props.contains(entity)
+                    && entities.is_alive(entity)
+                {
+                    let _ = hidden_props.insert(entity, HiddenPropagate::new());
+                }
+            }
+        }
+
+        // Keep the tooltip next to the cursor or its target, in the layout space of the root of
+        // the ui of the target, which the tooltip is added to.
+        if let Some(shown) = self.shown {
+            let placement = tooltips
+                .get(shown.target)
+                .map(|t| t.placement)
+                .unwrap_or_default();
+            let root = root_of(shown.target, &hierarchy);
+            if root != shown.entity && parents.get(shown.entity).map(|p| p.entity) != Some(root) {
+                let _ = parents.insert(shown.entity, Parent { entity: root });
+            }
+            let anchor_rect = match placement {
+                UiTooltipPlacement::Cursor => input
+                    .mouse_position()
+                    .and_then(|(x, y)| {
+                        let y = screen_dimensions.height() - y;
+                        cursor_in(root, (x, y), &entities, &canvases, &transforms, &images)
+                    })
+                    .map(|(x, y)| (x, y, 0.0, 0.0)),
+                _ => rect_in(shown.target, root, &transforms).map(|(left, bottom, right, top)| {
+                    (
+                        (left + right) / 2.0,
+                        (bottom + top) / 2.0,
+                        right - left,
+                        top - bottom,
+                    )
+                }),
+            };
+            let area = root_area(root, &canvases, &transforms, &screen_dimensions);
+            let root_corner = transforms.get(root).map(|root| {
+                (
+                    root.pixel_x() - root.pixel_width() / 2.0,
+                    root.pixel_y() - root.pixel_height() / 2.0,
+                )
+            });
+            if let (Some(anchor_rect), Some((root_left, root_bottom)), Some(transform)) =
+                (anchor_rect, root_corner, transforms.get_mut(shown.entity))
+            {
+                let (x, y) = place_tooltip(
+                    placement,
+                    anchor_rect,
+                    (transform.pixel_width(), transform.pixel_height()),
+                    style.gap,
+                    area,
+                );
+                // The tooltip is placed from the bottom left corner of the root.
+                transform.local_x = x - root_left;
+                transform.local_y = y - root_bottom;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: (f32, f32) = (800.0, 600.0);
+
+    #[test]
+    fn cursor_tooltips_go_below_right() {
+        let (x, y) = place_tooltip(
+            UiTooltipPlacement::Cursor,
+            (100.0, 300.0, 0.0, 0.0),
+            (200.0, 30.0),
+            8.0,
+            SCREEN,
+        );
+        assert_eq!((x, y), (208.0, 277.0));
+    }
+
+    #[test]
+    fn cursor_tooltips_flip_at_the_edges() {
+        // Near the bottom right corner of the screen.
+        let (x, y) = place_tooltip(
+            UiTooltipPlacement::Cursor,
+            (750.0, 20.0, 0.0, 0.0),
+            (200.0, 30.0),
+            8.0,
+            SCREEN,
+        );
+        assert_eq!((x, y), (642.0, 43.0));
+    }
+
+    #[test]
+    fn target_placements_are_centered_on_the_target() {
+        let target = (400.0, 300.0, 100.0, 50.0);
+        let place = |placement| place_tooltip(placement, target, (80.0, 20.0), 4.0, SCREEN);
+        assert_eq!(place(UiTooltipPlacement::Above), (400.0, 339.0));
+        assert_eq!(place(UiTooltipPlacement::Below), (400.0, 261.0));
+        assert_eq!(place(UiTooltipPlacement::Left), (306.0, 300.0));
+        assert_eq!(place(UiTooltipPlacement::Right), (494.0, 300.0));
+    }
+
+    #[test]
+    fn target_placements_flip_to_the_opposite_side() {
+        // A target at the top of the screen.
+        let (x, y) = place_tooltip(
+            UiTooltipPlacement::Above,
+            (400.0, 590.0, 100.0, 20.0),
+            (80.0, 20.0),
+            4.0,
+            SCREEN,
+        );
+        assert_eq!((x, y), (400.0, 566.0));
+        // A target at the left of the screen.
+        let (x, _) = place_tooltip(
+            UiTooltipPlacement::Left,
+            (20.0, 300.0, 40.0, 20.0),
+            (80.0, 20.0),
+            4.0,
+            SCREEN,
+        );
+        assert_eq!(x, 84.0);
+    }
+
+    #[test]
+    fn tooltips_are_clamped_inside_of_the_screen() {
+        // Neither side fits horizontally.
+        let (x, _) = place_tooltip(
+            UiTooltipPlacement::Right,
+            (400.0, 300.0, 700.0, 20.0),
+            (200.0, 20.0),
+            4.0,
+            SCREEN,
+        );
+        assert_eq!(x, 700.0);
+        // Above and below a target centered on the screen, but larger than it.
+        let (x, y) = place_tooltip(
+            UiTooltipPlacement::Below,
+            (10.0, 300.0, 20.0, 20.0),
+            (200.0, 20.0),
+            4.0,
+            SCREEN,
+        );
+        assert_eq!((x, y), (100.0, 276.0));
+    }
+
+    #[test]
+    fn data_builds_text_or_widget_tooltips() {
+        let data = UiTooltipData {
+            text: "Hello".to_string(),
+            ..Default::default()
+        };
+        match data.to_tooltip().content {
+            UiTooltipContent::Text(ref text) => assert_eq!(text, "Hello"),
+            ref content => panic!("Unexpected tooltip content {:?}", content),
+        }
+
+        let data = UiTooltipData {
+            widget: Some("item_details".to_string()),
+            delay: 1.0,
+            ..Default::default()
+        };
+        let tooltip = data.to_tooltip();
+        assert_eq!(tooltip.delay, 1.0);
+        match tooltip.content {
+            UiTooltipContent::WidgetId(ref id) => assert_eq!(id, "item_details"),
+            ref content => panic!("Unexpected tooltip content {:?}", content),
+        }
+    }
+}
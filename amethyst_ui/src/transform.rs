@@ -8,6 +8,7 @@ use amethyst_core::{
         shred::{ResourceId, SystemData},
         storage::GenericReadStorage,
     },
+    math::{Matrix3, Point2, Vector2},
     ParentHierarchy,
 };
 use amethyst_window::ScreenDimensions;
@@ -64,6 +65,14 @@ pub struct UiTransform {
     pub width: f32,
     /// The height of this UI element.
     pub height: f32,
+    /// Horizontal and vertical scale factors of this UI element, applied around its pivot.
+    /// Children are scaled along with their parent.
+    #[serde(default = "default_scale")]
+    pub scale: [f32; 2],
+    /// Counter-clockwise rotation in radians of this UI element, around its pivot.
+    /// Children are rotated along with their parent.
+    #[serde(default)]
+    pub rotation: f32,
     /// Global x position set by the `UiTransformSystem`.
     pub(crate) pixel_x: f32,
     /// Global y position set by the `UiTransformSystem`.
@@ -74,6 +83,18 @@ pub struct UiTransform {
     pub(crate) pixel_width: f32,
     /// Height in pixels, used for rendering.  Duplicate of `height` if `scale_mode == ScaleMode::Pixel`.
     pub(crate) pixel_height: f32,
+    /// Transforms the layout space of this element to screen space, set by the `UiTransformSystem`.
+    /// Combines the scale and rotation of this element and of all its ancestors.
+    #[serde(skip, default = "Matrix3::identity")]
+    pub(crate) global_matrix: Matrix3<f32>,
+    /// Moves points relative to the center of a root element to screen space, set by the
+    /// `UiWorldSystem` for `UiWorldCanvas` roots.
+    #[serde(skip)]
+    pub(crate) canvas_matrix: Option<Matrix3<f32>>,
+    /// How many screen pixels make up a pixel of this element, set by the `UiTransformSystem`
+    /// from the `UiScaler`. Used to rasterise text at the resolution it is drawn at.
+    #[serde(skip, default = "default_scale_factor")]
+    pub(crate) scale_factor: f32,
     /// The scale mode indicates if the position is in pixel or is relative (%) (WIP!) to the parent's size.
     pub scale_mode: ScaleMode,
     /// Indicates if actions on the ui can go through this element.
@@ -87,6 +108,14 @@ pub struct UiTransform {
     pd: PhantomData<()>,
 }
 
+fn default_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+fn default_scale_factor() -> f32 {
+    1.0
+}
+
 impl UiTransform {
     /// Creates a new UiTransform.
     /// By default, it is considered opaque.
@@ -110,11 +139,16 @@ impl UiTransform {
             local_z: z,
             width,
             height,
+            scale: default_scale(),
+            rotation: 0.,
             pixel_x: x,
             pixel_y: y,
             global_z: z,
             pixel_width: width,
             pixel_height: height,
+            global_matrix: Matrix3::identity(),
+            canvas_matrix: None,
+            scale_factor: default_scale_factor(),
             scale_mode: ScaleMode::Pixel,
             opaque: true,
             transparent_target: false,
@@ -131,13 +165,30 @@ impl UiTransform {
     }
 
     /// Checks if the input position is in the UiTransform rectangle.
+    /// Takes the scale and rotation of this element and its ancestors into account.
     pub fn position_inside(&self, x: f32, y: f32) -> bool {
+        let (x, y) = match self.to_layout_space(x, y) {
+            Some(pos) => pos,
+            None => return false,
+        };
         x > self.pixel_x - self.pixel_width / 2.0
             && y > self.pixel_y - self.pixel_height / 2.0
             && x < self.pixel_x + self.pixel_width / 2.0
             && y < self.pixel_y + self.pixel_height / 2.0
     }
 
+    /// Sets the scale factors of this UI element.
+    pub fn with_scale(mut self, x: f32, y: f32) -> Self {
+        self.scale = [x, y];
+        self
+    }
+
+    /// Sets the rotation of this UI element, in radians.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
     /// Renders this UI element by evaluating transform as a percentage of the parent size,
     /// rather than rendering it with pixel units.
     pub fn into_percent(mut self) -> Self {
@@ -181,6 +232,45 @@ impl UiTransform {
     pub fn pixel_height(&self) -> f32 {
         self.pixel_height
     }
+
+    /// Returns the matrix transforming the unrotated and unscaled layout of this UiTransform
+    /// (`pixel_x`, `pixel_y`, `pixel_width` and `pixel_height`) to screen space, as computed by
+    /// the `UiTransformSystem`.
+    pub fn global_matrix(&self) -> &Matrix3<f32> {
+        &self.global_matrix
+    }
+
+    /// Transforms a point from the layout space of this UiTransform to screen space.
+    pub fn to_screen_space(&self, x: f32, y: f32) -> (f32, f32) {
+        let point = self.global_matrix.transform_point(&Point2::new(x, y));
+        (point.x, point.y)
+    }
+
+    /// Transforms a screen space point to the layout space of this UiTransform.
+    /// Returns `None` if the element is scaled down to nothing.
+    pub fn to_layout_space(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        self.global_matrix.try_inverse().map(|inverse| {
+            let point = inverse.transform_point(&Point2::new(x, y));
+            (point.x, point.y)
+        })
+    }
+
+    /// Computes the scale and rotation of this element around its pivot, in the layout space of
+    /// its parent. Expects the pixel values to be up to date.
+    pub(crate) fn local_matrix(&self) -> Matrix3<f32> {
+        if self.rotation == 0. && self.scale == [1., 1.] {
+            return Matrix3::identity();
+        }
+        let pivot_norm = self.pivot.norm_offset();
+        let pivot = Vector2::new(
+            self.pixel_x + self.pixel_width * pivot_norm.0,
+            self.pixel_y + self.pixel_height * pivot_norm.1,
+        );
+        Matrix3::new_translation(&pivot)
+            * Matrix3::new_rotation(self.rotation)
+            * Matrix3::new_nonuniform_scaling(&Vector2::new(self.scale[0], self.scale[1]))
+            * Matrix3::new_translation(&-pivot)
+    }
 }
 
 impl Component for UiTransform {
@@ -188,14 +278,16 @@ impl Component for UiTransform {
 }
 
 /// Get the (width, height) in pixels of the parent of this `UiTransform`.
+/// For root elements, this is the size of the screen in ui pixels, as scaled by the `UiScaler`.
 pub fn get_parent_pixel_size<S: GenericReadStorage<Component = UiTransform>>(
     entity: Entity,
     hierarchy: &ParentHierarchy,
     ui_transforms: &S,
     screen_dimensions: &ScreenDimensions,
 ) -> (f32, f32) {
-    let mut parent_width = screen_dimensions.width();
-    let mut parent_height = screen_dimensions.height();
+    let scale_factor = ui_transforms.get(entity).map_or(1.0, |t| t.scale_factor);
+    let mut parent_width = screen_dimensions.width() / scale_factor;
+    let mut parent_height = screen_dimensions.height() / scale_factor;
 
     if let Some(parent) = hierarchy.parent(entity) {
         if let Some(ui_transform) = ui_transforms.get(parent) {
@@ -245,4 +337,59 @@ mod tests {
         let pos = (-1.49, 1.20);
         assert!(!tr.position_inside(pos.0, pos.1));
     }
+
+    fn bar() -> UiTransform {
+        // A 4x2 bar centered on (10, 10), pivoting around its left edge.
+        let mut tr = UiTransform::new(
+            "".to_string(),
+            Anchor::BottomLeft,
+            Anchor::MiddleLeft,
+            8.0,
+            10.0,
+            0.0,
+            4.0,
+            2.0,
+        );
+        tr.pixel_x = 10.0;
+        tr.pixel_y = 10.0;
+        tr
+    }
+
+    #[test]
+    fn inside_rotated() {
+        let mut tr = bar().with_rotation(std::f32::consts::FRAC_PI_2);
+        tr.global_matrix = tr.local_matrix();
+        // The bar now points up from its pivot at (8, 10).
+        assert!(tr.position_inside(8.0, 13.5));
+        assert!(tr.position_inside(8.9, 10.5));
+        assert!(!tr.position_inside(11.5, 10.0));
+        assert!(!tr.position_inside(8.0, 9.0));
+    }
+
+    #[test]
+    fn inside_scaled() {
+        let mut tr = bar().with_scale(2.0, 0.5);
+        tr.global_matrix = tr.local_matrix();
+        // The bar spans from x = 8 to x = 16 and from y = 9.5 to y = 10.5.
+        assert!(tr.position_inside(15.5, 10.0));
+        assert!(!tr.position_inside(16.5, 10.0));
+        assert!(!tr.position_inside(10.0, 10.75));
+    }
+
+    #[test]
+    fn inside_scaled_to_nothing() {
+        let mut tr = bar().with_scale(0.0, 1.0);
+        tr.global_matrix = tr.local_matrix();
+        assert!(!tr.position_inside(8.0, 10.0));
+    }
+
+    #[test]
+    fn screen_space_round_trip() {
+        let mut tr = bar().with_rotation(0.7).with_scale(1.5, 3.0);
+        tr.global_matrix = tr.local_matrix();
+        let (x, y) = tr.to_screen_space(11.0, 10.5);
+        let (x, y) = tr.to_layout_space(x, y).unwrap();
+        assert!((x - 11.0).abs() < 1e-4);
+        assert!((y - 10.5).abs() < 1e-4);
+    }
 }
@@ -0,0 +1,820 @@
+//! Tree views.
+
+use std::{collections::HashMap, fmt, marker::PhantomData, sync::Arc};
+
+use winit::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReaderId, System,
+        SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    HiddenPropagate, Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+
+use crate::{
+    font::default::get_default_font,
+    list::{set_layout, transform_id},
+    rows::{create_row, hide_row, show_row, show_selection, sync_selection, view_of, Row},
+    Anchor, FontAsset, FontHandle, Interactable, LineMode, Selectable, Selected, Stretch, UiEvent,
+    UiEventType, UiImage, UiText, UiTransform,
+};
+
+const DEFAULT_ROW_HEIGHT: f32 = 24.0;
+const DEFAULT_INDENT: f32 = 16.0;
+const DEFAULT_FONT_SIZE: f32 = 18.0;
+const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+type ChildLoader = Arc<dyn Fn(u64) -> Vec<UiTreeNode> + Send + Sync>;
+
+/// A node of a `UiTreeView`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiTreeNode {
+    /// The id of the node, carried by the events of the tree. Should be unique in the tree.
+    pub id: u64,
+    /// The text shown for the node.
+    pub label: String,
+    /// The children of the node.
+    pub children: Vec<UiTreeNode>,
+    /// Whether the children are loaded by the child loader of the tree the first time the node
+    /// is expanded.
+    pub lazy: bool,
+    /// Whether the children are shown.
+    pub expanded: bool,
+}
+
+impl UiTreeNode {
+    /// Creates a new collapsed node without children.
+    pub fn new<S: ToString>(id: u64, label: S) -> Self {
+        UiTreeNode {
+            id,
+            label: label.to_string(),
+            children: Vec::new(),
+            lazy: false,
+            expanded: false,
+        }
+    }
+
+    /// Sets the children of the node.
+    pub fn with_children(mut self, children: Vec<UiTreeNode>) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// Loads the children with the child loader of the tree when the node is first expanded.
+    pub fn lazy(mut self) -> Self {
+        self.lazy = true;
+        self
+    }
+
+    /// Shows the children of the node.
+    pub fn expanded(mut self) -> Self {
+        self.expanded = true;
+        self
+    }
+
+    /// Checks if the node has, or can load, children.
+    pub fn is_expandable(&self) -> bool {
+        self.lazy || !self.children.is_empty()
+    }
+}
+
+/// The children of a row showing a node.
+#[derive(Debug)]
+struct TreeRowElements {
+    toggle: Entity,
+    label: Entity,
+}
+
+/// A row showing a node.
+type TreeRow = Row<u64, TreeRowElements>;
+
+/// A node in the order the nodes are shown.
+#[derive(Debug)]
+struct FlatNode<'a> {
+    node: &'a UiTreeNode,
+    parent: Option<u64>,
+    depth: usize,
+}
+
+/// Something the keyboard did to a tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TreeAction {
+    Select(u64),
+    Expand(u64, bool),
+    Activate(u64),
+}
+
+/// Component showing a tree of `UiTreeNode`s in the `UiTransform` of its entity.
+///
+/// Each expanded node shows its children below it, indented. Clicking the toggle next to a node
+/// expands or collapses it. The rows are `Selectable`, with the tab orders following `tab_order`,
+/// and while a node is selected the arrow keys move the selection, right expands and left
+/// collapses the node, and enter activates it, like double clicking it does. The tree receives
+/// `NodeSelected`, `NodeActivated`, `NodeExpanded` and `NodeCollapsed` events carrying the id of
+/// the node.
+///
+/// Like `UiListView`, only the rows that fit in the tree get entities, and the mouse wheel scrolls
+/// the tree one row at a time.
+pub struct UiTreeView {
+    /// The top level nodes.
+    pub roots: Vec<UiTreeNode>,
+    child_loader: Option<ChildLoader>,
+    /// How much each level is indented.
+    pub indent: f32,
+    /// The height of the rows.
+    pub row_height: f32,
+    /// The font of the labels. Defaults to the default font.
+    pub font: Option<FontHandle>,
+    /// The font size of the labels.
+    pub font_size: f32,
+    /// The text color of the labels.
+    pub text_color: [f32; 4],
+    /// The image drawn behind the rows.
+    pub row_image: Option<UiImage>,
+    /// The image drawn behind the selected row.
+    pub selected_row_image: Option<UiImage>,
+    /// The tab order of the first shown row, the next rows take the following orders.
+    pub tab_order: u32,
+    selected: Option<u64>,
+    /// Whether the focus is on the tree, which keeps it while the selected row is out of view.
+    focused: bool,
+    /// The position of the first visible node.
+    first_row: usize,
+    rows: Vec<TreeRow>,
+}
+
+impl fmt::Debug for UiTreeView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UiTreeView")
+            .field("roots", &self.roots)
+            .field("child_loader", &self.child_loader.is_some())
+            .field("selected", &self.selected)
+            .field("first_row", &self.first_row)
+            .finish()
+    }
+}
+
+impl UiTreeView {
+    /// Creates a tree showing `roots`.
+    pub fn new(roots: Vec<UiTreeNode>) -> Self {
+        UiTreeView {
+            roots,
+            child_loader: None,
+            indent: DEFAULT_INDENT,
+            row_height: DEFAULT_ROW_HEIGHT,
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            text_color: DEFAULT_TXT_COLOR,
+            row_image: None,
+            selected_row_image: None,
+            tab_order: 0,
+            selected: None,
+            focused: false,
+            first_row: 0,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Sets the callback returning the children of a lazy node, called the first time the node
+    /// is expanded.
+    pub fn with_child_loader<F>(mut self, loader: F) -> Self
+    where
+        F: Fn(u64) -> Vec<UiTreeNode> + Send + Sync + 'static,
+    {
+        self.child_loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Sets how much each level is indented.
+    pub fn with_indent(mut self, indent: f32) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sets the height of the rows.
+    pub fn with_row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    /// Sets the font of the labels.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of the labels.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Sets the text color of the labels.
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    /// Sets the images drawn behind the rows and behind the selected row.
+    pub fn with_row_images(mut self, row: UiImage, selected: UiImage) -> Self {
+        self.row_image = Some(row);
+        self.selected_row_image = Some(selected);
+        self
+    }
+
+    /// Sets the tab order of the first shown row.
+    pub fn with_tab_order(mut self, tab_order: u32) -> Self {
+        self.tab_order = tab_order;
+        self
+    }
+
+    /// Returns the id of the selected node, if any.
+    pub fn selected_node(&self) -> Option<u64> {
+        self.selected
+    }
+
+    /// Selects a node.
+    pub fn select_node(&mut self, node: Option<u64>) {
+        self.selected = node;
+    }
+
+    /// Returns the node with the id `id`.
+    pub fn node(&self, id: u64) -> Option<&UiTreeNode> {
+        find_node(&self.roots, id)
+    }
+
+    /// Returns the node with the id `id`.
+    pub fn node_mut(&mut self, id: u64) -> Option<&mut UiTreeNode> {
+        find_node_mut(&mut self.roots, id)
+    }
+
+    /// Expands or collapses a node, loading its children if it is lazy. Returns whether the node
+    /// changed.
+    pub fn set_expanded(&mut self, id: u64, expanded: bool) -> bool {
+        let loader = self.child_loader.clone();
+        let node = match find_node_mut(&mut self.roots, id) {
+            Some(node) if node.is_expandable() && node.expanded != expanded => node,
+            _ => return false,
+        };
+        if expanded && node.lazy {
+            if let Some(loader) = loader {
+                node.children = loader(id);
+                node.lazy = false;
+            }
+        }
+        node.expanded = expanded;
+        true
+    }
+
+    /// Returns what a key pressed while the tree is focused does.
+    fn key_action(&self, key: VirtualKeyCode) -> Option<TreeAction> {
+        let selected = self.selected?;
+        let mut flat = Vec::new();
+        flatten(&self.roots, None, 0, &mut flat);
+        let index = flat.iter().position(|flat| flat.node.id == selected)?;
+        let current = &flat[index];
+        let next = flat.get(index + 1);
+        match key {
+            VirtualKeyCode::Up if index > 0 => Some(TreeAction::Select(flat[index - 1].node.id)),
+            VirtualKeyCode::Down => next.map(|next| TreeAction::Select(next.node.id)),
+            VirtualKeyCode::Right if current.node.is_expandable() && !current.node.expanded => {
+                Some(TreeAction::Expand(selected, true))
+            }
+            VirtualKeyCode::Right => next
+                .filter(|next| next.parent == Some(selected))
+                .map(|next| TreeAction::Select(next.node.id)),
+            VirtualKeyCode::Left if current.node.expanded => {
+                Some(TreeAction::Expand(selected, false))
+            }
+            VirtualKeyCode::Left => current.parent.map(TreeAction::Select),
+            VirtualKeyCode::Return => Some(TreeAction::Activate(selected)),
+            _ => None,
+        }
+    }
+}
+
+impl Component for UiTreeView {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn find_node(nodes: &[UiTreeNode], id: u64) -> Option<&UiTreeNode> {
+    nodes.iter().find_map(|node| {
+        if node.id == id {
+            Some(node)
+        } else {
+            find_node(&node.children, id)
+        }
+    })
+}
+
+fn find_node_mut(nodes: &mut [UiTreeNode], id: u64) -> Option<&mut UiTreeNode> {
+    for node in nodes.iter_mut() {
+        if node.id == id {
+            return Some(node);
+        }
+        if let Some(found) = find_node_mut(&mut node.children, id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Lists the shown nodes, in order.
+fn flatten<'a>(
+    nodes: &'a [UiTreeNode],
+    parent: Option<u64>,
+    depth: usize,
+    flat: &mut Vec<FlatNode<'a>>,
+) {
+    for node in nodes {
+        flat.push(FlatNode {
+            node,
+            parent,
+            depth,
+        });
+        if node.expanded {
+            flatten(&node.children, Some(node.id), depth + 1, flat);
+        }
+    }
+}
+
+/// System creating, recycling and updating the rows of the `UiTreeView`s, and handling their
+/// mouse and keyboard input.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiTreeViewSystemDesc))]
+pub struct UiTreeViewSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    #[system_desc(event_channel_reader)]
+    window_reader_id: ReaderId<Event>,
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+    phantom: PhantomData<G>,
+}
+
+impl<G> UiTreeViewSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    /// Creates a new `UiTreeViewSystem`.
+    pub fn new(window_reader_id: ReaderId<Event>, ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            window_reader_id,
+            ui_reader_id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, G> System<'a> for UiTreeViewSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<FontAsset>>,
+        ReadExpect<'a, ParentHierarchy>,
+        Read<'a, EventChannel<Event>>,
+        Write<'a, EventChannel<UiEvent>>,
+        WriteStorage<'a, UiTreeView>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, UiImage>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, Interactable>,
+        WriteStorage<'a, Selectable<G>>,
+        WriteStorage<'a, Selected>,
+        WriteStorage<'a, HiddenPropagate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            font_assets,
+            hierarchy,
+            window_events,
+            mut ui_events,
+            mut trees,
+            mut ui_transforms,
+            mut texts,
+            mut images,
+            mut parents,
+            mut interactables,
+            mut selectables,
+            mut selecteds,
+            mut hiddens,
+        ): Self::SystemData,
+    ) {
+        let keys = window_events
+            .read(&mut self.window_reader_id)
+            .filter_map(|event| match *event {
+                Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput {
+                            input:
+                                KeyboardInput {
+                                    state: ElementState::Pressed,
+                                    virtual_keycode: Some(key),
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } => Some(key),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The mouse input on the trees, as `(tree, node)`.
+        let mut toggled = Vec::new();
+        let mut activated = Vec::new();
+        let mut scrolls = HashMap::new();
+        let events = ui_events
+            .read(&mut self.ui_reader_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        for event in events {
+            let tree_entity = match view_of(event.target, &hierarchy, |e| trees.contains(e)) {
+                Some(tree_entity) => tree_entity,
+                None => continue,
+            };
+            let tree = trees
+                .get(tree_entity)
+                .expect("unreachable: We just found this tree");
+            let row = tree.rows.iter().find(|row| {
+                row.entity == event.target
+                    || row.elements.toggle == event.target
+                    || row.elements.label == event.target
+            });
+            let node = row.and_then(|row| {
+                row.bound
+                    .map(|node| (row.elements.toggle == event.target, node))
+            });
+            match (event.event_type, node) {
+                (UiEventType::Click(MouseButton::Left), Some((true, node))) => {
+                    toggled.push((tree_entity, node));
+                }
+                (UiEventType::DoubleClick(MouseButton::Left), Some((false, node))) => {
+                    activated.push((tree_entity, node));
+                }
+                (UiEventType::Scroll { delta }, _) => {
+                    scrolls.entry(tree_entity).or_insert(delta[1]);
+                }
+                _ => {}
+            }
+        }
+
+        for (tree_entity, tree) in (&*entities, &mut trees).join() {
+            let (width, height) = match ui_transforms.get(tree_entity) {
+                Some(transform) => (transform.pixel_width(), transform.pixel_height()),
+                None => continue,
+            };
+            let mut emit = |event_type: UiEventType| {
+                ui_events.single_write(UiEvent::new(event_type, tree_entity));
+            };
+
+            // Selection changes made with the mouse or the keyboard on the shown rows.
+            let newly_selected = sync_selection(
+                &tree.rows,
+                &mut tree.selected,
+                &mut tree.focused,
+                &entities,
+                &selecteds,
+            );
+            if let Some(node) = newly_selected {
+                emit(UiEventType::NodeSelected { node });
+            }
+
+            for (_, node) in toggled.iter().filter(|(entity, _)| *entity == tree_entity) {
+                let expanded = tree.node(*node).map_or(false, |node| !node.expanded);
+                if tree.set_expanded(*node, expanded) {
+                    emit(expansion_event(*node, expanded));
+                }
+            }
+            for (_, node) in activated
+                .iter()
+                .filter(|(entity, _)| *entity == tree_entity)
+            {
+                emit(UiEventType::NodeActivated { node: *node });
+            }
+
+            // The keyboard controls the tree while its selected node is focused.
+            let focused = tree
+                .rows
+                .iter()
+                .any(|row| row.bound.is_some() && row.bound == tree.selected && row.selected);
+            let mut moved = false;
+            for key in keys.iter().filter(|_| focused) {
+                match tree.key_action(*key) {
+                    Some(TreeAction::Select(node)) => {
+                        tree.selected = Some(node);
+                        moved = true;
+                        emit(UiEventType::NodeSelected { node });
+                    }
+                    Some(TreeAction::Expand(node, expanded)) => {
+                        if tree.set_expanded(node, expanded) {
+                            emit(expansion_event(node, expanded));
+                        }
+                    }
+                    Some(TreeAction::Activate(node)) => {
+                        emit(UiEventType::NodeActivated { node });
+                    }
+                    None => {}
+                }
+            }
+            if moved {
+                // Moves the focus to the row of the new node.
+                for row in tree.rows.iter() {
+                    selecteds.remove(row.entity);
+                }
+            }
+
+            let font = tree
+                .font
+                .get_or_insert_with(|| get_default_font(&loader, &font_assets))
+                .clone();
+            let new_text = |tree: &UiTreeView, text: String| {
+                UiText::new(
+                    font.clone(),
+                    text,
+                    tree.text_color,
+                    tree.font_size,
+                    LineMode::Single,
+                    Anchor::MiddleLeft,
+                )
+            };
+
+            let row_height = tree.row_height;
+            let fit = if row_height > 0.0 {
+                (height / row_height).floor().max(0.0) as usize
+            } else {
+                0
+            };
+            let mut flat = Vec::new();
+            flatten(&tree.roots, None, 0, &mut flat);
+
+            // Scrolling moves the first row, and the selection is kept in view.
+            if let Some(delta) = scrolls.get(&tree_entity) {
+                let first_row = tree.first_row as f32 - delta.round();
+                tree.first_row = first_row.max(0.0) as usize;
+            }
+            if moved {
+                let index = flat
+                    .iter()
+                    .position(|flat| Some(flat.node.id) == tree.selected);
+                if let Some(index) = index {
+                    if index < tree.first_row {
+                        tree.first_row = index;
+                    } else if fit > 0 && index >= tree.first_row + fit {
+                        tree.first_row = index + 1 - fit;
+                    }
+                }
+            }
+            tree.first_row = tree.first_row.min(flat.len().saturating_sub(fit));
+            let visible = flat
+                .iter()
+                .skip(tree.first_row)
+                .take(fit)
+                .collect::<Vec<_>>();
+
+            // Create the missing rows.
+            while tree.rows.len() < visible.len() {
+                let index = tree.rows.len();
+                let id = transform_id(tree_entity, &ui_transforms);
+                let entity = create_row(
+                    tree_entity,
+                    &id,
+                    index,
+                    tree.tab_order,
+                    &entities,
+                    &mut ui_transforms,
+                    &mut interactables,
+                    &mut selectables,
+                    &mut parents,
+                );
+                let mut create_child = |transform: UiTransform| {
+                    let child = entities.create();
+                    let _ = ui_transforms.insert(child, transform);
+                    let _ = texts.insert(child, new_text(tree, String::new()));
+                    let _ = parents.insert(child, Parent { entity });
+                    child
+                };
+                let toggle = create_child(UiTransform::new(
+                    format!("{}_row_{}_toggle", id, index),
+                    Anchor::MiddleLeft,
+                    Anchor::MiddleLeft,
+                    0.,
+                    0.,
+                    1.,
+                    row_height,
+                    row_height,
+                ));
+                let label = create_child(
+                    UiTransform::new(
+                        format!("{}_row_{}_label", id, index),
+                        Anchor::MiddleLeft,
+                        Anchor::MiddleLeft,
+                        0.,
+                        0.,
+                        1.,
+                        0.,
+                        0.,
+                    )
+                    .with_stretch(Stretch::Y { y_margin: 0. })
+                    .into_transparent(),
+                );
+                let _ = interactables.insert(toggle, Interactable);
+                tree.rows
+                    .push(TreeRow::new(entity, TreeRowElements { toggle, label }));
+            }
+
+            // Bind the shown rows, hide the others.
+            for (index, row) in tree.rows.iter_mut().enumerate() {
+                let flat = match visible.get(index) {
+                    Some(flat) => flat,
+                    None => {
+                        hide_row(row, &mut hiddens, &mut selecteds);
+                        continue;
+                    }
+                };
+                show_row(
+                    row,
+                    (index, flat.node.id),
+                    tree.tab_order,
+                    &mut hiddens,
+                    &mut selectables,
+                );
+                let x = flat.depth as f32 * tree.indent;
+                set_layout(
+                    row.entity,
+                    (0.0, index as f32 * row_height, width, row_height),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    row.elements.toggle,
+                    (x, 0.0, row_height, row_height),
+                    &mut ui_transforms,
+                );
+                set_layout(
+                    row.elements.label,
+                    (x + row_height, 0.0, width - x - row_height, 0.0),
+                    &mut ui_transforms,
+                );
+                let toggle = match (flat.node.is_expandable(), flat.node.expanded) {
+                    (false, _) => "",
+                    (true, false) => "+",
+                    (true, true) => "-",
+                };
+                for (entity, text) in [
+                    (row.elements.toggle, toggle),
+                    (row.elements.label, &flat.node.label[..]),
+                ]
+                .iter()
+                {
+                    if let Some(ui_text) = texts.get_mut(*entity) {
+                        if ui_text.text != *text {
+                            ui_text.text = text.to_string();
+                        }
+                    }
+                }
+            }
+
+            // Show the selection on the row of the selected node.
+            show_selection(
+                &mut tree.rows,
+                (tree.selected, tree.focused),
+                (tree.row_image.as_ref(), tree.selected_row_image.as_ref()),
+                &mut selecteds,
+                &mut images,
+            );
+        }
+    }
+}
+
+fn expansion_event(node: u64, expanded: bool) -> UiEventType {
+    if expanded {
+        UiEventType::NodeExpanded { node }
+    } else {
+        UiEventType::NodeCollapsed { node }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> UiTreeView {
+        UiTreeView::new(vec![
+            UiTreeNode::new(1, "a")
+                .with_children(vec![
+                    UiTreeNode::new(2, "b").with_children(vec![UiTreeNode::new(4, "e")]),
+                    UiTreeNode::new(3, "c"),
+                ])
+                .expanded(),
+            UiTreeNode::new(5, "d").lazy(),
+        ])
+        .with_child_loader(|id| vec![UiTreeNode::new(id * 10, "loaded")])
+    }
+
+    fn shown(tree: &UiTreeView) -> Vec<(u64, Option<u64>, usize)> {
+        let mut flat = Vec::new();
+        flatten(&tree.roots, None, 0, &mut flat);
+        flat.iter()
+            .map(|flat| (flat.node.id, flat.parent, flat.depth))
+            .collect()
+    }
+
+    fn action(tree: &mut UiTreeView, selected: u64, key: VirtualKeyCode) -> Option<TreeAction> {
+        tree.select_node(Some(selected));
+        tree.key_action(key)
+    }
+
+    #[test]
+    fn flatten_skips_collapsed_children() {
+        let mut tree = tree();
+        assert_eq!(
+            shown(&tree),
+            vec![(1, None, 0), (2, Some(1), 1), (3, Some(1), 1), (5, None, 0)]
+        );
+
+        assert!(tree.set_expanded(2, true));
+        assert!(!tree.set_expanded(2, true));
+        assert!(!tree.set_expanded(3, true));
+        assert!(tree.set_expanded(5, true));
+        assert_eq!(
+            shown(&tree),
+            vec![
+                (1, None, 0),
+                (2, Some(1), 1),
+                (4, Some(2), 2),
+                (3, Some(1), 1),
+                (5, None, 0),
+                (50, Some(5), 1),
+            ]
+        );
+
+        assert!(tree.set_expanded(1, false));
+        assert_eq!(shown(&tree), vec![(1, None, 0), (5, None, 0)]);
+    }
+
+    #[test]
+    fn arrows_move_the_selection() {
+        let mut tree = tree();
+        assert_eq!(tree.key_action(VirtualKeyCode::Down), None);
+        assert_eq!(action(&mut tree, 1, VirtualKeyCode::Up), None);
+        assert_eq!(
+            action(&mut tree, 2, VirtualKeyCode::Up),
+            Some(TreeAction::Select(1))
+        );
+        assert_eq!(
+            action(&mut tree, 2, VirtualKeyCode::Down),
+            Some(TreeAction::Select(3))
+        );
+        assert_eq!(action(&mut tree, 5, VirtualKeyCode::Down), None);
+        assert_eq!(
+            action(&mut tree, 3, VirtualKeyCode::Return),
+            Some(TreeAction::Activate(3))
+        );
+        assert_eq!(action(&mut tree, 3, VirtualKeyCode::Space), None);
+        // A node missing from the shown nodes does nothing.
+        assert_eq!(action(&mut tree, 4, VirtualKeyCode::Down), None);
+    }
+
+    #[test]
+    fn right_expands_and_left_collapses() {
+        let mut tree = tree();
+        assert_eq!(
+            action(&mut tree, 2, VirtualKeyCode::Right),
+            Some(TreeAction::Expand(2, true))
+        );
+        assert_eq!(
+            action(&mut tree, 5, VirtualKeyCode::Right),
+            Some(TreeAction::Expand(5, true))
+        );
+        // Right on an expanded node moves to its first child, and does nothing on a leaf.
+        assert_eq!(
+            action(&mut tree, 1, VirtualKeyCode::Right),
+            Some(TreeAction::Select(2))
+        );
+        assert_eq!(action(&mut tree, 3, VirtualKeyCode::Right), None);
+
+        assert_eq!(
+            action(&mut tree, 1, VirtualKeyCode::Left),
+            Some(TreeAction::Expand(1, false))
+        );
+        // Left on a collapsed node moves to its parent, and does nothing on a collapsed root.
+        assert_eq!(
+            action(&mut tree, 2, VirtualKeyCode::Left),
+            Some(TreeAction::Select(1))
+        );
+        assert_eq!(action(&mut tree, 5, VirtualKeyCode::Left), None);
+    }
+}
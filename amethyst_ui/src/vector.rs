@@ -0,0 +1,184 @@
+//! Module containing the system rasterising `UiImage::Vector` images into textures.
+
+use crate::{SvgAsset, UiImage, UiTransform};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::ecs::{
+    Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System, SystemData, Write,
+    WriteStorage,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_rendy::{
+    rendy::{
+        command::QueueId,
+        factory::{Factory, ImageState},
+        hal,
+        texture::{pixel::Rgba8Srgb, TextureBuilder},
+    },
+    Backend, Texture,
+};
+use std::marker::PhantomData;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// The largest width or height of a rasterised vector image, in pixels.
+const MAX_SIZE: u32 = 4096;
+
+/// The texture a `UiImage::Vector` image was last rasterised to, and at which size.
+#[derive(Debug, Clone)]
+pub struct UiVectorTexture {
+    pub(crate) svg: Handle<SvgAsset>,
+    pub(crate) size: [u32; 2],
+    pub(crate) texture: Handle<Texture>,
+}
+
+impl Component for UiVectorTexture {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Rasterises every `UiImage::Vector` image at the size in pixels of its `UiTransform`, and again
+/// whenever that size or the image changes. It is added by `RenderUi`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiVectorSystemDesc))]
+pub struct UiVectorSystem<B: Backend> {
+    marker: PhantomData<B>,
+}
+
+impl<B: Backend> Default for UiVectorSystem<B> {
+    fn default() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: Backend> System<'a> for UiVectorSystem<B> {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Option<Write<'a, Factory<B>>>,
+        Option<Read<'a, QueueId>>,
+        Entities<'a>,
+        ReadStorage<'a, UiImage>,
+        ReadStorage<'a, UiTransform>,
+        WriteStorage<'a, UiVectorTexture>,
+        Read<'a, AssetStorage<SvgAsset>>,
+        Write<'a, AssetStorage<Texture>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut maybe_factory,
+            maybe_queue,
+            entities,
+            images,
+            transforms,
+            mut vector_textures,
+            svg_storage,
+            mut tex_storage,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_vector_system");
+
+        let (factory, queue) =
+            if let (Some(factory), Some(queue)) = (maybe_factory.as_mut(), maybe_queue) {
+                (factory, queue)
+            } else {
+                log::trace!("Rendering not present: vector images rasterisation skipped");
+                return;
+            };
+
+        // Release the textures of the images which are no longer vector images.
+        let stale = (&*entities, &vector_textures)
+            .join()
+            .filter(|(entity, _)| match images.get(*entity) {
+                Some(UiImage::Vector(_)) => false,
+                _ => true,
+            })
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in stale {
+            vector_textures.remove(entity);
+        }
+
+        for (entity, image, transform) in (&*entities, &images, &transforms).join() {
+            let handle = match image {
+                UiImage::Vector(handle) => handle,
+                _ => continue,
+            };
+            let size = [
+                pixel_size(transform.pixel_width * transform.scale_factor),
+                pixel_size(transform.pixel_height * transform.scale_factor),
+            ];
+            if let Some(cached) = vector_textures.get(entity) {
+                if cached.svg == *handle && cached.size == size {
+                    continue;
+                }
+            }
+            // The image is still loading.
+            let svg = match svg_storage.get(handle) {
+                Some(svg) => svg,
+                None => continue,
+            };
+
+            let [w, h] = size;
+            let data = svg
+                .0
+                .rasterize(w, h)
+                .into_iter()
+                .map(|repr| Rgba8Srgb { repr })
+                .collect::<Vec<_>>();
+            let texture = TextureBuilder::new()
+                .with_kind(hal::image::Kind::D2(w, h, 1, 1))
+                .with_view_kind(hal::image::ViewKind::D2)
+                .with_data_width(w)
+                .with_data_height(h)
+                .with_data(data)
+                .build(
+                    ImageState {
+                        queue: *queue,
+                        stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
+                        access: hal::image::Access::SHADER_READ,
+                        layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                    },
+                    factory,
+                )
+                .map(B::wrap_texture);
+            let texture = match texture {
+                Ok(texture) => texture,
+                Err(err) => {
+                    log::error!("Failed to create the texture of a vector image: {:?}", err);
+                    continue;
+                }
+            };
+
+            match vector_textures.get_mut(entity) {
+                Some(cached) => {
+                    // The pass picks up the new texture without any handle to update.
+                    tex_storage.replace(&cached.texture, texture);
+                    cached.svg = handle.clone();
+                    cached.size = size;
+                }
+                None => {
+                    let texture = tex_storage.insert(texture);
+                    vector_textures
+                        .insert(
+                            entity,
+                            UiVectorTexture {
+                                svg: handle.clone(),
+                                size,
+                                texture,
+                            },
+                        )
+                        .expect("Unreachable: the entity is alive");
+                }
+            }
+        }
+    }
+}
+
+/// The size in pixels a length in screen pixels is rasterised at.
+fn pixel_size(length: f32) -> u32 {
+    (length.round().max(1.0) as u32).min(MAX_SIZE)
+}
@@ -104,6 +104,14 @@ where
         self.items.values_mut()
     }
 
+    /// Removes the widgets for which `keep` returns `false`.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&I, &mut T) -> bool,
+    {
+        self.items.retain(|id, widget| keep(id, widget));
+    }
+
     /// Provides an iterator over all IDs included in the resource.
     pub fn ids(&self) -> Keys<'_, I, T> {
         self.items.keys()
@@ -0,0 +1,1181 @@
+//! Movable, resizable windows.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, marker::PhantomData};
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::{
+        prelude::{
+            Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+            ReaderId, System, SystemData, World, Write, WriteExpect, WriteStorage,
+        },
+        shred::ResourceId,
+    },
+    math::Vector2,
+    shrev::EventChannel,
+    HiddenPropagate, Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_window::ScreenDimensions;
+use winit::MouseButton;
+
+use crate::{
+    define_widget, font::default::get_default_font, get_parent_pixel_size, Anchor, DragBounds,
+    Draggable, FontAsset, FontHandle, Interactable, LineMode, ScaleMode, Stretch, UiEvent,
+    UiEventType, UiImage, UiText, UiTransform, WidgetId, Widgets,
+};
+
+const DEFAULT_Z: f32 = 1.0;
+/// The `local_z` between two windows brought to front, leaving room for the layers of their
+/// contents.
+const WINDOW_Z_STEP: f32 = 10.0;
+const DEFAULT_WIDTH: f32 = 320.0;
+const DEFAULT_HEIGHT: f32 = 240.0;
+const DEFAULT_TITLE_HEIGHT: f32 = 32.0;
+const DEFAULT_BKGD_COLOR: [f32; 4] = [0.82, 0.83, 0.83, 1.0];
+const DEFAULT_TITLE_BKGD_COLOR: [f32; 4] = [0.6, 0.62, 0.65, 1.0];
+const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+/// The thickness of the resize handles, in pixels.
+pub(crate) const RESIZE_HANDLE_SIZE: f32 = 6.0;
+
+define_widget!(UiWindow =>
+    entities: [window_entity, title_entity, content_entity]
+    components: [
+        (has UiTransform as position on window_entity),
+        (has UiWindowFrame as frame on window_entity),
+        (has UiText as title on title_entity),
+        (has UiTransform as content_position on content_entity)
+    ]
+);
+
+/// Component turning an element into a window, which can be moved by its title bar, resized by
+/// its edges, closed, minimised and brought to the front when clicked.
+///
+/// The parts of the window are children of the element, marked with a `UiWindowPart`. The
+/// `UiWindowSystem` makes the window `Draggable` inside its parent unless it already is, and the
+/// title bar is transparent, so that dragging the title bar moves the whole window. Use the
+/// `UiWindowBuilder` or a `UiWidget::Window` prefab to create one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiWindowFrame {
+    /// The smallest size the window can be resized to.
+    pub min_size: [f32; 2],
+    /// The largest size the window can be resized to, if any.
+    pub max_size: Option<[f32; 2]>,
+    /// The height of the title bar, which is the height of the window when it is minimised.
+    pub title_height: f32,
+    /// Stores the position, size and minimised state of the window in the `UiWindowLayouts`
+    /// resource under this key, and restores them from it when the window is created.
+    pub persist_key: Option<String>,
+    #[serde(skip)]
+    minimized: bool,
+    /// The height of the window before it was minimised.
+    #[serde(skip)]
+    restored_height: f32,
+    /// Whether the `UiWindowSystem` set the window up.
+    #[serde(skip)]
+    initialized: bool,
+}
+
+impl Default for UiWindowFrame {
+    fn default() -> Self {
+        UiWindowFrame {
+            min_size: [DEFAULT_TITLE_HEIGHT * 3.0, DEFAULT_TITLE_HEIGHT],
+            max_size: None,
+            title_height: DEFAULT_TITLE_HEIGHT,
+            persist_key: None,
+            minimized: false,
+            restored_height: 0.0,
+            initialized: false,
+        }
+    }
+}
+
+impl UiWindowFrame {
+    /// Creates a new `UiWindowFrame` with a title bar of `title_height` pixels.
+    pub fn new(title_height: f32) -> Self {
+        UiWindowFrame {
+            title_height,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the smallest size the window can be resized to.
+    pub fn with_min_size(mut self, width: f32, height: f32) -> Self {
+        self.min_size = [width, height];
+        self
+    }
+
+    /// Sets the largest size the window can be resized to.
+    pub fn with_max_size(mut self, width: f32, height: f32) -> Self {
+        self.max_size = Some([width, height]);
+        self
+    }
+
+    /// Persists the layout of the window in the `UiWindowLayouts` under `key`.
+    pub fn with_persist_key<S: ToString>(mut self, key: S) -> Self {
+        self.persist_key = Some(key.to_string());
+        self
+    }
+
+    /// Checks if the window is minimised.
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Keeps the minimised state and the setup of `previous`, which this frame replaces.
+    pub(crate) fn keep_state_of(&mut self, previous: &UiWindowFrame) {
+        self.minimized = previous.minimized;
+        self.restored_height = previous.restored_height;
+        self.initialized = previous.initialized;
+    }
+
+    /// Clamps a size between the minimum and maximum sizes of the window.
+    fn clamp_size(&self, width: f32, height: f32) -> (f32, f32) {
+        let (max_width, max_height) = match self.max_size {
+            Some([max_width, max_height]) => (max_width, max_height),
+            None => (std::f32::MAX, std::f32::MAX),
+        };
+        (
+            width.min(max_width).max(self.min_size[0]),
+            height.min(max_height).max(self.min_size[1]),
+        )
+    }
+}
+
+impl Component for UiWindowFrame {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// An edge or a corner of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiWindowEdge {
+    /// The top edge.
+    Top,
+    /// The bottom edge.
+    Bottom,
+    /// The left edge.
+    Left,
+    /// The right edge.
+    Right,
+    /// The top left corner.
+    TopLeft,
+    /// The top right corner.
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+impl UiWindowEdge {
+    /// Every edge and corner.
+    pub const ALL: [UiWindowEdge; 8] = [
+        UiWindowEdge::Top,
+        UiWindowEdge::Bottom,
+        UiWindowEdge::Left,
+        UiWindowEdge::Right,
+        UiWindowEdge::TopLeft,
+        UiWindowEdge::TopRight,
+        UiWindowEdge::BottomLeft,
+        UiWindowEdge::BottomRight,
+    ];
+
+    /// The direction in which moving the mouse grows the window, as `-1`, `0` or `1` on each
+    /// axis, with y going up.
+    fn direction(self) -> (f32, f32) {
+        match self {
+            UiWindowEdge::Top => (0.0, 1.0),
+            UiWindowEdge::Bottom => (0.0, -1.0),
+            UiWindowEdge::Left => (-1.0, 0.0),
+            UiWindowEdge::Right => (1.0, 0.0),
+            UiWindowEdge::TopLeft => (-1.0, 1.0),
+            UiWindowEdge::TopRight => (1.0, 1.0),
+            UiWindowEdge::BottomLeft => (-1.0, -1.0),
+            UiWindowEdge::BottomRight => (1.0, -1.0),
+        }
+    }
+
+    /// Returns where the handle of this edge is anchored, how it is stretched and its size.
+    pub(crate) fn handle_layout(self) -> (Anchor, Stretch, f32, f32) {
+        let size = RESIZE_HANDLE_SIZE;
+        let along_x = Stretch::X { x_margin: size };
+        let along_y = Stretch::Y { y_margin: size };
+        match self {
+            UiWindowEdge::Top => (Anchor::TopMiddle, along_x, 0.0, size),
+            UiWindowEdge::Bottom => (Anchor::BottomMiddle, along_x, 0.0, size),
+            UiWindowEdge::Left => (Anchor::MiddleLeft, along_y, size, 0.0),
+            UiWindowEdge::Right => (Anchor::MiddleRight, along_y, size, 0.0),
+            UiWindowEdge::TopLeft => (Anchor::TopLeft, Stretch::NoStretch, size, size),
+            UiWindowEdge::TopRight => (Anchor::TopRight, Stretch::NoStretch, size, size),
+            UiWindowEdge::BottomLeft => (Anchor::BottomLeft, Stretch::NoStretch, size, size),
+            UiWindowEdge::BottomRight => (Anchor::BottomRight, Stretch::NoStretch, size, size),
+        }
+    }
+}
+
+/// Marks the children of a `UiWindowFrame` element that make up the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiWindowPart {
+    /// The title bar. It should be transparent, so that dragging it drags the window.
+    TitleBar,
+    /// Hides the window with `HiddenPropagate` when clicked.
+    Close,
+    /// Minimises or restores the window when clicked.
+    Minimise,
+    /// Holds the content of the window. Hidden while the window is minimised.
+    Content,
+    /// Resizes the window when dragged. Hidden while the window is minimised.
+    ResizeHandle(UiWindowEdge),
+}
+
+impl Component for UiWindowPart {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The position, size and minimised state of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UiWindowLayout {
+    /// The `local_x` of the window.
+    pub x: f32,
+    /// The `local_y` of the window.
+    pub y: f32,
+    /// The width of the window.
+    pub width: f32,
+    /// The height of the window, when it is not minimised.
+    pub height: f32,
+    /// Whether the window is minimised.
+    pub minimized: bool,
+}
+
+/// Resource holding the layouts of the windows with a `persist_key`.
+///
+/// The `UiWindowSystem` keeps it up to date, and restores the layout of a window when one with
+/// the same key is created. Save it with the other settings of the game and insert it back in the
+/// `World` on startup to persist the windows across runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UiWindowLayouts {
+    /// The layouts, by key.
+    pub layouts: HashMap<String, UiWindowLayout>,
+}
+
+/// A window being resized.
+#[derive(Debug)]
+struct Resize {
+    window: Entity,
+    edge: UiWindowEdge,
+    /// The mouse position when the resize started.
+    first: Vector2<f32>,
+    /// The local position and size of the window when the resize started.
+    origin: (f32, f32, f32, f32),
+}
+
+/// System handling the `UiWindowFrame`s: bringing them to the front, closing, minimising and
+/// resizing them, and keeping the `UiWindowLayouts` up to date.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiWindowSystemDesc))]
+pub struct UiWindowSystem<T: BindingTypes> {
+    #[system_desc(event_channel_reader)]
+    ui_reader_id: ReaderId<UiEvent>,
+
+    #[system_desc(skip)]
+    resizing: Option<Resize>,
+
+    phantom: PhantomData<T>,
+}
+
+impl<T> UiWindowSystem<T>
+where
+    T: BindingTypes,
+{
+    /// Creates a new `UiWindowSystem`.
+    pub fn new(ui_reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            ui_reader_id,
+            resizing: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'s, T> System<'s> for UiWindowSystem<T>
+where
+    T: BindingTypes,
+{
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, InputHandler<T>>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadExpect<'s, ParentHierarchy>,
+        Read<'s, EventChannel<UiEvent>>,
+        Write<'s, UiWindowLayouts>,
+        ReadStorage<'s, UiWindowPart>,
+        WriteStorage<'s, UiWindowFrame>,
+        WriteStorage<'s, Draggable>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, HiddenPropagate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            input_handler,
+            screen_dimensions,
+            hierarchy,
+            ui_events,
+            mut layouts,
+            parts,
+            mut frames,
+            mut draggables,
+            mut ui_transforms,
+            mut hiddens,
+        ): Self::SystemData,
+    ) {
+        let mouse_pos = input_handler.mouse_position().unwrap_or((0., 0.));
+        let mouse_pos = Vector2::new(mouse_pos.0, screen_dimensions.height() - mouse_pos.1);
+
+        // Set up the new windows and restore their layouts.
+        for (entity, frame) in (&*entities, &mut frames).join() {
+            if frame.initialized {
+                continue;
+            }
+            frame.initialized = true;
+            if !draggables.contains(entity) {
+                let _ = draggables.insert(entity, Draggable::new().with_bounds(DragBounds::Parent));
+            }
+            let layout = match frame
+                .persist_key
+                .as_ref()
+                .and_then(|key| layouts.layouts.get(key))
+            {
+                Some(layout) => *layout,
+                None => continue,
+            };
+            if let Some(transform) = ui_transforms.get_mut(entity) {
+                transform.local_x = layout.x;
+                transform.local_y = layout.y;
+                transform.width = layout.width;
+                transform.height = layout.height;
+            }
+            if layout.minimized {
+                set_minimized(
+                    entity,
+                    true,
+                    frame,
+                    &hierarchy,
+                    &screen_dimensions,
+                    &parts,
+                    &mut ui_transforms,
+                    &mut hiddens,
+                );
+            }
+        }
+
+        for event in ui_events.read(&mut self.ui_reader_id) {
+            let window = match window_of(event.target, &hierarchy, &frames) {
+                Some(window) => window,
+                None => continue,
+            };
+            match event.event_type {
+                UiEventType::ClickStart(button) => {
+                    bring_to_front(window, &entities, &hierarchy, &frames, &mut ui_transforms);
+                    if button != MouseButton::Left {
+                        continue;
+                    }
+                    if let Some(UiWindowPart::ResizeHandle(edge)) = parts.get(event.target) {
+                        let transform = match ui_transforms.get(window) {
+                            Some(transform) => transform,
+                            None => continue,
+                        };
+                        self.resizing = Some(Resize {
+                            window,
+                            edge: *edge,
+                            first: mouse_pos,
+                            origin: (
+                                transform.local_x,
+                                transform.local_y,
+                                transform.width,
+                                transform.height,
+                            ),
+                        });
+                    }
+                }
+                UiEventType::Click(MouseButton::Left) => match parts.get(event.target) {
+                    Some(UiWindowPart::Close) => {
+                        let _ = hiddens.insert(window, HiddenPropagate::new());
+                    }
+                    Some(UiWindowPart::Minimise) => {
+                        if let Some(frame) = frames.get_mut(window) {
+                            let minimized = !frame.minimized;
+                            set_minimized(
+                                window,
+                                minimized,
+                                frame,
+                                &hierarchy,
+                                &screen_dimensions,
+                                &parts,
+                                &mut ui_transforms,
+                                &mut hiddens,
+                            );
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if !input_handler.mouse_button_is_down(MouseButton::Left) {
+            self.resizing = None;
+        }
+        if let Some(resize) = &self.resizing {
+            if let (Some(frame), Some(transform)) =
+                (frames.get(resize.window), ui_transforms.get(resize.window))
+            {
+                let resized = resized_layout(resize, mouse_pos, frame, transform);
+                if resized
+                    != (
+                        transform.local_x,
+                        transform.local_y,
+                        transform.width,
+                        transform.height,
+                    )
+                {
+                    let transform = ui_transforms
+                        .get_mut(resize.window)
+                        .expect("unreachable: We just got this transform");
+                    transform.local_x = resized.0;
+                    transform.local_y = resized.1;
+                    transform.width = resized.2;
+                    transform.height = resized.3;
+                }
+            }
+        }
+
+        for (frame, transform) in (&frames, &ui_transforms).join() {
+            let key = match frame.persist_key {
+                Some(ref key) => key,
+                None => continue,
+            };
+            let layout = UiWindowLayout {
+                x: transform.local_x,
+                y: transform.local_y,
+                width: transform.width,
+                height: if frame.minimized {
+                    frame.restored_height
+                } else {
+                    transform.height
+                },
+                minimized: frame.minimized,
+            };
+            if layouts.layouts.get(key) != Some(&layout) {
+                layouts.layouts.insert(key.clone(), layout);
+            }
+        }
+    }
+}
+
+/// Finds the window `entity` is part of, which can be `entity` itself.
+fn window_of(
+    entity: Entity,
+    hierarchy: &ParentHierarchy,
+    frames: &WriteStorage<'_, UiWindowFrame>,
+) -> Option<Entity> {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if frames.contains(entity) {
+            return Some(entity);
+        }
+        current = hierarchy.parent(entity);
+    }
+    None
+}
+
+/// Puts `window` above the windows sharing its parent. The windows are stacked on distinct
+/// layers `WINDOW_Z_STEP` apart from the lowest one, keeping their order, with `window` on top.
+fn bring_to_front(
+    window: Entity,
+    entities: &Entities<'_>,
+    hierarchy: &ParentHierarchy,
+    frames: &WriteStorage<'_, UiWindowFrame>,
+    ui_transforms: &mut WriteStorage<'_, UiTransform>,
+) {
+    let parent = hierarchy.parent(window);
+    let mut siblings = (&**entities, frames, &*ui_transforms)
+        .join()
+        .filter(|(entity, _, _)| hierarchy.parent(*entity) == parent)
+        .map(|(entity, _, transform)| (entity, transform.local_z))
+        .collect::<Vec<_>>();
+    siblings.sort_by(|(_, z1), (_, z2)| z1.partial_cmp(z2).expect("Unexpected NaN"));
+
+    let base = match siblings.first() {
+        Some((_, z)) => *z,
+        None => return,
+    };
+    let order = siblings
+        .iter()
+        .map(|(entity, _)| *entity)
+        .filter(|entity| *entity != window)
+        .chain(Some(window));
+    for (index, entity) in order.enumerate() {
+        let z = base + index as f32 * WINDOW_Z_STEP;
+        let current = ui_transforms.get(entity).map(|t| t.local_z);
+        if current != Some(z) {
+            if let Some(transform) = ui_transforms.get_mut(entity) {
+                transform.local_z = z;
+            }
+        }
+    }
+}
+
+/// Minimises or restores `window`, keeping its top edge in place.
+#[allow(clippy::too_many_arguments)]
+fn set_minimized(
+    window: Entity,
+    minimized: bool,
+    frame: &mut UiWindowFrame,
+    hierarchy: &ParentHierarchy,
+    screen_dimensions: &ScreenDimensions,
+    parts: &ReadStorage<'_, UiWindowPart>,
+    ui_transforms: &mut WriteStorage<'_, UiTransform>,
+    hiddens: &mut WriteStorage<'_, HiddenPropagate>,
+) {
+    if frame.minimized == minimized {
+        return;
+    }
+    frame.minimized = minimized;
+
+    let (_, parent_height) =
+        get_parent_pixel_size(window, hierarchy, &*ui_transforms, screen_dimensions);
+    if let Some(transform) = ui_transforms.get_mut(window) {
+        let height = if minimized {
+            frame.restored_height = transform.height;
+            title_height_in(transform, frame.title_height, parent_height)
+        } else {
+            frame.restored_height
+        };
+        // The local position is in the same unit as the height.
+        let pivot_y = transform.pivot.norm_offset().1;
+        transform.local_y += (pivot_y - 0.5) * (height - transform.height);
+        transform.height = height;
+    }
+
+    for child in hierarchy.children(window) {
+        match parts.get(*child) {
+            Some(UiWindowPart::Content) | Some(UiWindowPart::ResizeHandle(_)) => {
+                if minimized {
+                    let _ = hiddens.insert(*child, HiddenPropagate::new());
+                } else {
+                    hiddens.remove(*child);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Converts the pixel height of the title bar to the unit of the `height` of `transform`, which is
+/// a fraction of `parent_height` for `ScaleMode::Percent`.
+fn title_height_in(transform: &UiTransform, title_height: f32, parent_height: f32) -> f32 {
+    match transform.scale_mode {
+        ScaleMode::Pixel => title_height,
+        ScaleMode::Percent => title_height / parent_height.max(std::f32::EPSILON),
+    }
+}
+
+/// Computes the local position and size of a window resized with the mouse at `mouse_pos`. The
+/// edges opposite to the one being dragged stay in place.
+fn resized_layout(
+    resize: &Resize,
+    mouse_pos: Vector2<f32>,
+    frame: &UiWindowFrame,
+    transform: &UiTransform,
+) -> (f32, f32, f32, f32) {
+    let (x, y, width, height) = resize.origin;
+    // Only pixel sized, unstretched windows can be resized.
+    let stretched = match transform.stretch {
+        Stretch::NoStretch => false,
+        _ => true,
+    };
+    if transform.scale_mode != ScaleMode::Pixel || stretched {
+        return (x, y, width, height);
+    }
+    // The mouse moves on the screen, the window in the layout space of the ui, which can be
+    // scaled, rotated or in a canvas.
+    let offset = match (
+        transform.to_layout_space(resize.first[0], resize.first[1]),
+        transform.to_layout_space(mouse_pos[0], mouse_pos[1]),
+    ) {
+        (Some(first), Some(current)) => Vector2::new(current.0 - first.0, current.1 - first.1),
+        _ => return (x, y, width, height),
+    };
+    let (dir_x, dir_y) = resize.edge.direction();
+    let (new_width, new_height) =
+        frame.clamp_size(width + dir_x * offset[0], height + dir_y * offset[1]);
+    let (new_width, new_height) = (
+        if dir_x == 0.0 { width } else { new_width },
+        if dir_y == 0.0 { height } else { new_height },
+    );
+    let (pivot_x, pivot_y) = transform.pivot.norm_offset();
+    (
+        x + (pivot_x + dir_x * 0.5) * (new_width - width),
+        y + (pivot_y + dir_y * 0.5) * (new_height - height),
+        new_width,
+        new_height,
+    )
+}
+
+/// Container for all the resources the builder needs to make a new UiWindow.
+#[allow(missing_debug_implementations)]
+#[derive(SystemData)]
+pub struct UiWindowBuilderResources<'a, I: WidgetId = u32> {
+    font_asset: Read<'a, AssetStorage<FontAsset>>,
+    loader: ReadExpect<'a, Loader>,
+    entities: Entities<'a>,
+    image: WriteStorage<'a, UiImage>,
+    mouse_reactive: WriteStorage<'a, Interactable>,
+    parent: WriteStorage<'a, Parent>,
+    text: WriteStorage<'a, UiText>,
+    transform: WriteStorage<'a, UiTransform>,
+    frame: WriteStorage<'a, UiWindowFrame>,
+    part: WriteStorage<'a, UiWindowPart>,
+    window_widgets: WriteExpect<'a, Widgets<UiWindow, I>>,
+}
+
+/// Convenience structure for building a window
+///
+/// Add the content of the window as children of its `content_entity`.
+#[derive(Debug, Clone)]
+pub struct UiWindowBuilder<I = u32>
+where
+    I: WidgetId,
+{
+    id: Option<I>,
+    x: f32,
+    y: f32,
+    z: f32,
+    width: f32,
+    height: f32,
+    anchor: Anchor,
+    title: String,
+    text_color: [f32; 4],
+    font: Option<FontHandle>,
+    font_size: f32,
+    background: UiImage,
+    title_background: UiImage,
+    frame: UiWindowFrame,
+    closable: bool,
+    minimizable: bool,
+    resizable: bool,
+    parent: Option<Entity>,
+}
+
+impl<I> Default for UiWindowBuilder<I>
+where
+    I: WidgetId,
+{
+    fn default() -> Self {
+        UiWindowBuilder {
+            id: None,
+            x: 0.,
+            y: 0.,
+            z: DEFAULT_Z,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            anchor: Anchor::Middle,
+            title: "".to_string(),
+            text_color: DEFAULT_TXT_COLOR,
+            font: None,
+            font_size: 24.,
+            background: UiImage::SolidColor(DEFAULT_BKGD_COLOR),
+            title_background: UiImage::SolidColor(DEFAULT_TITLE_BKGD_COLOR),
+            frame: UiWindowFrame::default(),
+            closable: true,
+            minimizable: true,
+            resizable: true,
+            parent: None,
+        }
+    }
+}
+
+impl<'a, I> UiWindowBuilder<I>
+where
+    I: WidgetId + 'static,
+{
+    /// Construct a new UiWindowBuilder, for a window titled `title`.
+    pub fn new<S: ToString>(title: S) -> UiWindowBuilder<I> {
+        let mut builder = UiWindowBuilder::default();
+        builder.title = title.to_string();
+        builder
+    }
+
+    /// Sets an ID for this widget. The type of this ID will determine which `Widgets`
+    /// resource this widget will be added to, see [`Widgets`](../struct.Widgets.html).
+    pub fn with_id(mut self, id: I) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Set window size
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set window position
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Provide a Z position, i.e UI layer
+    pub fn with_layer(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Add an anchor to the window.
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Set title text color
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    /// Use a different font for the title.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Set title font size
+    pub fn with_font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Set the image drawn behind the window.
+    pub fn with_background(mut self, image: UiImage) -> Self {
+        self.background = image;
+        self
+    }
+
+    /// Set the image drawn behind the title bar.
+    pub fn with_title_background(mut self, image: UiImage) -> Self {
+        self.title_background = image;
+        self
+    }
+
+    /// Set the height of the title bar.
+    pub fn with_title_height(mut self, height: f32) -> Self {
+        self.frame.title_height = height;
+        self
+    }
+
+    /// Set the smallest size the window can be resized to.
+    pub fn with_min_size(mut self, width: f32, height: f32) -> Self {
+        self.frame = self.frame.with_min_size(width, height);
+        self
+    }
+
+    /// Set the largest size the window can be resized to.
+    pub fn with_max_size(mut self, width: f32, height: f32) -> Self {
+        self.frame = self.frame.with_max_size(width, height);
+        self
+    }
+
+    /// Persist the position and size of the window in the `UiWindowLayouts` under `key`.
+    pub fn with_persist_key<S: ToString>(mut self, key: S) -> Self {
+        self.frame = self.frame.with_persist_key(key);
+        self
+    }
+
+    /// Set whether the window has a close button.
+    pub fn with_closable(mut self, closable: bool) -> Self {
+        self.closable = closable;
+        self
+    }
+
+    /// Set whether the window has a minimise button.
+    pub fn with_minimizable(mut self, minimizable: bool) -> Self {
+        self.minimizable = minimizable;
+        self
+    }
+
+    /// Set whether the window has resize handles.
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Add a parent to the window.
+    pub fn with_parent(mut self, parent: Entity) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Build this with the `UiWindowBuilderResources`.
+    pub fn build(self, mut res: UiWindowBuilderResources<'a, I>) -> (I, UiWindow) {
+        let window_entity = res.entities.create();
+        let title_entity = res.entities.create();
+        let content_entity = res.entities.create();
+        let widget = UiWindow::new(window_entity, title_entity, content_entity);
+
+        let id = {
+            let widget = widget.clone();
+
+            if let Some(id) = self.id {
+                let added_id = id.clone();
+                res.window_widgets.add_with_id(id, widget);
+                added_id
+            } else {
+                res.window_widgets.add(widget)
+            }
+        };
+
+        let title_height = self.frame.title_height;
+        let font_handle = self
+            .font
+            .unwrap_or_else(|| get_default_font(&res.loader, &res.font_asset));
+        let (text_color, font_size) = (self.text_color, self.font_size);
+        let text = |text: &str| {
+            UiText::new(
+                font_handle.clone(),
+                text.to_string(),
+                text_color,
+                font_size,
+                LineMode::Single,
+                Anchor::Middle,
+            )
+        };
+
+        res.transform
+            .insert(
+                window_entity,
+                UiTransform::new(
+                    format!("{}_window", id),
+                    self.anchor,
+                    Anchor::Middle,
+                    self.x,
+                    self.y,
+                    self.z,
+                    self.width,
+                    self.height,
+                ),
+            )
+            .expect("Unreachable: Inserting newly created entity");
+        res.image
+            .insert(window_entity, self.background)
+            .expect("Unreachable: Inserting newly created entity");
+        res.mouse_reactive
+            .insert(window_entity, Interactable)
+            .expect("Unreachable: Inserting newly created entity");
+        res.frame
+            .insert(window_entity, self.frame)
+            .expect("Unreachable: Inserting newly created entity");
+        if let Some(parent) = self.parent {
+            res.parent
+                .insert(window_entity, Parent { entity: parent })
+                .expect("Unreachable: Inserting newly created entity");
+        }
+
+        insert_part(
+            &mut res,
+            window_entity,
+            title_entity,
+            UiTransform::new(
+                format!("{}_window_title", id),
+                Anchor::TopMiddle,
+                Anchor::TopMiddle,
+                0.,
+                0.,
+                1.,
+                0.,
+                title_height,
+            )
+            .with_stretch(Stretch::X { x_margin: 0. })
+            .into_transparent(),
+            UiWindowPart::TitleBar,
+        );
+        res.text
+            .insert(title_entity, text(&self.title))
+            .expect("Unreachable: Inserting newly created entity");
+        res.image
+            .insert(title_entity, self.title_background)
+            .expect("Unreachable: Inserting newly created entity");
+        insert_part(
+            &mut res,
+            window_entity,
+            content_entity,
+            UiTransform::new(
+                format!("{}_window_content", id),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                -title_height / 2.,
+                1.,
+                0.,
+                0.,
+            )
+            .with_stretch(Stretch::XY {
+                x_margin: 0.,
+                y_margin: title_height / 2.,
+                keep_aspect_ratio: false,
+            }),
+            UiWindowPart::Content,
+        );
+
+        let mut buttons = Vec::new();
+        if self.closable {
+            buttons.push((UiWindowPart::Close, "x", "close"));
+        }
+        if self.minimizable {
+            buttons.push((UiWindowPart::Minimise, "_", "minimise"));
+        }
+        for (index, (part, label, name)) in buttons.into_iter().enumerate() {
+            let entity = res.entities.create();
+            insert_part(
+                &mut res,
+                window_entity,
+                entity,
+                UiTransform::new(
+                    format!("{}_window_{}", id, name),
+                    Anchor::TopRight,
+                    Anchor::TopRight,
+                    -(index as f32) * title_height,
+                    0.,
+                    2.,
+                    title_height,
+                    title_height,
+                ),
+                part,
+            );
+            res.text
+                .insert(entity, text(label))
+                .expect("Unreachable: Inserting newly created entity");
+            res.mouse_reactive
+                .insert(entity, Interactable)
+                .expect("Unreachable: Inserting newly created entity");
+        }
+
+        if self.resizable {
+            for edge in UiWindowEdge::ALL.iter() {
+                let (anchor, stretch, width, height) = edge.handle_layout();
+                let entity = res.entities.create();
+                insert_part(
+                    &mut res,
+                    window_entity,
+                    entity,
+                    UiTransform::new(
+                        format!("{}_window_resize_{:?}", id, edge),
+                        anchor,
+                        anchor,
+                        0.,
+                        0.,
+                        3.,
+                        width,
+                        height,
+                    )
+                    .with_stretch(stretch),
+                    UiWindowPart::ResizeHandle(*edge),
+                );
+                res.mouse_reactive
+                    .insert(entity, Interactable)
+                    .expect("Unreachable: Inserting newly created entity");
+            }
+        }
+
+        (id, widget)
+    }
+
+    /// Create the UiWindow based on provided configuration parameters.
+    pub fn build_from_world(self, world: &World) -> (I, UiWindow) {
+        self.build(UiWindowBuilderResources::<I>::fetch(&world))
+    }
+}
+
+/// Adds a `part` of `window` to `entity`.
+fn insert_part<I: WidgetId>(
+    res: &mut UiWindowBuilderResources<'_, I>,
+    window: Entity,
+    entity: Entity,
+    transform: UiTransform,
+    part: UiWindowPart,
+) {
+    res.transform
+        .insert(entity, transform)
+        .expect("Unreachable: Inserting newly created entity");
+    res.part
+        .insert(entity, part)
+        .expect("Unreachable: Inserting newly created entity");
+    res.parent
+        .insert(entity, Parent { entity: window })
+        .expect("Unreachable: Inserting newly created entity");
+}
+
+#[cfg(test)]
+mod tests {
+    use specs_hierarchy::HierarchySystem;
+
+    use super::*;
+    use amethyst_core::{
+        ecs::prelude::{Builder, WorldExt},
+        math::Matrix3,
+    };
+
+    fn window(anchor: Anchor) -> UiTransform {
+        UiTransform::new(
+            String::default(),
+            anchor,
+            anchor,
+            10.0,
+            20.0,
+            0.0,
+            200.0,
+            100.0,
+        )
+    }
+
+    fn resize(edge: UiWindowEdge) -> Resize {
+        Resize {
+            window: World::new().create_entity().build(),
+            edge,
+            first: Vector2::new(0.0, 0.0),
+            origin: (10.0, 20.0, 200.0, 100.0),
+        }
+    }
+
+    #[test]
+    fn clamp_size_between_min_and_max() {
+        let frame = UiWindowFrame::new(20.0)
+            .with_min_size(50.0, 40.0)
+            .with_max_size(300.0, 200.0);
+        assert_eq!(frame.clamp_size(10.0, 500.0), (50.0, 200.0));
+        assert_eq!(frame.clamp_size(100.0, 100.0), (100.0, 100.0));
+        let unbounded = UiWindowFrame::new(20.0).with_min_size(50.0, 40.0);
+        assert_eq!(unbounded.clamp_size(1000.0, 10.0), (1000.0, 40.0));
+    }
+
+    #[test]
+    fn resizing_keeps_the_opposite_edges() {
+        let frame = UiWindowFrame::new(20.0).with_min_size(50.0, 40.0);
+        let mouse = Vector2::new(30.0, -10.0);
+
+        // Centered windows move by half the growth.
+        let centered = window(Anchor::Middle);
+        assert_eq!(
+            resized_layout(&resize(UiWindowEdge::BottomRight), mouse, &frame, &centered),
+            (25.0, 15.0, 230.0, 110.0)
+        );
+        // Windows pivoting on the edges opposite to the dragged ones don't move.
+        let top_left = window(Anchor::TopLeft);
+        assert_eq!(
+            resized_layout(&resize(UiWindowEdge::BottomRight), mouse, &frame, &top_left),
+            (10.0, 20.0, 230.0, 110.0)
+        );
+        // Windows pivoting on the dragged edge move by the whole growth.
+        assert_eq!(
+            resized_layout(&resize(UiWindowEdge::Left), mouse, &frame, &top_left),
+            (40.0, 20.0, 170.0, 100.0)
+        );
+        // The size stays above the minimum.
+        assert_eq!(
+            resized_layout(
+                &resize(UiWindowEdge::Top),
+                Vector2::new(0.0, -500.0),
+                &frame,
+                &top_left
+            ),
+            (10.0, -40.0, 200.0, 40.0)
+        );
+    }
+
+    #[test]
+    fn resizing_follows_the_mouse_in_layout_space() {
+        let frame = UiWindowFrame::new(20.0);
+        let mut scaled = window(Anchor::TopLeft);
+        scaled.global_matrix = Matrix3::new_nonuniform_scaling(&Vector2::new(2.0, 2.0));
+        assert_eq!(
+            resized_layout(
+                &resize(UiWindowEdge::BottomRight),
+                Vector2::new(30.0, -10.0),
+                &frame,
+                &scaled
+            ),
+            (10.0, 20.0, 215.0, 105.0)
+        );
+    }
+
+    #[test]
+    fn bringing_to_front_stacks_windows_at_equal_z() {
+        let mut world = World::new();
+        world.register::<UiTransform>();
+        world.register::<UiWindowFrame>();
+        let _hierarchy = HierarchySystem::<Parent>::new(&mut world);
+        let windows = (0..3)
+            .map(|_| {
+                world
+                    .create_entity()
+                    .with(window(Anchor::Middle))
+                    .with(UiWindowFrame::new(20.0))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let z = |world: &World| {
+            let transforms = world.read_storage::<UiTransform>();
+            windows
+                .iter()
+                .map(|window| transforms.get(*window).unwrap().local_z)
+                .collect::<Vec<_>>()
+        };
+
+        let bring = |world: &mut World, window| {
+            let (entities, hierarchy, frames, mut transforms) = world.system_data::<(
+                Entities<'_>,
+                ReadExpect<'_, ParentHierarchy>,
+                WriteStorage<'_, UiWindowFrame>,
+                WriteStorage<'_, UiTransform>,
+            )>();
+            bring_to_front(window, &entities, &hierarchy, &frames, &mut transforms);
+        };
+        bring(&mut world, windows[0]);
+        assert_eq!(z(&world), vec![20.0, 0.0, 10.0]);
+        bring(&mut world, windows[1]);
+        assert_eq!(z(&world), vec![10.0, 20.0, 0.0]);
+        // The window on top stays there.
+        bring(&mut world, windows[1]);
+        assert_eq!(z(&world), vec![10.0, 20.0, 0.0]);
+    }
+
+    #[test]
+    fn percent_and_stretched_windows_are_not_resized() {
+        let frame = UiWindowFrame::new(20.0);
+        let mouse = Vector2::new(30.0, -10.0);
+        let percent = window(Anchor::Middle).into_percent();
+        assert_eq!(
+            resized_layout(&resize(UiWindowEdge::Right), mouse, &frame, &percent),
+            (10.0, 20.0, 200.0, 100.0)
+        );
+        let stretched = window(Anchor::Middle).with_stretch(Stretch::X { x_margin: 0.0 });
+        assert_eq!(
+            resized_layout(&resize(UiWindowEdge::Right), mouse, &frame, &stretched),
+            (10.0, 20.0, 200.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn title_height_follows_the_scale_mode() {
+        assert_eq!(title_height_in(&window(Anchor::Middle), 20.0, 400.0), 20.0);
+        let percent = window(Anchor::Middle).into_percent();
+        assert_eq!(title_height_in(&percent, 20.0, 400.0), 0.05);
+    }
+}
@@ -0,0 +1,387 @@
+//! Ui elements following entities of the scene.
+
+use std::collections::HashSet;
+
+use derive_new::new;
+
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    math::{Matrix3, Matrix4, Point2, Point3, Vector3},
+    HiddenPropagate, Transform,
+};
+use amethyst_rendy::camera::{ActiveCamera, Camera};
+use amethyst_window::ScreenDimensions;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{ScaleMode, UiScaler, UiTransform};
+
+/// Moves a root ui element so that its anchor point follows the projection of an entity of the
+/// scene on the screen, for example to show a nameplate or a health bar above a unit.
+///
+/// The ui element keeps its screen-space size and is drawn on top of the scene.
+/// Its `local_x` and `local_y` are overwritten every frame.
+#[derive(Debug, Clone, new)]
+pub struct UiWorldAnchor {
+    /// The entity to follow. It needs a `Transform`.
+    pub entity: Entity,
+    /// Offset from the origin of `entity`, in its local space: it is rotated and scaled along
+    /// with the `Transform` of `entity` before projecting.
+    #[new(value = "Vector3::zeros()")]
+    pub offset: Vector3<f32>,
+    /// Hide the element (using `HiddenPropagate`) while the followed point is behind the camera.
+    #[new(value = "true")]
+    pub hide_behind_camera: bool,
+}
+
+impl UiWorldAnchor {
+    /// Sets the offset from the origin of the followed entity, in its local space.
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+impl Component for UiWorldAnchor {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Lays a root ui element and its children out on the XY plane of an entity of the scene, like
+/// an in-world terminal or a sign.
+///
+/// The element is centered on the origin of `entity` and oriented along its `Transform`.
+/// The plane is projected exactly, so mouse input hits the elements where they are in the scene.
+/// Each quad is drawn with the affine approximation of the projection at its center, which
+/// only shows on large quads seen at a grazing angle through a perspective camera.
+///
+/// The ui is not rendered into the scene: it is drawn by the ui pass on top of it, without depth
+/// testing, so the scene geometry in front of the plane doesn't hide it. For a ui that can be
+/// occluded, draw it into a `UiCanvas` render target and put the texture of the target on a mesh
+/// of the scene instead.
+#[derive(Debug, Clone, new)]
+pub struct UiWorldCanvas {
+    /// The entity whose plane the ui is laid on. It needs a `Transform`.
+    pub entity: Entity,
+    /// How many ui pixels make up a world unit.
+    #[new(value = "100.0")]
+    pub pixels_per_unit: f32,
+}
+
+impl UiWorldCanvas {
+    /// Sets how many ui pixels make up a world unit.
+    pub fn with_pixels_per_unit(mut self, pixels_per_unit: f32) -> Self {
+        self.pixels_per_unit = pixels_per_unit;
+        self
+    }
+}
+
+impl Component for UiWorldCanvas {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Projects points of the scene into ui coordinates, where y goes up from the bottom of the screen.
+struct Projector {
+    proj_view: Matrix4<f32>,
+    screen: (f32, f32),
+}
+
+impl Projector {
+    /// Returns `None` if the point is behind the camera.
+    fn project(&self, point: Point3<f32>) -> Option<Point2<f32>> {
+        let clip = self.proj_view * point.to_homogeneous();
+        if clip.w <= 0.0 {
+            return None;
+        }
+        // Normalized device coordinates are y down.
+        let (ndc_x, ndc_y) = (clip.x / clip.w, clip.y / clip.w);
+        Some(Point2::new(
+            (ndc_x + 1.0) * 0.5 * self.screen.0,
+            (1.0 - ndc_y) * 0.5 * self.screen.1,
+        ))
+    }
+}
+
+/// System updating the ui elements having a `UiWorldAnchor` or a `UiWorldCanvas`.
+///
+/// Runs after the `TransformSystem` and before the `UiTransformSystem`.
+#[derive(Debug, Default, new)]
+pub struct UiWorldSystem {
+    #[new(default)]
+    hidden_by_us: HashSet<Entity>,
+}
+
+impl<'a> System<'a> for UiWorldSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, UiWorldAnchor>,
+        ReadStorage<'a, UiWorldCanvas>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, Camera>,
+        Read<'a, ActiveCamera>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, UiScaler>,
+        WriteStorage<'a, UiTransform>,
+        WriteStorage<'a, HiddenPropagate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            anchors,
+            canvases,
+            transforms,
+            cameras,
+            active_camera,
+            screen_dimensions,
+            scaler,
+            mut ui_transforms,
+            mut hidden_props,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_world_system");
+
+        let camera = active_camera
+            .entity
+            .and_then(|entity| Some((cameras.get(entity)?, transforms.get(entity)?)))
+            .or_else(|| (&cameras, &transforms).join().next());
+        let projector = match camera {
+            Some((camera, camera_transform)) => Projector {
+                proj_view: camera.matrix * camera_transform.global_view_matrix(),
+                screen: (screen_dimensions.width(), screen_dimensions.height()),
+            },
+            None => return,
+        };
+
+        let scale_factor = scaler.scale_factor(&screen_dimensions);
+        let mut behind_camera = HashSet::new();
+
+        // Joining over the transforms mutably would flag all of them as modified, which would
+        // cause the layout of their whole subtree to be recomputed every frame.
+        let mut positions = Vec::new();
+        let mut matrices = Vec::new();
+
+        for (entity, anchor, ui_transform) in (&*entities, &anchors, &ui_transforms).join() {
+            let local = transforms.get(anchor.entity).and_then(|transform| {
+                let point = transform
+                    .global_matrix()
+                    .transform_point(&Point3::from(anchor.offset));
+                anchored_position(&projector, point, ui_transform, scale_factor)
+            });
+            let local = match local {
+                Some(local) => local,
+                None => {
+                    if anchor.hide_behind_camera {
+                        behind_camera.insert(entity);
+                    }
+                    continue;
+                }
+            };
+            if (ui_transform.local_x, ui_transform.local_y) != local {
+                positions.push((entity, local));
+            }
+        }
+
+        for (entity, canvas, ui_transform) in (&*entities, &canvases, &ui_transforms).join() {
+            let matrix = transforms
+                .get(canvas.entity)
+                .and_then(|transform| canvas_matrix(&projector, transform, canvas));
+            match matrix {
+                Some(matrix) => {
+                    if ui_transform.canvas_matrix != Some(matrix) {
+                        matrices.push((entity, matrix));
+                    }
+                }
+                None => {
+                    behind_camera.insert(entity);
+                }
+            }
+        }
+
+        for (entity, (x, y)) in positions {
+            if let Some(ui_transform) = ui_transforms.get_mut(entity) {
+                ui_transform.local_x = x;
+                ui_transform.local_y = y;
+            }
+        }
+        for (entity, matrix) in matrices {
+            if let Some(ui_transform) = ui_transforms.get_mut(entity) {
+                ui_transform.canvas_matrix = Some(matrix);
+            }
+        }
+
+        // Leave alone the elements that were hidden by someone else.
+        let mut hidden_by_us = HashSet::new();
+        for entity in behind_camera {
+            if self.hidden_by_us.contains(&entity) {
+                hidden_by_us.insert(entity);
+            } else if !hidden_props.contains(entity) {
+                hidden_props
+                    .insert(entity, HiddenPropagate::new())
+                    .expect("unreachable: We are inserting");
+                hidden_by_us.insert(entity);
+            }
+        }
+        for entity in self.hidden_by_us.difference(&hidden_by_us) {
+            hidden_props.remove(*entity);
+        }
+        self.hidden_by_us = hidden_by_us;
+    }
+}
+
+/// Returns the `local_x` and `local_y` putting the anchor point of the root `ui_transform` on the
+/// projection of `point`, or `None` if the point is behind the camera.
+fn anchored_position(
+    projector: &Projector,
+    point: Point3<f32>,
+    ui_transform: &UiTransform,
+    scale_factor: f32,
+) -> Option<(f32, f32)> {
+    let position = projector.project(point)?;
+    // Undo the anchoring done by the layout, relative to the screen.
+    let norm = ui_transform.anchor.norm_offset();
+    let local = (
+        position.x - projector.screen.0 * (0.5 + norm.0),
+        position.y - projector.screen.1 * (0.5 + norm.1),
+    );
+    Some(match ui_transform.scale_mode {
+        ScaleMode::Pixel => (local.0 / scale_factor, local.1 / scale_factor),
+        ScaleMode::Percent => (local.0 / projector.screen.0, local.1 / projector.screen.1),
+    })
+}
+
+/// Computes the projective matrix moving points relative to the center of the canvas root to the
+/// screen. Returns `None` if the center is behind the camera.
+fn canvas_matrix(
+    projector: &Projector,
+    transform: &Transform,
+    canvas: &UiWorldCanvas,
+) -> Option<Matrix3<f32>> {
+    let plane = projector.proj_view * transform.global_matrix();
+    let scale = 1.0 / canvas.pixels_per_unit;
+    // The points of the plane have a z of zero, so the third column doesn't matter, and the
+    // depth of the clip coordinates isn't needed.
+    let clip = Matrix3::new(
+        plane[(0, 0)] * scale,
+        plane[(0, 1)] * scale,
+        plane[(0, 3)],
+        plane[(1, 0)] * scale,
+        plane[(1, 1)] * scale,
+        plane[(1, 3)],
+        plane[(3, 0)] * scale,
+        plane[(3, 1)] * scale,
+        plane[(3, 3)],
+    );
+    if clip[(2, 2)] <= 0.0 {
+        return None;
+    }
+    // Normalized device coordinates are y down.
+    let (width, height) = projector.screen;
+    let to_screen = Matrix3::new(
+        0.5 * width,
+        0.0,
+        0.5 * width,
+        0.0,
+        -0.5 * height,
+        0.5 * height,
+        0.0,
+        0.0,
+        1.0,
+    );
+    Some(to_screen * clip / clip[(2, 2)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Anchor;
+    use amethyst_core::{
+        ecs::prelude::{Builder, World, WorldExt},
+        math::{Perspective3, Translation3, UnitQuaternion},
+    };
+
+    #[test]
+    fn anchor_point_follows_the_projection() {
+        // A camera 4 units in front of the origin, with a vertical field of view of one radian.
+        let projector = Projector {
+            proj_view: Camera::perspective(1.5, 1.0, 0.1).matrix
+                * Translation3::new(0.0, 0.0, -4.0).to_homogeneous(),
+            screen: (1200.0, 800.0),
+        };
+        let top = Point3::new(0.0, 4.0 * 0.5f32.tan(), 0.0);
+        let ui_transform =
+            |anchor| UiTransform::new("".to_string(), anchor, anchor, 0., 0., 0., 10., 10.);
+        let near = |actual: Option<(f32, f32)>, expected: (f32, f32)| {
+            let actual = actual.unwrap();
+            assert!(
+                (actual.0 - expected.0).abs() < 1e-3 && (actual.1 - expected.1).abs() < 1e-3,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        };
+
+        let middle = ui_transform(Anchor::Middle);
+        near(
+            anchored_position(&projector, Point3::origin(), &middle, 2.0),
+            (0.0, 0.0),
+        );
+        // The top of the view is at the top of the screen, whatever the scale of the ui.
+        near(
+            anchored_position(&projector, top, &middle, 2.0),
+            (0.0, 200.0),
+        );
+        near(
+            anchored_position(&projector, top, &ui_transform(Anchor::BottomLeft), 2.0),
+            (300.0, 400.0),
+        );
+        near(
+            anchored_position(&projector, top, &middle.clone().into_percent(), 2.0),
+            (0.0, 0.5),
+        );
+        assert!(anchored_position(&projector, Point3::new(0.0, 0.0, 8.0), &middle, 2.0).is_none());
+    }
+
+    #[test]
+    fn canvas_matrix_is_the_exact_projection() {
+        let view = Translation3::new(0.3, -0.2, -4.0).to_homogeneous()
+            * UnitQuaternion::from_euler_angles(0.6, -0.4, 0.1).to_homogeneous();
+        let projector = Projector {
+            proj_view: Perspective3::new(1.5, 1.0, 0.1, 100.0).to_homogeneous() * view,
+            screen: (1200.0, 800.0),
+        };
+        let canvas =
+            UiWorldCanvas::new(World::new().create_entity().build()).with_pixels_per_unit(50.0);
+        let transform = Transform::default();
+        let matrix = canvas_matrix(&projector, &transform, &canvas).unwrap();
+
+        for &(x, y) in &[(0.0, 0.0), (100.0, 50.0), (-100.0, 50.0), (-100.0, -50.0)] {
+            let expected = projector
+                .project(Point3::new(x / 50.0, y / 50.0, 0.0))
+                .unwrap();
+            let actual = matrix.transform_point(&Point2::new(x, y));
+            assert!(
+                (expected - actual).norm() < 1e-2,
+                "{} != {}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn canvas_behind_the_camera_is_not_projected() {
+        let projector = Projector {
+            proj_view: Perspective3::new(1.5, 1.0, 0.1, 100.0).to_homogeneous()
+                * Translation3::new(0.0, 0.0, 4.0).to_homogeneous(),
+            screen: (1200.0, 800.0),
+        };
+        let canvas = UiWorldCanvas::new(World::new().create_entity().build());
+        assert!(canvas_matrix(&projector, &Transform::default(), &canvas).is_none());
+    }
+}
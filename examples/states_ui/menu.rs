@@ -3,7 +3,7 @@ use amethyst::{
     input::{is_close_requested, is_key_down},
     prelude::*,
     ui::{UiCreator, UiEvent, UiEventType, UiFinder},
-    winit::VirtualKeyCode,
+    winit::{MouseButton, VirtualKeyCode},
 };
 
 use crate::{credits::CreditsScreen, game::Game, welcome::WelcomeScreen};
@@ -69,8 +69,9 @@ impl SimpleState for MainMenu {
                 }
             }
             StateEvent::Ui(UiEvent {
-                event_type: UiEventType::Click,
+                event_type: UiEventType::Click(MouseButton::Left),
                 target,
+                ..
             }) => {
                 if Some(target) == self.button_credits {
                     log::info!("[Trans::Switch] Switching to CreditsScreen!");
@@ -4,7 +4,7 @@ use amethyst::{
     prelude::*,
     shrev::EventChannel,
     ui::{UiCreator, UiEvent, UiEventType, UiFinder},
-    winit::VirtualKeyCode,
+    winit::{MouseButton, VirtualKeyCode},
     TransEvent,
 };
 
@@ -62,8 +62,9 @@ impl<'a> SimpleState for PauseMenuState {
                 }
             }
             StateEvent::Ui(UiEvent {
-                event_type: UiEventType::Click,
+                event_type: UiEventType::Click(MouseButton::Left),
                 target,
+                ..
             }) => {
                 if Some(target) == self.resume_button {
                     log::info!("Resuming Game!");
@@ -0,0 +1,41 @@
+#! /bin/bash
+# Fails when a shader source was changed after its compiled SPIR-V, so that stale shaders don't
+# get committed. Run `make` to recompile them.
+#
+# The commit times are compared, as checking out doesn't preserve the modification times, so this
+# needs the full history (for example `fetch-depth: 0` on CI).
+set -e
+
+self_dir="$(dirname "$(readlink -f "${BASH_SOURCE}")")"
+repository_dir="$(dirname "${self_dir}")"
+cd "${repository_dir}"
+
+# The time `file` was last changed: now if it has uncommitted changes, else its last commit time.
+changed_at() {
+  if [ -n "$(git status --porcelain -- "$1")" ]; then
+    stat -c %Y "$1"
+  else
+    git log -1 --format=%ct -- "$1"
+  fi
+}
+
+stale=0
+shopt -s nullglob
+for shader in */shaders/*.vert */shaders/*.frag */shaders/*/*.vert */shaders/*/*.frag; do
+  case "${shader}" in
+    */header/*) continue ;;
+  esac
+  compiled="${shader/\/shaders\//\/compiled\/}.spv"
+  if [ ! -e "${compiled}" ]; then
+    echo "${compiled} is missing"
+    stale=1
+  elif [ "$(changed_at "${shader}")" -gt "$(changed_at "${compiled}")" ]; then
+    echo "${compiled} is older than ${shader}"
+    stale=1
+  fi
+done
+
+if [ "${stale}" -ne 0 ]; then
+  echo "Recompile the shaders with \`make\`."
+fi
+exit "${stale}"